@@ -63,6 +63,35 @@ pub enum ChatCommands {
         #[arg(short = 'c', long, required = true, value_name = "CHAT_ID", value_hint = ValueHint::Username)]
         chat_id: String,
     },
+    /// Create group or channel chat
+    CreateChat {
+        #[arg(short = 't', long, required = true, value_name = "TITLE")]
+        title: String,
+        #[arg(short = 'a', long, value_name = "ABOUT")]
+        about: Option<String>,
+        #[arg(short = 'r', long, value_name = "RULES")]
+        rules: Option<String>,
+        /// Initial members, repeat the option or separate user IDs with commas
+        #[arg(short = 'm', long = "member", value_name = "USER_ID", value_delimiter = ',', value_hint = ValueHint::Username)]
+        members: Vec<String>,
+        /// Make the chat public
+        #[arg(long)]
+        public: bool,
+        /// New members must be approved by admins
+        #[arg(long)]
+        join_moderation: bool,
+        /// Create a channel where only admins can write
+        #[arg(long)]
+        channel: bool,
+    },
+    /// Add members to chat
+    AddChatMembers {
+        #[arg(short = 'c', long, required = true, value_name = "CHAT_ID", value_hint = ValueHint::Username)]
+        chat_id: String,
+        /// Members to add, repeat the option or separate user IDs with commas
+        #[arg(short = 'm', long = "member", required = true, value_name = "USER_ID", value_delimiter = ',', value_hint = ValueHint::Username)]
+        members: Vec<String>,
+    },
 }
 
 #[async_trait]
@@ -84,6 +113,29 @@ impl Command for ChatCommands {
                 execute_send_action(bot, chat_id, action).await
             }
             ChatCommands::GetChatAdmins { chat_id } => execute_get_chat_admins(bot, chat_id).await,
+            ChatCommands::CreateChat {
+                title,
+                about,
+                rules,
+                members,
+                public,
+                join_moderation,
+                channel,
+            } => {
+                let request = create_chat_request(
+                    title,
+                    about.as_deref(),
+                    rules.as_deref(),
+                    members,
+                    *public,
+                    *join_moderation,
+                    *channel,
+                );
+                execute_create_chat(bot, &request).await
+            }
+            ChatCommands::AddChatMembers { chat_id, members } => {
+                execute_add_chat_members(bot, chat_id, members).await
+            }
         }
     }
 
@@ -111,6 +163,29 @@ impl Command for ChatCommands {
             ChatCommands::GetChatAdmins { chat_id } => {
                 execute_get_chat_admins_structured(bot, chat_id).await
             }
+            ChatCommands::CreateChat {
+                title,
+                about,
+                rules,
+                members,
+                public,
+                join_moderation,
+                channel,
+            } => {
+                let request = create_chat_request(
+                    title,
+                    about.as_deref(),
+                    rules.as_deref(),
+                    members,
+                    *public,
+                    *join_moderation,
+                    *channel,
+                );
+                execute_create_chat_structured(bot, &request).await
+            }
+            ChatCommands::AddChatMembers { chat_id, members } => {
+                execute_add_chat_members_structured(bot, chat_id, members).await
+            }
         };
 
         OutputFormatter::print(&response, output_format)?;
@@ -131,6 +206,8 @@ impl Command for ChatCommands {
             ChatCommands::SetChatAbout { .. } => "set-chat-about",
             ChatCommands::SendAction { .. } => "send-action",
             ChatCommands::GetChatAdmins { .. } => "get-chat-admins",
+            ChatCommands::CreateChat { .. } => "create-chat",
+            ChatCommands::AddChatMembers { .. } => "add-chat-members",
         }
     }
 
@@ -161,11 +238,64 @@ impl Command for ChatCommands {
             ChatCommands::GetChatAdmins { chat_id } => {
                 validate_chat_id(chat_id)?;
             }
+            ChatCommands::CreateChat {
+                title,
+                about,
+                members,
+                ..
+            } => {
+                validate_chat_title(title)?;
+                if let Some(about) = about {
+                    validate_chat_about(about)?;
+                }
+                for member in members {
+                    validate_chat_id(member)?;
+                }
+            }
+            ChatCommands::AddChatMembers { chat_id, members } => {
+                validate_chat_id(chat_id)?;
+                if members.is_empty() {
+                    return Err(CliError::InputError(
+                        "At least one member is required".to_string(),
+                    ));
+                }
+                for member in members {
+                    validate_chat_id(member)?;
+                }
+            }
         }
         Ok(())
     }
 }
 
+/// Build `chats/createChat` request from the `create-chat` arguments
+fn create_chat_request(
+    title: &str,
+    about: Option<&str>,
+    rules: Option<&str>,
+    members: &[String],
+    public: bool,
+    join_moderation: bool,
+    channel: bool,
+) -> RequestChatsCreateChat {
+    let mut request = RequestChatsCreateChat::new(title.to_string())
+        .with_public(public)
+        .with_join_moderation(join_moderation);
+    if let Some(about) = about {
+        request = request.with_about(about.to_string());
+    }
+    if let Some(rules) = rules {
+        request = request.with_rules(rules.to_string());
+    }
+    if !members.is_empty() {
+        request = request.with_members(members.iter().cloned().collect());
+    }
+    if channel {
+        request = request.with_default_role(ChatDefaultRole::Readonly);
+    }
+    request
+}
+
 // Command execution functions
 
 // Structured output versions
@@ -358,6 +488,53 @@ async fn execute_get_chat_admins_structured(
     }
 }
 
+async fn execute_create_chat_structured(
+    bot: &Bot,
+    request: &RequestChatsCreateChat,
+) -> CliResponse<serde_json::Value> {
+    debug!("Creating chat {}", request.name);
+
+    match bot.send_api_request(request.clone()).await {
+        Ok(result) => {
+            info!("Successfully created chat {}", result.chat_id);
+            let data = json!({
+                "chat_id": result.chat_id,
+                "title": request.name,
+                "members": result.members
+            });
+            CliResponse::success("create-chat", data)
+        }
+        Err(e) => CliResponse::error("create-chat", format!("Failed to create chat: {e}")),
+    }
+}
+
+async fn execute_add_chat_members_structured(
+    bot: &Bot,
+    chat_id: &str,
+    members: &[String],
+) -> CliResponse<serde_json::Value> {
+    debug!("Adding {} members to chat {}", members.len(), chat_id);
+
+    let request = RequestChatsMembersAdd::new((
+        ChatId::from_borrowed_str(chat_id),
+        members.iter().cloned().collect(),
+    ));
+    match bot.send_api_request(request).await {
+        Ok(result) => {
+            info!("Successfully added members to chat {}", chat_id);
+            let data = json!({
+                "chat_id": chat_id,
+                "members": result.members
+            });
+            CliResponse::success("add-chat-members", data)
+        }
+        Err(e) => CliResponse::error(
+            "add-chat-members",
+            format!("Failed to add chat members: {e}"),
+        ),
+    }
+}
+
 // Legacy output versions (for backward compatibility)
 async fn execute_get_chat_info(bot: &Bot, chat_id: &str) -> CliResult<()> {
     debug!("Getting chat info for {}", chat_id);
@@ -491,6 +668,36 @@ async fn execute_get_chat_admins(bot: &Bot, chat_id: &str) -> CliResult<()> {
     Ok(())
 }
 
+async fn execute_create_chat(bot: &Bot, request: &RequestChatsCreateChat) -> CliResult<()> {
+    debug!("Creating chat {}", request.name);
+
+    let result = bot
+        .send_api_request(request.clone())
+        .await
+        .map_err(CliError::ApiError)?;
+
+    info!("Successfully created chat {}", result.chat_id);
+    print_success_result(&result, &OutputFormat::Pretty)?;
+    Ok(())
+}
+
+async fn execute_add_chat_members(bot: &Bot, chat_id: &str, members: &[String]) -> CliResult<()> {
+    debug!("Adding {} members to chat {}", members.len(), chat_id);
+
+    let request = RequestChatsMembersAdd::new((
+        ChatId::from_borrowed_str(chat_id),
+        members.iter().cloned().collect(),
+    ));
+    let result = bot
+        .send_api_request(request)
+        .await
+        .map_err(CliError::ApiError)?;
+
+    info!("Successfully added members to chat {}", chat_id);
+    print_success_result(&result, &OutputFormat::Pretty)?;
+    Ok(())
+}
+
 // Validation functions are now imported from utils/validation module
 
 #[cfg(test)]
@@ -613,10 +820,91 @@ mod tests {
         assert!(res.is_ok()); // валидация не ограничивает длину
     }
 
+    fn create_chat_cmd() -> ChatCommands {
+        ChatCommands::CreateChat {
+            title: "Incident".to_string(),
+            about: Some("Outage".to_string()),
+            rules: None,
+            members: vec!["user1".to_string(), "user2".to_string()],
+            public: false,
+            join_moderation: true,
+            channel: true,
+        }
+    }
+
+    #[test]
+    fn test_create_chat_request() {
+        let request = create_chat_request(
+            "Incident",
+            Some("Outage"),
+            None,
+            &["user1".to_string(), "user2".to_string()],
+            false,
+            true,
+            true,
+        );
+        assert_eq!(request.name, "Incident");
+        assert_eq!(request.about.as_deref(), Some("Outage"));
+        assert_eq!(request.rules, None);
+        assert_eq!(request.members.unwrap().0.len(), 2);
+        assert_eq!(request.public, Some(false));
+        assert_eq!(request.join_moderation, Some(true));
+        assert_eq!(request.default_role, Some(ChatDefaultRole::Readonly));
+    }
+
+    #[test]
+    fn test_validate_create_chat() {
+        assert!(create_chat_cmd().validate().is_ok());
+        let cmd = ChatCommands::CreateChat {
+            title: "".to_string(),
+            about: None,
+            rules: None,
+            members: vec![],
+            public: false,
+            join_moderation: false,
+            channel: false,
+        };
+        assert!(cmd.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_add_chat_members_invalid_member() {
+        let cmd = ChatCommands::AddChatMembers {
+            chat_id: "12345@chat".to_string(),
+            members: vec!["user with spaces".to_string()],
+        };
+        assert!(cmd.validate().is_err());
+        let cmd = ChatCommands::AddChatMembers {
+            chat_id: "12345@chat".to_string(),
+            members: vec![],
+        };
+        assert!(cmd.validate().is_err());
+    }
+
     fn dummy_bot() -> Bot {
         Bot::with_params(&APIVersionUrl::V1, "dummy_token", "https://dummy.api.com").unwrap()
     }
 
+    #[test]
+    fn test_execute_create_chat_api_error() {
+        let bot = dummy_bot();
+        let rt = Runtime::new().unwrap();
+        let res = rt.block_on(create_chat_cmd().execute(&bot));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_execute_add_chat_members_api_error() {
+        let cmd = ChatCommands::AddChatMembers {
+            chat_id: "12345@chat".to_string(),
+            members: vec!["user1".to_string()],
+        };
+        let bot = dummy_bot();
+        let rt = Runtime::new().unwrap();
+        let res = rt.block_on(cmd.execute(&bot));
+        assert!(res.is_err());
+    }
+
     #[test]
     fn test_execute_get_chat_info_api_error() {
         let cmd = ChatCommands::GetChatInfo {
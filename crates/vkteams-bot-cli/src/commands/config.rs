@@ -724,7 +724,7 @@ async fn execute_config(show: bool, init: bool, wizard: bool) -> CliResult<()> {
     if show {
         // Print current configuration as TOML
         match Config::from_file() {
-            Ok(config) => match toml::to_string_pretty(&config) {
+            Ok(config) => match toml::to_string_pretty(&config.redacted()) {
                 Ok(config_str) => {
                     println!("Current configuration:\n{}", config_str.green());
                 }
@@ -983,7 +983,7 @@ async fn execute_config_structured(
     } else if show {
         match Config::from_file() {
             Ok(config) => {
-                let config_json = serde_json::to_value(&config).unwrap_or(json!({}));
+                let config_json = serde_json::to_value(config.redacted()).unwrap_or(json!({}));
                 CliResponse::success(
                     "config",
                     json!({
@@ -1089,6 +1089,34 @@ mod tests {
         assert!(res.is_ok());
     }
 
+    #[test]
+    fn test_config_redacted() {
+        const TOKEN: &str = "001.3344556677.8899001122:1000000003";
+        let mut config = Config::default();
+        config.api.token = Some(TOKEN.to_string());
+        config.proxy = Some(crate::config::ProxyConfig {
+            url: "http://proxy.example.com:8080".to_string(),
+            user: Some("bot".to_string()),
+            password: Some("proxy_password".to_string()),
+        });
+
+        let debug = format!("{config:?}");
+        assert!(!debug.contains(TOKEN));
+        assert!(!debug.contains("proxy_password"));
+
+        let redacted = config.redacted();
+        let shown = toml::to_string_pretty(&redacted).unwrap();
+        assert!(!shown.contains(TOKEN));
+        assert!(!shown.contains("proxy_password"));
+        assert!(shown.contains("[REDACTED]"));
+        assert!(shown.contains("proxy.example.com"));
+        assert_eq!(config.api.token.as_deref(), Some(TOKEN));
+
+        config.register_secrets();
+        let message = format!("Error: request to /self/get?chatId=1&t={TOKEN} failed");
+        assert!(!vkteams_bot::secret::redact(&message).contains(TOKEN));
+    }
+
     #[tokio::test]
     async fn test_execute_config_show_success() {
         // Should not return error when showing config
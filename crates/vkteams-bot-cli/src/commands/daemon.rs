@@ -9,7 +9,10 @@ use std::sync::Arc;
 use std::time::Instant;
 use tokio::signal;
 use tracing::{debug, error, info, warn};
-use vkteams_bot::prelude::{Bot, ResponseEventsGet};
+use vkteams_bot::prelude::{
+    Bot, CancellationToken, Checkpoint, CheckpointMode, FileCheckpointStore, ResponseEventsGet,
+    serve_metrics,
+};
 #[cfg(feature = "storage")]
 use vkteams_bot::storage::StorageManager;
 
@@ -33,6 +36,22 @@ pub enum DaemonCommands {
         /// Chat ID to listen (optional, uses config default)
         #[arg(long)]
         chat_id: Option<String>,
+
+        /// File to persist the last processed event ID, resumed on startup
+        #[arg(long, value_name = "PATH")]
+        checkpoint_file: Option<String>,
+
+        /// When the last event ID is persisted
+        #[arg(long, value_enum, default_value_t = DaemonCheckpointMode::AfterSuccess)]
+        checkpoint_mode: DaemonCheckpointMode,
+
+        /// Do not persist the last event ID, start from the beginning
+        #[arg(long, conflicts_with = "checkpoint_file")]
+        no_checkpoint: bool,
+
+        /// Serve Prometheus metrics on the address, e.g. 127.0.0.1:9090
+        #[arg(long, value_name = "ADDR")]
+        metrics_addr: Option<String>,
     },
 
     /// Stop daemon
@@ -52,6 +71,25 @@ pub enum DaemonCommands {
     },
 }
 
+/// When the daemon persists the last processed event ID
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DaemonCheckpointMode {
+    /// After the events are processed: events are received again after a crash
+    #[default]
+    AfterSuccess,
+    /// Before the events are processed: events are not received again after a crash
+    BeforeHandling,
+}
+
+impl From<DaemonCheckpointMode> for CheckpointMode {
+    fn from(mode: DaemonCheckpointMode) -> Self {
+        match mode {
+            DaemonCheckpointMode::AfterSuccess => CheckpointMode::AfterSuccess,
+            DaemonCheckpointMode::BeforeHandling => CheckpointMode::BeforeHandling,
+        }
+    }
+}
+
 #[async_trait]
 impl Command for DaemonCommands {
     async fn execute(&self, bot: &Bot) -> CliResult<()> {
@@ -59,10 +97,22 @@ impl Command for DaemonCommands {
             DaemonCommands::Start {
                 foreground,
                 auto_save,
+                checkpoint_file,
+                checkpoint_mode,
+                no_checkpoint,
+                metrics_addr,
                 ..
             } => {
                 if *foreground {
-                    start_foreground_daemon(bot, *auto_save).await
+                    let checkpoint = if *no_checkpoint {
+                        None
+                    } else {
+                        Some(daemon_checkpoint(
+                            checkpoint_file.as_deref(),
+                            *checkpoint_mode,
+                        )?)
+                    };
+                    start_foreground_daemon(bot, *auto_save, checkpoint, metrics_addr.clone()).await
                 } else {
                     start_background_daemon(bot, *auto_save).await
                 }
@@ -311,12 +361,41 @@ impl AutoSaveEventProcessor {
     }
 }
 
+/// Checkpoint of the last processed event ID,
+/// stored in the data directory unless the file is given
+fn daemon_checkpoint(
+    checkpoint_file: Option<&str>,
+    mode: DaemonCheckpointMode,
+) -> CliResult<Checkpoint> {
+    let path = match checkpoint_file {
+        Some(path) => std::path::PathBuf::from(path),
+        None => {
+            let mut data_dir = dirs::data_dir()
+                .ok_or_else(|| CliError::Config("Cannot determine data directory".to_string()))?;
+            data_dir.push("vkteams-bot");
+            data_dir.push("daemon.checkpoint");
+            data_dir
+        }
+    };
+    info!("Using event checkpoint file: {}", path.display());
+    Ok(Checkpoint::new(FileCheckpointStore::new(path)).with_mode(mode.into()))
+}
+
 /// Start daemon in foreground mode
-async fn start_foreground_daemon(bot: &Bot, auto_save: bool) -> CliResult<()> {
+async fn start_foreground_daemon(
+    bot: &Bot,
+    auto_save: bool,
+    checkpoint: Option<Checkpoint>,
+    metrics_addr: Option<String>,
+) -> CliResult<()> {
     info!(
         "Starting VKTeams Bot daemon in foreground mode with auto_save={}",
         auto_save
     );
+    let bot = match checkpoint {
+        Some(checkpoint) => bot.clone().with_checkpoint(checkpoint),
+        None => bot.clone(),
+    };
 
     let processor = if auto_save {
         // Load config for storage initialization
@@ -327,8 +406,26 @@ async fn start_foreground_daemon(bot: &Bot, auto_save: bool) -> CliResult<()> {
         None
     };
 
-    // Setup graceful shutdown
-    let shutdown_signal = setup_shutdown_signal();
+    // Setup graceful shutdown: stop polling and let the batch in progress finish
+    let shutdown = CancellationToken::new();
+    let signal_task = tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            setup_shutdown_signal().await;
+            info!("Received shutdown signal, stopping daemon...");
+            shutdown.cancel();
+        }
+    });
+
+    // Serve Prometheus metrics until the daemon stops
+    let metrics_task = metrics_addr.map(|addr| {
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_metrics(addr, shutdown).await {
+                error!("Metrics endpoint error: {}", e);
+            }
+        })
+    });
 
     // Create the event processing function
     let event_processor = {
@@ -352,16 +449,17 @@ async fn start_foreground_daemon(bot: &Bot, auto_save: bool) -> CliResult<()> {
         }
     };
 
-    tokio::select! {
-        result = bot.event_listener(event_processor) => {
-            match result {
-                Ok(_) => info!("Event listener finished successfully"),
-                Err(e) => error!("Event listener error: {}", e),
-            }
-        }
-        _ = shutdown_signal => {
-            info!("Received shutdown signal, stopping daemon...");
-        }
+    match bot
+        .event_listener_with_shutdown(event_processor, shutdown.clone())
+        .await
+    {
+        Ok(_) => info!("Event listener finished successfully"),
+        Err(e) => error!("Event listener error: {}", e),
+    }
+    signal_task.abort();
+    shutdown.cancel();
+    if let Some(metrics_task) = metrics_task {
+        let _ = metrics_task.await;
     }
 
     Ok(())
@@ -647,6 +745,10 @@ mod tests {
             pid_file: Some("/tmp/test.pid".to_string()),
             auto_save: true,
             chat_id: Some("test-chat".to_string()),
+            checkpoint_file: None,
+            checkpoint_mode: DaemonCheckpointMode::AfterSuccess,
+            no_checkpoint: false,
+            metrics_addr: None,
         };
         assert_eq!(start_cmd.name(), "daemon");
 
@@ -661,6 +763,21 @@ mod tests {
         assert_eq!(status_cmd.name(), "daemon");
     }
 
+    #[tokio::test]
+    async fn test_daemon_checkpoint_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.checkpoint");
+        let checkpoint = daemon_checkpoint(
+            Some(path.to_str().unwrap()),
+            DaemonCheckpointMode::BeforeHandling,
+        )
+        .unwrap();
+        assert_eq!(checkpoint.mode(), CheckpointMode::BeforeHandling);
+        assert_eq!(checkpoint.load().await.unwrap(), None);
+        checkpoint.store().save(15).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "15");
+    }
+
     #[test]
     fn test_processor_stats_default() {
         let stats = ProcessorStats::default();
@@ -772,6 +889,7 @@ mod tests {
                         format: None,
                         parts: vec![],
                         timestamp: Timestamp(1234567890),
+                        parent_topic: None,
                     })),
                 },
                 EventMessage {
@@ -784,6 +902,7 @@ mod tests {
                         from: test_from,
                         format: None,
                         edited_timestamp: Timestamp(1234567900),
+                        parent_topic: None,
                     })),
                 },
             ],
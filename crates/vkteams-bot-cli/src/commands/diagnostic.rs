@@ -98,12 +98,10 @@ impl Command for DiagnosticCommands {
             DiagnosticCommands::RateLimitTest {
                 requests,
                 delay_ms: _,
-            } => {
-                if *requests == 0 || *requests > 1000 {
-                    return Err(CliError::InputError(
-                        "Number of requests must be between 1 and 1000".to_string(),
-                    ));
-                }
+            } if *requests == 0 || *requests > 1000 => {
+                return Err(CliError::InputError(
+                    "Number of requests must be between 1 and 1000".to_string(),
+                ));
             }
             _ => {} // Other commands don't need validation
         }
@@ -265,6 +263,30 @@ async fn execute_health_check(bot: &Bot) -> CliResult<()> {
         }
     }
 
+    // Test 4: Circuit breaker
+    print!("{} Checking circuit breaker... ", emoji::GEAR);
+    let circuits = bot.circuit_stats();
+    match circuit_breaker_status(&circuits) {
+        "pass" => println!("{} - {} circuit(s) closed", "PASS".green(), circuits.len()),
+        status => {
+            if status == "fail" {
+                println!("{}", "FAIL".red());
+                all_passed = false;
+            } else {
+                println!("{}", "WARN".yellow());
+            }
+            for circuit in circuits.iter().filter(|c| c.state != CircuitState::Closed) {
+                println!(
+                    "  {}: {}, {}/{} requests failed",
+                    circuit.key.cyan(),
+                    circuit.state.to_string().red(),
+                    circuit.failures,
+                    circuit.requests
+                );
+            }
+        }
+    }
+
     println!();
     if all_passed {
         println!("{} All health checks passed!", emoji::CHECK.bold().green());
@@ -412,6 +434,22 @@ async fn execute_rate_limit_test(bot: &Bot, requests: u32, delay_ms: u64) -> Cli
         requests as f64 / total_time.as_secs_f64()
     );
 
+    let layers = bot.rate_limiter().lock().await.get_layer_stats().await;
+    if !layers.is_empty() {
+        println!();
+        println!("{}", "Rate Limit Layers:".bold().green());
+        for (layer, stats) in layers {
+            println!(
+                "  {}: {}/{} tokens, {} allowed, {} limited",
+                layer.to_string().cyan(),
+                stats.available_tokens,
+                stats.capacity,
+                stats.allowed_requests,
+                stats.rate_limited_requests.to_string().red()
+            );
+        }
+    }
+
     Ok(())
 }
 
@@ -595,6 +633,18 @@ async fn execute_health_check_structured(bot: &Bot) -> CliResponse<serde_json::V
     };
     tests.push(latency_result);
 
+    // Test 4: Circuit breaker
+    let circuits = bot.circuit_stats();
+    let circuit_status = circuit_breaker_status(&circuits);
+    if circuit_status == "fail" {
+        all_passed = false;
+    }
+    tests.push(json!({
+        "name": "Circuit Breaker",
+        "status": circuit_status,
+        "circuits": circuit_stats_json(&circuits)
+    }));
+
     let data = json!({
         "overall_status": if all_passed { "healthy" } else { "unhealthy" },
         "tests": tests,
@@ -746,6 +796,7 @@ async fn execute_rate_limit_test_structured(
             "average_rate_per_second": average_rate,
             "delay_between_requests_ms": delay_ms
         },
+        "layers": rate_limit_layers_json(bot).await,
         "request_details": request_results
     });
 
@@ -754,6 +805,51 @@ async fn execute_rate_limit_test_structured(
 
 // Utility functions
 
+/// Per-layer statistics of the bot rate limiter
+async fn rate_limit_layers_json(bot: &Bot) -> Vec<serde_json::Value> {
+    let layers = bot.rate_limiter().lock().await.get_layer_stats().await;
+    layers
+        .into_iter()
+        .map(|(layer, stats)| {
+            json!({
+                "layer": layer.to_string(),
+                "capacity": stats.capacity,
+                "available_tokens": stats.available_tokens,
+                "total_requests": stats.total_requests,
+                "allowed": stats.allowed_requests,
+                "rate_limited": stats.rate_limited_requests
+            })
+        })
+        .collect()
+}
+
+/// Health of the circuits: `fail` if any is open, `warn` if any is half-open
+fn circuit_breaker_status(circuits: &[CircuitStats]) -> &'static str {
+    if circuits.iter().any(|c| c.state == CircuitState::Open) {
+        "fail"
+    } else if circuits.iter().any(|c| c.state == CircuitState::HalfOpen) {
+        "warn"
+    } else {
+        "pass"
+    }
+}
+
+/// Statistics of the circuit breaker of the bot
+fn circuit_stats_json(circuits: &[CircuitStats]) -> Vec<serde_json::Value> {
+    circuits
+        .iter()
+        .map(|circuit| {
+            json!({
+                "key": circuit.key,
+                "state": circuit.state.to_string(),
+                "requests": circuit.requests,
+                "failures": circuit.failures,
+                "retry_in_secs": circuit.retry_in.map(|d| d.as_secs())
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -854,6 +950,37 @@ mod tests {
         assert!(res.is_ok());
     }
 
+    #[test]
+    fn test_circuit_breaker_status() {
+        let circuit = |state| CircuitStats {
+            key: "api.example.com".to_string(),
+            state,
+            requests: 10,
+            failures: 5,
+            retry_in: None,
+        };
+        assert_eq!(circuit_breaker_status(&[]), "pass");
+        assert_eq!(
+            circuit_breaker_status(&[circuit(CircuitState::Closed)]),
+            "pass"
+        );
+        assert_eq!(
+            circuit_breaker_status(&[
+                circuit(CircuitState::Closed),
+                circuit(CircuitState::HalfOpen)
+            ]),
+            "warn"
+        );
+        assert_eq!(
+            circuit_breaker_status(&[circuit(CircuitState::HalfOpen), circuit(CircuitState::Open)]),
+            "fail"
+        );
+        assert_eq!(
+            circuit_stats_json(&[circuit(CircuitState::Open)])[0]["state"],
+            "open"
+        );
+    }
+
     #[test]
     fn test_execute_network_test_api_error() {
         let cmd = DiagnosticCommands::NetworkTest;
@@ -1,11 +1,13 @@
 //! File upload and management commands
 
+use crate::commands::storage::storage_manager;
 use crate::commands::{Command, OutputFormat};
 use crate::errors::prelude::{CliError, Result as CliResult};
+use crate::file_utils;
 use crate::output::{CliResponse, OutputFormatter};
 use async_trait::async_trait;
 use base64::Engine;
-use clap::{Args, Subcommand};
+use clap::{Args, Subcommand, ValueHint};
 use serde_json::json;
 use vkteams_bot::prelude::*;
 
@@ -19,6 +21,8 @@ pub enum FileCommands {
     UploadJson(UploadJsonArgs),
     /// Get file information
     Info(FileInfoArgs),
+    /// Download files by ID or every attachment of a stored message
+    Download(DownloadFileArgs),
 }
 
 #[derive(Debug, Clone, Args)]
@@ -101,6 +105,29 @@ pub struct FileInfoArgs {
     pub file_id: String,
 }
 
+#[derive(Debug, Clone, Args)]
+pub struct DownloadFileArgs {
+    /// File ID to download, can be repeated
+    #[arg(long = "file-id", value_name = "FILE_ID")]
+    pub file_ids: Vec<String>,
+
+    /// Chat ID of a stored message to download all attachments from
+    #[arg(long, requires = "message_id")]
+    pub chat_id: Option<String>,
+
+    /// Message ID of a stored message to download all attachments from
+    #[arg(long, requires = "chat_id")]
+    pub message_id: Option<String>,
+
+    /// Target directory (download directory from config or current directory by default)
+    #[arg(long, value_hint = ValueHint::DirPath)]
+    pub dir: Option<String>,
+
+    /// Expected SHA-256 hash of the file, only for a single file
+    #[arg(long)]
+    pub sha256: Option<String>,
+}
+
 impl FileCommands {
     pub async fn execute_with_output(
         &self,
@@ -112,6 +139,7 @@ impl FileCommands {
             FileCommands::UploadText(args) => self.handle_upload_text(bot, args).await,
             FileCommands::UploadJson(args) => self.handle_upload_json(bot, args).await,
             FileCommands::Info(args) => self.handle_file_info(bot, args).await,
+            FileCommands::Download(args) => self.handle_download(bot, args).await,
         };
 
         OutputFormatter::print(&response, output_format)?;
@@ -353,6 +381,66 @@ impl FileCommands {
             Err(e) => CliResponse::error("file-info", format!("Failed to get file info: {e}")),
         }
     }
+
+    async fn handle_download(
+        &self,
+        bot: &Bot,
+        args: &DownloadFileArgs,
+    ) -> CliResponse<serde_json::Value> {
+        let mut file_ids = args.file_ids.clone();
+        if let (Some(chat_id), Some(message_id)) = (&args.chat_id, &args.message_id) {
+            match stored_attachments(chat_id, message_id).await {
+                Ok(attachments) => file_ids.extend(attachments),
+                Err(e) => return CliResponse::error("download-file", e),
+            }
+        }
+        if file_ids.is_empty() {
+            return CliResponse::error("download-file", "No files to download");
+        }
+
+        let dir = args.dir.as_deref().unwrap_or_default();
+        let mut files = Vec::with_capacity(file_ids.len());
+        for file_id in &file_ids {
+            match file_utils::download_file(bot, file_id, dir, args.sha256.as_deref()).await {
+                Ok((path, downloaded)) => files.push(json!({
+                    "file_id": file_id,
+                    "path": path,
+                    "size": downloaded.size,
+                    "resumed_from": downloaded.resumed_from,
+                    "sha256": downloaded.sha256
+                })),
+                Err(e) => {
+                    return CliResponse::error(
+                        "download-file",
+                        format!("Failed to download file {file_id}: {e}"),
+                    );
+                }
+            }
+        }
+
+        CliResponse::success(
+            "download-file",
+            json!({
+                "count": files.len(),
+                "files": files
+            }),
+        )
+    }
+}
+
+/// File ids of the attachments of a message from the storage
+async fn stored_attachments(
+    chat_id: &str,
+    message_id: &str,
+) -> std::result::Result<Vec<String>, String> {
+    let storage = storage_manager().await?;
+    match storage.get_message(chat_id, message_id).await {
+        Ok(Some(message)) => Ok(message.attachment_file_ids()),
+        Ok(None) => Err(format!(
+            "Message {message_id} in chat {chat_id} not found in storage"
+        )),
+        Err(e) => Err(format!("Failed to get stored message: {e}")),
+    }
 }
 
 #[async_trait]
@@ -368,6 +456,7 @@ impl Command for FileCommands {
             FileCommands::UploadText(_) => "upload-text",
             FileCommands::UploadJson(_) => "upload-json",
             FileCommands::Info(_) => "file-info",
+            FileCommands::Download(_) => "download-file",
         }
     }
 
@@ -409,6 +498,28 @@ impl Command for FileCommands {
                     return Err(CliError::InputError("File ID cannot be empty".to_string()));
                 }
             }
+            FileCommands::Download(args) => {
+                if args.file_ids.is_empty() && args.message_id.is_none() {
+                    return Err(CliError::InputError(
+                        "Specify --file-id or --chat-id with --message-id".to_string(),
+                    ));
+                }
+                if args.file_ids.iter().any(String::is_empty) {
+                    return Err(CliError::InputError("File ID cannot be empty".to_string()));
+                }
+                if let Some(sha256) = &args.sha256 {
+                    if args.file_ids.len() != 1 || args.message_id.is_some() {
+                        return Err(CliError::InputError(
+                            "SHA-256 hash can only be checked for a single file".to_string(),
+                        ));
+                    }
+                    if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+                        return Err(CliError::InputError(
+                            "SHA-256 hash must be 64 hex characters".to_string(),
+                        ));
+                    }
+                }
+            }
         }
         Ok(())
     }
@@ -633,6 +744,57 @@ mod tests {
         assert!(cmd.validate().is_err());
     }
 
+    #[test]
+    fn test_download_validation() {
+        let args = |file_ids: &[&str], message_id: Option<&str>, sha256: Option<&str>| {
+            FileCommands::Download(DownloadFileArgs {
+                file_ids: file_ids.iter().map(|id| id.to_string()).collect(),
+                chat_id: message_id.map(|_| "chat".to_string()),
+                message_id: message_id.map(str::to_string),
+                dir: None,
+                sha256: sha256.map(str::to_string),
+            })
+        };
+        let sha256 = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        assert!(args(&["file1"], None, None).validate().is_ok());
+        assert!(args(&["file1", "file2"], None, None).validate().is_ok());
+        assert!(args(&[], Some("msg"), None).validate().is_ok());
+        assert!(args(&["file1"], None, Some(sha256)).validate().is_ok());
+
+        assert!(args(&[], None, None).validate().is_err());
+        assert!(args(&[""], None, None).validate().is_err());
+        assert!(
+            args(&["file1", "file2"], None, Some(sha256))
+                .validate()
+                .is_err()
+        );
+        assert!(
+            args(&["file1"], Some("msg"), Some(sha256))
+                .validate()
+                .is_err()
+        );
+        assert!(args(&["file1"], None, Some("abc")).validate().is_err());
+        assert_eq!(args(&["file1"], None, None).name(), "download-file");
+    }
+
+    #[tokio::test]
+    async fn test_download_no_files() {
+        let bot = crate::utils::create_dummy_bot();
+        let cmd = FileCommands::Info(FileInfoArgs {
+            file_id: "unused".to_string(),
+        });
+        let args = DownloadFileArgs {
+            file_ids: vec![],
+            chat_id: None,
+            message_id: None,
+            dir: None,
+            sha256: None,
+        };
+        let response = cmd.handle_download(&bot, &args).await;
+        assert!(!response.success);
+    }
+
     #[test]
     fn test_json_filename_with_extension() {
         // Test filename that already has .json extension
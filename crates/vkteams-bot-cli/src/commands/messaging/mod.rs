@@ -9,8 +9,8 @@ use crate::file_utils;
 use crate::output::{CliResponse, OutputFormatter};
 use crate::utils::output::print_success_result;
 use crate::utils::{
-    validate_chat_id, validate_file_path, validate_message_id, validate_message_text,
-    validate_voice_file_path,
+    validate_chat_id, validate_file_path, validate_long_message_text, validate_message_id,
+    validate_message_text, validate_thread_id, validate_voice_file_path,
 };
 
 use async_trait::async_trait;
@@ -29,6 +29,15 @@ pub enum MessagingCommands {
         chat_id: String,
         #[arg(short = 'm', long, required = true, value_name = "MESSAGE")]
         message: String,
+        /// Convert message from Markdown to HTML
+        #[arg(long)]
+        markdown: bool,
+        /// Send the other parts of a long message as replies to the first one
+        #[arg(long)]
+        reply_chain: bool,
+        /// Send the message to the thread
+        #[arg(long, value_name = "THREAD_ID")]
+        thread_id: Option<String>,
     },
     /// Send file to user or chat
     SendFile {
@@ -80,8 +89,22 @@ pub enum MessagingCommands {
 impl Command for MessagingCommands {
     async fn execute(&self, bot: &Bot) -> CliResult<()> {
         match self {
-            MessagingCommands::SendText { chat_id, message } => {
-                execute_send_text(bot, chat_id, message).await
+            MessagingCommands::SendText {
+                chat_id,
+                message,
+                markdown,
+                reply_chain,
+                thread_id,
+            } => {
+                execute_send_text(
+                    bot,
+                    chat_id,
+                    message,
+                    *markdown,
+                    *reply_chain,
+                    thread_id.as_deref(),
+                )
+                .await
             }
             MessagingCommands::SendFile { chat_id, file_path } => {
                 execute_send_file(bot, chat_id, file_path).await
@@ -112,8 +135,22 @@ impl Command for MessagingCommands {
     /// New method for structured output support
     async fn execute_with_output(&self, bot: &Bot, output_format: &OutputFormat) -> CliResult<()> {
         let response = match self {
-            MessagingCommands::SendText { chat_id, message } => {
-                execute_send_text_structured(bot, chat_id, message).await
+            MessagingCommands::SendText {
+                chat_id,
+                message,
+                markdown,
+                reply_chain,
+                thread_id,
+            } => {
+                execute_send_text_structured(
+                    bot,
+                    chat_id,
+                    message,
+                    *markdown,
+                    *reply_chain,
+                    thread_id.as_deref(),
+                )
+                .await
             }
             MessagingCommands::SendFile { chat_id, file_path } => {
                 execute_send_file_structured(bot, chat_id, file_path).await
@@ -163,9 +200,17 @@ impl Command for MessagingCommands {
 
     fn validate(&self) -> CliResult<()> {
         match self {
-            MessagingCommands::SendText { chat_id, message } => {
+            MessagingCommands::SendText {
+                chat_id,
+                message,
+                thread_id,
+                ..
+            } => {
                 validate_chat_id(chat_id)?;
-                validate_message_text(message)?;
+                validate_long_message_text(message)?;
+                if let Some(thread_id) = thread_id {
+                    validate_thread_id(thread_id)?;
+                }
             }
             MessagingCommands::SendFile { chat_id, file_path } => {
                 validate_chat_id(chat_id)?;
@@ -206,30 +251,69 @@ impl Command for MessagingCommands {
 
 // Command execution functions
 
+/// Text parser for the message, Markdown is converted to HTML
+fn message_parser(message: &str, markdown: bool) -> MessageTextParser {
+    if markdown {
+        MessageTextParser::from_markdown(message)
+    } else {
+        MessageTextParser::new().add(MessageTextFormat::Plain(message.to_string()))
+    }
+}
+
+/// Text message request, sent to the thread if `thread_id` is set
+fn send_text_request(
+    chat_id: &str,
+    message: &str,
+    markdown: bool,
+    thread_id: Option<&str>,
+) -> vkteams_bot::error::Result<RequestMessagesSendText> {
+    let mut request = RequestMessagesSendText::new(ChatId::from_borrowed_str(chat_id))
+        .set_text(message_parser(message, markdown))?;
+    if let Some(thread_id) = thread_id {
+        request = request.with_thread_id(ThreadId(thread_id.to_string()));
+    }
+    Ok(request)
+}
+
+/// Long messages are split into several messages
+fn split_options(reply_chain: bool) -> SplitOptions {
+    SplitOptions {
+        reply_chain,
+        ..Default::default()
+    }
+}
+
 // Structured output versions
 async fn execute_send_text_structured(
     bot: &Bot,
     chat_id: &str,
     message: &str,
+    markdown: bool,
+    reply_chain: bool,
+    thread_id: Option<&str>,
 ) -> CliResponse<serde_json::Value> {
     debug!("Sending text message to {}", chat_id);
 
-    let parser = MessageTextParser::new().add(MessageTextFormat::Plain(message.to_string()));
-    let request =
-        match RequestMessagesSendText::new(ChatId::from_borrowed_str(chat_id)).set_text(parser) {
-            Ok(req) => req,
-            Err(e) => {
-                return CliResponse::error("send-text", format!("Failed to create message: {e}"));
-            }
-        };
+    let request = match send_text_request(chat_id, message, markdown, thread_id) {
+        Ok(req) => req,
+        Err(e) => {
+            return CliResponse::error("send-text", format!("Failed to create message: {e}"));
+        }
+    };
 
-    match bot.send_api_request(request).await {
+    match bot
+        .send_text_split(request, split_options(reply_chain))
+        .await
+    {
         Ok(result) => {
             info!("Successfully sent text message to {}", chat_id);
+            let message_ids: Vec<&MsgId> = result.iter().map(|r| &r.msg_id).collect();
             let data = json!({
                 "chat_id": chat_id,
                 "message": message,
-                "message_id": result.msg_id
+                "message_id": message_ids.first(),
+                "message_ids": message_ids,
+                "thread_id": thread_id
             });
             CliResponse::success("send-text", data)
         }
@@ -399,16 +483,21 @@ async fn execute_unpin_message_structured(
 }
 
 // Legacy output versions (for backward compatibility)
-async fn execute_send_text(bot: &Bot, chat_id: &str, message: &str) -> CliResult<()> {
+async fn execute_send_text(
+    bot: &Bot,
+    chat_id: &str,
+    message: &str,
+    markdown: bool,
+    reply_chain: bool,
+    thread_id: Option<&str>,
+) -> CliResult<()> {
     debug!("Sending text message to {}", chat_id);
 
-    let parser = MessageTextParser::new().add(MessageTextFormat::Plain(message.to_string()));
-    let request = RequestMessagesSendText::new(ChatId::from_borrowed_str(chat_id))
-        .set_text(parser)
+    let request = send_text_request(chat_id, message, markdown, thread_id)
         .map_err(|e| CliError::InputError(format!("Failed to create message: {e}")))?;
 
     let result = bot
-        .send_api_request(request)
+        .send_text_split(request, split_options(reply_chain))
         .await
         .map_err(CliError::ApiError)?;
 
@@ -543,6 +632,9 @@ mod tests {
         let cmd = MessagingCommands::SendText {
             chat_id: "user123".to_string(),
             message: "Hello".to_string(),
+            markdown: false,
+            reply_chain: false,
+            thread_id: None,
         };
         assert!(cmd.validate().is_ok());
     }
@@ -552,6 +644,9 @@ mod tests {
         let cmd = MessagingCommands::SendText {
             chat_id: "user with spaces".to_string(),
             message: "Hello".to_string(),
+            markdown: false,
+            reply_chain: false,
+            thread_id: None,
         };
         assert!(cmd.validate().is_err());
     }
@@ -561,6 +656,9 @@ mod tests {
         let cmd = MessagingCommands::SendText {
             chat_id: "user123".to_string(),
             message: "".to_string(),
+            markdown: false,
+            reply_chain: false,
+            thread_id: None,
         };
         assert!(cmd.validate().is_err());
     }
@@ -621,6 +719,15 @@ mod tests {
         assert!(cmd.validate().is_err());
     }
 
+    #[test]
+    fn test_message_parser_markdown() {
+        let (text, mode) = message_parser("**a** <b>", true).parse().unwrap();
+        assert_eq!(text, "<b>a</b> &lt;b&gt;");
+        assert_eq!(mode, ParseMode::HTML);
+        let (text, _) = message_parser("**a**", false).parse().unwrap();
+        assert_eq!(text, "**a**");
+    }
+
     fn dummy_bot() -> Bot {
         Bot::with_params(&APIVersionUrl::V1, "dummy_token", "https://dummy.api.com").unwrap()
     }
@@ -630,6 +737,9 @@ mod tests {
         let cmd = MessagingCommands::SendText {
             chat_id: "12345@chat".to_string(),
             message: "hello".to_string(),
+            markdown: false,
+            reply_chain: false,
+            thread_id: None,
         };
         let bot = dummy_bot();
         let rt = Runtime::new().unwrap();
@@ -15,6 +15,7 @@ pub mod files;
 pub mod messaging;
 pub mod scheduling;
 pub mod storage;
+pub mod threads;
 
 /// Trait that all CLI commands must implement
 #[async_trait]
@@ -168,6 +169,10 @@ pub enum Commands {
     #[command(flatten)]
     Chat(chat::ChatCommands),
 
+    // Thread commands
+    #[command(flatten)]
+    Threads(threads::ThreadCommands),
+
     // Scheduling commands
     #[command(flatten)]
     Scheduling(scheduling::SchedulingCommands),
@@ -199,6 +204,7 @@ impl Command for Commands {
         match self {
             Commands::Messaging(cmd) => cmd.execute(bot).await,
             Commands::Chat(cmd) => cmd.execute(bot).await,
+            Commands::Threads(cmd) => cmd.execute(bot).await,
             Commands::Scheduling(cmd) => cmd.execute(bot).await,
             Commands::Config(cmd) => cmd.execute(bot).await,
             Commands::Diagnostic(cmd) => cmd.execute(bot).await,
@@ -212,6 +218,7 @@ impl Command for Commands {
         match self {
             Commands::Messaging(cmd) => cmd.name(),
             Commands::Chat(cmd) => cmd.name(),
+            Commands::Threads(cmd) => cmd.name(),
             Commands::Scheduling(cmd) => cmd.name(),
             Commands::Config(cmd) => Command::name(cmd),
             Commands::Diagnostic(cmd) => cmd.name(),
@@ -225,6 +232,7 @@ impl Command for Commands {
         match self {
             Commands::Messaging(cmd) => cmd.validate(),
             Commands::Chat(cmd) => cmd.validate(),
+            Commands::Threads(cmd) => cmd.validate(),
             Commands::Scheduling(cmd) => cmd.validate(),
             Commands::Config(cmd) => Command::validate(cmd),
             Commands::Diagnostic(cmd) => cmd.validate(),
@@ -116,6 +116,58 @@ pub enum ContextType {
     UserProfile,
 }
 
+/// Open the storage manager with the CLI storage configuration
+pub async fn storage_manager() -> std::result::Result<StorageManager, String> {
+    // Try to load storage configuration
+    let config = match storage_config().await {
+        Ok(config) => config,
+        Err(e) => return Err(format!("Failed to load storage configuration: {e}")),
+    };
+
+    match StorageManager::new(&config).await {
+        Ok(storage) => Ok(storage),
+        Err(e) => Err(format!("Failed to initialize storage manager: {e}")),
+    }
+}
+
+/// Load the storage configuration from the main configuration or the environment
+pub async fn storage_config() -> std::result::Result<StorageConfig, String> {
+    // Try to load from main library configuration first
+    #[cfg(feature = "storage")]
+    {
+        use vkteams_bot::config::get_config;
+
+        // Try to load from configuration file
+        if let Ok(main_config) = get_config() {
+            return Ok(main_config.get_storage_config());
+        }
+    }
+
+    // Fallback to environment-based configuration if main config fails
+    let database_url = std::env::var("DATABASE_URL")
+        .or_else(|_| std::env::var("VKTEAMS_BOT_DATABASE_URL"))
+        .unwrap_or_else(|_| "postgresql://localhost/vkteams_bot".to_string());
+
+    let config = StorageConfig {
+        database: DatabaseConfig {
+            url: SecretString::url(database_url),
+            max_connections: 20,
+            connection_timeout: 30,
+            auto_migrate: true,
+            ssl: Default::default(),
+        },
+        settings: StorageSettings {
+            event_retention_days: 365,
+            cleanup_interval_hours: 24,
+            batch_size: 100,
+            max_memory_events: 10000,
+        },
+        ..Default::default()
+    };
+
+    Ok(config)
+}
+
 impl StorageCommands {
     pub async fn execute_with_output(
         &self,
@@ -140,53 +192,11 @@ impl StorageCommands {
     }
 
     pub async fn get_storage_manager(&self) -> std::result::Result<StorageManager, String> {
-        // Try to load storage configuration
-        let config = match self.load_storage_config().await {
-            Ok(config) => config,
-            Err(e) => return Err(format!("Failed to load storage configuration: {e}")),
-        };
-
-        match StorageManager::new(&config).await {
-            Ok(storage) => Ok(storage),
-            Err(e) => Err(format!("Failed to initialize storage manager: {e}")),
-        }
+        storage_manager().await
     }
 
     pub async fn load_storage_config(&self) -> std::result::Result<StorageConfig, String> {
-        // Try to load from main library configuration first
-        #[cfg(feature = "storage")]
-        {
-            use vkteams_bot::config::get_config;
-
-            // Try to load from configuration file
-            if let Ok(main_config) = get_config() {
-                return Ok(main_config.get_storage_config());
-            }
-        }
-
-        // Fallback to environment-based configuration if main config fails
-        let database_url = std::env::var("DATABASE_URL")
-            .or_else(|_| std::env::var("VKTEAMS_BOT_DATABASE_URL"))
-            .unwrap_or_else(|_| "postgresql://localhost/vkteams_bot".to_string());
-
-        let config = StorageConfig {
-            database: DatabaseConfig {
-                url: database_url,
-                max_connections: 20,
-                connection_timeout: 30,
-                auto_migrate: true,
-                ssl: Default::default(),
-            },
-            settings: StorageSettings {
-                event_retention_days: 365,
-                cleanup_interval_hours: 24,
-                batch_size: 100,
-                max_memory_events: 10000,
-            },
-            ..Default::default()
-        };
-
-        Ok(config)
+        storage_config().await
     }
 
     pub async fn handle_database(&self, action: &DatabaseAction) -> CliResponse<serde_json::Value> {
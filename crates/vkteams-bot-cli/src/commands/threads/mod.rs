@@ -0,0 +1,421 @@
+//! Thread commands module
+//!
+//! This module contains commands for creating threads on messages and managing thread subscriptions.
+
+use crate::commands::{Command, OutputFormat};
+use crate::errors::prelude::{CliError, Result as CliResult};
+use crate::output::{CliResponse, OutputFormatter};
+use crate::utils::output::print_success_result;
+use crate::utils::{validate_chat_id, validate_message_id, validate_thread_id};
+use async_trait::async_trait;
+use clap::{Subcommand, ValueHint};
+use serde_json::json;
+use tracing::{debug, info};
+use vkteams_bot::prelude::*;
+
+/// All thread commands
+#[derive(Subcommand, Debug, Clone)]
+pub enum ThreadCommands {
+    /// Create a thread on the message
+    CreateThread {
+        #[arg(short = 'c', long, required = true, value_name = "CHAT_ID", value_hint = ValueHint::Username)]
+        chat_id: String,
+        #[arg(short = 'm', long, required = true, value_name = "MESSAGE_ID")]
+        message_id: String,
+    },
+    /// Get thread subscribers
+    GetThreadSubscribers {
+        #[arg(short = 't', long, required = true, value_name = "THREAD_ID")]
+        thread_id: String,
+        #[arg(long, value_name = "CURSOR")]
+        cursor: Option<String>,
+        #[arg(long, value_name = "PAGE_SIZE")]
+        page_size: Option<u32>,
+    },
+    /// Enable or disable auto-subscribe to the chat threads
+    SetThreadAutosubscribe {
+        #[arg(short = 'c', long, required = true, value_name = "CHAT_ID", value_hint = ValueHint::Username)]
+        chat_id: String,
+        /// Enable auto-subscribe, disable if not set
+        #[arg(long)]
+        enable: bool,
+        /// Also subscribe to the existing threads
+        #[arg(long)]
+        with_existing: bool,
+    },
+}
+
+#[async_trait]
+impl Command for ThreadCommands {
+    async fn execute(&self, bot: &Bot) -> CliResult<()> {
+        match self {
+            ThreadCommands::CreateThread {
+                chat_id,
+                message_id,
+            } => execute_create_thread(bot, chat_id, message_id).await,
+            ThreadCommands::GetThreadSubscribers {
+                thread_id,
+                cursor,
+                page_size,
+            } => {
+                execute_get_thread_subscribers(bot, thread_id, cursor.as_deref(), *page_size).await
+            }
+            ThreadCommands::SetThreadAutosubscribe {
+                chat_id,
+                enable,
+                with_existing,
+            } => execute_set_thread_autosubscribe(bot, chat_id, *enable, *with_existing).await,
+        }
+    }
+
+    /// New method for structured output support
+    async fn execute_with_output(&self, bot: &Bot, output_format: &OutputFormat) -> CliResult<()> {
+        let response = match self {
+            ThreadCommands::CreateThread {
+                chat_id,
+                message_id,
+            } => execute_create_thread_structured(bot, chat_id, message_id).await,
+            ThreadCommands::GetThreadSubscribers {
+                thread_id,
+                cursor,
+                page_size,
+            } => {
+                execute_get_thread_subscribers_structured(
+                    bot,
+                    thread_id,
+                    cursor.as_deref(),
+                    *page_size,
+                )
+                .await
+            }
+            ThreadCommands::SetThreadAutosubscribe {
+                chat_id,
+                enable,
+                with_existing,
+            } => {
+                execute_set_thread_autosubscribe_structured(bot, chat_id, *enable, *with_existing)
+                    .await
+            }
+        };
+
+        OutputFormatter::print(&response, output_format)?;
+
+        if !response.success {
+            return Err(CliError::UnexpectedError("Command failed".to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ThreadCommands::CreateThread { .. } => "create-thread",
+            ThreadCommands::GetThreadSubscribers { .. } => "get-thread-subscribers",
+            ThreadCommands::SetThreadAutosubscribe { .. } => "set-thread-autosubscribe",
+        }
+    }
+
+    fn validate(&self) -> CliResult<()> {
+        match self {
+            ThreadCommands::CreateThread {
+                chat_id,
+                message_id,
+            } => {
+                validate_chat_id(chat_id)?;
+                validate_message_id(message_id)?;
+            }
+            ThreadCommands::GetThreadSubscribers {
+                thread_id,
+                cursor,
+                page_size,
+            } => {
+                validate_thread_id(thread_id)?;
+                if cursor.as_deref() == Some("") {
+                    return Err(CliError::InputError("Cursor cannot be empty".to_string()));
+                }
+                if *page_size == Some(0) {
+                    return Err(CliError::InputError(
+                        "Page size must be greater than 0".to_string(),
+                    ));
+                }
+            }
+            ThreadCommands::SetThreadAutosubscribe { chat_id, .. } => {
+                validate_chat_id(chat_id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn subscribers_request(
+    thread_id: &str,
+    cursor: Option<&str>,
+    page_size: Option<u32>,
+) -> RequestThreadsSubscribersGet {
+    let mut request = RequestThreadsSubscribersGet::new(ThreadId(thread_id.to_string()));
+    if let Some(cursor) = cursor {
+        request = request.with_cursor(cursor.to_string());
+    }
+    if let Some(page_size) = page_size {
+        request = request.with_page_size(page_size);
+    }
+    request
+}
+
+fn autosubscribe_request(
+    chat_id: &str,
+    enable: bool,
+    with_existing: bool,
+) -> RequestThreadsAutosubscribe {
+    let request = RequestThreadsAutosubscribe::new((ChatId::from_borrowed_str(chat_id), enable));
+    if with_existing {
+        request.with_with_existing(true)
+    } else {
+        request
+    }
+}
+
+// Structured output versions
+async fn execute_create_thread_structured(
+    bot: &Bot,
+    chat_id: &str,
+    message_id: &str,
+) -> CliResponse<serde_json::Value> {
+    debug!(
+        "Creating thread on message {} in chat {}",
+        message_id, chat_id
+    );
+
+    let request = RequestThreadsAdd::new((
+        ChatId::from_borrowed_str(chat_id),
+        MsgId(message_id.to_string()),
+    ));
+    match bot.send_api_request(request).await {
+        Ok(result) => {
+            info!("Successfully created thread {}", result.thread_id.0);
+            let data = json!({
+                "chat_id": chat_id,
+                "message_id": message_id,
+                "thread_id": result.thread_id
+            });
+            CliResponse::success("create-thread", data)
+        }
+        Err(e) => CliResponse::error("create-thread", format!("Failed to create thread: {e}")),
+    }
+}
+
+async fn execute_get_thread_subscribers_structured(
+    bot: &Bot,
+    thread_id: &str,
+    cursor: Option<&str>,
+    page_size: Option<u32>,
+) -> CliResponse<serde_json::Value> {
+    debug!("Getting subscribers of thread {}", thread_id);
+
+    let request = subscribers_request(thread_id, cursor, page_size);
+    match bot.send_api_request(request).await {
+        Ok(result) => {
+            info!("Successfully retrieved subscribers of thread {}", thread_id);
+            let data = json!({
+                "thread_id": thread_id,
+                "subscribers": result.subscribers,
+                "cursor": result.cursor
+            });
+            CliResponse::success("get-thread-subscribers", data)
+        }
+        Err(e) => CliResponse::error(
+            "get-thread-subscribers",
+            format!("Failed to get thread subscribers: {e}"),
+        ),
+    }
+}
+
+async fn execute_set_thread_autosubscribe_structured(
+    bot: &Bot,
+    chat_id: &str,
+    enable: bool,
+    with_existing: bool,
+) -> CliResponse<serde_json::Value> {
+    debug!("Setting thread auto-subscribe in {} to {}", chat_id, enable);
+
+    let request = autosubscribe_request(chat_id, enable, with_existing);
+    match bot.send_api_request(request).await {
+        Ok(_result) => {
+            info!("Successfully set thread auto-subscribe in {}", chat_id);
+            let data = json!({
+                "chat_id": chat_id,
+                "enable": enable,
+                "with_existing": with_existing
+            });
+            CliResponse::success("set-thread-autosubscribe", data)
+        }
+        Err(e) => CliResponse::error(
+            "set-thread-autosubscribe",
+            format!("Failed to set thread auto-subscribe: {e}"),
+        ),
+    }
+}
+
+// Legacy output versions (for backward compatibility)
+async fn execute_create_thread(bot: &Bot, chat_id: &str, message_id: &str) -> CliResult<()> {
+    debug!(
+        "Creating thread on message {} in chat {}",
+        message_id, chat_id
+    );
+
+    let request = RequestThreadsAdd::new((
+        ChatId::from_borrowed_str(chat_id),
+        MsgId(message_id.to_string()),
+    ));
+    let result = bot
+        .send_api_request(request)
+        .await
+        .map_err(CliError::ApiError)?;
+
+    info!("Successfully created thread {}", result.thread_id.0);
+    print_success_result(&result, &OutputFormat::Pretty)?;
+    Ok(())
+}
+
+async fn execute_get_thread_subscribers(
+    bot: &Bot,
+    thread_id: &str,
+    cursor: Option<&str>,
+    page_size: Option<u32>,
+) -> CliResult<()> {
+    debug!("Getting subscribers of thread {}", thread_id);
+
+    let request = subscribers_request(thread_id, cursor, page_size);
+    let result = bot
+        .send_api_request(request)
+        .await
+        .map_err(CliError::ApiError)?;
+
+    info!("Successfully retrieved subscribers of thread {}", thread_id);
+    print_success_result(&result, &OutputFormat::Pretty)?;
+    Ok(())
+}
+
+async fn execute_set_thread_autosubscribe(
+    bot: &Bot,
+    chat_id: &str,
+    enable: bool,
+    with_existing: bool,
+) -> CliResult<()> {
+    debug!("Setting thread auto-subscribe in {} to {}", chat_id, enable);
+
+    let request = autosubscribe_request(chat_id, enable, with_existing);
+    let result = bot
+        .send_api_request(request)
+        .await
+        .map_err(CliError::ApiError)?;
+
+    info!("Successfully set thread auto-subscribe in {}", chat_id);
+    print_success_result(&result, &OutputFormat::Pretty)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::runtime::Runtime;
+
+    fn dummy_bot() -> Bot {
+        Bot::with_params(&APIVersionUrl::V1, "dummy_token", "https://dummy.api.com").unwrap()
+    }
+
+    #[test]
+    fn test_validate_create_thread_invalid_message_id() {
+        let cmd = ThreadCommands::CreateThread {
+            chat_id: "12345@chat".to_string(),
+            message_id: "".to_string(),
+        };
+        assert!(cmd.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_get_thread_subscribers() {
+        let cmd = ThreadCommands::GetThreadSubscribers {
+            thread_id: "12345@chat.agent".to_string(),
+            cursor: Some("next".to_string()),
+            page_size: Some(20),
+        };
+        assert!(cmd.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_get_thread_subscribers_zero_page_size() {
+        let cmd = ThreadCommands::GetThreadSubscribers {
+            thread_id: "12345@chat.agent".to_string(),
+            cursor: None,
+            page_size: Some(0),
+        };
+        assert!(cmd.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_set_thread_autosubscribe_invalid_chat_id() {
+        let cmd = ThreadCommands::SetThreadAutosubscribe {
+            chat_id: "chat with spaces".to_string(),
+            enable: true,
+            with_existing: false,
+        };
+        assert!(cmd.validate().is_err());
+    }
+
+    #[test]
+    fn test_subscribers_request() {
+        let request = subscribers_request("t1", Some("c1"), Some(10));
+        assert_eq!(request.thread_id.0, "t1");
+        assert_eq!(request.cursor.as_deref(), Some("c1"));
+        assert_eq!(request.page_size, Some(10));
+    }
+
+    #[test]
+    fn test_autosubscribe_request() {
+        let request = autosubscribe_request("c1", true, false);
+        assert!(request.enable);
+        assert_eq!(request.with_existing, None);
+        let request = autosubscribe_request("c1", false, true);
+        assert!(!request.enable);
+        assert_eq!(request.with_existing, Some(true));
+    }
+
+    #[test]
+    fn test_execute_create_thread_api_error() {
+        let cmd = ThreadCommands::CreateThread {
+            chat_id: "12345@chat".to_string(),
+            message_id: "m1".to_string(),
+        };
+        let bot = dummy_bot();
+        let rt = Runtime::new().unwrap();
+        let res = rt.block_on(cmd.execute(&bot));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_execute_get_thread_subscribers_api_error() {
+        let cmd = ThreadCommands::GetThreadSubscribers {
+            thread_id: "t1".to_string(),
+            cursor: None,
+            page_size: None,
+        };
+        let bot = dummy_bot();
+        let rt = Runtime::new().unwrap();
+        let res = rt.block_on(cmd.execute(&bot));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_execute_set_thread_autosubscribe_api_error() {
+        let cmd = ThreadCommands::SetThreadAutosubscribe {
+            chat_id: "12345@chat".to_string(),
+            enable: true,
+            with_existing: true,
+        };
+        let bot = dummy_bot();
+        let rt = Runtime::new().unwrap();
+        let res = rt.block_on(cmd.execute(&bot));
+        assert!(res.is_err());
+    }
+}
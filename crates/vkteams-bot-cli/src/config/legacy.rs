@@ -3,6 +3,7 @@ use dashmap::DashMap;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -10,6 +11,7 @@ use std::time::{Duration, SystemTime};
 use tokio::sync::RwLock;
 use tokio::time::Instant;
 use toml;
+use vkteams_bot::secret::{REDACTED, redact, register_secret};
 
 // Use constants from the constants module
 use crate::constants::config::{CONFIG_FILE_NAME, DEFAULT_CONFIG_DIR, ENV_PREFIX};
@@ -44,7 +46,7 @@ pub struct Config {
 }
 
 /// API Configuration options
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ApiConfig {
     /// API token for VK Teams Bot
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -63,6 +65,17 @@ pub struct ApiConfig {
     pub max_retries: u32,
 }
 
+impl fmt::Debug for ApiConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiConfig")
+            .field("token", &self.token.as_ref().map(|_| REDACTED))
+            .field("url", &self.url)
+            .field("timeout", &self.timeout)
+            .field("max_retries", &self.max_retries)
+            .finish()
+    }
+}
+
 impl Default for ApiConfig {
     fn default() -> Self {
         Self {
@@ -158,7 +171,7 @@ impl Default for UiConfig {
 }
 
 /// Proxy configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ProxyConfig {
     /// Proxy URL
     #[serde(default)]
@@ -173,6 +186,16 @@ pub struct ProxyConfig {
     pub password: Option<String>,
 }
 
+impl fmt::Debug for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyConfig")
+            .field("url", &redact(&self.url))
+            .field("user", &self.user)
+            .field("password", &self.password.as_ref().map(|_| REDACTED))
+            .finish()
+    }
+}
+
 impl Default for ProxyConfig {
     fn default() -> Self {
         Self {
@@ -313,6 +336,32 @@ impl Config {
         manager.load_config().await
     }
 
+    /// Register the API token and proxy password to be masked in logs and errors
+    pub fn register_secrets(&self) {
+        let secrets = [
+            self.api.token.as_deref(),
+            self.proxy.as_ref().and_then(|p| p.password.as_deref()),
+        ];
+        for secret in secrets.into_iter().flatten() {
+            register_secret(secret);
+        }
+    }
+
+    /// Copy of the configuration with the API token and proxy password masked, for display
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        if config.api.token.is_some() {
+            config.api.token = Some(REDACTED.to_string());
+        }
+        if let Some(proxy) = &mut config.proxy {
+            proxy.url = redact(&proxy.url).into_owned();
+            if proxy.password.is_some() {
+                proxy.password = Some(REDACTED.to_string());
+            }
+        }
+        config
+    }
+
     /// Save configuration to file
     ///
     /// # Errors
@@ -127,6 +127,8 @@ pub mod time {
 /// Scheduler constants
 pub mod scheduler {
     pub const CHECK_INTERVAL_SECONDS: u64 = 60;
+    /// Delay before a task failed with a temporary error is run again
+    pub const FAILED_TASK_RETRY_SECONDS: i64 = 60;
     pub const MAX_TASK_HISTORY: usize = 100;
     pub const DEFAULT_TASK_NAME: &str = "Unnamed Task";
 
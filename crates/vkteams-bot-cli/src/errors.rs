@@ -1,5 +1,5 @@
 use std::fmt;
-use vkteams_bot::error::BotError;
+use vkteams_bot::error::{ApiErrorKind, BotError};
 
 // Static error message prefixes to avoid repeated allocations
 pub static FILE_NOT_FOUND: &str = "File not found: ";
@@ -80,7 +80,7 @@ impl CliError {
     #[must_use]
     pub fn exit_code(&self) -> i32 {
         match self {
-            CliError::ApiError(_) => exitcode::UNAVAILABLE,
+            CliError::ApiError(err) => bot_error_exit_code(err),
             CliError::FileError(_) => exitcode::IOERR,
             CliError::InputError(_) => exitcode::USAGE,
             CliError::UnexpectedError(_) => exitcode::SOFTWARE,
@@ -92,6 +92,26 @@ impl CliError {
         }
     }
 
+    /// Same command may succeed later, see [`BotError::is_retryable`]
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            CliError::ApiError(err) => err.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Same command fails until the input or configuration is changed,
+    /// see [`BotError::is_permanent`]
+    #[must_use]
+    pub fn is_permanent(&self) -> bool {
+        match self {
+            CliError::ApiError(err) => err.is_permanent(),
+            CliError::InputError(_) | CliError::Config(_) => true,
+            _ => false,
+        }
+    }
+
     // TODO: Enable this method when we need direct error exit functionality
     // /// Prints the error message and exits with appropriate code
     // pub fn exit_with_error(self) -> ! {
@@ -108,6 +128,25 @@ impl CliError {
     // }
 }
 
+/// Exit code of the bot error by its classification:
+/// - `NOPERM` - invalid token or missing rights in the chat
+/// - `TEMPFAIL` - the command may succeed later
+/// - `DATAERR` - the request is rejected, e.g. unknown chat or message
+/// - `UNAVAILABLE` - other errors
+fn bot_error_exit_code(err: &BotError) -> i32 {
+    match err.api_error_kind() {
+        Some(
+            ApiErrorKind::InvalidToken
+            | ApiErrorKind::NotChatMember
+            | ApiErrorKind::PermissionDenied,
+        ) => exitcode::NOPERM,
+        _ if matches!(err, BotError::Config(_)) => exitcode::CONFIG,
+        _ if err.is_retryable() => exitcode::TEMPFAIL,
+        _ if err.is_permanent() => exitcode::DATAERR,
+        _ => exitcode::UNAVAILABLE,
+    }
+}
+
 /// A module to re-export all error types and constants
 pub mod prelude {
     pub use super::{
@@ -145,6 +184,36 @@ mod tests {
         assert_eq!(unexp_err.exit_code(), exitcode::SOFTWARE);
     }
 
+    #[test]
+    fn test_bot_error_exit_codes() {
+        let api_err = |description: &str| {
+            CliError::ApiError(BotError::Api(vkteams_bot::error::ApiError {
+                description: description.to_string(),
+            }))
+        };
+        assert_eq!(api_err("Invalid token").exit_code(), exitcode::NOPERM);
+        assert_eq!(
+            api_err("Bot is not a member of the chat").exit_code(),
+            exitcode::NOPERM
+        );
+        assert_eq!(api_err("Chat not found").exit_code(), exitcode::DATAERR);
+        assert_eq!(api_err("Internal error").exit_code(), exitcode::TEMPFAIL);
+        assert_eq!(
+            CliError::ApiError(BotError::RateLimited(None)).exit_code(),
+            exitcode::TEMPFAIL
+        );
+        assert_eq!(
+            CliError::ApiError(BotError::Config("no token".to_string())).exit_code(),
+            exitcode::CONFIG
+        );
+
+        assert!(api_err("Internal error").is_retryable());
+        assert!(api_err("Chat not found").is_permanent());
+        assert!(CliError::InputError("bad arg".to_string()).is_permanent());
+        assert!(!api_err("api fail").is_retryable());
+        assert!(!api_err("api fail").is_permanent());
+    }
+
     #[test]
     fn test_from_bot_error() {
         let bot_err = BotError::Api(vkteams_bot::error::ApiError {
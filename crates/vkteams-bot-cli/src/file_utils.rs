@@ -1,31 +1,16 @@
 use crate::config::CONFIG;
-use crate::errors::prelude::{CliError, Result as CliResult};
+use crate::errors::prelude::{CliError, DOWNLOAD_ERROR, Result as CliResult};
 use crate::progress;
 use crate::utils::{validate_directory_path, validate_file_path};
-use futures::StreamExt;
+use indicatif::ProgressBar;
 use std::fmt::Debug;
 use std::path::PathBuf;
-use tokio::io::AsyncWriteExt;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tracing::{debug, info};
 use vkteams_bot::prelude::*;
 
 // Validation functions are now imported from utils/validation module
 
-// TODO: Enable this function when we need streaming file uploads
-// /// Streams a file from disk for uploading
-// ///
-// /// # Errors
-// /// - Returns `CliError::FileError` if the file doesn't exist or cannot be opened
-// pub async fn read_file_stream(file_path: &str) -> CliResult<tokio::fs::File> {
-//     validate_file_path(file_path)?;
-//
-//     let file = tokio::fs::File::open(file_path)
-//         .await
-//         .map_err(|e| CliError::FileError(format!("Failed to open file {file_path}: {e}")))?;
-//
-//     Ok(file)
-// }
-
 /// Stream downloads a file and saves it to disk
 ///
 /// # Errors
@@ -36,6 +21,23 @@ pub async fn download_and_save_file(
     file_id: &str,
     dir_path: &str,
 ) -> CliResult<PathBuf> {
+    download_file(bot, file_id, dir_path, None)
+        .await
+        .map(|(file_path, _)| file_path)
+}
+
+/// Stream downloads a file into the directory, resuming a partial download left by
+/// an interrupted one, and checks the size reported by the API and the optional SHA-256 hash
+///
+/// # Errors
+/// - Returns `CliError::FileError` if there are issues with file operations or the checks fail
+/// - Returns `CliError::ApiError` if there are issues with the API
+pub async fn download_file(
+    bot: &Bot,
+    file_id: &str,
+    dir_path: &str,
+    sha256: Option<&str>,
+) -> CliResult<(PathBuf, DownloadedFile)> {
     let cfg = &CONFIG.files;
     // Use directory from path or config or current directory
     let target_dir = if !dir_path.is_empty() {
@@ -54,37 +56,7 @@ pub async fn download_and_save_file(
         .await
         .map_err(CliError::ApiError)?;
 
-    let mut file_path = PathBuf::from(&target_dir);
-    file_path.push(&file_info.file_name);
-
-    debug!("Creating file at path: {}", file_path.display());
-    let file = tokio::fs::File::create(&file_path).await.map_err(|e| {
-        CliError::FileError(format!(
-            "Failed to create file {}: {}",
-            file_path.display(),
-            e
-        ))
-    })?;
-
-    debug!("Starting file download stream");
-    let client = reqwest::Client::new();
-    let url = file_info.url.clone();
-
-    let response = client
-        .get(url)
-        .send()
-        .await
-        .map_err(|e| CliError::FileError(format!("Failed to initiate download: {e}")))?;
-
-    if !response.status().is_success() {
-        return Err(CliError::FileError(format!(
-            "Failed to download file, status code: {}",
-            response.status()
-        )));
-    }
-
-    let total_size = response.content_length().unwrap_or(0);
-
+    let total_size = u64::from(file_info.file_size);
     if total_size > cfg.max_file_size as u64 {
         return Err(CliError::FileError(format!(
             "File size exceeds maximum allowed size of {} bytes",
@@ -92,63 +64,58 @@ pub async fn download_and_save_file(
         )));
     }
 
-    let mut file_writer = tokio::io::BufWriter::with_capacity(cfg.buffer_size, file);
-
-    let mut stream = response.bytes_stream();
-    let mut downloaded: u64 = 0;
+    let file_name = if file_info.file_name.is_empty() {
+        file_id
+    } else {
+        &file_info.file_name
+    };
+    let mut file_path = PathBuf::from(&target_dir);
+    file_path.push(file_name);
 
     // Create a progress bar for the download
-    let progress_bar = progress::create_download_progress_bar(total_size, &file_info.file_name);
-
-    debug!("Streaming file content to disk");
-    while let Some(chunk_result) = stream.next().await {
-        let chunk = chunk_result.map_err(|e| {
-            progress::abandon_progress(&progress_bar, "Download failed");
-            CliError::FileError(format!("Error during download: {e}"))
-        })?;
-
-        file_writer.write_all(&chunk).await.map_err(|e| {
-            progress::abandon_progress(&progress_bar, "Write failed");
-            CliError::FileError(format!("Failed to write to file: {e}"))
-        })?;
+    let progress_bar = progress::create_download_progress_bar(total_size, file_name);
+    let mut options = match progress_bar.clone() {
+        Some(pb) => {
+            DownloadOptions::new().with_progress(move |downloaded| pb.set_position(downloaded))
+        }
+        // Log progress for large files if progress bar is disabled
+        None if total_size > 1024 * 1024 => {
+            let logged_mb = AtomicU64::new(0);
+            DownloadOptions::new().with_progress(move |downloaded| {
+                let downloaded_mb = downloaded / 1_048_576;
+                if logged_mb.swap(downloaded_mb, Ordering::Relaxed) != downloaded_mb {
+                    info!(
+                        "Download progress: {}MB / {}MB",
+                        downloaded_mb,
+                        total_size / 1_048_576
+                    );
+                }
+            })
+        }
+        None => DownloadOptions::new(),
+    };
+    if let Some(sha256) = sha256 {
+        options = options.with_sha256(sha256);
+    }
 
-        downloaded += chunk.len() as u64;
-        progress::increment_progress(&progress_bar, chunk.len() as u64);
-
-        // Log progress for large files (if progress bar is disabled)
-        if !&CONFIG.ui.show_progress
-            && total_size > 1024 * 1024
-            && downloaded % (1024 * 1024) < chunk.len() as u64
-        {
-            let downloaded_mb = {
-                #[allow(clippy::cast_precision_loss)]
-                let val = (downloaded / 1_048_576) as f64;
-                val
-            };
-            let total_mb = {
-                #[allow(clippy::cast_precision_loss)]
-                let val = (total_size / 1_048_576) as f64;
-                val
-            };
-            info!(
-                "Download progress: {:.1}MB / {:.1}MB",
-                downloaded_mb, total_mb
+    debug!("Streaming file content to {}", file_path.display());
+    match bot
+        .download_file_to_path(&file_info, &file_path, options)
+        .await
+    {
+        Ok(downloaded) => {
+            progress::finish_progress(
+                &progress_bar,
+                &format!("Downloaded to {}", file_path.display()),
             );
+            info!("Successfully downloaded file to: {}", file_path.display());
+            Ok((file_path, downloaded))
+        }
+        Err(e) => {
+            progress::abandon_progress(&progress_bar, "Download failed");
+            Err(CliError::FileError(format!("{DOWNLOAD_ERROR}{e}")))
         }
     }
-
-    debug!("Flushing and finalizing file");
-    file_writer.flush().await.map_err(|e| {
-        progress::abandon_progress(&progress_bar, "File flush failed");
-        CliError::FileError(format!("Failed to flush file data: {e}"))
-    })?;
-
-    progress::finish_progress(
-        &progress_bar,
-        &format!("Downloaded to {}", file_path.display()),
-    );
-    info!("Successfully downloaded file to: {}", file_path.display());
-    Ok(file_path)
 }
 
 /// Stream uploads a file to the API
@@ -191,11 +158,15 @@ pub async fn upload_file(
     let progress_bar = progress::create_upload_progress_bar(file_size, &source_path);
 
     // Start the upload
+    let upload = streamed_upload(&source_path, &progress_bar).await?;
     let result = match bot
-        .send_api_request(RequestMessagesSendFile::new((
-            ChatId::from_borrowed_str(user_id),
-            MultipartName::FilePath(source_path.to_string()),
-        )))
+        .send_api_request_with_upload(
+            RequestMessagesSendFile::new((
+                ChatId::from_borrowed_str(user_id),
+                MultipartName::FilePath(source_path.to_string()),
+            )),
+            upload,
+        )
         .await
     {
         Ok(res) => {
@@ -252,11 +223,15 @@ pub async fn upload_voice(
     let progress_bar = progress::create_upload_progress_bar(file_size, &source_path);
 
     // Start the voice upload
+    let upload = streamed_upload(&source_path, &progress_bar).await?;
     let result = match bot
-        .send_api_request(RequestMessagesSendVoice::new((
-            ChatId::from_borrowed_str(user_id),
-            MultipartName::FilePath(source_path.to_string()),
-        )))
+        .send_api_request_with_upload(
+            RequestMessagesSendVoice::new((
+                ChatId::from_borrowed_str(user_id),
+                MultipartName::FilePath(source_path.to_string()),
+            )),
+            upload,
+        )
         .await
     {
         Ok(res) => {
@@ -273,6 +248,26 @@ pub async fn upload_voice(
     Ok(result)
 }
 
+/// Streams the file from disk, reopening it on retries, and reports sent bytes to the progress bar
+///
+/// # Errors
+/// - Returns `CliError::FileError` if the file cannot be opened
+async fn streamed_upload(
+    source_path: &str,
+    progress_bar: &Option<ProgressBar>,
+) -> CliResult<RetryableMultipartForm> {
+    let upload = RetryableMultipartForm::from_file_path(source_path.to_string())
+        .await
+        .map_err(|e| {
+            progress::abandon_progress(progress_bar, "Upload failed");
+            CliError::FileError(format!("Failed to open file {source_path}: {e}"))
+        })?;
+    Ok(match progress_bar.clone() {
+        Some(pb) => upload.with_progress(move |sent| pb.set_position(sent)),
+        None => upload,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,6 +291,22 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[tokio::test]
+    async fn test_streamed_upload() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("report.txt");
+        fs::write(&file_path, "report").unwrap();
+        let upload = streamed_upload(file_path.to_str().unwrap(), &Some(ProgressBar::hidden()))
+            .await
+            .unwrap();
+        assert_eq!(upload.size(), 6);
+        assert_eq!(upload.filename, "report.txt");
+
+        let missing = temp_dir.path().join("missing.txt");
+        let res = streamed_upload(missing.to_str().unwrap(), &None).await;
+        assert!(matches!(res, Err(CliError::FileError(_))));
+    }
+
     #[tokio::test]
     async fn test_upload_voice_invalid_format() {
         let bot = create_dummy_bot();
@@ -91,6 +91,8 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
             exit(exit_codes::CONFIG);
         }
     };
+    // Mask the token in logs and error messages
+    config.register_secrets();
 
     // Handle config save if requested
     if let Some(path) = &cli.save_config {
@@ -187,6 +189,7 @@ async fn execute_command(
         Commands::Storage(cmd) => cmd.execute_with_output(&bot, output_format).await,
         Commands::Messaging(cmd) => cmd.execute_with_output(&bot, output_format).await,
         Commands::Chat(cmd) => cmd.execute_with_output(&bot, output_format).await,
+        Commands::Threads(cmd) => cmd.execute_with_output(&bot, output_format).await,
         Commands::Daemon(cmd) => cmd.execute_with_output(&bot, output_format).await,
         Commands::Diagnostic(cmd) => cmd.execute_with_output(&bot, output_format).await,
         Commands::Config(cmd) => cmd.execute_with_output(&bot, output_format).await,
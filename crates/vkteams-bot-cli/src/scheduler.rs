@@ -1,3 +1,4 @@
+use crate::constants::scheduler::FAILED_TASK_RETRY_SECONDS;
 use crate::errors::prelude::{CliError, Result as CliResult};
 use chrono::{DateTime, Duration, Utc};
 use cron::Schedule;
@@ -419,6 +420,7 @@ impl Scheduler {
 
             if let Err(e) = result {
                 error!("Task execution failed: {}", e);
+                self.update_task_after_failure(&task_id, &e).await?;
             }
         }
 
@@ -563,6 +565,38 @@ impl Scheduler {
         Ok(())
     }
 
+    /// Postpone the failed task by the delay requested by the server or the default one,
+    /// a task failed with a permanent error is disabled
+    async fn update_task_after_failure(&mut self, task_id: &str, err: &CliError) -> CliResult<()> {
+        {
+            let mut tasks = self.tasks.write().await;
+            let Some(task) = tasks.get_mut(task_id) else {
+                return Ok(());
+            };
+            if err.is_permanent() {
+                error!("Task {} disabled after permanent error: {}", task_id, err);
+                task.enabled = false;
+            } else {
+                let retry_after = match err {
+                    CliError::ApiError(e) => e.retry_after(),
+                    _ => None,
+                };
+                let delay = retry_after
+                    .and_then(|d| Duration::from_std(d).ok())
+                    .unwrap_or_else(|| Duration::seconds(FAILED_TASK_RETRY_SECONDS));
+                info!(
+                    "Task {} will be retried in {}s",
+                    task_id,
+                    delay.num_seconds()
+                );
+                task.next_run = Utc::now() + delay;
+            }
+        }
+
+        self.rebuild_queue().await;
+        self.save_tasks_async().await
+    }
+
     pub async fn extract_ready_tasks(&self) -> Vec<String> {
         let now = Utc::now();
         let mut ready_tasks = Vec::new();
@@ -1052,6 +1086,57 @@ mod tests {
         assert!(task.enabled);
     }
 
+    #[tokio::test]
+    async fn test_task_after_failure() {
+        let (mut scheduler, _temp_dir) = Scheduler::create_test_scheduler().await;
+        let mut add_task = async || {
+            scheduler
+                .add_task(
+                    TaskType::SendText {
+                        chat_id: "test_chat".to_string(),
+                        message: "test message".to_string(),
+                    },
+                    ScheduleType::Once(Utc::now()),
+                    None,
+                )
+                .await
+                .unwrap()
+        };
+        let rate_limited = add_task().await;
+        let failed = add_task().await;
+        let not_found = add_task().await;
+
+        let err = CliError::ApiError(BotError::RateLimited(Some(std::time::Duration::from_secs(
+            600,
+        ))));
+        scheduler
+            .update_task_after_failure(&rate_limited, &err)
+            .await
+            .unwrap();
+        let task = scheduler.get_task(&rate_limited).await.unwrap();
+        assert!(task.enabled);
+        assert!(task.next_run > Utc::now() + Duration::seconds(590));
+
+        let err = CliError::ApiError(BotError::Server(reqwest::StatusCode::BAD_GATEWAY));
+        scheduler
+            .update_task_after_failure(&failed, &err)
+            .await
+            .unwrap();
+        let task = scheduler.get_task(&failed).await.unwrap();
+        assert!(task.enabled);
+        assert!(task.next_run > Utc::now() + Duration::seconds(FAILED_TASK_RETRY_SECONDS - 10));
+
+        let err = CliError::ApiError(BotError::Api(ApiError {
+            description: "Chat not found".to_string(),
+        }));
+        scheduler
+            .update_task_after_failure(&not_found, &err)
+            .await
+            .unwrap();
+        assert!(!scheduler.get_task(&not_found).await.unwrap().enabled);
+        assert_eq!(scheduler.extract_ready_tasks().await, Vec::<String>::new());
+    }
+
     #[tokio::test]
     async fn test_cron_schedule() {
         let schedule = ScheduleType::Cron("0 0 0 * * *".to_string()); // Daily at midnight (sec min hour day month dayofweek)
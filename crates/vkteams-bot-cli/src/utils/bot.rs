@@ -6,6 +6,7 @@
 use crate::commands::Commands;
 use crate::config::Config;
 use crate::errors::prelude::{CliError, Result as CliResult};
+use vkteams_bot::config::types::NetworkConfig;
 use vkteams_bot::prelude::*;
 
 /// Create a bot instance from configuration
@@ -32,7 +33,38 @@ pub fn create_bot_instance(config: &Config) -> CliResult<Bot> {
     // Set environment variables for bot initialization
     setup_bot_environment(config);
 
-    Bot::with_params(&APIVersionUrl::V1, token.as_str(), url.as_str()).map_err(CliError::ApiError)
+    let network = network_config(config);
+    let mut builder = Bot::builder()
+        .token(token.as_str())
+        .api_url(url.as_str())
+        .network(network.clone());
+    if let Some(proxy) = &config.proxy {
+        let mut proxy_builder = reqwest::Proxy::all(proxy.url.as_str())
+            .map_err(|e| CliError::InputError(format!("Invalid proxy URL: {e}")))?;
+        if let Some(user) = &proxy.user {
+            proxy_builder =
+                proxy_builder.basic_auth(user, proxy.password.as_deref().unwrap_or_default());
+        }
+        let client = ConnectionPool::client_builder(&network)
+            .proxy(proxy_builder)
+            .build()
+            .map_err(|e| CliError::UnexpectedError(format!("Failed to build HTTP client: {e}")))?;
+        builder = builder.client(client);
+    }
+
+    builder.build().map_err(CliError::ApiError)
+}
+
+/// Network settings of the bot from the CLI API configuration
+///
+/// Request timeout and retries are taken from `api.timeout` and `api.max_retries`,
+/// other settings from the library defaults.
+pub fn network_config(config: &Config) -> NetworkConfig {
+    NetworkConfig {
+        retries: config.api.max_retries as usize,
+        request_timeout_secs: config.api.timeout,
+        ..Default::default()
+    }
 }
 
 /// Create a dummy bot instance for commands that don't need real API access
@@ -267,6 +299,25 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[test]
+    fn test_create_bot_instance_uses_api_settings() {
+        let mut config: crate::config::Config = toml::from_str("").unwrap();
+        config.api.token = Some("test_token_12345".to_string());
+        config.api.url = Some("https://example.com".to_string());
+        config.api.timeout = 5;
+        config.api.max_retries = 1;
+        let bot = create_bot_instance(&config).unwrap();
+        assert_eq!(bot.network_config().request_timeout_secs, 5);
+        assert_eq!(bot.network_config().retries, 1);
+
+        config.proxy = Some(crate::config::ProxyConfig {
+            url: "http://proxy.example.com:3128".to_string(),
+            user: Some("user".to_string()),
+            password: Some("pass".to_string()),
+        });
+        assert!(create_bot_instance(&config).is_ok());
+    }
+
     #[test]
     fn test_test_bot_connectivity_api_error() {
         let bot =
@@ -38,12 +38,14 @@ pub use validation::{
     // File validation
     validate_file_path,
     validate_length,
+    validate_long_message_text,
     validate_message_id,
     // Message validation
     validate_message_text,
     // Generic validation
     validate_not_empty,
     validate_range,
+    validate_thread_id,
     validate_voice_file_path,
 };
 
@@ -112,6 +112,22 @@ pub fn validate_cursor(cursor: &str) -> CliResult<()> {
     Ok(())
 }
 
+/// Validate a thread ID
+///
+/// # Arguments
+/// * `thread_id` - The thread ID to validate
+///
+/// # Returns
+/// * `Ok(())` if the thread ID is valid
+/// * `Err(CliError::InputError)` if the thread ID is invalid
+///
+/// # Validation Rules
+/// Thread IDs are chat IDs of the thread chats, the rules of [`validate_chat_id`] apply
+pub fn validate_thread_id(thread_id: &str) -> CliResult<()> {
+    validate_chat_id(thread_id)
+        .map_err(|_| CliError::InputError(format!("Invalid thread ID: {thread_id}")))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -155,6 +171,13 @@ mod tests {
         assert!(validate_cursor("12.5").is_err());
     }
 
+    #[test]
+    fn test_validate_thread_id() {
+        assert!(validate_thread_id("123456@chat.agent").is_ok());
+        assert!(validate_thread_id("").is_err());
+        assert!(validate_thread_id("thread id").is_err());
+    }
+
     proptest! {
         #[test]
         fn prop_validate_chat_id_random(s in ".{0,128}") {
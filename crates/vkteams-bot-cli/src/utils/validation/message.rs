@@ -21,13 +21,29 @@ use crate::errors::prelude::{CliError, Result as CliResult};
 /// - Must be between 1 and 4096 characters
 /// - Basic content validation (no null bytes)
 pub fn validate_message_text(message: &str) -> CliResult<()> {
-    validate_not_empty(message, "Message")?;
+    validate_long_message_text(message)?;
     validate_length(
         message,
         "Message",
         validation::MIN_MESSAGE_LENGTH,
         validation::MAX_MESSAGE_LENGTH,
-    )?;
+    )
+}
+
+/// Validate text of a message which is split into several messages when too long
+///
+/// # Arguments
+/// * `message` - The message text to validate
+///
+/// # Returns
+/// * `Ok(())` if the message is valid
+/// * `Err(CliError::InputError)` if the message is invalid
+///
+/// # Validation Rules
+/// - Cannot be empty after trimming
+/// - Basic content validation (no null bytes)
+pub fn validate_long_message_text(message: &str) -> CliResult<()> {
+    validate_not_empty(message, "Message")?;
 
     // Check for null bytes which can cause issues
     if message.contains('\0') {
@@ -219,6 +235,13 @@ mod tests {
         assert!(validate_message_text(&max_length_message).is_ok());
     }
 
+    #[test]
+    fn test_validate_long_message_text() {
+        assert!(validate_long_message_text(&"a".repeat(10000)).is_ok());
+        assert!(validate_long_message_text("  ").is_err());
+        assert!(validate_long_message_text("a\0b").is_err());
+    }
+
     #[test]
     fn test_validate_message_id() {
         assert!(validate_message_id("msg123").is_ok());
@@ -0,0 +1,223 @@
+//! CLI commands as the MCP server tools run them, against the local mock API server
+
+use serde_json::Value;
+use std::process::Command;
+use vkteams_bot::prelude::ChatType;
+use vkteams_bot_mock::{MockChat, MockServer};
+
+/// Run the CLI like the MCP bridge does: JSON output, credentials of the mock server
+/// and an empty home directory
+async fn run_mcp_command(server: &MockServer, args: &[&str]) -> (bool, Value) {
+    let url = server.url().to_string();
+    let token = server.token().to_string();
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    tokio::task::spawn_blocking(move || {
+        let home = tempfile::tempdir().unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_vkteams-bot-cli"))
+            .env("HOME", home.path())
+            .env("VKTEAMS_BOT_API_URL", url)
+            .env("VKTEAMS_BOT_API_TOKEN", token)
+            .args(["--output", "json"])
+            .args(&args)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let json = serde_json::from_str(&stdout).unwrap_or(Value::Null);
+        (output.status.success(), json)
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_send_text_with_markdown() {
+    let server = MockServer::start().await.unwrap();
+
+    let (success, json) = run_mcp_command(
+        &server,
+        &[
+            "send-text",
+            "--message",
+            "**Alert**: `disk`",
+            "--chat-id",
+            "chat",
+            "--markdown",
+        ],
+    )
+    .await;
+
+    assert!(success, "send-text failed: {json}");
+    assert_eq!(json["success"], true);
+    let requests = server.requests_for("messages/sendText");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].param("text"),
+        Some("<b>Alert</b>: <code>disk</code>")
+    );
+    assert_eq!(requests[0].param("parseMode"), Some("HTML"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_send_text_with_reply_chain() {
+    let server = MockServer::start().await.unwrap();
+    let text = "Lorem ipsum dolor sit amet. ".repeat(200);
+
+    let (success, json) = run_mcp_command(
+        &server,
+        &[
+            "send-text",
+            "--message",
+            &text,
+            "--chat-id",
+            "chat",
+            "--reply-chain",
+        ],
+    )
+    .await;
+
+    assert!(success, "send-text failed: {json}");
+    let requests = server.requests_for("messages/sendText");
+    assert_eq!(requests.len(), 2);
+    assert_eq!(
+        requests[1].param("replyMsgId"),
+        json["data"]["message_id"].as_str()
+    );
+    assert_eq!(json["data"]["message_ids"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_send_text_api_error() {
+    let server = MockServer::start().await.unwrap();
+    server.fail_next("messages/sendText", "Chat is read-only");
+
+    let (success, _) = run_mcp_command(
+        &server,
+        &["send-text", "--message", "Hi", "--chat-id", "chat"],
+    )
+    .await;
+
+    assert!(!success);
+    assert!(server.messages("chat").is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_create_chat_and_add_members() {
+    let server = MockServer::start().await.unwrap();
+
+    let (success, json) = run_mcp_command(
+        &server,
+        &[
+            "create-chat",
+            "--title",
+            "Incident",
+            "--member",
+            "alice",
+            "--member",
+            "bob",
+            "--join-moderation",
+            "--channel",
+        ],
+    )
+    .await;
+
+    assert!(success, "create-chat failed: {json}");
+    assert_eq!(json["data"]["members"][0]["status"], "pending");
+    let requests = server.requests_for("chats/createChat");
+    assert_eq!(requests[0].param("name"), Some("Incident"));
+    assert_eq!(requests[0].param("defaultRole"), Some("readonly"));
+    let chat_id = json["data"]["chat_id"].as_str().unwrap().to_string();
+
+    let (success, json) = run_mcp_command(
+        &server,
+        &[
+            "add-chat-members",
+            "--chat-id",
+            &chat_id,
+            "--member",
+            "carol",
+        ],
+    )
+    .await;
+    assert!(success, "add-chat-members failed: {json}");
+    assert_eq!(json["data"]["members"][0]["userId"], "carol");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_thread_commands() {
+    let server = MockServer::builder()
+        .chat("team", MockChat::new(ChatType::Group).with_member("alice"))
+        .start()
+        .await
+        .unwrap();
+
+    let (_, json) = run_mcp_command(
+        &server,
+        &["send-text", "--message", "Topic", "--chat-id", "team"],
+    )
+    .await;
+    let msg_id = json["data"]["message_id"].as_str().unwrap().to_string();
+
+    let (success, json) = run_mcp_command(
+        &server,
+        &[
+            "create-thread",
+            "--message-id",
+            &msg_id,
+            "--chat-id",
+            "team",
+        ],
+    )
+    .await;
+    assert!(success, "create-thread failed: {json}");
+    let thread_id = json["data"]["thread_id"].as_str().unwrap().to_string();
+
+    let (success, json) = run_mcp_command(
+        &server,
+        &[
+            "send-text",
+            "--message",
+            "Reply",
+            "--chat-id",
+            "team",
+            "--thread-id",
+            &thread_id,
+        ],
+    )
+    .await;
+    assert!(success, "send-text failed: {json}");
+    assert_eq!(
+        server.requests_for("messages/sendText")[1].param("threadId"),
+        Some(thread_id.as_str())
+    );
+
+    let (success, json) = run_mcp_command(
+        &server,
+        &[
+            "set-thread-autosubscribe",
+            "--chat-id",
+            "team",
+            "--enable",
+            "--with-existing",
+        ],
+    )
+    .await;
+    assert!(success, "set-thread-autosubscribe failed: {json}");
+
+    let (success, json) = run_mcp_command(
+        &server,
+        &[
+            "get-thread-subscribers",
+            "--thread-id",
+            &thread_id,
+            "--page-size",
+            "10",
+        ],
+    )
+    .await;
+    assert!(success, "get-thread-subscribers failed: {json}");
+    assert_eq!(json["data"]["subscribers"][0]["userId"], "alice");
+    assert_eq!(
+        server.requests_for("threads/subscribers/get")[0].param("pageSize"),
+        Some("10")
+    );
+}
@@ -0,0 +1,308 @@
+//! End-to-end CLI tests against the local mock API server
+
+use assert_cmd::Command;
+use serde_json::Value;
+use vkteams_bot::prelude::ChatType;
+use vkteams_bot_mock::{MockChat, MockFile, MockServer};
+
+/// Run the CLI with credentials pointing to the mock server and an empty home directory
+async fn run_cli(server: &MockServer, args: &[&str]) -> (bool, Value) {
+    let url = server.url().to_string();
+    let token = server.token().to_string();
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    tokio::task::spawn_blocking(move || {
+        let home = tempfile::tempdir().unwrap();
+        let output = Command::cargo_bin("vkteams-bot-cli")
+            .unwrap()
+            .env("HOME", home.path())
+            .env("VKTEAMS_BOT_API_URL", url)
+            .env("VKTEAMS_BOT_API_TOKEN", token)
+            .args(["--output", "json"])
+            .args(&args)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let json = serde_json::from_str(&stdout).unwrap_or(Value::Null);
+        (output.status.success(), json)
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_send_text_against_mock() {
+    let server = MockServer::start().await.unwrap();
+
+    let (success, json) = run_cli(
+        &server,
+        &["send-text", "-u", "chat@example.com", "-m", "Hi"],
+    )
+    .await;
+
+    assert!(success, "send-text failed: {json}");
+    assert_eq!(json["success"], true);
+    let requests = server.requests_for("messages/sendText");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].param("chatId"), Some("chat@example.com"));
+    assert_eq!(server.messages("chat@example.com").len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_send_markdown_text_against_mock() {
+    let server = MockServer::start().await.unwrap();
+
+    let (success, json) = run_cli(
+        &server,
+        &[
+            "send-text",
+            "-u",
+            "chat",
+            "-m",
+            "**Alert**: `disk`",
+            "--markdown",
+        ],
+    )
+    .await;
+
+    assert!(success, "send-text failed: {json}");
+    let requests = server.requests_for("messages/sendText");
+    assert_eq!(
+        requests[0].param("text"),
+        Some("<b>Alert</b>: <code>disk</code>")
+    );
+    assert_eq!(requests[0].param("parseMode"), Some("HTML"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_send_long_text_against_mock() {
+    let server = MockServer::start().await.unwrap();
+    let message = "Lorem ipsum dolor sit amet. ".repeat(200);
+
+    let (success, json) = run_cli(
+        &server,
+        &["send-text", "-u", "chat", "-m", &message, "--reply-chain"],
+    )
+    .await;
+
+    assert!(success, "send-text failed: {json}");
+    let requests = server.requests_for("messages/sendText");
+    assert_eq!(requests.len(), 2);
+    assert!(
+        requests
+            .iter()
+            .all(|r| r.param("text").unwrap().chars().count() <= 4096)
+    );
+    assert_eq!(
+        requests[1].param("replyMsgId"),
+        json["data"]["message_id"].as_str()
+    );
+    assert_eq!(json["data"]["message_ids"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_send_file_against_mock() {
+    let server = MockServer::start().await.unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("archive.log");
+    let content: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    std::fs::write(&path, &content).unwrap();
+
+    let (success, json) = run_cli(
+        &server,
+        &[
+            "send-file",
+            "-u",
+            "chat@example.com",
+            "-p",
+            path.to_str().unwrap(),
+        ],
+    )
+    .await;
+
+    assert!(success, "send-file failed: {json}");
+    let requests = server.requests_for("messages/sendFile");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].files[0].file_name.as_deref(),
+        Some("archive.log")
+    );
+    assert_eq!(requests[0].files[0].content, content);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_download_against_mock() {
+    let server = MockServer::start().await.unwrap();
+    let content: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+    let file_id = server.add_file(MockFile {
+        file_name: "report.bin".to_string(),
+        file_type: "file".to_string(),
+        content: content.clone(),
+    });
+    let dir = tempfile::tempdir().unwrap();
+    let dir_path = dir.path().to_str().unwrap();
+    // Partial file left by an interrupted download
+    std::fs::write(dir.path().join("report.bin.part"), &content[..30_000]).unwrap();
+
+    let (success, json) = run_cli(
+        &server,
+        &["download", "--file-id", &file_id, "--dir", dir_path],
+    )
+    .await;
+
+    assert!(success, "download failed: {json}");
+    assert_eq!(json["data"]["count"], 1);
+    assert_eq!(json["data"]["files"][0]["resumed_from"], 30_000);
+    assert_eq!(json["data"]["files"][0]["size"], content.len());
+    assert_eq!(
+        std::fs::read(dir.path().join("report.bin")).unwrap(),
+        content
+    );
+
+    let sha256 = json["data"]["files"][0]["sha256"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let wrong = "0".repeat(64);
+    let (success, _) = run_cli(
+        &server,
+        &[
+            "download",
+            "--file-id",
+            &file_id,
+            "--dir",
+            dir_path,
+            "--sha256",
+            &wrong,
+        ],
+    )
+    .await;
+    assert!(!success);
+    let (success, json) = run_cli(
+        &server,
+        &[
+            "download",
+            "--file-id",
+            &file_id,
+            "--dir",
+            dir_path,
+            "--sha256",
+            &sha256,
+        ],
+    )
+    .await;
+    assert!(success, "download failed: {json}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_chat_info_against_mock() {
+    let server = MockServer::builder()
+        .chat("team", MockChat::new(ChatType::Group).with_title("Team"))
+        .start()
+        .await
+        .unwrap();
+
+    let (success, json) = run_cli(&server, &["get-chat-info", "-c", "team"]).await;
+    assert!(success, "get-chat-info failed: {json}");
+    assert!(json.to_string().contains("Team"));
+
+    let (success, _) = run_cli(&server, &["get-chat-info", "-c", "unknown"]).await;
+    assert!(!success);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_create_chat_and_add_members_against_mock() {
+    let server = MockServer::start().await.unwrap();
+
+    let (success, json) = run_cli(
+        &server,
+        &[
+            "create-chat",
+            "-t",
+            "Incident",
+            "--member",
+            "alice,bob",
+            "--join-moderation",
+        ],
+    )
+    .await;
+    assert!(success, "create-chat failed: {json}");
+    assert_eq!(json["data"]["members"][0]["status"], "pending");
+    let chat_id = json["data"]["chat_id"].as_str().unwrap().to_string();
+    let requests = server.requests_for("chats/createChat");
+    assert_eq!(
+        requests[0].param("members"),
+        Some(r#"[{"sn":"alice"},{"sn":"bob"}]"#)
+    );
+
+    let (success, json) = run_cli(
+        &server,
+        &["add-chat-members", "-c", &chat_id, "-m", "carol"],
+    )
+    .await;
+    assert!(success, "add-chat-members failed: {json}");
+    assert_eq!(json["data"]["members"][0]["userId"], "carol");
+    assert_eq!(json["data"]["members"][0]["status"], "pending");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_threads_against_mock() {
+    let server = MockServer::builder()
+        .chat("team", MockChat::new(ChatType::Group).with_member("alice"))
+        .start()
+        .await
+        .unwrap();
+    let (_, json) = run_cli(&server, &["send-text", "-u", "team", "-m", "Topic"]).await;
+    let msg_id = json["data"]["message_id"].as_str().unwrap().to_string();
+
+    let (success, json) = run_cli(&server, &["create-thread", "-c", "team", "-m", &msg_id]).await;
+    assert!(success, "create-thread failed: {json}");
+    let thread_id = json["data"]["thread_id"].as_str().unwrap().to_string();
+
+    let (success, json) = run_cli(
+        &server,
+        &[
+            "send-text",
+            "-u",
+            "team",
+            "-m",
+            "Reply",
+            "--thread-id",
+            &thread_id,
+        ],
+    )
+    .await;
+    assert!(success, "send-text failed: {json}");
+    assert_eq!(
+        server.requests_for("messages/sendText")[1].param("threadId"),
+        Some(thread_id.as_str())
+    );
+
+    let (success, json) = run_cli(
+        &server,
+        &[
+            "set-thread-autosubscribe",
+            "-c",
+            "team",
+            "--enable",
+            "--with-existing",
+        ],
+    )
+    .await;
+    assert!(success, "set-thread-autosubscribe failed: {json}");
+
+    let (success, json) = run_cli(&server, &["get-thread-subscribers", "-t", &thread_id]).await;
+    assert!(success, "get-thread-subscribers failed: {json}");
+    assert_eq!(json["data"]["subscribers"][0]["userId"], "alice");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_api_error_is_reported() {
+    let server = MockServer::start().await.unwrap();
+    server.fail_next("messages/sendText", "Chat is read-only");
+
+    let (success, json) = run_cli(&server, &["send-text", "-u", "chat", "-m", "Hi"]).await;
+
+    assert!(!success);
+    assert_eq!(json["success"], false);
+    assert!(server.messages("chat").is_empty());
+}
@@ -4,7 +4,9 @@
 
 use clap::Parser;
 use vkteams_bot_cli::commands::Command;
-use vkteams_bot_cli::commands::daemon::{AutoSaveEventProcessor, DaemonCommands};
+use vkteams_bot_cli::commands::daemon::{
+    AutoSaveEventProcessor, DaemonCheckpointMode, DaemonCommands,
+};
 use vkteams_bot_cli::config::Config;
 
 #[derive(Parser)]
@@ -58,6 +60,10 @@ fn test_daemon_command_name() {
         pid_file: None,
         auto_save: false,
         chat_id: None,
+        checkpoint_file: None,
+        checkpoint_mode: DaemonCheckpointMode::AfterSuccess,
+        no_checkpoint: false,
+        metrics_addr: None,
     };
     assert_eq!(cmd.name(), "daemon");
 
@@ -172,11 +178,19 @@ fn test_daemon_start_command_flags() {
             auto_save,
             pid_file,
             chat_id,
+            checkpoint_file,
+            checkpoint_mode,
+            no_checkpoint,
+            metrics_addr,
         } => {
             assert!(foreground);
             assert!(auto_save);
             assert_eq!(pid_file, Some("/tmp/test.pid".to_string()));
             assert_eq!(chat_id, Some("chat123".to_string()));
+            assert_eq!(checkpoint_file, None);
+            assert_eq!(checkpoint_mode, DaemonCheckpointMode::AfterSuccess);
+            assert!(!no_checkpoint);
+            assert_eq!(metrics_addr, None);
         }
         _ => panic!("Expected Start command"),
     }
@@ -268,3 +282,57 @@ async fn test_processor_with_storage_integration() {
     assert_eq!(stats.events_saved, 0);
     assert_eq!(stats.events_failed, 0);
 }
+
+#[test]
+fn test_daemon_checkpoint_flags() {
+    let args = vec![
+        "test",
+        "start",
+        "--foreground",
+        "--checkpoint-file",
+        "/tmp/daemon.checkpoint",
+        "--checkpoint-mode",
+        "before-handling",
+    ];
+    let cli = TestCli::try_parse_from(args).unwrap();
+    match cli.daemon {
+        DaemonCommands::Start {
+            checkpoint_file,
+            checkpoint_mode,
+            no_checkpoint,
+            ..
+        } => {
+            assert_eq!(checkpoint_file, Some("/tmp/daemon.checkpoint".to_string()));
+            assert_eq!(checkpoint_mode, DaemonCheckpointMode::BeforeHandling);
+            assert!(!no_checkpoint);
+        }
+        _ => panic!("Expected Start command"),
+    }
+
+    let args = vec![
+        "test",
+        "start",
+        "--no-checkpoint",
+        "--checkpoint-file",
+        "/tmp/daemon.checkpoint",
+    ];
+    assert!(TestCli::try_parse_from(args).is_err());
+}
+
+#[test]
+fn test_daemon_metrics_addr_flag() {
+    let args = vec![
+        "test",
+        "start",
+        "--foreground",
+        "--metrics-addr",
+        "127.0.0.1:9090",
+    ];
+    let cli = TestCli::try_parse_from(args).unwrap();
+    match cli.daemon {
+        DaemonCommands::Start { metrics_addr, .. } => {
+            assert_eq!(metrics_addr, Some("127.0.0.1:9090".to_string()));
+        }
+        _ => panic!("Expected Start command"),
+    }
+}
@@ -77,15 +77,7 @@ pub fn derive_get_field(input: TokenStream) -> TokenStream {
     // Check for chat_id: ChatId
     let has_chat_id = fields.iter().any(|f| {
         f.ident.as_ref().map(|id| id == "chat_id").unwrap_or(false)
-            && match &f.ty {
-                Type::Path(type_path) => type_path
-                    .path
-                    .segments
-                    .last()
-                    .map(|s| s.ident == "ChatId")
-                    .unwrap_or(false),
-                _ => false,
-            }
+            && is_type_named(&f.ty, "ChatId")
     });
     // Check for multipart: MultipartName
     let has_multipart = fields.iter().any(|f| {
@@ -161,4 +153,14 @@ mod tests {
         let ty: Type = parse_str("DummyChatId").unwrap();
         assert!(!is_type_named(&ty, "OtherType"));
     }
+
+    #[test]
+    fn test_is_type_named_group() {
+        // Field types passed through `macro_rules!` fragments are wrapped in invisible groups
+        let ty = Type::Group(syn::TypeGroup {
+            group_token: Default::default(),
+            elem: Box::new(parse_str("ChatId").unwrap()),
+        });
+        assert!(is_type_named(&ty, "ChatId"));
+    }
 }
@@ -6,6 +6,7 @@
 
 use crate::cli_bridge::CliBridge;
 use crate::errors::BridgeError;
+use crate::types::CreateChatParams;
 use serde_json::Value;
 
 impl CliBridge {
@@ -17,6 +18,9 @@ impl CliBridge {
         text: &str,
         chat_id: Option<&str>,
         reply_msg_id: Option<&str>,
+        markdown: bool,
+        reply_chain: bool,
+        thread_id: Option<&str>,
     ) -> Result<Value, BridgeError> {
         let mut args = vec!["send-text", "--message", text];
 
@@ -28,6 +32,18 @@ impl CliBridge {
             args.extend(&["--reply-msg-id", reply_id]);
         }
 
+        if markdown {
+            args.push("--markdown");
+        }
+
+        if reply_chain {
+            args.push("--reply-chain");
+        }
+
+        if let Some(thread_id) = thread_id.filter(|id| !id.trim().is_empty()) {
+            args.extend(&["--thread-id", thread_id]);
+        }
+
         self.execute_command(&args).await
     }
 
@@ -227,6 +243,120 @@ impl CliBridge {
         self.execute_command(&args).await
     }
 
+    /// Create group or channel chat
+    pub async fn create_chat(&self, params: &CreateChatParams) -> Result<Value, BridgeError> {
+        let mut args = vec!["create-chat", "--title", params.title.as_str()];
+
+        if let Some(about) = params.about.as_deref().filter(|a| !a.trim().is_empty()) {
+            args.extend(&["--about", about]);
+        }
+
+        if let Some(rules) = params.rules.as_deref().filter(|r| !r.trim().is_empty()) {
+            args.extend(&["--rules", rules]);
+        }
+
+        for member in params.members.iter().flatten() {
+            if !member.trim().is_empty() {
+                args.extend(&["--member", member.as_str()]);
+            }
+        }
+
+        if params.public.unwrap_or(false) {
+            args.push("--public");
+        }
+
+        if params.join_moderation.unwrap_or(false) {
+            args.push("--join-moderation");
+        }
+
+        if params.channel.unwrap_or(false) {
+            args.push("--channel");
+        }
+
+        self.execute_command(&args).await
+    }
+
+    /// Add members to chat
+    pub async fn add_chat_members(
+        &self,
+        members: &[String],
+        chat_id: Option<&str>,
+    ) -> Result<Value, BridgeError> {
+        let mut args = vec!["add-chat-members"];
+
+        if let Some(chat_id) = chat_id.filter(|id| !id.trim().is_empty()) {
+            args.extend(&["--chat-id", chat_id]);
+        }
+
+        for member in members.iter().filter(|m| !m.trim().is_empty()) {
+            args.extend(&["--member", member.as_str()]);
+        }
+
+        self.execute_command(&args).await
+    }
+
+    // === Thread Commands ===
+
+    /// Create a thread on the message
+    pub async fn create_thread(
+        &self,
+        message_id: &str,
+        chat_id: Option<&str>,
+    ) -> Result<Value, BridgeError> {
+        let mut args = vec!["create-thread", "--message-id", message_id];
+
+        if let Some(chat_id) = chat_id.filter(|id| !id.trim().is_empty()) {
+            args.extend(&["--chat-id", chat_id]);
+        }
+
+        self.execute_command(&args).await
+    }
+
+    /// Get thread subscribers with optional cursor and page size
+    pub async fn get_thread_subscribers(
+        &self,
+        thread_id: &str,
+        cursor: Option<&str>,
+        page_size: Option<u32>,
+    ) -> Result<Value, BridgeError> {
+        let page_size_str = page_size.map(|size| size.to_string());
+        let mut args = vec!["get-thread-subscribers", "--thread-id", thread_id];
+
+        if let Some(cursor) = cursor.filter(|c| !c.trim().is_empty()) {
+            args.extend(&["--cursor", cursor]);
+        }
+
+        if let Some(ref page_size) = page_size_str {
+            args.extend(&["--page-size", page_size]);
+        }
+
+        self.execute_command(&args).await
+    }
+
+    /// Enable or disable auto-subscribe to the chat threads
+    pub async fn set_thread_autosubscribe(
+        &self,
+        enable: bool,
+        with_existing: bool,
+        chat_id: Option<&str>,
+    ) -> Result<Value, BridgeError> {
+        let mut args = vec!["set-thread-autosubscribe"];
+
+        if let Some(chat_id) = chat_id.filter(|id| !id.trim().is_empty()) {
+            args.extend(&["--chat-id", chat_id]);
+        }
+
+        if enable {
+            args.push("--enable");
+        }
+
+        if with_existing {
+            args.push("--with-existing");
+        }
+
+        self.execute_command(&args).await
+    }
+
     // === File Upload Commands ===
 
     /// Upload file from base64 content
@@ -7,12 +7,13 @@
 use crate::cli_bridge::CliBridge;
 use crate::errors::BridgeError;
 use crate::types::{
-    ChatInfoParams, DeleteMessageParams, EditMessageParams, EventsGetParams, FileInfoParams,
-    GetChatAdminsParams, GetChatMembersParams, GetContextParams, GetDatabaseStatsParams,
-    GetProfileParams, GetRecentMessagesParams, PinMessageParams, ResetSessionParams,
+    AddChatMembersParams, ChatInfoParams, CreateChatParams, CreateThreadParams,
+    DeleteMessageParams, EditMessageParams, EventsGetParams, FileInfoParams, GetChatAdminsParams,
+    GetChatMembersParams, GetContextParams, GetDatabaseStatsParams, GetProfileParams,
+    GetRecentMessagesParams, GetThreadSubscribersParams, PinMessageParams, ResetSessionParams,
     SearchSemanticParams, SearchTextParams, SendActionParams, SendFileParams, SendTextParams,
-    SendVoiceParams, Server, SetChatAboutParams, SetChatTitleParams, UnpinMessageParams,
-    UploadFileFromBase64Params, UploadJsonFileParams, UploadTextAsFileParams,
+    SendVoiceParams, Server, SetChatAboutParams, SetChatTitleParams, SetThreadAutosubscribeParams,
+    UnpinMessageParams, UploadFileFromBase64Params, UploadJsonFileParams, UploadTextAsFileParams,
 };
 use rmcp::{
     ServerHandler, elicit_safe,
@@ -133,7 +134,7 @@ This server automatically requests chat_id from you when first used. No manual c
 Available Tools:
 
 ## Messaging
-- send_text(text: string, reply_msg_id?: string) — Send text message
+- send_text(text: string, reply_msg_id?: string, markdown?: bool, reply_chain?: bool, thread_id?: string) — Send text message, optionally written in Markdown or to a thread; long text is split into several messages
 - send_file(file_path: string, caption?: string) — Send file from path
 - send_voice(file_path: string) — Send voice message
 - edit_message(message_id: string, new_text: string) — Edit existing message
@@ -153,6 +154,13 @@ Available Tools:
 - get_chat_admins() — Get chat administrators
 - set_chat_title(title: string) — Set chat title
 - set_chat_about(about: string) — Set chat description
+- create_chat(title: string, about?: string, rules?: string, members?: string[], public?: bool, join_moderation?: bool, channel?: bool) — Create group or channel chat
+- add_chat_members(members: string[]) — Add members to chat, returns status of each member
+
+## Threads
+- create_thread(message_id: string) — Create a thread on the message
+- get_thread_subscribers(thread_id: string, cursor?: string, page_size?: number) — Get thread subscribers
+- set_thread_autosubscribe(enable: bool, with_existing?: bool) — Toggle auto-subscribe to the chat threads
 
 ## Storage & Search
 - search_semantic(query: string, limit?: number) — Semantic search in messages
@@ -364,7 +372,13 @@ impl Server {
     #[tool(description = "Send text message to chat")]
     async fn send_text(
         &self,
-        Parameters(SendTextParams { text, reply_msg_id }): Parameters<SendTextParams>,
+        Parameters(SendTextParams {
+            text,
+            reply_msg_id,
+            markdown,
+            reply_chain,
+            thread_id,
+        }): Parameters<SendTextParams>,
         peer: Peer<RoleServer>,
     ) -> MCPResult {
         let target_chat_id = self
@@ -374,7 +388,14 @@ impl Server {
 
         convert_bridge_result(
             self.cli
-                .send_text(&text, target_chat_id.as_deref(), reply_msg_id.as_deref())
+                .send_text(
+                    &text,
+                    target_chat_id.as_deref(),
+                    reply_msg_id.as_deref(),
+                    markdown.unwrap_or(false),
+                    reply_chain.unwrap_or(false),
+                    thread_id.as_deref(),
+                )
                 .await,
         )
     }
@@ -589,6 +610,87 @@ impl Server {
         )
     }
 
+    #[tool(description = "Create group or channel chat")]
+    async fn create_chat(&self, Parameters(params): Parameters<CreateChatParams>) -> MCPResult {
+        convert_bridge_result(self.cli.create_chat(&params).await)
+    }
+
+    #[tool(description = "Add members to chat")]
+    async fn add_chat_members(
+        &self,
+        Parameters(AddChatMembersParams { members }): Parameters<AddChatMembersParams>,
+        peer: Peer<RoleServer>,
+    ) -> MCPResult {
+        let target_chat_id = self
+            .get_or_elicit_chat_id(None, &peer)
+            .await
+            .map_err(convert_elicitation_error)?;
+        convert_bridge_result(
+            self.cli
+                .add_chat_members(&members, target_chat_id.as_deref())
+                .await,
+        )
+    }
+
+    // === Thread Commands ===
+
+    #[tool(description = "Create a thread on the message")]
+    async fn create_thread(
+        &self,
+        Parameters(CreateThreadParams { message_id }): Parameters<CreateThreadParams>,
+        peer: Peer<RoleServer>,
+    ) -> MCPResult {
+        let target_chat_id = self
+            .get_or_elicit_chat_id(None, &peer)
+            .await
+            .map_err(convert_elicitation_error)?;
+        convert_bridge_result(
+            self.cli
+                .create_thread(&message_id, target_chat_id.as_deref())
+                .await,
+        )
+    }
+
+    #[tool(description = "Get thread subscribers")]
+    async fn get_thread_subscribers(
+        &self,
+        Parameters(GetThreadSubscribersParams {
+            thread_id,
+            cursor,
+            page_size,
+        }): Parameters<GetThreadSubscribersParams>,
+    ) -> MCPResult {
+        convert_bridge_result(
+            self.cli
+                .get_thread_subscribers(&thread_id, cursor.as_deref(), page_size)
+                .await,
+        )
+    }
+
+    #[tool(description = "Enable or disable auto-subscribe to the chat threads")]
+    async fn set_thread_autosubscribe(
+        &self,
+        Parameters(SetThreadAutosubscribeParams {
+            enable,
+            with_existing,
+        }): Parameters<SetThreadAutosubscribeParams>,
+        peer: Peer<RoleServer>,
+    ) -> MCPResult {
+        let target_chat_id = self
+            .get_or_elicit_chat_id(None, &peer)
+            .await
+            .map_err(convert_elicitation_error)?;
+        convert_bridge_result(
+            self.cli
+                .set_thread_autosubscribe(
+                    enable,
+                    with_existing.unwrap_or(false),
+                    target_chat_id.as_deref(),
+                )
+                .await,
+        )
+    }
+
     // === File Upload Commands ===
 
     #[tool(description = "Upload file from base64 content")]
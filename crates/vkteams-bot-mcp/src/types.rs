@@ -34,10 +34,20 @@ pub struct SendTextParams {
             <a href="http://www.example.com/">inline URL</a>
             <code>inline code</code>
             <pre>pre-formatted code block</pre>
+        Or Markdown when `markdown` is true.
+        Long text is split into several messages.
         "#)]
     pub text: String,
     #[schemars(description = "Reply to message ID (optional)")]
     pub reply_msg_id: Option<String>,
+    #[schemars(description = "Convert text from Markdown to HTML (optional, default: false)")]
+    pub markdown: Option<bool>,
+    #[schemars(
+        description = "Send the other parts of a long text as replies to the first one (optional, default: false)"
+    )]
+    pub reply_chain: Option<bool>,
+    #[schemars(description = "Send the message to the thread with this ID (optional)")]
+    pub thread_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
@@ -102,6 +112,56 @@ pub struct GetChatAdminsParams {
     // No parameters needed - chat_id is obtained via elicitation
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CreateChatParams {
+    #[schemars(description = "Chat title")]
+    pub title: String,
+    #[schemars(description = "Chat description (optional)")]
+    pub about: Option<String>,
+    #[schemars(description = "Chat rules (optional)")]
+    pub rules: Option<String>,
+    #[schemars(description = "User IDs of the initial members (optional)")]
+    pub members: Option<Vec<String>>,
+    #[schemars(description = "Make the chat public (optional, default: false)")]
+    pub public: Option<bool>,
+    #[schemars(description = "New members must be approved by admins (optional, default: false)")]
+    pub join_moderation: Option<bool>,
+    #[schemars(
+        description = "Create a channel where only admins can write (optional, default: false)"
+    )]
+    pub channel: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct AddChatMembersParams {
+    #[schemars(description = "User IDs to add to the chat")]
+    pub members: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CreateThreadParams {
+    #[schemars(description = "Message ID to create the thread on")]
+    pub message_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetThreadSubscribersParams {
+    #[schemars(description = "Thread ID")]
+    pub thread_id: String,
+    #[schemars(description = "Cursor for pagination (optional)")]
+    pub cursor: Option<String>,
+    #[schemars(description = "Number of subscribers per page (optional)")]
+    pub page_size: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SetThreadAutosubscribeParams {
+    #[schemars(description = "Enable auto-subscribe to new threads of the chat")]
+    pub enable: bool,
+    #[schemars(description = "Also subscribe to the existing threads (optional, default: false)")]
+    pub with_existing: Option<bool>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct SetChatTitleParams {
     #[schemars(description = "New chat title")]
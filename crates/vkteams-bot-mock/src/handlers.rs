@@ -0,0 +1,625 @@
+//! Mock implementations of the Bot API methods
+use crate::state::{
+    MockChat, MockFile, MockMessage, MockState, MockThread, RecordedFile, RecordedRequest,
+};
+use axum::Json;
+use axum::body::Body;
+use axum::extract::{FromRequest, Multipart, Path, RawQuery, Request, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use tracing::debug;
+use vkteams_bot::prelude::*;
+
+/// State shared between the server handle and the handlers
+#[derive(Debug)]
+pub(crate) struct Shared {
+    pub(crate) token: String,
+    pub(crate) base_url: String,
+    pub(crate) max_poll_time: Duration,
+    pub(crate) state: Mutex<MockState>,
+    pub(crate) events_notify: Notify,
+}
+
+impl Shared {
+    pub(crate) fn lock(&self) -> MutexGuard<'_, MockState> {
+        // A panicked test must not poison the server for the other tests
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+type MethodResult = std::result::Result<Value, String>;
+
+fn ok(payload: Value) -> Json<Value> {
+    let mut body = json!({ "ok": true });
+    if let (Some(body), Value::Object(payload)) = (body.as_object_mut(), payload) {
+        body.extend(payload);
+    }
+    Json(body)
+}
+
+fn error(description: impl Into<String>) -> Json<Value> {
+    Json(json!({ "ok": false, "description": description.into() }))
+}
+
+fn too_many_requests(retry_after: Option<u64>) -> Response {
+    let body = error("Too many requests");
+    match retry_after {
+        Some(secs) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, secs.to_string())],
+            body,
+        )
+            .into_response(),
+        None => (StatusCode::TOO_MANY_REQUESTS, body).into_response(),
+    }
+}
+
+/// Handle `/bot/v1/{*method}`
+pub(crate) async fn api(
+    State(shared): State<Arc<Shared>>,
+    Path(method): Path<String>,
+    RawQuery(query): RawQuery,
+    request: Request,
+) -> Response {
+    let mut params: Vec<(String, String)> = query
+        .map(|q| {
+            url::form_urlencoded::parse(q.as_bytes())
+                .into_owned()
+                .collect()
+        })
+        .unwrap_or_default();
+    let token = params
+        .iter()
+        .position(|(key, _)| key == "token")
+        .map(|idx| params.remove(idx).1);
+    let http_method = request.method().to_string();
+    let files = read_files(request).await;
+    let request = RecordedRequest {
+        method,
+        http_method,
+        params,
+        files,
+    };
+    debug!("Mock request {} {:?}", request.method, request.params);
+
+    {
+        let mut state = shared.lock();
+        state.requests.push(request.clone());
+        if token.as_deref() != Some(shared.token.as_str()) {
+            return error("Invalid token").into_response();
+        }
+        if let Some(retry_after) = state.take_throttle(&request.method) {
+            return too_many_requests(retry_after);
+        }
+        if let Some(status) = state.take_status(&request.method) {
+            return StatusCode::from_u16(status)
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+                .into_response();
+        }
+        if let Some(description) = state.take_error(&request.method) {
+            return error(description).into_response();
+        }
+        if request.method != "events/get" {
+            return match handle_method(&mut state, &shared.base_url, &request) {
+                Ok(payload) => ok(payload),
+                Err(description) => error(description),
+            }
+            .into_response();
+        }
+    }
+    events_get(&shared, &request).await.into_response()
+}
+
+/// Handle `/files/{file_id}`, serves content of the files returned by `files/getInfo`.
+/// Supports the `Range: bytes=<start>-` header used to resume downloads.
+pub(crate) async fn download(
+    State(shared): State<Arc<Shared>>,
+    Path(file_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(content) = shared
+        .lock()
+        .files
+        .get(&file_id)
+        .map(|file| file.content.clone())
+    else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let total = content.len();
+    let builder = match range_start(&headers) {
+        Some(start) if start >= total => {
+            return Response::builder()
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{total}"))
+                .body(Body::empty())
+                .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+        Some(start) => Response::builder()
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes {start}-{}/{total}", total - 1),
+            )
+            .header(header::CONTENT_LENGTH, total - start)
+            .body(Body::from(content[start..].to_vec())),
+        None => Response::builder()
+            .header(header::CONTENT_LENGTH, total)
+            .body(Body::from(content)),
+    };
+    builder.unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// Start of an open `Range: bytes=<start>-` header
+fn range_start(headers: &HeaderMap) -> Option<usize> {
+    headers
+        .get(header::RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes=")?
+        .strip_suffix('-')?
+        .parse()
+        .ok()
+}
+
+async fn read_files(request: Request) -> Vec<RecordedFile> {
+    let is_multipart = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("multipart/form-data"));
+    if !is_multipart {
+        return Vec::new();
+    }
+    let Ok(mut multipart) = Multipart::from_request(request, &()).await else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    while let Ok(Some(field)) = multipart.next_field().await {
+        let field_name = field.name().unwrap_or_default().to_string();
+        let file_name = field.file_name().map(str::to_string);
+        let content = field.bytes().await.map(|b| b.to_vec()).unwrap_or_default();
+        files.push(RecordedFile {
+            field: field_name,
+            file_name,
+            content,
+        });
+    }
+    files
+}
+
+/// Long polling: wait for events after `lastEventId` up to `pollTime` seconds,
+/// limited by the server's maximum poll time
+async fn events_get(shared: &Shared, request: &RecordedRequest) -> Json<Value> {
+    let last_event_id: EventId = request
+        .param("lastEventId")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_default();
+    let poll_time = request
+        .param("pollTime")
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or_default()
+        .min(shared.max_poll_time);
+    let deadline = Instant::now() + poll_time;
+    loop {
+        let notified = shared.events_notify.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+
+        let events = shared.lock().events_after(last_event_id);
+        if !events.is_empty() || Instant::now() >= deadline {
+            return ok(json!({ "events": events }));
+        }
+        let _ = tokio::time::timeout_at(deadline, notified).await;
+    }
+}
+
+fn required<'a>(request: &'a RecordedRequest, name: &str) -> std::result::Result<&'a str, String> {
+    request
+        .param(name)
+        .ok_or_else(|| format!("Missing required parameter {name}"))
+}
+
+fn flag(request: &RecordedRequest, name: &str) -> bool {
+    request.param(name) == Some("true")
+}
+
+fn optional(request: &RecordedRequest, name: &str) -> Option<String> {
+    request.param(name).map(str::to_string)
+}
+
+fn existing_chat<'a>(
+    state: &'a mut MockState,
+    request: &RecordedRequest,
+) -> std::result::Result<&'a mut crate::state::MockChat, String> {
+    let chat_id = required(request, "chatId")?;
+    state
+        .chats
+        .get_mut(chat_id)
+        .ok_or_else(|| format!("Chat {chat_id} not found"))
+}
+
+fn users(users: &[UserId]) -> Value {
+    json!(
+        users
+            .iter()
+            .map(|user_id| json!({ "userId": user_id }))
+            .collect::<Vec<_>>()
+    )
+}
+
+fn handle_method(state: &mut MockState, base_url: &str, request: &RecordedRequest) -> MethodResult {
+    match request.method.as_str() {
+        "self/get" => Ok(json!({
+            "userId": state.bot_info.user_id,
+            "nick": state.bot_info.nick,
+            "firstName": state.bot_info.first_name,
+            "about": state.bot_info.about,
+        })),
+        "messages/sendText" => {
+            let chat_id = required(request, "chatId")?;
+            let msg_id = state.add_message(chat_id, message_from_request(request));
+            Ok(json!({ "msgId": msg_id }))
+        }
+        "messages/sendFile" | "messages/sendVoice" => send_file(state, request),
+        "messages/editText" => {
+            let msg_id = required(request, "msgId")?;
+            let chat = existing_chat(state, request)?;
+            let message = chat
+                .messages
+                .iter_mut()
+                .find(|m| m.msg_id.0 == msg_id)
+                .ok_or_else(|| format!("Message {msg_id} not found"))?;
+            let edited = message_from_request(request);
+            message.text = edited.text;
+            message.format = edited.format;
+            message.parse_mode = edited.parse_mode;
+            message.inline_keyboard_markup = edited.inline_keyboard_markup;
+            message.edited = true;
+            Ok(json!({}))
+        }
+        "messages/deleteMessages" => {
+            let msg_ids: Vec<&str> = request
+                .params
+                .iter()
+                .filter(|(key, _)| key == "msgId")
+                .map(|(_, value)| value.as_str())
+                .collect();
+            if msg_ids.is_empty() {
+                return Err("Missing required parameter msgId".to_string());
+            }
+            let chat = existing_chat(state, request)?;
+            chat.messages
+                .retain(|m| !msg_ids.contains(&m.msg_id.0.as_str()));
+            Ok(json!({}))
+        }
+        "messages/answerCallbackQuery" => {
+            let query_id = required(request, "queryId")?;
+            state.answered_queries.push(QueryId(query_id.to_string()));
+            Ok(json!({}))
+        }
+        "chats/getInfo" => {
+            let chat = existing_chat(state, request)?;
+            Ok(match &chat.chat_type {
+                ChatType::Private => json!({
+                    "type": "private",
+                    "firstName": chat.title,
+                    "about": chat.about,
+                }),
+                chat_type => json!({
+                    "type": chat_type.as_str(),
+                    "title": chat.title,
+                    "about": chat.about,
+                    "rules": chat.rules,
+                }),
+            })
+        }
+        "chats/getAdmins" => {
+            let chat = existing_chat(state, request)?;
+            Ok(json!({ "admins": users(&chat.admins) }))
+        }
+        "chats/getMembers" => {
+            let chat = existing_chat(state, request)?;
+            let members: Vec<Value> = chat
+                .members
+                .iter()
+                .map(|user_id| {
+                    if chat.admins.contains(user_id) {
+                        json!({ "userId": user_id, "admin": true })
+                    } else {
+                        json!({ "userId": user_id })
+                    }
+                })
+                .collect();
+            Ok(json!({ "members": members }))
+        }
+        "chats/getBlockedUsers" => {
+            let chat = existing_chat(state, request)?;
+            Ok(json!({ "users": users(&chat.blocked) }))
+        }
+        "chats/getPendingUsers" => {
+            let chat = existing_chat(state, request)?;
+            Ok(json!({ "users": users(&chat.pending) }))
+        }
+        "chats/blockUser" => {
+            let user_id = UserId(required(request, "userId")?.to_string());
+            let chat = existing_chat(state, request)?;
+            chat.members.retain(|member| *member != user_id);
+            if !chat.blocked.contains(&user_id) {
+                chat.blocked.push(user_id);
+            }
+            Ok(json!({}))
+        }
+        "chats/unblockUser" => {
+            let user_id = UserId(required(request, "userId")?.to_string());
+            let chat = existing_chat(state, request)?;
+            chat.blocked.retain(|blocked| *blocked != user_id);
+            Ok(json!({}))
+        }
+        "chats/resolvePending" => {
+            let approve = flag(request, "approve");
+            let everyone = flag(request, "everyone");
+            let user_id = optional(request, "userId").map(UserId);
+            let chat = existing_chat(state, request)?;
+            let (resolved, pending) = std::mem::take(&mut chat.pending)
+                .into_iter()
+                .partition(|pending| everyone || Some(pending) == user_id.as_ref());
+            chat.pending = pending;
+            if approve {
+                chat.members.extend::<Vec<UserId>>(resolved);
+            }
+            Ok(json!({}))
+        }
+        "chats/setTitle" => {
+            let title = required(request, "title")?.to_string();
+            existing_chat(state, request)?.title = Some(title);
+            Ok(json!({}))
+        }
+        "chats/setAbout" => {
+            let about = required(request, "about")?.to_string();
+            existing_chat(state, request)?.about = Some(about);
+            Ok(json!({}))
+        }
+        "chats/setRules" => {
+            let rules = required(request, "rules")?.to_string();
+            existing_chat(state, request)?.rules = Some(rules);
+            Ok(json!({}))
+        }
+        "chats/pinMessage" => {
+            let msg_id = MsgId(required(request, "msgId")?.to_string());
+            let chat = existing_chat(state, request)?;
+            if !chat.pinned.contains(&msg_id) {
+                chat.pinned.push(msg_id);
+            }
+            Ok(json!({}))
+        }
+        "chats/unpinMessage" => {
+            let msg_id = MsgId(required(request, "msgId")?.to_string());
+            existing_chat(state, request)?
+                .pinned
+                .retain(|pinned| *pinned != msg_id);
+            Ok(json!({}))
+        }
+        "chats/sendActions" => {
+            existing_chat(state, request)?;
+            Ok(json!({}))
+        }
+        "chats/createChat" => {
+            let title = required(request, "name")?.to_string();
+            let chat_type = match request.param("defaultRole") {
+                Some("readonly") => ChatType::Channel,
+                _ => ChatType::Group,
+            };
+            let mut chat = MockChat::new(chat_type);
+            chat.title = Some(title);
+            chat.about = optional(request, "about");
+            chat.rules = optional(request, "rules");
+            chat.public = flag(request, "public");
+            chat.join_moderation = flag(request, "joinModeration");
+            chat.admins.push(state.bot_info.user_id.clone());
+            chat.members.push(state.bot_info.user_id.clone());
+            let results = add_members(&mut chat, &members_param(request));
+            let chat_id = state.create_chat(chat);
+            Ok(json!({ "sn": chat_id, "members": results }))
+        }
+        "chats/members/add" => {
+            let members = members_param(request);
+            if members.is_empty() {
+                return Err("Missing required parameter members".to_string());
+            }
+            let chat = existing_chat(state, request)?;
+            Ok(json!({ "members": add_members(chat, &members) }))
+        }
+        "chats/members/delete" => {
+            let removed: Vec<UserId> = request
+                .params
+                .iter()
+                .filter(|(key, _)| key == "members" || key == "userId")
+                .flat_map(|(_, value)| member_ids(value))
+                .collect();
+            let chat = existing_chat(state, request)?;
+            chat.members.retain(|member| !removed.contains(member));
+            Ok(json!({}))
+        }
+        "chats/avatar/set" => {
+            let content = request
+                .files
+                .first()
+                .map(|file| file.content.clone())
+                .ok_or("File not specified")?;
+            existing_chat(state, request)?.avatar = Some(content);
+            Ok(json!({}))
+        }
+        "files/getInfo" => {
+            let file_id = required(request, "fileId")?;
+            let file = state
+                .files
+                .get(file_id)
+                .ok_or_else(|| format!("File {file_id} not found"))?;
+            Ok(json!({
+                "type": file.file_type,
+                "size": file.content.len(),
+                "filename": file.file_name,
+                "url": format!("{base_url}/files/{file_id}"),
+            }))
+        }
+        "threads/add" => {
+            let msg_id = required(request, "msgId")?;
+            let chat = existing_chat(state, request)?;
+            if chat.message(msg_id).is_none() {
+                return Err(format!("Message {msg_id} not found"));
+            }
+            let subscribers = if chat.autosubscribe {
+                chat.members.clone()
+            } else {
+                vec![]
+            };
+            let thread_id = state.add_thread(MockThread {
+                chat_id: ChatId::from(required(request, "chatId")?.to_string()),
+                msg_id: MsgId(msg_id.to_string()),
+                subscribers,
+            });
+            Ok(json!({ "threadId": thread_id }))
+        }
+        "threads/subscribers/get" => {
+            let thread_id = required(request, "threadId")?;
+            let thread = state
+                .threads
+                .get(thread_id)
+                .ok_or_else(|| format!("Thread {thread_id} not found"))?;
+            let start: usize = optional(request, "cursor")
+                .map(|cursor| {
+                    cursor
+                        .parse()
+                        .map_err(|_| format!("Invalid cursor {cursor}"))
+                })
+                .transpose()?
+                .unwrap_or(0);
+            let page_size: usize = optional(request, "pageSize")
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(thread.subscribers.len().max(1));
+            let page: Vec<UserId> = thread
+                .subscribers
+                .iter()
+                .skip(start)
+                .take(page_size)
+                .cloned()
+                .collect();
+            let next = start + page.len();
+            if next < thread.subscribers.len() {
+                Ok(json!({ "subscribers": users(&page), "cursor": next.to_string() }))
+            } else {
+                Ok(json!({ "subscribers": users(&page) }))
+            }
+        }
+        "threads/autosubscribe" => {
+            let enable = flag(request, "enable");
+            let with_existing = flag(request, "withExisting");
+            let chat_id = required(request, "chatId")?.to_string();
+            let chat = existing_chat(state, request)?;
+            chat.autosubscribe = enable;
+            let members = chat.members.clone();
+            if enable && with_existing {
+                for thread in state
+                    .threads
+                    .values_mut()
+                    .filter(|thread| thread.chat_id.0 == chat_id)
+                {
+                    for member in &members {
+                        if !thread.subscribers.contains(member) {
+                            thread.subscribers.push(member.clone());
+                        }
+                    }
+                }
+            }
+            Ok(json!({}))
+        }
+        method => Err(format!("Unknown method {method}")),
+    }
+}
+
+fn members_param(request: &RecordedRequest) -> Vec<UserId> {
+    request
+        .params
+        .iter()
+        .filter(|(key, _)| key == "members")
+        .flat_map(|(_, value)| member_ids(value))
+        .collect()
+}
+
+/// Add users to the chat, blocked users fail and users wait for approval with join moderation
+fn add_members(chat: &mut MockChat, members: &[UserId]) -> Vec<Value> {
+    members
+        .iter()
+        .map(|user_id| {
+            if chat.members.contains(user_id) {
+                json!({ "sn": user_id, "status": "alreadyMember" })
+            } else if chat.blocked.contains(user_id) {
+                json!({ "sn": user_id, "status": "failed", "error": "User is blocked" })
+            } else if chat.join_moderation {
+                if !chat.pending.contains(user_id) {
+                    chat.pending.push(user_id.clone());
+                }
+                json!({ "sn": user_id, "status": "pending" })
+            } else {
+                chat.members.push(user_id.clone());
+                json!({ "sn": user_id, "status": "added" })
+            }
+        })
+        .collect()
+}
+
+/// `members` is a JSON array of `{"sn": ...}` objects or a single user id
+fn member_ids(value: &str) -> Vec<UserId> {
+    match serde_json::from_str::<Vec<Value>>(value) {
+        Ok(members) => members
+            .iter()
+            .filter_map(|member| member.get("sn").and_then(Value::as_str))
+            .map(|sn| UserId(sn.to_string()))
+            .collect(),
+        Err(_) => vec![UserId(value.to_string())],
+    }
+}
+
+fn message_from_request(request: &RecordedRequest) -> MockMessage {
+    MockMessage {
+        text: optional(request, "text"),
+        reply_msg_id: optional(request, "replyMsgId").map(MsgId),
+        format: optional(request, "format"),
+        parse_mode: optional(request, "parseMode"),
+        inline_keyboard_markup: optional(request, "inlineKeyboardMarkup"),
+        thread_id: optional(request, "threadId").map(ThreadId),
+        ..Default::default()
+    }
+}
+
+fn send_file(state: &mut MockState, request: &RecordedRequest) -> MethodResult {
+    let chat_id = required(request, "chatId")?;
+    let file_id = match (request.files.first(), request.param("fileId")) {
+        (Some(file), _) => {
+            let file_name = file.file_name.clone().unwrap_or_else(|| file.field.clone());
+            let file_type = if request.method == "messages/sendVoice" {
+                "voice"
+            } else {
+                "file"
+            };
+            state.add_file(MockFile {
+                file_name,
+                file_type: file_type.to_string(),
+                content: file.content.clone(),
+            })
+        }
+        (None, Some(file_id)) if state.files.contains_key(file_id) => file_id.to_string(),
+        (None, Some(file_id)) => return Err(format!("File not found: {file_id}")),
+        (None, None) => return Err("File not specified".to_string()),
+    };
+    let mut message = message_from_request(request);
+    message.file_id = Some(file_id.clone());
+    let msg_id = state.add_message(chat_id, message);
+    Ok(json!({ "msgId": msg_id, "fileId": file_id }))
+}
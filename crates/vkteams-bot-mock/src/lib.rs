@@ -0,0 +1,40 @@
+//! # Mock VK Teams Bot API server
+//! Local [`axum`] server implementing the Bot API methods with in-memory state,
+//! for tests that must run without network access.
+//!
+//! - every request is recorded and available with [`MockServer::requests`]
+//! - events for `events/get` long polling are scripted with [`MockServer::push_event`]
+//! - API errors are injected with [`MockServer::fail_next`],
+//!   `429 Too Many Requests` responses with [`MockServer::throttle_next`],
+//!   other HTTP errors with [`MockServer::fail_status_next`]
+//! - files uploaded with `messages/sendFile` are served at the URL from `files/getInfo`,
+//!   with `Range` requests to resume downloads
+//!
+//! ```no_run
+//! use vkteams_bot::prelude::*;
+//! use vkteams_bot_mock::MockServer;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let server = MockServer::start().await?;
+//! let bot = server.bot()?;
+//! bot.send_api_request(RequestMessagesSendText::new(ChatId::from("chat")).with_text("Hi".to_string()))
+//!     .await?;
+//! assert_eq!(server.requests_for("messages/sendText").len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Other processes, e.g. the CLI, can use the server through
+//! `VKTEAMS_BOT_API_URL` = [`MockServer::url`] and `VKTEAMS_BOT_API_TOKEN` = [`MockServer::token`].
+//!
+//! [`axum`]: https://docs.rs/axum
+mod handlers;
+mod server;
+mod state;
+
+pub use server::{DEFAULT_MAX_POLL_TIME, MOCK_TOKEN, MockServer, MockServerBuilder};
+pub use state::{
+    MockBotInfo, MockChat, MockFile, MockMessage, MockState, MockThread, RecordedFile,
+    RecordedRequest,
+};
@@ -0,0 +1,268 @@
+//! Mock server handle
+use crate::handlers::{self, Shared};
+use crate::state::{MockChat, MockFile, MockMessage, MockState, RecordedRequest};
+use axum::Router;
+use axum::routing::{any, get};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::{Notify, oneshot};
+use tokio::task::JoinHandle;
+use tracing::debug;
+use vkteams_bot::prelude::*;
+
+/// Default token accepted by the mock server
+pub const MOCK_TOKEN: &str = "mock_token";
+/// Default maximum long polling time
+pub const DEFAULT_MAX_POLL_TIME: Duration = Duration::from_secs(1);
+
+/// Running mock VK Teams Bot API server
+///
+/// The server listens on a random local port and stops when the handle is dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    shutdown: Option<oneshot::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// Mock server builder
+#[derive(Debug, Clone)]
+pub struct MockServerBuilder {
+    token: String,
+    max_poll_time: Duration,
+    state: MockState,
+}
+
+impl Default for MockServerBuilder {
+    fn default() -> Self {
+        Self {
+            token: MOCK_TOKEN.to_string(),
+            max_poll_time: DEFAULT_MAX_POLL_TIME,
+            state: MockState::default(),
+        }
+    }
+}
+
+impl MockServerBuilder {
+    /// Token accepted by the server, other tokens get an API error
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = token.into();
+        self
+    }
+
+    /// Maximum time `events/get` waits for new events, regardless of `pollTime`
+    pub fn max_poll_time(mut self, max_poll_time: Duration) -> Self {
+        self.max_poll_time = max_poll_time;
+        self
+    }
+
+    /// Add chat
+    pub fn chat(mut self, chat_id: impl Into<String>, chat: MockChat) -> Self {
+        self.state.chats.insert(chat_id.into(), chat);
+        self
+    }
+
+    /// Start server on `127.0.0.1` with a random port
+    ///
+    /// ## Errors
+    /// - unable to bind the port
+    pub async fn start(self) -> std::io::Result<MockServer> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let shared = Arc::new(Shared {
+            token: self.token,
+            base_url: format!("http://{addr}"),
+            max_poll_time: self.max_poll_time,
+            state: Mutex::new(self.state),
+            events_notify: Notify::new(),
+        });
+        let app = Router::new()
+            .route("/bot/v1/{*method}", any(handlers::api))
+            .route("/files/{file_id}", get(handlers::download))
+            .with_state(shared.clone());
+
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+                let _ = shutdown_rx.await;
+            });
+            if let Err(e) = server.await {
+                debug!("Mock server error: {e}");
+            }
+        });
+        debug!("Mock server listening on {addr}");
+        Ok(MockServer {
+            addr,
+            shared,
+            shutdown: Some(shutdown),
+            handle: Some(handle),
+        })
+    }
+}
+
+impl MockServer {
+    /// Create server builder
+    pub fn builder() -> MockServerBuilder {
+        MockServerBuilder::default()
+    }
+
+    /// Start server with default settings
+    ///
+    /// ## Errors
+    /// - unable to bind the port
+    pub async fn start() -> std::io::Result<Self> {
+        Self::builder().start().await
+    }
+
+    /// Server address
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL to pass as `VKTEAMS_BOT_API_URL`, e.g. `http://127.0.0.1:12345`
+    pub fn url(&self) -> &str {
+        &self.shared.base_url
+    }
+
+    /// Token accepted by the server
+    pub fn token(&self) -> &str {
+        &self.shared.token
+    }
+
+    /// Create bot connected to the server
+    ///
+    /// ## Errors
+    /// - `BotError::Url` - URL parsing error
+    pub fn bot(&self) -> Result<Bot> {
+        Bot::with_params(&APIVersionUrl::V1, self.token(), self.url())
+    }
+
+    /// Access server state
+    pub fn with_state<R>(&self, f: impl FnOnce(&mut MockState) -> R) -> R {
+        f(&mut self.shared.lock())
+    }
+
+    /// All requests received so far
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.shared.lock().requests.clone()
+    }
+
+    /// Requests to the API method, e.g. `messages/sendText`
+    pub fn requests_for(&self, method: &str) -> Vec<RecordedRequest> {
+        self.shared
+            .lock()
+            .requests
+            .iter()
+            .filter(|request| request.method == method)
+            .cloned()
+            .collect()
+    }
+
+    /// Forget recorded requests
+    pub fn clear_requests(&self) {
+        self.shared.lock().requests.clear();
+    }
+
+    /// Add event for `events/get` and wake up waiting long polls
+    pub fn push_event(&self, event_type: EventType) -> EventId {
+        let event_id = self.shared.lock().push_event(event_type);
+        self.shared.events_notify.notify_waiters();
+        event_id
+    }
+
+    /// Add `newMessage` event from the user
+    pub fn push_text_message(
+        &self,
+        chat_id: impl Into<String>,
+        user_id: impl Into<String>,
+        text: impl Into<String>,
+    ) -> EventId {
+        let chat_id = chat_id.into();
+        let chat_type = self
+            .chat(&chat_id)
+            .map(|chat| chat.chat_type)
+            .unwrap_or_default();
+        let text = text.into();
+        let msg_id = self.shared.lock().add_message(
+            &chat_id,
+            MockMessage {
+                text: Some(text.clone()),
+                ..Default::default()
+            },
+        );
+        self.push_event(EventType::NewMessage(Box::new(EventPayloadNewMessage {
+            msg_id,
+            text,
+            chat: Chat {
+                chat_id: ChatId::from(chat_id),
+                title: None,
+                chat_type: chat_type.as_str().to_string(),
+            },
+            from: From {
+                first_name: "User".to_string(),
+                last_name: None,
+                user_id: UserId(user_id.into()),
+            },
+            ..Default::default()
+        })))
+    }
+
+    /// Make the next request to the API method fail with the description
+    pub fn fail_next(&self, method: impl Into<String>, description: impl Into<String>) {
+        self.shared.lock().fail_next(method, description);
+    }
+
+    /// Make the next request to the API method fail with HTTP 429 Too Many Requests
+    /// and the `Retry-After` header in seconds, if set
+    pub fn throttle_next(&self, method: impl Into<String>, retry_after: Option<u64>) {
+        self.shared.lock().throttle_next(method, retry_after);
+    }
+
+    /// Make the next request to the API method fail with the HTTP status, e.g. `503`
+    pub fn fail_status_next(&self, method: impl Into<String>, status: u16) {
+        self.shared.lock().fail_status_next(method, status);
+    }
+
+    /// Add or replace chat
+    pub fn add_chat(&self, chat_id: impl Into<String>, chat: MockChat) {
+        self.shared.lock().chats.insert(chat_id.into(), chat);
+    }
+
+    /// Get chat
+    pub fn chat(&self, chat_id: &str) -> Option<MockChat> {
+        self.shared.lock().chats.get(chat_id).cloned()
+    }
+
+    /// Messages in the chat
+    pub fn messages(&self, chat_id: &str) -> Vec<MockMessage> {
+        self.chat(chat_id)
+            .map(|chat| chat.messages)
+            .unwrap_or_default()
+    }
+
+    /// Add file for `files/getInfo`, returns the file id
+    pub fn add_file(&self, file: MockFile) -> String {
+        self.shared.lock().add_file(file)
+    }
+
+    /// Stop server and wait for it to finish
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.await;
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
@@ -0,0 +1,267 @@
+//! In-memory state of the mock server
+use std::collections::{HashMap, VecDeque};
+use vkteams_bot::prelude::*;
+
+/// Request received by the mock server
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    /// API method, e.g. `messages/sendText`
+    pub method: String,
+    /// HTTP method, `GET` or `POST`
+    pub http_method: String,
+    /// Query parameters without the token
+    pub params: Vec<(String, String)>,
+    /// Uploaded multipart files
+    pub files: Vec<RecordedFile>,
+}
+
+impl RecordedRequest {
+    /// Get the first query parameter with the name
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// File uploaded in a multipart request
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedFile {
+    /// Multipart field name
+    pub field: String,
+    /// File name
+    pub file_name: Option<String>,
+    /// File content
+    pub content: Vec<u8>,
+}
+
+/// Message stored in a mock chat
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MockMessage {
+    pub msg_id: MsgId,
+    pub text: Option<String>,
+    pub file_id: Option<String>,
+    pub reply_msg_id: Option<MsgId>,
+    pub format: Option<String>,
+    pub parse_mode: Option<String>,
+    pub inline_keyboard_markup: Option<String>,
+    pub thread_id: Option<ThreadId>,
+    pub edited: bool,
+}
+
+/// Mock chat
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MockChat {
+    pub chat_type: ChatType,
+    pub title: Option<String>,
+    pub about: Option<String>,
+    pub rules: Option<String>,
+    pub members: Vec<UserId>,
+    pub admins: Vec<UserId>,
+    pub blocked: Vec<UserId>,
+    pub pending: Vec<UserId>,
+    pub pinned: Vec<MsgId>,
+    pub messages: Vec<MockMessage>,
+    pub avatar: Option<Vec<u8>>,
+    pub autosubscribe: bool,
+    pub public: bool,
+    pub join_moderation: bool,
+}
+
+impl MockChat {
+    /// Create chat of the given type
+    pub fn new(chat_type: ChatType) -> Self {
+        Self {
+            chat_type,
+            ..Default::default()
+        }
+    }
+
+    /// Set chat title
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Add chat member
+    pub fn with_member(mut self, user_id: impl Into<String>) -> Self {
+        self.members.push(UserId(user_id.into()));
+        self
+    }
+
+    /// Add chat admin, admins are also members
+    pub fn with_admin(mut self, user_id: impl Into<String>) -> Self {
+        let user_id = UserId(user_id.into());
+        if !self.members.contains(&user_id) {
+            self.members.push(user_id.clone());
+        }
+        self.admins.push(user_id);
+        self
+    }
+
+    /// Add user waiting for approval to join
+    pub fn with_pending(mut self, user_id: impl Into<String>) -> Self {
+        self.pending.push(UserId(user_id.into()));
+        self
+    }
+
+    /// Find message by id
+    pub fn message(&self, msg_id: &str) -> Option<&MockMessage> {
+        self.messages.iter().find(|m| m.msg_id.0 == msg_id)
+    }
+}
+
+/// Mock file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MockFile {
+    pub file_name: String,
+    pub file_type: String,
+    pub content: Vec<u8>,
+}
+
+/// Thread created on a chat message
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MockThread {
+    pub chat_id: ChatId,
+    pub msg_id: MsgId,
+    pub subscribers: Vec<UserId>,
+}
+
+/// Bot account returned by `self/get`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockBotInfo {
+    pub user_id: UserId,
+    pub nick: String,
+    pub first_name: String,
+    pub about: Option<String>,
+}
+
+impl Default for MockBotInfo {
+    fn default() -> Self {
+        Self {
+            user_id: UserId("1000000".to_string()),
+            nick: "mock_bot".to_string(),
+            first_name: "Mock Bot".to_string(),
+            about: None,
+        }
+    }
+}
+
+/// Mock server state
+///
+/// Chats are created implicitly when a message is sent to an unknown chat id.
+#[derive(Debug, Clone, Default)]
+pub struct MockState {
+    pub bot_info: MockBotInfo,
+    pub chats: HashMap<String, MockChat>,
+    pub files: HashMap<String, MockFile>,
+    pub threads: HashMap<String, MockThread>,
+    pub requests: Vec<RecordedRequest>,
+    pub events: Vec<EventMessage>,
+    pub answered_queries: Vec<QueryId>,
+    pub(crate) errors: HashMap<String, VecDeque<String>>,
+    pub(crate) throttles: HashMap<String, VecDeque<Option<u64>>>,
+    pub(crate) statuses: HashMap<String, VecDeque<u16>>,
+    next_event_id: EventId,
+    next_msg_id: u64,
+    next_file_id: u64,
+    next_thread_id: u64,
+    next_chat_id: u64,
+}
+
+impl MockState {
+    /// Add event to the `events/get` queue, returns the assigned event id
+    pub fn push_event(&mut self, event_type: EventType) -> EventId {
+        self.next_event_id += 1;
+        self.events.push(EventMessage {
+            event_id: self.next_event_id,
+            event_type,
+        });
+        self.next_event_id
+    }
+
+    /// Events with id greater than `last_event_id`
+    pub fn events_after(&self, last_event_id: EventId) -> Vec<EventMessage> {
+        self.events
+            .iter()
+            .filter(|event| event.event_id > last_event_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Make the next request to the method fail with the description
+    pub fn fail_next(&mut self, method: impl Into<String>, description: impl Into<String>) {
+        self.errors
+            .entry(method.into())
+            .or_default()
+            .push_back(description.into());
+    }
+
+    /// Make the next request to the method fail with HTTP 429 Too Many Requests
+    /// and the `Retry-After` header in seconds, if set
+    pub fn throttle_next(&mut self, method: impl Into<String>, retry_after: Option<u64>) {
+        self.throttles
+            .entry(method.into())
+            .or_default()
+            .push_back(retry_after);
+    }
+
+    /// Make the next request to the method fail with the HTTP status, e.g. `503`
+    pub fn fail_status_next(&mut self, method: impl Into<String>, status: u16) {
+        self.statuses
+            .entry(method.into())
+            .or_default()
+            .push_back(status);
+    }
+
+    pub(crate) fn take_throttle(&mut self, method: &str) -> Option<Option<u64>> {
+        self.throttles.get_mut(method)?.pop_front()
+    }
+
+    pub(crate) fn take_status(&mut self, method: &str) -> Option<u16> {
+        self.statuses.get_mut(method)?.pop_front()
+    }
+
+    pub(crate) fn take_error(&mut self, method: &str) -> Option<String> {
+        self.errors.get_mut(method)?.pop_front()
+    }
+
+    /// Get chat, creating a private chat if it does not exist
+    pub fn chat_mut(&mut self, chat_id: &str) -> &mut MockChat {
+        self.chats.entry(chat_id.to_string()).or_default()
+    }
+
+    /// Store new group or channel chat, returns the assigned chat id
+    pub fn create_chat(&mut self, chat: MockChat) -> ChatId {
+        self.next_chat_id += 1;
+        let chat_id = format!("{}@chat.agent", 680000000 + self.next_chat_id);
+        self.chats.insert(chat_id.clone(), chat);
+        ChatId::from(chat_id)
+    }
+
+    /// Store message in the chat, returns the assigned message id
+    pub fn add_message(&mut self, chat_id: &str, mut message: MockMessage) -> MsgId {
+        self.next_msg_id += 1;
+        message.msg_id = MsgId(self.next_msg_id.to_string());
+        let msg_id = message.msg_id.clone();
+        self.chat_mut(chat_id).messages.push(message);
+        msg_id
+    }
+
+    /// Store file, returns the assigned file id
+    pub fn add_file(&mut self, file: MockFile) -> String {
+        self.next_file_id += 1;
+        let file_id = format!("file{}", self.next_file_id);
+        self.files.insert(file_id.clone(), file);
+        file_id
+    }
+
+    /// Store thread, returns the assigned thread id
+    pub fn add_thread(&mut self, thread: MockThread) -> ThreadId {
+        self.next_thread_id += 1;
+        let thread_id = format!("thread{}", self.next_thread_id);
+        self.threads.insert(thread_id.clone(), thread);
+        ThreadId(thread_id)
+    }
+}
@@ -0,0 +1,933 @@
+//! End-to-end tests of the bot client against the mock server
+use std::time::{Duration, Instant};
+use vkteams_bot::prelude::*;
+use vkteams_bot_mock::{MockChat, MockServer};
+
+fn group() -> MockChat {
+    MockChat::new(ChatType::Group)
+        .with_title("Team")
+        .with_admin("admin")
+        .with_member("alice")
+        .with_pending("bob")
+}
+
+#[tokio::test]
+async fn test_send_text_is_recorded() {
+    let server = MockServer::start().await.unwrap();
+    let bot = server.bot().unwrap();
+
+    let res = bot
+        .send_api_request(
+            RequestMessagesSendText::new(ChatId::from("chat@example.com"))
+                .with_text("Hello".to_string())
+                .with_parse_mode(ParseMode::HTML),
+        )
+        .await
+        .unwrap();
+
+    let requests = server.requests_for("messages/sendText");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].http_method, "GET");
+    assert_eq!(requests[0].param("chatId"), Some("chat@example.com"));
+    assert_eq!(requests[0].param("text"), Some("Hello"));
+    assert_eq!(requests[0].param("token"), None);
+
+    let messages = server.messages("chat@example.com");
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].msg_id, res.msg_id);
+    assert_eq!(messages[0].text.as_deref(), Some("Hello"));
+}
+
+#[tokio::test]
+async fn test_send_text_split() {
+    let server = MockServer::start().await.unwrap();
+    let bot = server.bot().unwrap();
+    let keyboard = Keyboard {
+        buttons: vec![vec![ButtonKeyboard {
+            text: "Ok".to_string(),
+            url: None,
+            callback_data: Some("ok".to_string()),
+            style: None,
+        }]],
+    };
+    let request = RequestMessagesSendText::new(ChatId::from("chat"))
+        .with_text("<b>first part</b>\n\n<b>second part</b>".to_string())
+        .with_parse_mode(ParseMode::HTML)
+        .set_keyboard(keyboard)
+        .unwrap();
+
+    let options = SplitOptions {
+        max_length: 20,
+        reply_chain: true,
+    };
+    let res = bot.send_text_split(request, options).await.unwrap();
+
+    assert_eq!(res.len(), 2);
+    let requests = server.requests_for("messages/sendText");
+    assert_eq!(requests[0].param("text"), Some("<b>first part</b>"));
+    assert_eq!(requests[0].param("replyMsgId"), None);
+    assert_eq!(requests[0].param("inlineKeyboardMarkup"), None);
+    assert_eq!(requests[1].param("text"), Some("<b>second part</b>"));
+    assert_eq!(
+        requests[1].param("replyMsgId"),
+        Some(res[0].msg_id.0.as_str())
+    );
+    assert!(requests[1].param("inlineKeyboardMarkup").is_some());
+
+    // Short text is sent as is
+    server.clear_requests();
+    let request = RequestMessagesSendText::new(ChatId::from("chat")).with_text("Hi".to_string());
+    let res = bot
+        .send_text_split(request, SplitOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(res.len(), 1);
+    assert_eq!(server.requests_for("messages/sendText").len(), 1);
+}
+
+#[tokio::test]
+async fn test_invalid_token_and_injected_errors() {
+    let server = MockServer::start().await.unwrap();
+    let bot = Bot::with_params(&APIVersionUrl::V1, "wrong", server.url()).unwrap();
+    assert!(matches!(
+        bot.send_api_request(RequestSelfGet::new(())).await,
+        Err(BotError::Api(_))
+    ));
+
+    let bot = server.bot().unwrap();
+    server.fail_next("self/get", "Permission denied");
+    match bot.send_api_request(RequestSelfGet::new(())).await {
+        Err(BotError::Api(e)) => assert_eq!(e.description, "Permission denied"),
+        other => panic!("Unexpected result: {other:?}"),
+    }
+    let me = bot.send_api_request(RequestSelfGet::new(())).await.unwrap();
+    assert_eq!(me.nick, "mock_bot");
+}
+
+#[tokio::test]
+async fn test_server_and_unavailable_errors_retried() {
+    use vkteams_bot::config::types::NetworkConfig;
+
+    let server = MockServer::start().await.unwrap();
+    let bot = Bot::builder()
+        .token(server.token())
+        .api_url(server.url())
+        .network(NetworkConfig {
+            retries: 2,
+            max_backoff_ms: 10,
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    let send = || bot.send_api_request(RequestSelfGet::new(()));
+
+    server.fail_status_next("self/get", 503);
+    assert_eq!(send().await.unwrap().nick, "mock_bot");
+    assert_eq!(server.requests_for("self/get").len(), 2);
+
+    server.clear_requests();
+    server.fail_next("self/get", "Service unavailable");
+    assert_eq!(send().await.unwrap().nick, "mock_bot");
+    assert_eq!(server.requests_for("self/get").len(), 2);
+
+    // Out of retries
+    server.clear_requests();
+    for _ in 0..3 {
+        server.fail_next("self/get", "Service unavailable");
+    }
+    match send().await {
+        Err(e) => assert_eq!(e.api_error_kind(), Some(ApiErrorKind::Unavailable)),
+        other => panic!("Unexpected result: {other:?}"),
+    }
+    assert_eq!(server.requests_for("self/get").len(), 3);
+}
+
+#[tokio::test]
+async fn test_outbox_retries_until_delivered() {
+    use vkteams_bot::config::types::NetworkConfig;
+
+    let server = MockServer::start().await.unwrap();
+    let bot = Bot::builder()
+        .token(server.token())
+        .api_url(server.url())
+        .network(NetworkConfig {
+            retries: 0,
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    let outbox = Outbox::new(MemoryOutboxStore::new())
+        .with_retry_delay(Duration::from_millis(10), Duration::from_millis(10));
+    let mut events = outbox.subscribe();
+    let request =
+        RequestMessagesSendText::new(ChatId::from("alerts")).with_text("Disk is full".to_string());
+
+    // The same idempotency key is enqueued once
+    let message = OutboxMessage::new("alert-1", &request)
+        .unwrap()
+        .with_priority(OutboxPriority::High);
+    assert!(outbox.enqueue(message.clone()).await.unwrap());
+    assert!(!outbox.enqueue(message).await.unwrap());
+
+    server.throttle_next("messages/sendText", None);
+    assert_eq!(outbox.deliver_due(&bot).await.unwrap(), 1);
+    let entry = events.recv().await.unwrap();
+    assert_eq!((entry.status, entry.attempts), (OutboxStatus::Pending, 1));
+    assert!(entry.last_error.is_some());
+    assert!(server.messages("alerts").is_empty());
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(outbox.deliver_due(&bot).await.unwrap(), 1);
+    let entry = outbox.get("alert-1").await.unwrap().unwrap();
+    assert_eq!((entry.status, entry.attempts), (OutboxStatus::Delivered, 2));
+    let messages = server.messages("alerts");
+    assert_eq!(messages.len(), 1);
+    assert_eq!(
+        entry.response.unwrap()["msgId"].as_str(),
+        Some(messages[0].msg_id.0.as_str())
+    );
+    assert_eq!(outbox.deliver_due(&bot).await.unwrap(), 0);
+
+    // API errors are not retried
+    let api_errors = metrics().api_requests("messages/sendText", "api_error");
+    server.fail_next("messages/sendText", "Chat not found");
+    let message = OutboxMessage::new("alert-2", &request).unwrap();
+    outbox.enqueue(message).await.unwrap();
+    outbox.deliver_due(&bot).await.unwrap();
+    let entry = outbox.get("alert-2").await.unwrap().unwrap();
+    assert_eq!(entry.status, OutboxStatus::Failed);
+    assert!(entry.last_error.unwrap().contains("Chat not found"));
+    // Deliveries are recorded in the metrics
+    assert!(metrics().api_requests("messages/sendText", "api_error") > api_errors);
+}
+
+#[tokio::test]
+async fn test_too_many_requests_retried() {
+    use vkteams_bot::config::types::NetworkConfig;
+
+    let server = MockServer::start().await.unwrap();
+    let bot = Bot::builder()
+        .token(server.token())
+        .api_url(server.url())
+        .network(NetworkConfig {
+            retries: 2,
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    // Method without a chat, so the chat rate limiter does not delay the retries
+    let send = || bot.send_api_request(RequestSelfGet::new(()));
+
+    // HTTP 429 with Retry-After and the API error are both retried
+    server.throttle_next("self/get", Some(0));
+    server.fail_next("self/get", "Too many requests");
+    assert_eq!(send().await.unwrap().nick, "mock_bot");
+    assert_eq!(server.requests_for("self/get").len(), 3);
+
+    // Out of retries
+    server.clear_requests();
+    for _ in 0..3 {
+        server.throttle_next("self/get", None);
+    }
+    assert!(matches!(send().await, Err(BotError::RateLimited(None))));
+    assert_eq!(server.requests_for("self/get").len(), 3);
+
+    // Retry-After longer than the bot is willing to wait
+    server.clear_requests();
+    server.throttle_next("self/get", Some(3600));
+    match send().await {
+        Err(BotError::RateLimited(Some(retry_after))) => {
+            assert_eq!(retry_after, Duration::from_secs(3600))
+        }
+        other => panic!("Unexpected result: {other:?}"),
+    }
+    assert_eq!(server.requests_for("self/get").len(), 1);
+}
+
+#[tokio::test]
+async fn test_events_long_polling() {
+    let server = MockServer::builder()
+        .max_poll_time(Duration::from_secs(5))
+        .start()
+        .await
+        .unwrap();
+    let bot = server.bot().unwrap();
+
+    let first = server.push_text_message("chat", "alice", "/start");
+    let res = bot
+        .send_api_request(RequestEventsGet::new(0).with_poll_time(30))
+        .await
+        .unwrap();
+    assert_eq!(res.events.len(), 1);
+    assert_eq!(res.events[0].event_id, first);
+    assert_eq!(res.events[0].event_type.text(), Some("/start"));
+
+    // A waiting poll returns as soon as an event is pushed
+    let started = Instant::now();
+    let poll = tokio::spawn({
+        let bot = bot.clone();
+        async move {
+            bot.send_api_request(RequestEventsGet::new(first).with_poll_time(30))
+                .await
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let second = server.push_text_message("chat", "alice", "next");
+    let res = poll.await.unwrap().unwrap();
+    assert_eq!(res.events.len(), 1);
+    assert_eq!(res.events[0].event_id, second);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_send_file_and_get_info() {
+    let server = MockServer::start().await.unwrap();
+    let bot = server.bot().unwrap();
+
+    let path = std::env::temp_dir().join(format!("mock_report_{}.txt", std::process::id()));
+    std::fs::write(&path, b"hello").unwrap();
+
+    let res = bot
+        .send_api_request(RequestMessagesSendFile::new((
+            ChatId::from("chat"),
+            MultipartName::FilePath(path.to_string_lossy().to_string()),
+        )))
+        .await;
+    std::fs::remove_file(&path).unwrap();
+    let res = res.unwrap();
+    let file_id = res.file_id.unwrap();
+    let request = &server.requests_for("messages/sendFile")[0];
+    assert_eq!(request.http_method, "POST");
+    assert_eq!(request.files[0].content, b"hello");
+
+    let info = bot
+        .send_api_request(RequestFilesGetInfo::new(FileId(file_id.clone())))
+        .await
+        .unwrap();
+    assert!(info.file_name.ends_with(".txt"));
+    assert_eq!(info.file_size, 5);
+    assert_eq!(info.url, format!("{}/files/{file_id}", server.url()));
+
+    assert!(
+        bot.send_api_request(RequestFilesGetInfo::new(FileId("missing".to_string())))
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_send_file_streamed_with_progress() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    let server = MockServer::start().await.unwrap();
+    let bot = server.bot().unwrap();
+
+    let content: Vec<u8> = (0..300_000u32).map(|i| (i % 251) as u8).collect();
+    let sent = Arc::new(AtomicU64::new(0));
+    let progress = sent.clone();
+    let source = content.clone();
+    let upload = RetryableMultipartForm::from_reader(
+        "archive.log".to_string(),
+        "file".to_string(),
+        move || {
+            let source = source.clone();
+            async move { Ok(std::io::Cursor::new(source)) }
+        },
+    )
+    .with_progress(move |bytes| progress.store(bytes, Ordering::SeqCst));
+
+    let res = bot
+        .send_api_request_with_upload(
+            RequestMessagesSendFile::new((
+                ChatId::from("chat"),
+                MultipartName::FilePath("archive.log".to_string()),
+            )),
+            upload,
+        )
+        .await
+        .unwrap();
+    assert!(res.file_id.is_some());
+    assert_eq!(sent.load(Ordering::SeqCst), content.len() as u64);
+    let request = &server.requests_for("messages/sendFile")[0];
+    assert_eq!(request.files[0].file_name.as_deref(), Some("archive.log"));
+    assert_eq!(request.files[0].content, content);
+}
+
+#[tokio::test]
+async fn test_upload_cache_reuses_and_invalidates_file_id() {
+    let server = MockServer::start().await.unwrap();
+    let bot = server
+        .bot()
+        .unwrap()
+        .with_upload_cache(UploadCache::new(MemoryUploadCacheStore::new()));
+
+    let send = || {
+        bot.send_api_request_with_upload(
+            RequestMessagesSendFile::new((
+                ChatId::from("chat"),
+                MultipartName::FilePath("report.png".to_string()),
+            )),
+            RetryableMultipartForm::from_content(
+                "report.png".to_string(),
+                "file".to_string(),
+                b"report".to_vec(),
+            ),
+        )
+    };
+
+    let first = send().await.unwrap().file_id.unwrap();
+    let second = send().await.unwrap().file_id.unwrap();
+    assert_eq!(first, second);
+    let requests = server.requests_for("messages/sendFile");
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].http_method, "POST");
+    assert_eq!(requests[1].http_method, "GET");
+    assert_eq!(requests[1].param("fileId"), Some(first.as_str()));
+    assert!(requests[1].files.is_empty());
+
+    // Stale id is rejected by the API: the file is uploaded again and the new id is cached
+    server.with_state(|state| state.files.remove(&first));
+    let third = send().await.unwrap().file_id.unwrap();
+    assert_ne!(third, first);
+    let fourth = send().await.unwrap().file_id.unwrap();
+    assert_eq!(fourth, third);
+    let methods: Vec<_> = server
+        .requests_for("messages/sendFile")
+        .into_iter()
+        .map(|request| request.http_method)
+        .collect();
+    assert_eq!(methods, ["POST", "GET", "GET", "POST", "GET"]);
+
+    // Error unrelated to the file keeps the cached id
+    server.fail_next("messages/sendFile", "Chat not found");
+    assert!(send().await.is_err());
+    assert_eq!(send().await.unwrap().file_id.unwrap(), third);
+    let requests = server.requests_for("messages/sendFile");
+    assert_eq!(requests.len(), 7);
+    assert!(
+        requests[5..]
+            .iter()
+            .all(|request| request.http_method == "GET")
+    );
+}
+
+#[tokio::test]
+async fn test_download_file_resume_and_checks() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use vkteams_bot_mock::MockFile;
+
+    let server = MockServer::start().await.unwrap();
+    let bot = server.bot().unwrap();
+    let content: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    let file_id = server.add_file(MockFile {
+        file_name: "archive.log".to_string(),
+        file_type: "file".to_string(),
+        content: content.clone(),
+    });
+    let info = bot
+        .send_api_request(RequestFilesGetInfo::new(FileId(file_id)))
+        .await
+        .unwrap();
+
+    let mut buffer = Vec::new();
+    let downloaded = bot
+        .download_file_to_writer(&info, &mut buffer, DownloadOptions::new())
+        .await
+        .unwrap();
+    assert_eq!(buffer, content);
+    assert_eq!(downloaded.size, content.len() as u64);
+    assert_eq!(downloaded.resumed_from, 0);
+
+    let dir = std::env::temp_dir().join(format!("mock_download_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("archive.log");
+    let partial = dir.join("archive.log.part");
+    std::fs::write(&partial, &content[..50_000]).unwrap();
+
+    let sent = Arc::new(AtomicU64::new(0));
+    let progress = sent.clone();
+    let resumed = bot
+        .download_file_to_path(
+            &info,
+            &path,
+            DownloadOptions::new()
+                .with_sha256(downloaded.sha256.to_uppercase())
+                .with_progress(move |bytes| progress.store(bytes, Ordering::SeqCst)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resumed.resumed_from, 50_000);
+    assert_eq!(resumed.sha256, downloaded.sha256);
+    assert_eq!(sent.load(Ordering::SeqCst), content.len() as u64);
+    assert_eq!(std::fs::read(&path).unwrap(), content);
+    assert!(!partial.exists());
+
+    // A corrupted partial file fails the hash check and is removed
+    std::fs::remove_file(&path).unwrap();
+    std::fs::write(&partial, vec![0u8; 1000]).unwrap();
+    let res = bot
+        .download_file_to_path(
+            &info,
+            &path,
+            DownloadOptions::new().with_sha256(&downloaded.sha256),
+        )
+        .await;
+    assert!(matches!(res, Err(BotError::Validation(_))));
+    assert!(!path.exists());
+    assert!(!partial.exists());
+
+    // The size from `files/getInfo` is checked too
+    let mut buffer = Vec::new();
+    let res = bot
+        .download_file_to_writer(
+            &info,
+            &mut buffer,
+            DownloadOptions::new().with_expected_size(1),
+        )
+        .await;
+    assert!(matches!(res, Err(BotError::Validation(_))));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_chat_methods() {
+    let server = MockServer::builder()
+        .chat("team", group())
+        .start()
+        .await
+        .unwrap();
+    let bot = server.bot().unwrap();
+    let chat_id = ChatId::from("team");
+
+    let info = bot
+        .send_api_request(RequestChatsGetInfo::new(chat_id.clone()))
+        .await
+        .unwrap();
+    match info.types {
+        EnumChatsGetInfo::Group(group) => assert_eq!(group.title.as_deref(), Some("Team")),
+        other => panic!("Unexpected chat info: {other:?}"),
+    }
+
+    bot.send_api_request(RequestChatsSetTitle::new((
+        chat_id.clone(),
+        "Renamed".to_string(),
+    )))
+    .await
+    .unwrap();
+    bot.send_api_request(
+        RequestChatsResolvePending::new((chat_id.clone(), true))
+            .with_user_id(UserId("bob".to_string())),
+    )
+    .await
+    .unwrap();
+    bot.send_api_request(RequestChatsBlockUser::new((
+        chat_id.clone(),
+        UserId("alice".to_string()),
+    )))
+    .await
+    .unwrap();
+
+    let members = bot
+        .send_api_request(RequestChatsGetMembers::new(chat_id.clone()))
+        .await
+        .unwrap();
+    let members: Vec<_> = members
+        .members
+        .iter()
+        .map(|m| m.user_id.0.as_str())
+        .collect();
+    assert_eq!(members, vec!["admin", "bob"]);
+    let blocked = bot
+        .send_api_request(RequestChatsGetBlockedUsers::new(chat_id.clone()))
+        .await
+        .unwrap();
+    assert_eq!(blocked.users[0].user_id.0, "alice");
+    assert_eq!(
+        server.chat("team").unwrap().title.as_deref(),
+        Some("Renamed")
+    );
+
+    assert!(
+        bot.send_api_request(RequestChatsGetInfo::new(ChatId::from("unknown")))
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_dispatcher_end_to_end() {
+    let server = MockServer::start().await.unwrap();
+    let bot = server.bot().unwrap();
+
+    let echo = |bot: Bot, event: EventMessage| async move {
+        let chat_id = event.event_type.chat().unwrap().chat_id.clone();
+        let text = event.event_type.text().unwrap_or_default().to_string();
+        bot.send_api_request(RequestMessagesSendText::new(chat_id).with_text(text))
+            .await?;
+        Ok(())
+    };
+    let dispatcher = Dispatcher::new().on(Filter::kind(EventKind::NewMessage), echo);
+    let listener = tokio::spawn(async move { bot.run_dispatcher(dispatcher).await });
+
+    server.push_text_message("chat", "alice", "ping");
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while server.requests_for("messages/sendText").is_empty() {
+        assert!(Instant::now() < deadline, "Echo was not sent");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    listener.abort();
+
+    let reply = &server.requests_for("messages/sendText")[0];
+    assert_eq!(reply.param("chatId"), Some("chat"));
+    assert_eq!(reply.param("text"), Some("ping"));
+}
+
+#[tokio::test]
+async fn test_checkpoint_resume() {
+    let server = MockServer::start().await.unwrap();
+    let store = std::sync::Arc::new(MemoryCheckpointStore::new());
+    let checkpoint = Checkpoint::from_arc(store.clone());
+
+    // Failed handler does not move the checkpoint in the default mode
+    server.push_text_message("chat", "alice", "first");
+    let bot = server.bot().unwrap().with_checkpoint(checkpoint.clone());
+    let res = bot
+        .event_listener(|_bot, _events| async {
+            Err(BotError::System("handler failed".to_string()))
+        })
+        .await;
+    assert!(res.is_err());
+    assert_eq!(store.load().await.unwrap(), None);
+
+    // Successful handler saves the last event id
+    let second = server.push_text_message("chat", "alice", "second");
+    let bot = server.bot().unwrap().with_checkpoint(checkpoint.clone());
+    let listener =
+        tokio::spawn(async move { bot.event_listener(|_bot, _events| async { Ok(()) }).await });
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while store.load().await.unwrap() != Some(second) {
+        assert!(Instant::now() < deadline, "Checkpoint was not saved");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    listener.abort();
+
+    // New bot resumes from the checkpoint
+    server.clear_requests();
+    let bot = server.bot().unwrap().with_checkpoint(checkpoint);
+    let listener =
+        tokio::spawn(async move { bot.event_listener(|_bot, _events| async { Ok(()) }).await });
+    while server.requests_for("events/get").is_empty() {
+        assert!(Instant::now() < deadline, "Events were not requested");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    listener.abort();
+    let second = second.to_string();
+    assert_eq!(
+        server.requests_for("events/get")[0].param("lastEventId"),
+        Some(second.as_str())
+    );
+}
+
+#[tokio::test]
+async fn test_failed_batch_is_polled_again() {
+    let server = MockServer::start().await.unwrap();
+    let checkpoint = Checkpoint::from_arc(std::sync::Arc::new(MemoryCheckpointStore::new()));
+    let bot = server.bot().unwrap().with_checkpoint(checkpoint);
+    server.push_text_message("chat", "alice", "first");
+
+    let res = bot
+        .event_listener(|_bot, _events| async {
+            Err(BotError::System("handler failed".to_string()))
+        })
+        .await;
+    assert!(res.is_err());
+    // The event id is not advanced past the failed batch
+    assert_eq!(bot.get_last_event_id(), 0);
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let listener = tokio::spawn({
+        let bot = bot.clone();
+        async move {
+            bot.event_listener(move |_bot, events| {
+                let tx = tx.clone();
+                async move {
+                    for event in events.events {
+                        let _ = tx.send(event.event_type.text().map(str::to_string));
+                    }
+                    Ok(())
+                }
+            })
+            .await
+        }
+    });
+    let text = tokio::time::timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("Failed batch was not polled again");
+    listener.abort();
+    assert_eq!(text.flatten().as_deref(), Some("first"));
+}
+
+#[tokio::test]
+async fn test_dispatcher_keeps_failed_events_with_checkpoint() {
+    let server = MockServer::start().await.unwrap();
+    let store = std::sync::Arc::new(MemoryCheckpointStore::new());
+    let checkpoint = Checkpoint::from_arc(store.clone()).with_mode(CheckpointMode::AfterSuccess);
+    let bot = server.bot().unwrap().with_checkpoint(checkpoint);
+    server.push_text_message("chat", "alice", "first");
+
+    // The default dispatcher drops handler errors, the checkpoint must not pass the failed event
+    let failing = |_bot: Bot, _event: EventMessage| async move {
+        Err(BotError::System("handler failed".to_string()))
+    };
+    let dispatcher = Dispatcher::new().on(Filter::kind(EventKind::NewMessage), failing);
+    let res = tokio::time::timeout(Duration::from_secs(10), bot.run_dispatcher(dispatcher))
+        .await
+        .expect("Handler error was not propagated");
+    assert!(res.is_err());
+    assert_eq!(store.load().await.unwrap(), None);
+    assert_eq!(bot.get_last_event_id(), 0);
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let handler = move |_bot: Bot, event: EventMessage| {
+        let tx = tx.clone();
+        async move {
+            let _ = tx.send(event.event_type.text().map(str::to_string));
+            Ok(())
+        }
+    };
+    let listener = tokio::spawn({
+        let bot = bot.clone();
+        async move {
+            bot.run_dispatcher(Dispatcher::new().on(Filter::kind(EventKind::NewMessage), handler))
+                .await
+        }
+    });
+    let text = tokio::time::timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("Failed event was not dispatched again");
+    listener.abort();
+    assert_eq!(text.flatten().as_deref(), Some("first"));
+}
+
+#[tokio::test]
+async fn test_create_chat_and_add_members() {
+    let server = MockServer::start().await.unwrap();
+    let bot = server.bot().unwrap();
+
+    let created = bot
+        .send_api_request(
+            RequestChatsCreateChat::new("Incident".to_string())
+                .with_about("Outage".to_string())
+                .with_default_role(ChatDefaultRole::Readonly)
+                .with_members(["alice"].into_iter().collect()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.members.len(), 1);
+    assert_eq!(created.members[0].status, MemberAddStatus::Added);
+    let chat = server.chat(created.chat_id.as_str()).unwrap();
+    assert_eq!(chat.chat_type, ChatType::Channel);
+    assert_eq!(chat.title.as_deref(), Some("Incident"));
+
+    let added = bot
+        .send_api_request(RequestChatsMembersAdd::new((
+            created.chat_id.clone(),
+            ["alice", "bob"].into_iter().collect(),
+        )))
+        .await
+        .unwrap();
+    let statuses: Vec<_> = added.members.iter().map(|m| m.status.clone()).collect();
+    assert_eq!(
+        statuses,
+        vec![MemberAddStatus::AlreadyMember, MemberAddStatus::Added]
+    );
+    assert!(
+        server
+            .chat(created.chat_id.as_str())
+            .unwrap()
+            .members
+            .contains(&UserId("bob".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn test_threads() {
+    let server = MockServer::start().await.unwrap();
+    server.add_chat("team", group());
+    let bot = server.bot().unwrap();
+    let root = bot
+        .send_api_request(
+            RequestMessagesSendText::new(ChatId::from("team")).with_text("Topic".to_string()),
+        )
+        .await
+        .unwrap();
+
+    let thread = bot
+        .send_api_request(RequestThreadsAdd::new((
+            ChatId::from("team"),
+            root.msg_id.clone(),
+        )))
+        .await
+        .unwrap();
+    bot.send_api_request(
+        RequestMessagesSendText::new(ChatId::from("team"))
+            .with_text("Reply".to_string())
+            .with_thread_id(thread.thread_id.clone()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        server.messages("team")[1].thread_id,
+        Some(thread.thread_id.clone())
+    );
+
+    bot.send_api_request(
+        RequestThreadsAutosubscribe::new((ChatId::from("team"), true)).with_with_existing(true),
+    )
+    .await
+    .unwrap();
+    assert!(server.chat("team").unwrap().autosubscribe);
+
+    let first = bot
+        .send_api_request(
+            RequestThreadsSubscribersGet::new(thread.thread_id.clone()).with_page_size(1),
+        )
+        .await
+        .unwrap();
+    assert_eq!(first.subscribers.len(), 1);
+    let cursor = first.cursor.expect("next page cursor");
+    let second = bot
+        .send_api_request(
+            RequestThreadsSubscribersGet::new(thread.thread_id)
+                .with_page_size(1)
+                .with_cursor(cursor),
+        )
+        .await
+        .unwrap();
+    assert_eq!(second.subscribers.len(), 1);
+    assert_eq!(second.cursor, None);
+}
+
+#[tokio::test]
+async fn test_events_stream() {
+    use futures::StreamExt;
+
+    let server = MockServer::start().await.unwrap();
+    let bot = server.bot().unwrap();
+    server.push_text_message("chat", "alice", "one");
+    let second = server.push_text_message("chat", "alice", "two");
+
+    {
+        let events = bot.events();
+        let mut events = std::pin::pin!(events);
+        let first = events.next().await.unwrap().unwrap();
+        assert_eq!(first.event_type.text(), Some("one"));
+        let event = events.next().await.unwrap().unwrap();
+        assert_eq!(event.event_id, second);
+        assert_eq!(bot.get_last_event_id(), second);
+
+        // Errors are yielded and polling continues
+        server.fail_next("events/get", "Temporary failure");
+        assert!(matches!(events.next().await, Some(Err(BotError::Api(_)))));
+        let third = server.push_text_message("chat", "alice", "three");
+        let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.event_id, third);
+    }
+
+    // Dropped stream does not poll anymore
+    server.clear_requests();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(server.requests_for("events/get").is_empty());
+}
+
+#[tokio::test]
+async fn test_listener_shutdown_drains_batch() {
+    let server = MockServer::builder()
+        .max_poll_time(Duration::from_secs(30))
+        .start()
+        .await
+        .unwrap();
+    let bot = server.bot().unwrap();
+
+    // Waiting poll is abandoned immediately
+    let shutdown = CancellationToken::new();
+    let listener = tokio::spawn({
+        let bot = bot.clone();
+        let shutdown = shutdown.clone();
+        async move {
+            bot.event_listener_with_shutdown(|_bot, _events| async { Ok(()) }, shutdown)
+                .await
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let started = Instant::now();
+    shutdown.cancel();
+    listener.await.unwrap().unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    // Batch in progress finishes before the listener stops
+    let handled = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let shutdown = CancellationToken::new();
+    let listener = tokio::spawn({
+        let handled = handled.clone();
+        let shutdown = shutdown.clone();
+        async move {
+            bot.event_listener_with_shutdown(
+                move |_bot, _events| {
+                    let handled = handled.clone();
+                    async move {
+                        tokio::time::sleep(Duration::from_millis(300)).await;
+                        handled.store(true, std::sync::atomic::Ordering::SeqCst);
+                        Ok(())
+                    }
+                },
+                shutdown,
+            )
+            .await
+        }
+    });
+    server.push_text_message("chat", "alice", "slow");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    shutdown.cancel();
+    listener.await.unwrap().unwrap();
+    assert!(handled.load(std::sync::atomic::Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn test_listener_shutdown_deadline() {
+    let server = MockServer::start().await.unwrap();
+    let listener = vkteams_bot::config::types::EventListenerConfig {
+        shutdown_timeout_ms: 100,
+        ..Default::default()
+    };
+    let bot = Bot::builder()
+        .token(server.token())
+        .api_url(server.url())
+        .listener(listener)
+        .build()
+        .unwrap();
+
+    let shutdown = CancellationToken::new();
+    let task = tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            bot.event_listener_with_shutdown(
+                |_bot, _events| std::future::pending::<Result<()>>(),
+                shutdown,
+            )
+            .await
+        }
+    });
+    server.push_text_message("chat", "alice", "stuck");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let started = Instant::now();
+    shutdown.cancel();
+    task.await.unwrap().unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
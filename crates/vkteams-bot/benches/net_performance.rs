@@ -39,8 +39,11 @@ fn bench_form_conversion(c: &mut Criterion) {
     let filename = "test.txt".to_string();
     let retryable_form = RetryableMultipartForm::from_content(filename.clone(), filename, content);
 
+    let rt = tokio::runtime::Runtime::new().unwrap();
+
     c.bench_function("form_conversion", |b| {
-        b.iter(|| black_box(retryable_form.to_form()))
+        b.to_async(&rt)
+            .iter(|| async { black_box(retryable_form.to_form().await.unwrap()) })
     });
 }
 
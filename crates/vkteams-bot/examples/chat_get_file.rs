@@ -38,15 +38,16 @@ pub async fn download_files(bot: &Bot, file_id: FileId) -> Result<()> {
     let file_info = bot
         .send_api_request(RequestFilesGetInfo::new(file_id))
         .await?;
-    // Download file data
-    let file_data = file_info.download(reqwest::Client::new()).await?;
-    // Save file to the disk
-    file_save(&file_info.file_name.to_owned(), file_data).await?;
-    Ok(())
-}
-// Save file to the disk
-pub async fn file_save(file_name: &str, file_data: Vec<u8>) -> Result<()> {
-    let file_path = format!("tests/{file_name}");
-    tokio::fs::write(file_path.to_owned(), file_data).await?;
+    // Stream the file to the disk, an interrupted download is resumed on the next run
+    let file_path = std::path::PathBuf::from(format!("tests/{}", file_info.file_name));
+    let downloaded = bot
+        .download_file_to_path(&file_info, &file_path, DownloadOptions::new())
+        .await?;
+    info!(
+        "Saved {} bytes to {} (sha256: {})",
+        downloaded.size,
+        file_path.display(),
+        downloaded.sha256
+    );
     Ok(())
 }
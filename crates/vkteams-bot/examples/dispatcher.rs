@@ -0,0 +1,75 @@
+use tracing::info;
+use vkteams_bot::prelude::*;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load .env file
+    dotenvy::dotenv().expect("unable to load .env file");
+    // Initialize logger
+    let _guard = otlp::init().map_err(|e| BotError::Otlp(e.into()))?;
+    info!("Starting...");
+    // Make bot
+    let bot = Bot::default();
+    // Register handlers, the first matching handler gets the event
+    let dispatcher = Dispatcher::new()
+        .on(Filter::command("/start"), start)
+        .on(Filter::callback_prefix("vote:"), vote)
+        .on(
+            Filter::kind(EventKind::NewMessage).and(Filter::chat_type(ChatType::Private)),
+            echo,
+        )
+        .fallback(skip);
+    // Start event listener with dispatcher
+    bot.run_dispatcher(dispatcher).await
+}
+
+async fn start(bot: Bot, event: EventMessage) -> Result<()> {
+    let Some(chat) = event.event_type.chat() else {
+        return Ok(());
+    };
+    let keyboard = Keyboard::new()
+        .add_button(&ButtonKeyboard::cb(
+            "Yes".to_string(),
+            "vote:yes".to_string(),
+            ButtonStyle::Primary,
+        ))
+        .add_button(&ButtonKeyboard::cb(
+            "No".to_string(),
+            "vote:no".to_string(),
+            ButtonStyle::Attention,
+        ))
+        .to_owned();
+    let parser = MessageTextParser::new().add(MessageTextFormat::Plain("Do you like it?".into()));
+    bot.send_api_request(
+        RequestMessagesSendText::new(chat.chat_id.clone())
+            .set_text(parser)?
+            .set_keyboard(keyboard)?,
+    )
+    .await?;
+    Ok(())
+}
+
+async fn vote(bot: Bot, event: EventMessage) -> Result<()> {
+    if let EventType::CallbackQuery(payload) = event.event_type {
+        bot.send_api_request(
+            RequestMessagesAnswerCallbackQuery::new(payload.query_id)
+                .with_text(format!("You voted: {}", payload.callback_data)),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+async fn echo(bot: Bot, event: EventMessage) -> Result<()> {
+    if let (Some(chat), Some(text)) = (event.event_type.chat(), event.event_type.text()) {
+        let parser = MessageTextParser::new().add(MessageTextFormat::Plain(text.to_string()));
+        bot.send_api_request(RequestMessagesSendText::new(chat.chat_id.clone()).set_text(parser)?)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn skip(_: Bot, event: EventMessage) -> Result<()> {
+    info!("Skipped event {}", event.event_id);
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use futures::StreamExt;
+use std::time::Duration;
+use tracing::{info, warn};
+use vkteams_bot::prelude::*;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load .env file
+    dotenvy::dotenv().expect("unable to load .env file");
+    // Initialize logger
+    let _guard = otlp::init().map_err(|e| BotError::Otlp(e.into()))?;
+    info!("Starting...");
+    // Make bot
+    let bot = Bot::default();
+    // Take events from the stream until Ctrl+C, polling stops when the stream is dropped
+    let mut events = std::pin::pin!(bot.events());
+    loop {
+        tokio::select! {
+            event = events.next() => match event {
+                Some(Ok(event)) => println!("{}", serde_json::to_string(&event)?),
+                Some(Err(e)) => warn!("Error getting events: {e}"),
+                None => break,
+            },
+            _ = tokio::time::sleep(Duration::from_secs(300)) => info!("No events for 5 minutes"),
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,89 @@
+#![allow(unused_parens)]
+//! Create group or channel chat method `chats/createChat`
+//! [More info](https://teams.vk.com/botapi/#/chats/get_chats_createChat)
+use crate::api::types::*;
+bot_api_method! {
+    method   = "chats/createChat",
+    request  = RequestChatsCreateChat {
+        required {
+            name: String,
+        },
+        optional {
+            about: String,
+            rules: String,
+            members: MemberList,
+            public: bool,
+            default_role: ChatDefaultRole,
+            join_moderation: bool,
+        }
+    },
+    response = ResponseChatsCreateChat {
+        #[serde(alias = "sn")]
+        chat_id: ChatId,
+        #[serde(default)]
+        members: Vec<MemberAddResult>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::types::{ChatDefaultRole, MemberAddStatus, MemberList};
+    use serde_json::json;
+
+    #[test]
+    fn test_request_chats_create_chat_serialize() {
+        let req = RequestChatsCreateChat::new("Incident".to_string())
+            .with_about("About".to_string())
+            .with_members(["u1", "u2"].into_iter().collect::<MemberList>())
+            .with_public(false)
+            .with_default_role(ChatDefaultRole::Readonly)
+            .with_join_moderation(true);
+        let val = serde_json::to_value(&req).unwrap();
+        assert_eq!(val["name"], "Incident");
+        assert_eq!(val["about"], "About");
+        assert_eq!(val["members"], r#"[{"sn":"u1"},{"sn":"u2"}]"#);
+        assert_eq!(val["public"], false);
+        assert_eq!(val["defaultRole"], "readonly");
+        assert_eq!(val["joinModeration"], true);
+        assert!(val.get("rules").is_none());
+    }
+
+    #[test]
+    fn test_request_chats_create_chat_query_string() {
+        let req = RequestChatsCreateChat::new("Team".to_string())
+            .with_members(["u1"].into_iter().collect::<MemberList>());
+        let query = serde_url_params::to_string(&req).unwrap();
+        assert!(query.starts_with("name=Team&members="));
+    }
+
+    #[test]
+    fn test_request_chats_create_chat_missing_name() {
+        let val = json!({"about": "a"});
+        let res: Result<RequestChatsCreateChat, _> = serde_json::from_value(val);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_response_chats_create_chat_deserialize() {
+        let val = json!({
+            "sn": "c1@chat.agent",
+            "members": [
+                {"sn": "u1", "status": "added"},
+                {"sn": "u2", "status": "failed", "error": "User not found"}
+            ]
+        });
+        let resp: ResponseChatsCreateChat = serde_json::from_value(val).unwrap();
+        assert_eq!(resp.chat_id.0, "c1@chat.agent");
+        assert_eq!(resp.members[0].status, MemberAddStatus::Added);
+        assert_eq!(resp.members[1].error.as_deref(), Some("User not found"));
+    }
+
+    #[test]
+    fn test_response_chats_create_chat_without_members() {
+        let val = json!({"chatId": "c2"});
+        let resp: ResponseChatsCreateChat = serde_json::from_value(val).unwrap();
+        assert_eq!(resp.chat_id.0, "c2");
+        assert!(resp.members.is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+//! Add members to the chat method `chats/members/add`
+//! [More info](https://teams.vk.com/botapi/#/chats/get_chats_members_add)
+use crate::api::types::*;
+bot_api_method! {
+    method   = "chats/members/add",
+    request  = RequestChatsMembersAdd {
+        required {
+            chat_id: ChatId,
+            members: MemberList,
+        },
+        optional {}
+    },
+    response = ResponseChatsMembersAdd {
+        #[serde(default)]
+        members: Vec<MemberAddResult>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::types::{ChatId, MemberAddStatus, MemberList, UserId};
+    use serde_json::json;
+
+    #[test]
+    fn test_request_chats_members_add_serialize() {
+        let req = RequestChatsMembersAdd::new((
+            ChatId::from("c1"),
+            ["u1", "u2"].into_iter().collect::<MemberList>(),
+        ));
+        let val = serde_json::to_value(&req).unwrap();
+        assert_eq!(val["chatId"], "c1");
+        assert_eq!(val["members"], r#"[{"sn":"u1"},{"sn":"u2"}]"#);
+    }
+
+    #[test]
+    fn test_request_chats_members_add_deserialize() {
+        let val = json!({"chatId": "c2", "members": "[{\"sn\": \"u3\"}]"});
+        let req: RequestChatsMembersAdd = serde_json::from_value(val).unwrap();
+        assert_eq!(req.members.0, vec![UserId("u3".to_string())]);
+    }
+
+    #[test]
+    fn test_request_chats_members_add_missing_members() {
+        let val = json!({"chatId": "c3"});
+        let res: Result<RequestChatsMembersAdd, _> = serde_json::from_value(val);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_response_chats_members_add_deserialize() {
+        let val = json!({"members": [
+            {"sn": "u1", "status": "added"},
+            {"sn": "u2", "status": "pending"},
+            {"sn": "u3", "status": "alreadyMember"}
+        ]});
+        let resp: ResponseChatsMembersAdd = serde_json::from_value(val).unwrap();
+        let statuses: Vec<_> = resp.members.iter().map(|m| m.status.clone()).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                MemberAddStatus::Added,
+                MemberAddStatus::Pending,
+                MemberAddStatus::AlreadyMember
+            ]
+        );
+    }
+
+    #[test]
+    fn test_response_chats_members_add_empty() {
+        let resp: ResponseChatsMembersAdd = serde_json::from_value(json!({})).unwrap();
+        assert!(resp.members.is_empty());
+    }
+}
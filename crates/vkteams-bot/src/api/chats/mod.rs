@@ -1,10 +1,12 @@
 pub mod avatar_set;
 pub mod block_user;
+pub mod create_chat;
 pub mod get_admins;
 pub mod get_blocked_users;
 pub mod get_info;
 pub mod get_members;
 pub mod get_pending_users;
+pub mod members_add;
 pub mod members_delete;
 pub mod pin_message;
 pub mod resolve_pendings;
@@ -16,7 +18,8 @@ pub mod unblock_user;
 pub mod unpin_message;
 
 pub use {
-    avatar_set::*, block_user::*, get_admins::*, get_blocked_users::*, get_info::*, get_members::*,
-    get_pending_users::*, members_delete::*, pin_message::*, resolve_pendings::*, send_action::*,
-    set_about::*, set_rules::*, set_title::*, unblock_user::*, unpin_message::*,
+    avatar_set::*, block_user::*, create_chat::*, get_admins::*, get_blocked_users::*, get_info::*,
+    get_members::*, get_pending_users::*, members_add::*, members_delete::*, pin_message::*,
+    resolve_pendings::*, send_action::*, set_about::*, set_rules::*, set_title::*, unblock_user::*,
+    unpin_message::*,
 };
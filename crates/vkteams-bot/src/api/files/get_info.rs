@@ -30,10 +30,28 @@ impl ResponseFilesGetInfo {
     /// ## Parameters
     /// - `client`: [`reqwest::Client`] - reqwest client
     pub async fn download(&self, client: reqwest::Client) -> Result<Vec<u8>> {
+        get_bytes_response(client, self.download_url()?).await
+    }
+
+    /// Parsed file URL
+    ///
+    /// ## Errors
+    /// - `BotError::Validation` - URL is empty
+    /// - `BotError::Url` - URL parsing error
+    pub fn download_url(&self) -> Result<Url> {
         if self.url.is_empty() {
             return Err(BotError::Validation("URL is empty".to_string()));
         }
-        get_bytes_response(client, Url::parse(&self.url)?).await
+        Ok(Url::parse(&self.url)?)
+    }
+
+    /// Download options checking the file size, if the API reported it
+    pub fn download_options(&self, options: DownloadOptions) -> DownloadOptions {
+        if options.expected_size().is_none() && self.file_size > 0 {
+            options.with_expected_size(self.file_size.into())
+        } else {
+            options
+        }
     }
 }
 
@@ -18,6 +18,7 @@ bot_api_method! {
             inline_keyboard_markup: String,
             format: MessageFormat,
             parse_mode: ParseMode,
+            thread_id: ThreadId,
         }
     },
     response = ResponseMessagesSendFile {
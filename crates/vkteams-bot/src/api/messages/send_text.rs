@@ -16,6 +16,7 @@ bot_api_method! {
             inline_keyboard_markup: String,
             format: MessageFormat,
             parse_mode: ParseMode,
+            thread_id: ThreadId,
         }
     },
     response = ResponseMessagesSendText {
@@ -39,6 +40,63 @@ impl MessageTextSetters for RequestMessagesSendText {
     }
 }
 
+impl Bot {
+    /// Send text of any length, splitting it into several `messages/sendText` requests
+    /// with [`split_text`]
+    ///
+    /// The first part keeps the reply and forward settings of the request,
+    /// the inline keyboard is attached to the last part.
+    /// With [`SplitOptions::reply_chain`] the other parts reply to the first one.
+    /// ## Parameters
+    /// - `request`: [`RequestMessagesSendText`] with the whole text
+    /// - `options`: [`SplitOptions`]
+    ///
+    /// ## Errors
+    /// - `BotError::Validation` - text with `format` ranges can't be split
+    /// - errors of [`Bot::send_api_request`], parts sent before the error are not deleted
+    pub async fn send_text_split(
+        &self,
+        request: RequestMessagesSendText,
+        options: SplitOptions,
+    ) -> Result<Vec<ResponseMessagesSendText>> {
+        let parts = match &request.text {
+            Some(text) => split_text(text, request.parse_mode, options.max_length),
+            None => vec![],
+        };
+        if parts.len() <= 1 {
+            return Ok(vec![self.send_api_request(request).await?]);
+        }
+        if request.format.is_some() {
+            return Err(BotError::Validation(
+                "Text with format ranges can't be split".to_string(),
+            ));
+        }
+
+        let last = parts.len() - 1;
+        let mut responses: Vec<ResponseMessagesSendText> = Vec::with_capacity(parts.len());
+        for (i, text) in parts.into_iter().enumerate() {
+            let mut part = RequestMessagesSendText {
+                text: Some(text),
+                inline_keyboard_markup: None,
+                ..request.clone()
+            };
+            if i > 0 {
+                part.reply_msg_id = match responses.first() {
+                    Some(first) if options.reply_chain => Some(first.msg_id.clone()),
+                    _ => None,
+                };
+                part.forward_chat_id = None;
+                part.forward_msg_id = None;
+            }
+            if i == last {
+                part.inline_keyboard_markup = request.inline_keyboard_markup.clone();
+            }
+            responses.push(self.send_api_request(part).await?);
+        }
+        Ok(responses)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
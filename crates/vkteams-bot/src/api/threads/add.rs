@@ -0,0 +1,52 @@
+//! Create a thread on the message method `threads/add`
+//! [More info](https://teams.vk.com/botapi/#/threads/post_threads_add)
+use crate::api::types::*;
+bot_api_method! {
+    method   = "threads/add",
+    request  = RequestThreadsAdd {
+        required {
+            chat_id: ChatId,
+            msg_id: MsgId,
+        },
+        optional {}
+    },
+    response = ResponseThreadsAdd {
+        thread_id: ThreadId,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::types::{ChatId, MsgId, ThreadId};
+    use serde_json::json;
+
+    #[test]
+    fn test_request_threads_add_serialize() {
+        let req = RequestThreadsAdd::new((ChatId::from("c1"), MsgId("m1".to_string())));
+        let val = serde_json::to_value(&req).unwrap();
+        assert_eq!(val["chatId"], "c1");
+        assert_eq!(val["msgId"], "m1");
+    }
+
+    #[test]
+    fn test_request_threads_add_missing_msg_id() {
+        let val = json!({"chatId": "c2"});
+        let res: Result<RequestThreadsAdd, _> = serde_json::from_value(val);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_response_threads_add_deserialize() {
+        let val = json!({"threadId": "t1"});
+        let resp: ResponseThreadsAdd = serde_json::from_value(val).unwrap();
+        assert_eq!(resp.thread_id, ThreadId("t1".to_string()));
+    }
+
+    #[test]
+    fn test_response_threads_add_missing_thread_id() {
+        let val = json!({});
+        let res: Result<ResponseThreadsAdd, _> = serde_json::from_value(val);
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,48 @@
+//! Toggle thread auto-subscribe in the chat method `threads/autosubscribe`
+//! [More info](https://teams.vk.com/botapi/#/threads/get_threads_autosubscribe)
+use crate::api::types::*;
+bot_api_method! {
+    method   = "threads/autosubscribe",
+    request  = RequestThreadsAutosubscribe {
+        required {
+            chat_id: ChatId,
+            enable: bool,
+        },
+        optional {
+            with_existing: bool,
+        }
+    },
+    response = ResponseThreadsAutosubscribe {},
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::types::ChatId;
+    use serde_json::json;
+
+    #[test]
+    fn test_request_threads_autosubscribe_serialize() {
+        let req =
+            RequestThreadsAutosubscribe::new((ChatId::from("c1"), true)).with_with_existing(false);
+        let val = serde_json::to_value(&req).unwrap();
+        assert_eq!(val["chatId"], "c1");
+        assert_eq!(val["enable"], true);
+        assert_eq!(val["withExisting"], false);
+    }
+
+    #[test]
+    fn test_request_threads_autosubscribe_deserialize() {
+        let val = json!({"chatId": "c2", "enable": false});
+        let req: RequestThreadsAutosubscribe = serde_json::from_value(val).unwrap();
+        assert!(!req.enable);
+        assert_eq!(req.with_existing, None);
+    }
+
+    #[test]
+    fn test_response_threads_autosubscribe_serialize_deserialize() {
+        let resp = ResponseThreadsAutosubscribe {};
+        let val = serde_json::to_value(&resp).unwrap();
+        let _: ResponseThreadsAutosubscribe = serde_json::from_value(val).unwrap();
+    }
+}
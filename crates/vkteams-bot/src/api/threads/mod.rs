@@ -0,0 +1,5 @@
+pub mod add;
+pub mod autosubscribe;
+pub mod subscribers_get;
+
+pub use {add::*, autosubscribe::*, subscribers_get::*};
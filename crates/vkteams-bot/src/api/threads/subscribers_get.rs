@@ -0,0 +1,71 @@
+#![allow(unused_parens)]
+//! # Get thread subscribers method `threads/subscribers/get`
+//! [More info](https://teams.vk.com/botapi/#/threads/get_threads_subscribers_get)
+use crate::api::types::*;
+bot_api_method! {
+    method   = "threads/subscribers/get",
+    request  = RequestThreadsSubscribersGet {
+        required {
+            thread_id: ThreadId,
+        },
+        optional {
+            page_size: u32,
+            cursor: String,
+        }
+    },
+    response = ResponseThreadsSubscribersGet {
+        #[serde(default)]
+        subscribers: Vec<Subscriber>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cursor: Option<String>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::types::{Subscriber, ThreadId, UserId};
+    use serde_json::json;
+
+    #[test]
+    fn test_request_threads_subscribers_get_serialize() {
+        let req = RequestThreadsSubscribersGet::new(ThreadId("t1".to_string()))
+            .with_page_size(50)
+            .with_cursor("next".to_string());
+        let val = serde_json::to_value(&req).unwrap();
+        assert_eq!(val["threadId"], "t1");
+        assert_eq!(val["pageSize"], 50);
+        assert_eq!(val["cursor"], "next");
+    }
+
+    #[test]
+    fn test_request_threads_subscribers_get_no_optional() {
+        let req = RequestThreadsSubscribersGet::new(ThreadId("t2".to_string()));
+        let val = serde_json::to_value(&req).unwrap();
+        assert!(val.get("pageSize").is_none());
+        assert!(val.get("cursor").is_none());
+    }
+
+    #[test]
+    fn test_response_threads_subscribers_get_serialize_deserialize() {
+        let resp = ResponseThreadsSubscribersGet {
+            subscribers: vec![Subscriber {
+                user_id: UserId("u1".to_string()),
+            }],
+            cursor: Some("c".to_string()),
+        };
+        let val = serde_json::to_value(&resp).unwrap();
+        assert_eq!(val["subscribers"][0]["userId"], "u1");
+        let resp2: ResponseThreadsSubscribersGet = serde_json::from_value(val).unwrap();
+        assert_eq!(resp2.subscribers.len(), 1);
+        assert_eq!(resp2.cursor.as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn test_response_threads_subscribers_get_last_page() {
+        let val = json!({"subscribers": []});
+        let resp: ResponseThreadsSubscribersGet = serde_json::from_value(val).unwrap();
+        assert!(resp.subscribers.is_empty());
+        assert_eq!(resp.cursor, None);
+    }
+}
@@ -1,12 +1,12 @@
 //! API types
 use crate::error::{ApiError, BotError, Result};
-use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde::{Deserialize, Deserializer, Serialize, de::DeserializeOwned};
 use std::borrow::Cow;
 use std::fmt::*;
 use std::time::Duration;
 #[cfg(feature = "templates")]
 use tera::{Context, Tera};
-use tracing::debug;
+use tracing::{debug, warn};
 
 /// Environment variable name for bot API URL
 pub const VKTEAMS_BOT_API_URL: &str = "VKTEAMS_BOT_API_URL";
@@ -22,9 +22,10 @@ pub const POLL_DURATION: &Duration = &Duration::from_secs(POLL_TIME + 10);
 
 pub const SERVICE_NAME: &str = "BOT";
 /// Supported API versions
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub enum APIVersionUrl {
     /// default V1
+    #[default]
     V1,
 }
 /// Supported API HTTP methods
@@ -57,7 +58,7 @@ pub trait BotRequest {
 /// API event id type
 pub type EventId = u32;
 /// Message text struct
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Clone, Debug, PartialEq)]
 pub enum MessageTextFormat {
     /// Plain text
     Plain(String),
@@ -164,6 +165,32 @@ pub enum EventType {
     CallbackQuery(Box<EventPayloadCallbackQuery>),
     #[default]
     None,
+    /// Event type not supported by this version, with raw payload.
+    /// Also used for a known type whose payload does not match the expected schema,
+    /// a warning is logged in this case.
+    #[serde(untagged, deserialize_with = "deserialize_unknown_event")]
+    Unknown {
+        #[serde(rename = "type")]
+        event_type: String,
+        #[serde(default)]
+        payload: serde_json::Value,
+    },
+}
+/// Kind of [`EventType`] without payload
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum EventKind {
+    NewMessage,
+    EditedMessage,
+    DeleteMessage,
+    PinnedMessage,
+    UnpinnedMessage,
+    NewChatMembers,
+    LeftChatMembers,
+    CallbackQuery,
+    None,
+    /// Event type not supported by this version
+    Unknown,
 }
 /// Message payload event type newMessage
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
@@ -179,6 +206,13 @@ pub struct EventPayloadNewMessage {
     #[serde(default)]
     pub parts: Vec<MessageParts>,
     pub timestamp: Timestamp,
+    /// Parent message of the thread, if the message is sent to a thread
+    #[serde(
+        default,
+        alias = "parent_topic",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parent_topic: Option<ParentTopic>,
 }
 /// Message payload event type editedMessage
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
@@ -192,6 +226,13 @@ pub struct EventPayloadEditedMessage {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub format: Option<MessageFormat>,
     pub edited_timestamp: Timestamp,
+    /// Parent message of the thread, if the message is in a thread
+    #[serde(
+        default,
+        alias = "parent_topic",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parent_topic: Option<ParentTopic>,
 }
 /// Message payload event type deleteMessage
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
@@ -271,7 +312,66 @@ pub enum MessagePartsType {
     Forward(Box<MessagePartsPayloadForward>),
     Reply(Box<MessagePartsPayloadReply>),
     InlineKeyboardMarkup(Vec<Vec<MessagePartsPayloadInlineKeyboard>>),
+    /// Message part type not supported by this version, with raw payload.
+    /// Also used for a known type whose payload does not match the expected schema,
+    /// a warning is logged in this case.
+    #[serde(untagged, deserialize_with = "deserialize_unknown_part")]
+    Unknown {
+        #[serde(rename = "type")]
+        part_type: String,
+        #[serde(default)]
+        payload: serde_json::Value,
+    },
+}
+/// Tags of the known [`MessagePartsType`] variants
+const MESSAGE_PART_TYPES: &[&str] = &[
+    "sticker",
+    "mention",
+    "voice",
+    "file",
+    "forward",
+    "reply",
+    "inlineKeyboardMarkup",
+];
+
+/// Raw `type` and `payload` of an event or a message part
+#[derive(Deserialize)]
+struct RawTagged {
+    #[serde(rename = "type")]
+    tag: String,
+    #[serde(default)]
+    payload: serde_json::Value,
+}
+
+/// Deserialize [`EventType::Unknown`], warn if the type is known but its payload is malformed
+fn deserialize_unknown_event<'de, D>(
+    deserializer: D,
+) -> std::result::Result<(String, serde_json::Value), D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let RawTagged { tag, payload } = RawTagged::deserialize(deserializer)?;
+    let kind = serde_json::from_value::<EventKind>(serde_json::Value::String(tag.clone()));
+    if matches!(kind, Ok(kind) if kind != EventKind::Unknown) {
+        warn!("Malformed payload of event type {tag}, deserialized as unknown event");
+    }
+    Ok((tag, payload))
 }
+
+/// Deserialize [`MessagePartsType::Unknown`], warn if the type is known but its payload is malformed
+fn deserialize_unknown_part<'de, D>(
+    deserializer: D,
+) -> std::result::Result<(String, serde_json::Value), D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let RawTagged { tag, payload } = RawTagged::deserialize(deserializer)?;
+    if MESSAGE_PART_TYPES.contains(&tag.as_str()) {
+        warn!("Malformed payload of message part type {tag}, deserialized as unknown part");
+    }
+    Ok((tag, payload))
+}
+
 /// Message parts payload sticker
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -402,6 +502,9 @@ pub struct FileId(pub String);
 /// Query id struct
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Hash, Eq)]
 pub struct QueryId(pub String);
+/// Thread id struct
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Hash, Eq)]
+pub struct ThreadId(pub String);
 /// Timestamp struct
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Hash, Eq)]
 pub struct Timestamp(pub u32);
@@ -433,7 +536,7 @@ pub enum Languages {
     En,
 }
 /// Chat types
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub enum ChatType {
     #[default]
@@ -489,6 +592,28 @@ pub struct Member {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub admin: Option<bool>,
 }
+/// Thread subscriber struct
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscriber {
+    pub user_id: UserId,
+}
+/// Parent message of the thread the message belongs to
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ParentTopic {
+    /// Chat of the parent message
+    pub chat_id: ChatId,
+    /// Parent message id
+    #[serde(default, alias = "messageId", skip_serializing_if = "Option::is_none")]
+    pub msg_id: Option<MsgId>,
+    /// Thread id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<ThreadId>,
+    /// Topic type, e.g. `thread`
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub topic_type: Option<String>,
+}
 /// Sn struct for members
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -496,6 +621,46 @@ pub struct Sn {
     pub sn: String,
     pub user_id: UserId,
 }
+/// List of users passed in the `members` parameter
+///
+/// Serialized as a JSON array `[{"sn": "<user id>"}, ...]` in a single query parameter.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemberList(pub Vec<UserId>);
+/// Default role of the new chat members
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ChatDefaultRole {
+    #[default]
+    Member,
+    /// Members can only read messages, used for channels
+    Readonly,
+}
+/// Status of the user in the `members` result
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum MemberAddStatus {
+    /// User is added to the chat
+    #[default]
+    Added,
+    /// User must be approved by the chat admins
+    Pending,
+    /// User is already a member of the chat
+    AlreadyMember,
+    /// User is not added, see `error`
+    Failed,
+    #[serde(other)]
+    Unknown,
+}
+/// Result of adding a user to the chat
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberAddResult {
+    #[serde(alias = "sn")]
+    pub user_id: UserId,
+    pub status: MemberAddStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
 /// Photo url struct
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct PhotoUrl {
@@ -545,7 +710,12 @@ where
                 } else {
                     debug!("Answer is NOT ok and error description is provided");
                 }
-                Err(BotError::Api(ApiError { description }))
+                let error = ApiError { description };
+                if error.is_rate_limited() {
+                    // Retried by the bot like HTTP 429
+                    return Err(BotError::RateLimited(None));
+                }
+                Err(BotError::Api(error))
             }
         }
     }
@@ -648,16 +818,238 @@ impl Default for Keyboard {
     }
 }
 
+impl ChatType {
+    /// Chat type as it is sent by the API in [`Chat`]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatType::Private => "private",
+            ChatType::Group => "group",
+            ChatType::Channel => "channel",
+        }
+    }
+}
+
+impl EventType {
+    /// Get [`EventKind`] of the event
+    pub fn kind(&self) -> EventKind {
+        match self {
+            EventType::NewMessage(_) => EventKind::NewMessage,
+            EventType::EditedMessage(_) => EventKind::EditedMessage,
+            EventType::DeleteMessage(_) => EventKind::DeleteMessage,
+            EventType::PinnedMessage(_) => EventKind::PinnedMessage,
+            EventType::UnpinnedMessage(_) => EventKind::UnpinnedMessage,
+            EventType::NewChatMembers(_) => EventKind::NewChatMembers,
+            EventType::LeftChatMembers(_) => EventKind::LeftChatMembers,
+            EventType::CallbackQuery(_) => EventKind::CallbackQuery,
+            EventType::None => EventKind::None,
+            EventType::Unknown { .. } => EventKind::Unknown,
+        }
+    }
+
+    /// Get [`Chat`] where the event happened
+    pub fn chat(&self) -> Option<&Chat> {
+        match self {
+            EventType::NewMessage(p) => Some(&p.chat),
+            EventType::EditedMessage(p) => Some(&p.chat),
+            EventType::DeleteMessage(p) => Some(&p.chat),
+            EventType::PinnedMessage(p) => Some(&p.chat),
+            EventType::UnpinnedMessage(p) => Some(&p.chat),
+            EventType::NewChatMembers(p) => Some(&p.chat),
+            EventType::LeftChatMembers(p) => Some(&p.chat),
+            EventType::CallbackQuery(p) => Some(&p.message.chat),
+            EventType::None | EventType::Unknown { .. } => None,
+        }
+    }
+
+    /// Get time of the event, if any
+    /// The edit time for the edited message
+    pub fn timestamp(&self) -> Option<&Timestamp> {
+        match self {
+            EventType::NewMessage(p) => Some(&p.timestamp),
+            EventType::EditedMessage(p) => Some(&p.edited_timestamp),
+            EventType::DeleteMessage(p) => Some(&p.timestamp),
+            EventType::PinnedMessage(p) => Some(&p.timestamp),
+            EventType::UnpinnedMessage(p) => Some(&p.timestamp),
+            _ => None,
+        }
+    }
+
+    /// Get author of the event, if any
+    pub fn from(&self) -> Option<&From> {
+        match self {
+            EventType::NewMessage(p) => Some(&p.from),
+            EventType::EditedMessage(p) => Some(&p.from),
+            EventType::PinnedMessage(p) => Some(&p.from),
+            EventType::NewChatMembers(p) => Some(&p.added_by),
+            EventType::LeftChatMembers(p) => p.removed_by.as_ref(),
+            EventType::CallbackQuery(p) => Some(&p.from),
+            _ => None,
+        }
+    }
+
+    /// Get message text of the event, if any
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            EventType::NewMessage(p) => Some(&p.text),
+            EventType::EditedMessage(p) => Some(&p.text),
+            EventType::PinnedMessage(p) => Some(&p.text),
+            _ => None,
+        }
+    }
+
+    /// Get callback data of `callbackQuery` event
+    pub fn callback_data(&self) -> Option<&str> {
+        match self {
+            EventType::CallbackQuery(p) => Some(&p.callback_data),
+            _ => None,
+        }
+    }
+}
+
 impl std::fmt::Display for UserId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
+#[derive(Serialize, Deserialize)]
+struct MemberSn {
+    sn: UserId,
+}
+
+impl<T: Into<String>> FromIterator<T> for MemberList {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(iter.into_iter().map(|id| UserId(id.into())).collect())
+    }
+}
+
+impl Serialize for MemberList {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let members: Vec<MemberSn> = self
+            .0
+            .iter()
+            .map(|user_id| MemberSn {
+                sn: user_id.clone(),
+            })
+            .collect();
+        let json = serde_json::to_string(&members).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&json)
+    }
+}
+
+impl<'de> Deserialize<'de> for MemberList {
+    /// Accepts the JSON string sent in the query or an already parsed array
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Json(String),
+            List(Vec<MemberSn>),
+        }
+        let members = match Raw::deserialize(deserializer)? {
+            Raw::Json(json) => serde_json::from_str(&json).map_err(serde::de::Error::custom)?,
+            Raw::List(members) => members,
+        };
+        Ok(Self(members.into_iter().map(|member| member.sn).collect()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Capture warnings logged while running the closure
+    #[cfg(feature = "otlp")]
+    fn capture_warnings(f: impl FnOnce()) -> String {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::WARN)
+            .with_writer({
+                let buffer = buffer.clone();
+                move || buffer.clone()
+            })
+            .finish();
+        tracing::subscriber::with_default(subscriber, f);
+        String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap()
+    }
+
+    #[cfg(feature = "otlp")]
+    #[test]
+    fn test_malformed_known_type_is_logged() {
+        let logs = capture_warnings(|| {
+            let event: EventType =
+                serde_json::from_str(r#"{"type": "newMessage", "payload": {"msgId": 1}}"#).unwrap();
+            assert!(matches!(
+                event,
+                EventType::Unknown { ref event_type, .. } if event_type == "newMessage"
+            ));
+            let part: MessagePartsType =
+                serde_json::from_str(r#"{"type": "sticker", "payload": {}}"#).unwrap();
+            assert!(matches!(
+                part,
+                MessagePartsType::Unknown { ref part_type, .. } if part_type == "sticker"
+            ));
+        });
+        assert!(logs.contains("Malformed payload of event type newMessage"));
+        assert!(logs.contains("Malformed payload of message part type sticker"));
+
+        // New types are expected to be unknown
+        let logs = capture_warnings(|| {
+            let event: EventType =
+                serde_json::from_str(r#"{"type": "changedChatInfo", "payload": {}}"#).unwrap();
+            assert_eq!(event.kind(), EventKind::Unknown);
+            let part: MessagePartsType =
+                serde_json::from_str(r#"{"type": "poll", "payload": {}}"#).unwrap();
+            assert!(matches!(part, MessagePartsType::Unknown { .. }));
+        });
+        assert!(logs.is_empty());
+    }
+
+    #[test]
+    fn test_member_list_serialize() {
+        let members: MemberList = ["u1", "u2"].into_iter().collect();
+        let val = serde_json::to_value(&members).unwrap();
+        assert_eq!(val, r#"[{"sn":"u1"},{"sn":"u2"}]"#);
+        let back: MemberList = serde_json::from_value(val).unwrap();
+        assert_eq!(back, members);
+        let parsed: MemberList = serde_json::from_str(r#"[{"sn": "u3"}]"#).unwrap();
+        assert_eq!(parsed.0, vec![UserId("u3".to_string())]);
+    }
+
+    #[test]
+    fn test_member_add_result_deserialize() {
+        let res: MemberAddResult =
+            serde_json::from_str(r#"{"sn": "u1", "status": "failed", "error": "not found"}"#)
+                .unwrap();
+        assert_eq!(res.user_id.0, "u1");
+        assert_eq!(res.status, MemberAddStatus::Failed);
+        assert_eq!(res.error.as_deref(), Some("not found"));
+        let res: MemberAddResult =
+            serde_json::from_str(r#"{"userId": "u2", "status": "invited"}"#).unwrap();
+        assert_eq!(res.status, MemberAddStatus::Unknown);
+    }
+
     #[test]
     fn test_chat_id_display() {
         let id = ChatId::from("test_id");
@@ -772,6 +1164,16 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[test]
+    fn test_apiresponsewrapper_from_rate_limited_error() {
+        let wrap = ApiResponseWrapper::<i32>::Error {
+            ok: false,
+            description: "Too many requests".to_string(),
+        };
+        let res: Result<i32> = wrap.into();
+        assert!(matches!(res, Err(BotError::RateLimited(None))));
+    }
+
     #[test]
     fn test_message_text_format_variants() {
         let _ = MessageTextFormat::Plain("text".to_string());
@@ -793,4 +1195,30 @@ mod tests {
     fn test_eventtype_default() {
         assert_eq!(EventType::default(), EventType::None);
     }
+
+    #[test]
+    fn test_eventtype_accessors() {
+        let payload = EventPayloadNewMessage {
+            text: "/start now".to_string(),
+            chat: Chat {
+                chat_id: ChatId::from("chat"),
+                title: None,
+                chat_type: "private".to_string(),
+            },
+            from: From {
+                user_id: UserId("user".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let event = EventType::NewMessage(Box::new(payload));
+        assert_eq!(event.kind(), EventKind::NewMessage);
+        assert_eq!(event.chat().unwrap().chat_id.as_str(), "chat");
+        assert_eq!(event.from().unwrap().user_id.0, "user");
+        assert_eq!(event.text(), Some("/start now"));
+        assert_eq!(event.callback_data(), None);
+
+        assert_eq!(EventType::None.kind(), EventKind::None);
+        assert!(EventType::None.chat().is_none());
+    }
 }
@@ -0,0 +1,406 @@
+//! # Incoming message formatting
+//! Convert the text and [`MessageFormat`] ranges of incoming messages back to [`MessageTextFormat`],
+//! so messages can be quoted, forwarded or archived with their formatting.
+//!
+//! Offsets and lengths are counted in UTF-16 code units.
+//! [`MessageTextFormat`] has one style per text fragment, so overlapping ranges are flattened:
+//! - pre, quote and ordered list blocks, inline code, links and mentions take their whole range,
+//!   styles inside them are dropped
+//! - for the other overlapping styles the innermost range wins
+//! - ranges outside of the text are clipped, empty ranges are ignored
+//!
+//! ```
+//! use vkteams_bot::prelude::*;
+//!
+//! # fn main() -> Result<()> {
+//! let format = MessageFormat {
+//!     bold: Some(vec![MessageFormatStruct { offset: 0, length: 5 }]),
+//!     ..Default::default()
+//! };
+//! let parser = MessageTextParser::from_message_format("Alert: disk is full", Some(&format));
+//! assert_eq!(parser.parse()?.0, "<b>Alert</b>: disk is full");
+//! let parser = parser.with_parse_mode(ParseMode::MarkdownV2);
+//! assert_eq!(parser.parse()?.0, "*Alert*: disk is full");
+//! # Ok(())
+//! # }
+//! ```
+use crate::api::types::*;
+use reqwest::Url;
+
+impl MessageTextParser {
+    /// Create HTML parser from the text and formatting of an incoming message
+    pub fn from_message_format(text: &str, format: Option<&MessageFormat>) -> Self {
+        let text = match format {
+            Some(format) => format.to_text_formats(text),
+            None if text.is_empty() => vec![],
+            None => vec![MessageTextFormat::Plain(text.to_string())],
+        };
+        Self {
+            text,
+            ..Default::default()
+        }
+        .with_parse_mode(ParseMode::HTML)
+    }
+
+    /// Set parse mode
+    pub fn with_parse_mode(mut self, parse_mode: ParseMode) -> Self {
+        self.parse_mode = parse_mode;
+        self
+    }
+}
+
+impl EventPayloadNewMessage {
+    /// Message text with formatting
+    pub fn text_parser(&self) -> MessageTextParser {
+        MessageTextParser::from_message_format(&self.text, self.format.as_ref())
+    }
+}
+
+impl EventPayloadEditedMessage {
+    /// Message text with formatting
+    pub fn text_parser(&self) -> MessageTextParser {
+        MessageTextParser::from_message_format(&self.text, self.format.as_ref())
+    }
+}
+
+/// Range which takes the text as a whole
+#[derive(Debug)]
+enum Atom<'a> {
+    Pre(Option<&'a str>),
+    Quote,
+    OrderedList,
+    Code,
+    Link(&'a str),
+    Mention,
+}
+
+/// Style which can be split by other styles
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Style {
+    Bold,
+    Italic,
+    Underline,
+    Strikethrough,
+}
+
+/// Range in UTF-16 code units
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: usize,
+    end: usize,
+}
+
+impl MessageFormat {
+    /// Convert message text with formatting ranges to [`MessageTextFormat`] items
+    pub fn to_text_formats(&self, text: &str) -> Vec<MessageTextFormat> {
+        let utf16: Vec<u16> = text.encode_utf16().collect();
+        let span = |offset: i32, length: i32| {
+            let start = usize::try_from(offset).ok()?.min(utf16.len());
+            let end = start
+                .saturating_add(usize::try_from(length).ok()?)
+                .min(utf16.len());
+            (start < end).then_some(Span { start, end })
+        };
+        let spans = |ranges: &Option<Vec<MessageFormatStruct>>| {
+            ranges
+                .iter()
+                .flatten()
+                .filter_map(|r| span(r.offset, r.length))
+                .collect::<Vec<_>>()
+        };
+
+        // Atoms in priority order for ranges with the same start
+        let mut atoms: Vec<(Span, Atom)> = Vec::new();
+        for pre in self.pre.iter().flatten() {
+            if let Some(s) = span(pre.offset, pre.length) {
+                atoms.push((s, Atom::Pre(pre.code.as_deref())));
+            }
+        }
+        atoms.extend(spans(&self.quote).into_iter().map(|s| (s, Atom::Quote)));
+        atoms.extend(
+            spans(&self.ordered_list)
+                .into_iter()
+                .map(|s| (s, Atom::OrderedList)),
+        );
+        atoms.extend(
+            spans(&self.inline_code)
+                .into_iter()
+                .map(|s| (s, Atom::Code)),
+        );
+        for link in self.link.iter().flatten() {
+            if let Some(s) = span(link.offset, link.length) {
+                atoms.push((s, Atom::Link(&link.url)));
+            }
+        }
+        atoms.extend(spans(&self.mention).into_iter().map(|s| (s, Atom::Mention)));
+        atoms.sort_by_key(|(s, _)| s.start);
+
+        let mut styles: Vec<(Span, Style)> = Vec::new();
+        for (ranges, style) in [
+            (&self.bold, Style::Bold),
+            (&self.italic, Style::Italic),
+            (&self.underline, Style::Underline),
+            (&self.strikethrough, Style::Strikethrough),
+        ] {
+            styles.extend(spans(ranges).into_iter().map(|s| (s, style)));
+        }
+
+        let slice = |start: usize, end: usize| String::from_utf16_lossy(&utf16[start..end]);
+        let mut formats = Vec::new();
+        let mut pos = 0;
+        for (s, atom) in atoms {
+            // Overlaps an earlier atom
+            if s.start < pos {
+                continue;
+            }
+            push_styled(&mut formats, &styles, pos, s.start, &slice);
+            let content = slice(s.start, s.end);
+            let format = match atom {
+                Atom::Pre(lang) => MessageTextFormat::Pre(content, lang.map(str::to_string)),
+                Atom::Quote => MessageTextFormat::Quote(content),
+                Atom::OrderedList => {
+                    MessageTextFormat::OrderedList(content.lines().map(str::to_string).collect())
+                }
+                Atom::Code => MessageTextFormat::Code(content),
+                Atom::Link(url) if Url::parse(url).is_ok() => {
+                    MessageTextFormat::Link(url.to_string(), content)
+                }
+                Atom::Link(_) => MessageTextFormat::Plain(content),
+                Atom::Mention => {
+                    let id = content
+                        .strip_prefix("@[")
+                        .and_then(|id| id.strip_suffix(']'))
+                        .unwrap_or(&content);
+                    MessageTextFormat::Mention(ChatId::from(id.to_string()))
+                }
+            };
+            push_merged(&mut formats, format);
+            pos = s.end;
+        }
+        push_styled(&mut formats, &styles, pos, utf16.len(), &slice);
+        formats
+    }
+}
+
+/// Push text between `start` and `end` split by the styles
+fn push_styled(
+    formats: &mut Vec<MessageTextFormat>,
+    styles: &[(Span, Style)],
+    start: usize,
+    end: usize,
+    slice: &impl Fn(usize, usize) -> String,
+) {
+    if start >= end {
+        return;
+    }
+    let mut bounds = vec![start, end];
+    for (s, _) in styles {
+        bounds.extend(
+            [s.start, s.end]
+                .into_iter()
+                .filter(|b| start < *b && *b < end),
+        );
+    }
+    bounds.sort_unstable();
+    bounds.dedup();
+    for pair in bounds.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        // Innermost style covering the fragment
+        let style = styles
+            .iter()
+            .filter(|(s, _)| s.start <= from && to <= s.end)
+            .min_by_key(|(s, _)| s.end - s.start)
+            .map(|(_, style)| *style);
+        let text = slice(from, to);
+        let format = match style {
+            Some(Style::Bold) => MessageTextFormat::Bold(text),
+            Some(Style::Italic) => MessageTextFormat::Italic(text),
+            Some(Style::Underline) => MessageTextFormat::Underline(text),
+            Some(Style::Strikethrough) => MessageTextFormat::Strikethrough(text),
+            None => MessageTextFormat::Plain(text),
+        };
+        push_merged(formats, format);
+    }
+}
+
+/// Push format, merging adjacent fragments with the same style
+fn push_merged(formats: &mut Vec<MessageTextFormat>, format: MessageTextFormat) {
+    use MessageTextFormat::*;
+    match (formats.last_mut(), format) {
+        (Some(Plain(last)), Plain(text))
+        | (Some(Bold(last)), Bold(text))
+        | (Some(Italic(last)), Italic(text))
+        | (Some(Underline(last)), Underline(text))
+        | (Some(Strikethrough(last)), Strikethrough(text)) => last.push_str(&text),
+        (_, format) => formats.push(format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::utils::MessageTextHTMLParser;
+
+    fn range(offset: i32, length: i32) -> Option<Vec<MessageFormatStruct>> {
+        Some(vec![MessageFormatStruct { offset, length }])
+    }
+
+    fn html(text: &str, format: &MessageFormat) -> String {
+        MessageTextParser::from_message_format(text, Some(format))
+            .parse()
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn test_no_format() {
+        let parser = MessageTextParser::from_message_format("a < b", None);
+        assert_eq!(parser.parse().unwrap().0, "a &lt; b");
+        assert!(
+            MessageTextParser::from_message_format("", None)
+                .text
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_inline_styles() {
+        let format = MessageFormat {
+            bold: range(0, 4),
+            italic: range(5, 6),
+            underline: range(12, 5),
+            strikethrough: range(18, 6),
+            inline_code: range(25, 4),
+            ..Default::default()
+        };
+        assert_eq!(
+            html("bold italic under strike code", &format),
+            "<b>bold</b> <i>italic</i> <u>under</u> <s>strike</s> <code>code</code>"
+        );
+    }
+
+    #[test]
+    fn test_overlapping_styles_innermost_wins() {
+        let format = MessageFormat {
+            bold: range(0, 9),
+            italic: range(3, 3),
+            ..Default::default()
+        };
+        let formats = format.to_text_formats("aaabbbccc");
+        assert_eq!(
+            formats,
+            vec![
+                MessageTextFormat::Bold("aaa".to_string()),
+                MessageTextFormat::Italic("bbb".to_string()),
+                MessageTextFormat::Bold("ccc".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_link_and_mention() {
+        let format = MessageFormat {
+            link: Some(vec![MessageFormatStructLink {
+                offset: 4,
+                length: 4,
+                url: "https://example.com".to_string(),
+            }]),
+            mention: range(12, 16),
+            bold: range(4, 2),
+            ..Default::default()
+        };
+        assert_eq!(
+            html("see site by @[user@mail.ru]", &format),
+            "see <a href=\"https://example.com/\">site</a> by <a>@[user@mail.ru]</a>"
+        );
+    }
+
+    #[test]
+    fn test_invalid_link_is_plain_text() {
+        let format = MessageFormat {
+            link: Some(vec![MessageFormatStructLink {
+                offset: 0,
+                length: 4,
+                url: "not a url".to_string(),
+            }]),
+            ..Default::default()
+        };
+        assert_eq!(html("link", &format), "link");
+    }
+
+    #[test]
+    fn test_blocks() {
+        let text = "code\nquote\none\ntwo";
+        let format = MessageFormat {
+            pre: Some(vec![MessageFormatStructPre {
+                offset: 0,
+                length: 4,
+                code: Some("rust".to_string()),
+            }]),
+            quote: range(5, 5),
+            ordered_list: range(11, 7),
+            ..Default::default()
+        };
+        assert_eq!(
+            html(text, &format),
+            "<pre class=\"rust\">code</pre>\n<blockquote>quote</blockquote>\n<ol><li>one</li><li>two</li></ol>"
+        );
+    }
+
+    #[test]
+    fn test_utf16_offsets() {
+        // Emoji is two UTF-16 code units
+        let format = MessageFormat {
+            bold: range(3, 6),
+            ..Default::default()
+        };
+        assert_eq!(html("🔥 Привет!", &format), "🔥 <b>Привет</b>!");
+    }
+
+    #[test]
+    fn test_invalid_ranges_are_ignored() {
+        let format = MessageFormat {
+            bold: Some(vec![
+                MessageFormatStruct {
+                    offset: -1,
+                    length: 2,
+                },
+                MessageFormatStruct {
+                    offset: 2,
+                    length: 0,
+                },
+                MessageFormatStruct {
+                    offset: 3,
+                    length: 100,
+                },
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(html("text", &format), "tex<b>t</b>");
+    }
+
+    #[test]
+    fn test_markdown_output() {
+        let format = MessageFormat {
+            bold: range(0, 5),
+            inline_code: range(6, 3),
+            ..Default::default()
+        };
+        let parser = MessageTextParser::from_message_format("Hello foo.", Some(&format))
+            .with_parse_mode(ParseMode::MarkdownV2);
+        assert_eq!(parser.parse().unwrap().0, "*Hello* `foo`\\.");
+    }
+
+    #[test]
+    fn test_payload_text_parser() {
+        let payload = EventPayloadEditedMessage {
+            text: "edited".to_string(),
+            format: Some(MessageFormat {
+                italic: range(0, 6),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(payload.text_parser().parse().unwrap().0, "<i>edited</i>");
+    }
+}
@@ -0,0 +1,442 @@
+//! # Markdown conversion
+//! Convert CommonMark text, e.g. alerts or LLM output, to the HTML subset supported by VK Teams.
+//!
+//! Markdown is converted to [`MessageTextFormat`] items, which are rendered
+//! with the same rules as a hand-built [`MessageTextParser`].
+//! Constructs without a VK Teams counterpart degrade to text:
+//! - nested inline styles - the innermost style is kept
+//! - headings - bold text
+//! - nested lists - items are flattened into the top level list
+//! - formatting inside lists and quotes - plain text
+//! - tables - pre-formatted block with cells separated by `|`
+//! - images - link to the image with the alt text
+//! - links with relative URLs - link text
+//!
+//! ```
+//! use vkteams_bot::prelude::*;
+//!
+//! # fn main() -> Result<()> {
+//! let parser = MessageTextParser::from_markdown("**Alert**: disk is *full*");
+//! let (html, _) = parser.parse()?;
+//! assert_eq!(html, "<b>Alert</b>: disk is <i>full</i>");
+//! # Ok(())
+//! # }
+//! ```
+use crate::api::types::*;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use reqwest::Url;
+
+impl MessageTextParser {
+    /// Create HTML parser from CommonMark text
+    ///
+    /// Tables, strikethrough and task lists extensions are enabled.
+    pub fn from_markdown(markdown: &str) -> Self {
+        let options =
+            Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+        let mut converter = MarkdownConverter::default();
+        for event in Parser::new_ext(markdown, options) {
+            converter.event(event);
+        }
+        Self {
+            text: converter.formats,
+            ..Default::default()
+        }
+        .with_parse_mode(ParseMode::HTML)
+    }
+}
+
+/// Inline style applied to text outside of blocks
+#[derive(Debug, Clone, Copy)]
+enum InlineStyle {
+    Bold,
+    Italic,
+    Strikethrough,
+}
+
+/// Block rendered as a single [`MessageTextFormat`], nested content is collected as text
+#[derive(Debug)]
+enum Block {
+    Quote(String),
+    List { ordered: bool, items: Vec<String> },
+    Code { lang: Option<String>, text: String },
+    Table(String),
+}
+
+impl Block {
+    /// Text the content is appended to
+    fn text_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Block::Quote(text) | Block::Code { text, .. } | Block::Table(text) => Some(text),
+            Block::List { items, .. } => items.last_mut(),
+        }
+    }
+
+    fn into_format(self) -> MessageTextFormat {
+        match self {
+            Block::Quote(text) => MessageTextFormat::Quote(text.trim_end().to_string()),
+            Block::List { ordered, items } => {
+                let items = items.iter().map(|item| item.trim().to_string()).collect();
+                if ordered {
+                    MessageTextFormat::OrderedList(items)
+                } else {
+                    MessageTextFormat::UnOrderedList(items)
+                }
+            }
+            Block::Code { lang, text } => {
+                MessageTextFormat::Pre(text.trim_end_matches('\n').to_string(), lang)
+            }
+            Block::Table(text) => MessageTextFormat::Pre(text.trim_end().to_string(), None),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MarkdownConverter {
+    formats: Vec<MessageTextFormat>,
+    styles: Vec<InlineStyle>,
+    /// Top level block being collected
+    block: Option<Block>,
+    /// Nesting level of quotes and lists inside the top level block
+    depth: usize,
+    /// URL and text of the link being collected
+    link: Option<(String, String)>,
+    /// Last top level block was a paragraph or a heading
+    text_block: bool,
+}
+
+impl MarkdownConverter {
+    fn event(&mut self, event: Event<'_>) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => self.text(&text),
+            Event::InlineMath(text) | Event::DisplayMath(text) => self.text(&text),
+            Event::Code(text) => self.code(&text),
+            Event::SoftBreak | Event::HardBreak => self.line_break(),
+            Event::Rule => {
+                if self.block.is_none() {
+                    self.start_block(true);
+                    self.push(MessageTextFormat::Plain("---".to_string()));
+                }
+            }
+            Event::TaskListMarker(checked) => self.text(if checked { "[x] " } else { "[ ] " }),
+            Event::FootnoteReference(_) => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Paragraph | Tag::HtmlBlock => self.start_text_block(),
+            Tag::Heading { .. } => {
+                self.start_text_block();
+                if self.block.is_none() {
+                    self.styles.push(InlineStyle::Bold);
+                }
+            }
+            Tag::BlockQuote(_) => self.start_container(Block::Quote(String::new())),
+            Tag::List(start) => self.start_container(Block::List {
+                ordered: start.is_some(),
+                items: Vec::new(),
+            }),
+            Tag::Item => match &mut self.block {
+                Some(Block::List { items, .. }) => items.push(String::new()),
+                Some(_) => {
+                    self.block_break();
+                    self.text("- ");
+                }
+                None => {}
+            },
+            Tag::CodeBlock(kind) => {
+                if self.block.is_some() {
+                    self.block_break();
+                    return;
+                }
+                self.start_block(false);
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().map(|lang| lang.to_string())
+                    }
+                    CodeBlockKind::Indented => None,
+                };
+                self.block = Some(Block::Code {
+                    lang,
+                    text: String::new(),
+                });
+            }
+            Tag::Table(_) => {
+                if self.block.is_some() {
+                    self.block_break();
+                    return;
+                }
+                self.start_block(false);
+                self.block = Some(Block::Table(String::new()));
+            }
+            Tag::Emphasis => self.styles.push(InlineStyle::Italic),
+            Tag::Strong => self.styles.push(InlineStyle::Bold),
+            Tag::Strikethrough => self.styles.push(InlineStyle::Strikethrough),
+            Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => {
+                self.link = Some((dest_url.to_string(), String::new()));
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(_) if self.block.is_none() => {
+                self.styles.pop();
+            }
+            TagEnd::BlockQuote(_) | TagEnd::List(_) => {
+                if self.depth > 0 {
+                    self.depth -= 1;
+                } else {
+                    self.finish_block();
+                }
+            }
+            TagEnd::CodeBlock if matches!(self.block, Some(Block::Code { .. })) => {
+                self.finish_block()
+            }
+            TagEnd::Table if matches!(self.block, Some(Block::Table(_))) => self.finish_block(),
+            TagEnd::TableCell => self.text(" | "),
+            TagEnd::TableHead | TagEnd::TableRow => {
+                if let Some(text) = self.block.as_mut().and_then(Block::text_mut) {
+                    text.truncate(text.trim_end_matches(" | ").len());
+                    text.push('\n');
+                }
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => {
+                self.styles.pop();
+            }
+            TagEnd::Link | TagEnd::Image => self.finish_link(),
+            _ => {}
+        }
+    }
+
+    /// Paragraph or heading: new top level block or line break inside a block
+    fn start_text_block(&mut self) {
+        if self.block.is_some() {
+            self.block_break();
+        } else {
+            self.start_block(true);
+        }
+    }
+
+    /// Quote or list: new top level block or nested container inside a block
+    fn start_container(&mut self, block: Block) {
+        if self.block.is_some() {
+            self.depth += 1;
+            self.block_break();
+        } else {
+            self.start_block(false);
+            self.block = Some(block);
+        }
+    }
+
+    /// Separate top level blocks: blank line between paragraphs, line break otherwise
+    fn start_block(&mut self, text_block: bool) {
+        if !self.formats.is_empty() {
+            let separator = if self.text_block && text_block {
+                "\n\n"
+            } else {
+                "\n"
+            };
+            self.push(MessageTextFormat::Plain(separator.to_string()));
+        }
+        self.text_block = text_block;
+    }
+
+    fn finish_block(&mut self) {
+        if let Some(block) = self.block.take() {
+            self.depth = 0;
+            self.push(block.into_format());
+        }
+    }
+
+    /// Start a new line inside a block
+    fn block_break(&mut self) {
+        if let Some(text) = self.block.as_mut().and_then(Block::text_mut)
+            && !text.is_empty()
+            && !text.ends_with('\n')
+        {
+            text.push('\n');
+        }
+    }
+
+    fn finish_link(&mut self) {
+        let Some((url, text)) = self.link.take() else {
+            return;
+        };
+        let text = if text.is_empty() { url.clone() } else { text };
+        if let Some(block_text) = self.block.as_mut().and_then(Block::text_mut) {
+            block_text.push_str(&text);
+            if text != url {
+                block_text.push_str(&format!(" ({url})"));
+            }
+        } else if Url::parse(&url).is_ok() {
+            self.push(MessageTextFormat::Link(url, text));
+        } else {
+            self.push(MessageTextFormat::Plain(text));
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if let Some((_, link_text)) = &mut self.link {
+            link_text.push_str(text);
+            return;
+        }
+        if let Some(block) = &mut self.block {
+            if let Some(block_text) = block.text_mut() {
+                block_text.push_str(text);
+            }
+            return;
+        }
+        let text = text.to_string();
+        let format = match self.styles.last() {
+            Some(InlineStyle::Bold) => MessageTextFormat::Bold(text),
+            Some(InlineStyle::Italic) => MessageTextFormat::Italic(text),
+            Some(InlineStyle::Strikethrough) => MessageTextFormat::Strikethrough(text),
+            None => MessageTextFormat::Plain(text),
+        };
+        self.push(format);
+    }
+
+    fn code(&mut self, text: &str) {
+        if self.link.is_some() || self.block.is_some() {
+            self.text(text);
+        } else {
+            self.push(MessageTextFormat::Code(text.to_string()));
+        }
+    }
+
+    fn line_break(&mut self) {
+        if self.link.is_some() {
+            self.text(" ");
+        } else if self.block.is_some() {
+            self.text("\n");
+        } else {
+            self.push(MessageTextFormat::Plain("\n".to_string()));
+        }
+    }
+
+    /// Push format, merging adjacent plain text
+    fn push(&mut self, format: MessageTextFormat) {
+        if let (Some(MessageTextFormat::Plain(last)), MessageTextFormat::Plain(text)) =
+            (self.formats.last_mut(), &format)
+        {
+            last.push_str(text);
+            return;
+        }
+        self.formats.push(format);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::utils::MessageTextHTMLParser;
+
+    fn html(markdown: &str) -> String {
+        let (html, mode) = MessageTextParser::from_markdown(markdown).parse().unwrap();
+        assert_eq!(mode, ParseMode::HTML);
+        html
+    }
+
+    #[test]
+    fn test_inline_styles() {
+        assert_eq!(
+            html("**bold** *italic* ~~strike~~ `code`"),
+            "<b>bold</b> <i>italic</i> <s>strike</s> <code>code</code>"
+        );
+    }
+
+    #[test]
+    fn test_nested_styles_keep_innermost() {
+        assert_eq!(html("**bold *both***"), "<b>bold </b><i>both</i>");
+    }
+
+    #[test]
+    fn test_paragraphs_and_headings() {
+        assert_eq!(
+            html("# Title\n\nFirst line\nsecond line\n\nNext"),
+            "<b>Title</b>\n\nFirst line\nsecond line\n\nNext"
+        );
+    }
+
+    #[test]
+    fn test_escapes_html() {
+        assert_eq!(html("a < b && c > d"), "a &lt; b &amp;&amp; c &gt; d");
+        assert_eq!(html("<script>x</script>"), "&lt;script&gt;x&lt;/script&gt;");
+    }
+
+    #[test]
+    fn test_links() {
+        assert_eq!(
+            html("[site](https://example.com) <https://a.com/x>"),
+            "<a href=\"https://example.com/\">site</a> <a href=\"https://a.com/x\">https://a.com/x</a>"
+        );
+        // Relative links degrade to text
+        assert_eq!(html("[docs](/docs)"), "docs");
+    }
+
+    #[test]
+    fn test_image_becomes_link() {
+        assert_eq!(
+            html("![chart](https://example.com/c.png)"),
+            "<a href=\"https://example.com/c.png\">chart</a>"
+        );
+    }
+
+    #[test]
+    fn test_code_block() {
+        assert_eq!(
+            html("Run:\n\n```rust\nfn main() {}\n```\n\n    indented"),
+            "Run:\n<pre class=\"rust\">fn main() {}</pre>\n<pre>indented</pre>"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        assert_eq!(
+            html("1. one\n2. **two**\n\n- a\n- b"),
+            "<ol><li>one</li><li>two</li></ol>\n<ul><li>a</li><li>b</li></ul>"
+        );
+    }
+
+    #[test]
+    fn test_nested_list_is_flattened() {
+        assert_eq!(
+            html("- a\n  - b\n  - c\n- d\n\nafter"),
+            "<ul><li>a</li><li>b</li><li>c</li><li>d</li></ul>\nafter"
+        );
+    }
+
+    #[test]
+    fn test_task_list() {
+        assert_eq!(
+            html("- [x] done\n- [ ] todo"),
+            "<ul><li>[x] done</li><li>[ ] todo</li></ul>"
+        );
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(
+            html("> **quoted** [link](https://example.com)\n>\n> - item"),
+            "<blockquote>quoted link (https://example.com)\n- item</blockquote>"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            html("| a | b |\n|---|---|\n| 1 | 2 |"),
+            "<pre>a | b\n1 | 2</pre>"
+        );
+    }
+
+    #[test]
+    fn test_rule_and_empty_input() {
+        assert_eq!(html("a\n\n---\n\nb"), "a\n\n---\n\nb");
+        assert_eq!(html(""), "");
+    }
+}
@@ -1,10 +1,15 @@
+pub mod format;
 pub mod keyboard;
+#[cfg(feature = "markdown")]
+pub mod markdown;
 pub mod parser;
+pub mod split;
 #[cfg(feature = "templates")]
 pub mod templates;
 pub use crate::api::types::*;
 use crate::error::{BotError, Result};
 pub use parser::*;
+pub use split::*;
 #[allow(unused_variables)]
 pub trait MessageTextSetters {
     /// Set text
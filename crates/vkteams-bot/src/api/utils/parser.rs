@@ -3,6 +3,10 @@ use crate::error::{BotError, Result};
 use reqwest::Url;
 use std::convert::From;
 const HTML_LIST_ITEM_OVERHEAD: usize = 10; // <li></li> is 7 characters long
+/// Characters that must be escaped with `\\` in MarkdownV2 text
+const MARKDOWN_RESERVED_CHARS: &[char] = &[
+    '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!', '\\',
+];
 pub trait MessageTextHTMLParser {
     /// Create new parser
     fn new() -> Self
@@ -17,7 +21,7 @@ pub trait MessageTextHTMLParser {
     fn next_line(&mut self) -> Self;
     /// Add space to parser
     fn space(&mut self) -> Self;
-    /// Parse text to HTML
+    /// Parse text to HTML or MarkdownV2, depending on the parse mode
     fn parse(&self) -> Result<(String, ParseMode)>;
 }
 impl MessageTextParser {
@@ -78,6 +82,63 @@ impl MessageTextParser {
             )),
         }
     }
+    /// Parse [`MessageTextFormat`] types to MarkdownV2 string
+    fn parse_markdown(&self, text: &MessageTextFormat) -> Result<String> {
+        match text {
+            MessageTextFormat::Plain(text) => Ok(self.escape_markdown(text)),
+            MessageTextFormat::Link(url, text) => {
+                let parsed_url = Url::parse(url)?;
+                Ok(format!(
+                    "[{}]({})",
+                    self.escape_markdown(text),
+                    escape_markdown_with(parsed_url.as_str(), &[')', '\\'])
+                ))
+            }
+            MessageTextFormat::Bold(text) => Ok(format!("*{}*", self.escape_markdown(text))),
+            MessageTextFormat::Italic(text) => Ok(format!("_{}_", self.escape_markdown(text))),
+            MessageTextFormat::Underline(text) => Ok(format!("__{}__", self.escape_markdown(text))),
+            MessageTextFormat::Strikethrough(text) => {
+                Ok(format!("~{}~", self.escape_markdown(text)))
+            }
+            MessageTextFormat::Code(text) => {
+                Ok(format!("`{}`", escape_markdown_with(text, &['`', '\\'])))
+            }
+            MessageTextFormat::Pre(text, class) => Ok(format!(
+                "```{}\n{}\n```",
+                class
+                    .as_deref()
+                    .map(|class| self.escape_markdown(class))
+                    .unwrap_or_default(),
+                escape_markdown_with(text, &['`', '\\'])
+            )),
+            MessageTextFormat::Mention(chat_id) => {
+                Ok(format!("@\\[{}\\]", self.escape_markdown(&chat_id.0)))
+            }
+            MessageTextFormat::Quote(text) => Ok(text
+                .lines()
+                .map(|line| format!(">{}", self.escape_markdown(line)))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            MessageTextFormat::OrderedList(list) => Ok(list
+                .iter()
+                .enumerate()
+                .map(|(i, item)| format!("{}. {}", i + 1, self.escape_markdown(item)))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            MessageTextFormat::UnOrderedList(list) => Ok(list
+                .iter()
+                .map(|item| format!("- {}", self.escape_markdown(item)))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            MessageTextFormat::None => Err(BotError::Validation(
+                "MessageTextFormat::None is not supported".to_string(),
+            )),
+        }
+    }
+    /// Escape MarkdownV2 reserved characters with `\\`
+    fn escape_markdown(&self, text: &str) -> String {
+        escape_markdown_with(text, MARKDOWN_RESERVED_CHARS)
+    }
     /// Replace special characters with HTML entities
     fn replace_chars(&self, text: &str) -> String {
         text.replace('&', "&amp;")
@@ -85,6 +146,27 @@ impl MessageTextParser {
             .replace('>', "&gt;")
     }
 }
+/// Prefix each of the `chars` in `text` with `\\`
+fn escape_markdown_with(text: &str, chars: &[char]) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if chars.contains(&c) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+/// Block formats must start on a separate line in MarkdownV2
+fn is_markdown_block(text: &MessageTextFormat) -> bool {
+    matches!(
+        text,
+        MessageTextFormat::Pre(..)
+            | MessageTextFormat::Quote(_)
+            | MessageTextFormat::OrderedList(_)
+            | MessageTextFormat::UnOrderedList(_)
+    )
+}
 impl MessageTextHTMLParser for MessageTextParser {
     /// Add plain text to [`MessageTextFormat`]
     /// ## Parameters
@@ -122,11 +204,25 @@ impl MessageTextHTMLParser for MessageTextParser {
                 Ok((result, ParseMode::HTML))
             }
             ParseMode::MarkdownV2 => {
-                // MarkdownV2 is not supported in this parser
-                Err(BotError::Validation(format!(
-                    "Parse mode not supported: {:?}. Supported modes: HTML, Template",
-                    self.parse_mode
-                )))
+                let mut after_block = false;
+                for item in &self.text {
+                    if let MessageTextFormat::None = item {
+                        continue;
+                    }
+                    let part = self.parse_markdown(item)?;
+                    // Keep blocks on their own lines, otherwise they are rendered as text
+                    let block = is_markdown_block(item);
+                    if (block || after_block)
+                        && !result.is_empty()
+                        && !result.ends_with('\n')
+                        && !part.starts_with('\n')
+                    {
+                        result.push('\n');
+                    }
+                    result.push_str(&part);
+                    after_block = block;
+                }
+                Ok((result, self.parse_mode))
             }
         }
     }
@@ -266,20 +362,134 @@ mod tests {
         assert_eq!(mode, ParseMode::HTML);
     }
 
-    #[test]
-    fn test_markdownv2_parse_mode_returns_error() {
-        let parser = MessageTextParser {
-            text: vec![MessageTextFormat::Plain("Hello".to_string())],
+    fn parser_markdown() -> MessageTextParser {
+        MessageTextParser {
+            text: vec![],
             parse_mode: ParseMode::MarkdownV2,
             ..Default::default()
-        };
-        let result = parser.parse();
-        assert!(result.is_err());
-        if let Err(BotError::Validation(msg)) = result {
-            assert!(msg.contains("Parse mode not supported"));
-            assert!(msg.contains("MarkdownV2"));
-        } else {
-            panic!("Expected BotError::Validation");
         }
     }
+
+    #[test]
+    fn test_markdown_plain_text() {
+        let mut parser = parser_markdown();
+        parser = parser.add(MessageTextFormat::Plain("Hello".to_string()));
+        let (md, mode) = parser.parse().unwrap();
+        assert_eq!(md, "Hello");
+        assert_eq!(mode, ParseMode::MarkdownV2);
+    }
+
+    #[test]
+    fn test_markdown_bold_italic_code() {
+        let mut parser = parser_markdown();
+        parser = parser.add(MessageTextFormat::Bold("B".to_string()));
+        parser = parser.add(MessageTextFormat::Italic("I".to_string()));
+        parser = parser.add(MessageTextFormat::Code("C".to_string()));
+        let (md, _) = parser.parse().unwrap();
+        assert_eq!(md, "*B*_I_`C`");
+    }
+
+    #[test]
+    fn test_markdown_pre_with_and_without_class() {
+        let mut parser = parser_markdown();
+        parser = parser.add(MessageTextFormat::Pre(
+            "code".to_string(),
+            Some("lang".to_string()),
+        ));
+        parser = parser.add(MessageTextFormat::Pre("code2".to_string(), None));
+        let (md, _) = parser.parse().unwrap();
+        assert_eq!(md, "```lang\ncode\n```\n```\ncode2\n```");
+    }
+
+    #[test]
+    fn test_markdown_link_and_mention() {
+        let mut parser = parser_markdown();
+        parser = parser.add(MessageTextFormat::Link(
+            "http://a.com".to_string(),
+            "A".to_string(),
+        ));
+        parser = parser.add(MessageTextFormat::Mention(ChatId::from("cid")));
+        let (md, _) = parser.parse().unwrap();
+        assert_eq!(md, "[A](http://a.com/)@\\[cid\\]");
+    }
+
+    #[test]
+    fn test_markdown_strikethrough_underline_quote() {
+        let mut parser = parser_markdown();
+        parser = parser.add(MessageTextFormat::Strikethrough("S".to_string()));
+        parser = parser.add(MessageTextFormat::Underline("U".to_string()));
+        parser = parser.add(MessageTextFormat::Quote("Q".to_string()));
+        let (md, _) = parser.parse().unwrap();
+        assert_eq!(md, "~S~__U__\n>Q");
+    }
+
+    #[test]
+    fn test_markdown_ordered_and_unordered_list() {
+        let mut parser = parser_markdown();
+        parser = parser.add(MessageTextFormat::OrderedList(vec![
+            "A".to_string(),
+            "B".to_string(),
+        ]));
+        parser = parser.add(MessageTextFormat::UnOrderedList(vec!["X".to_string()]));
+        let (md, _) = parser.parse().unwrap();
+        assert_eq!(md, "1. A\n2. B\n- X");
+    }
+
+    #[test]
+    fn test_markdown_block_followed_by_text() {
+        let mut parser = parser_markdown();
+        parser = parser.add(MessageTextFormat::Plain("Intro:".to_string()));
+        parser = parser.add(MessageTextFormat::Quote("line 1\nline 2".to_string()));
+        parser = parser.add(MessageTextFormat::Plain("Outro".to_string()));
+        let (md, _) = parser.parse().unwrap();
+        assert_eq!(md, "Intro:\n>line 1\n>line 2\nOutro");
+    }
+
+    #[test]
+    fn test_markdown_none_format_is_ignored() {
+        let mut parser = parser_markdown();
+        parser = parser.add(MessageTextFormat::None);
+        let (md, _) = parser.parse().unwrap();
+        assert_eq!(md, "");
+    }
+
+    #[test]
+    fn test_markdown_escape_reserved_chars() {
+        let parser = parser_markdown();
+        let s = parser.escape_markdown("_*[]()~`>#+-=|{}.!\\ text");
+        assert_eq!(
+            s,
+            "\\_\\*\\[\\]\\(\\)\\~\\`\\>\\#\\+\\-\\=\\|\\{\\}\\.\\!\\\\ text"
+        );
+    }
+
+    #[test]
+    fn test_markdown_escape_in_formats() {
+        let mut parser = parser_markdown();
+        parser = parser.add(MessageTextFormat::Bold("1+1=2!".to_string()));
+        parser = parser.add(MessageTextFormat::Code("a_b `c` \\d.".to_string()));
+        parser = parser.add(MessageTextFormat::Link(
+            "https://example.com/a_(b)".to_string(),
+            "[x]".to_string(),
+        ));
+        parser = parser.add(MessageTextFormat::Pre(
+            "fn main() {}".to_string(),
+            Some("rust".to_string()),
+        ));
+        let (md, _) = parser.parse().unwrap();
+        assert_eq!(
+            md,
+            "*1\\+1\\=2\\!*`a_b \\`c\\` \\\\d.`[\\[x\\]](https://example.com/a_(b\\))\n```rust\nfn main() {}\n```"
+        );
+    }
+
+    #[test]
+    fn test_markdown_link_invalid_url_returns_error() {
+        let mut parser = parser_markdown();
+        parser = parser.add(MessageTextFormat::Link(
+            "not a url".to_string(),
+            "A".to_string(),
+        ));
+        assert!(parser.parse().is_err());
+    }
 }
@@ -0,0 +1,599 @@
+//! # Message splitting
+//! Split text longer than the API limit into several messages.
+//!
+//! Text is split at the best boundary which fits into a part, in order of preference:
+//! 1. paragraphs, ends of blocks and list items
+//! 2. lines
+//! 3. spaces
+//! 4. lines inside pre-formatted blocks
+//!
+//! Tags, entities and escaped characters are never split, links, mentions and inline code
+//! only when they don't fit into a part. Formatting open at the split point is closed at the end
+//! of the part and reopened at the start of the next one, so each part is valid on its own.
+//!
+//! Length is counted in characters.
+//!
+//! ```
+//! use vkteams_bot::prelude::*;
+//!
+//! let parts = split_text(
+//!     "<b>First paragraph</b>\n\n<b>Second</b>",
+//!     Some(ParseMode::HTML),
+//!     25,
+//! );
+//! assert_eq!(parts, vec!["<b>First paragraph</b>", "<b>Second</b>"]);
+//! ```
+use crate::api::types::ParseMode;
+
+/// Maximum length of the message text accepted by `messages/sendText`
+pub const MAX_TEXT_LENGTH: usize = 4096;
+
+/// Options of [`Bot::send_text_split`](crate::bot::Bot::send_text_split)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SplitOptions {
+    /// Maximum length of each part in characters
+    pub max_length: usize,
+    /// Send parts after the first one as replies to the first part
+    pub reply_chain: bool,
+}
+
+impl Default for SplitOptions {
+    fn default() -> Self {
+        Self {
+            max_length: MAX_TEXT_LENGTH,
+            reply_chain: false,
+        }
+    }
+}
+
+/// Split text into parts of at most `max_length` characters
+/// ## Parameters
+/// - `text`: rendered message text
+/// - `parse_mode`: [`ParseMode`] of the text, `None` for plain text
+/// - `max_length`: maximum length of each part
+///
+/// Text which fits is returned as a single part.
+/// A tag, entity or link longer than `max_length` is put into a part of its own as is.
+pub fn split_text(text: &str, parse_mode: Option<ParseMode>, max_length: usize) -> Vec<String> {
+    if text.chars().count() <= max_length {
+        return vec![text.to_string()];
+    }
+    let atoms = match parse_mode {
+        Some(ParseMode::MarkdownV2) => markdown_atoms(text),
+        Some(_) => html_atoms(text),
+        None => text
+            .char_indices()
+            .map(|(i, c)| char_atom(&text[i..i + c.len_utf8()]))
+            .collect(),
+    };
+    split_atoms(&atoms, max_length.max(1))
+}
+
+/// HTML tags which start a new block
+const HTML_BLOCK_TAGS: &[&str] = &["p", "blockquote", "ol", "ul", "li"];
+/// HTML tags which must not be split
+const HTML_ATOMIC_TAGS: &[&str] = &["a", "code"];
+
+/// How the content of a formatting element can be split
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Class {
+    /// Inline style
+    Inline,
+    /// Inline element which is split only if it doesn't fit
+    Atomic,
+    /// Block element
+    Block,
+    /// Pre-formatted block, split only at lines
+    Pre,
+}
+
+#[derive(Debug)]
+enum Kind {
+    /// Character, entity or escaped character
+    Text,
+    Space,
+    Newline,
+    /// Line break tag
+    Break,
+    /// Start of formatting with the text to close it
+    Open {
+        close: &'static str,
+        class: Class,
+    },
+    /// End of the last open formatting
+    Close,
+}
+
+/// Smallest piece of text which is never split
+#[derive(Debug)]
+struct Atom<'a> {
+    text: &'a str,
+    kind: Kind,
+}
+
+/// Single character
+fn char_atom(text: &str) -> Atom<'_> {
+    let kind = match text {
+        "\n" => Kind::Newline,
+        _ if text.chars().all(char::is_whitespace) => Kind::Space,
+        _ => Kind::Text,
+    };
+    Atom { text, kind }
+}
+
+/// Split HTML into tags, entities and characters
+fn html_atoms(text: &str) -> Vec<Atom<'_>> {
+    let mut atoms = Vec::new();
+    let mut pos = 0;
+    while let Some(c) = text[pos..].chars().next() {
+        let rest = &text[pos..];
+        let len = match c {
+            '<' => rest.find('>').map(|end| end + 1),
+            '&' => rest
+                .bytes()
+                .take(12)
+                .position(|b| b == b';')
+                .filter(|&end| {
+                    end > 1
+                        && rest.as_bytes()[1..end]
+                            .iter()
+                            .all(|b| b.is_ascii_alphanumeric() || *b == b'#')
+                })
+                .map(|end| end + 1),
+            _ => None,
+        };
+        let atom = match len {
+            Some(len) if c == '<' => html_tag(&rest[..len]),
+            Some(len) => Atom {
+                text: &rest[..len],
+                kind: Kind::Text,
+            },
+            None => char_atom(&rest[..c.len_utf8()]),
+        };
+        pos += atom.text.len();
+        atoms.push(atom);
+    }
+    atoms
+}
+
+fn html_tag(tag: &str) -> Atom<'_> {
+    let name = tag[1..]
+        .trim_start_matches('/')
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let kind = if tag.starts_with("</") {
+        Kind::Close
+    } else if name == "br" || tag.ends_with("/>") {
+        Kind::Break
+    } else {
+        let class = if name == "pre" {
+            Class::Pre
+        } else if HTML_BLOCK_TAGS.contains(&name.as_str()) {
+            Class::Block
+        } else if HTML_ATOMIC_TAGS.contains(&name.as_str()) {
+            Class::Atomic
+        } else {
+            Class::Inline
+        };
+        Kind::Open {
+            close: html_close_tag(&name),
+            class,
+        }
+    };
+    Atom { text: tag, kind }
+}
+
+fn html_close_tag(name: &str) -> &'static str {
+    match name {
+        "b" => "</b>",
+        "i" => "</i>",
+        "u" => "</u>",
+        "s" => "</s>",
+        "a" => "</a>",
+        "code" => "</code>",
+        "pre" => "</pre>",
+        "blockquote" => "</blockquote>",
+        "ol" => "</ol>",
+        "ul" => "</ul>",
+        "li" => "</li>",
+        "p" => "</p>",
+        "strong" => "</strong>",
+        "em" => "</em>",
+        "ins" => "</ins>",
+        "del" => "</del>",
+        "strike" => "</strike>",
+        "span" => "</span>",
+        _ => "",
+    }
+}
+
+/// Split MarkdownV2 into formatting markers, escaped and other characters
+fn markdown_atoms(text: &str) -> Vec<Atom<'_>> {
+    let mut atoms = Vec::new();
+    let mut styles: Vec<&str> = Vec::new();
+    let (mut in_code, mut in_fence) = (false, false);
+    let mut pos = 0;
+    while let Some(c) = text[pos..].chars().next() {
+        let rest = &text[pos..];
+        let line_start = pos == 0 || text[..pos].ends_with('\n');
+        let escaped = || {
+            let next = rest[1..].chars().next().map_or(0, char::len_utf8);
+            Atom {
+                text: &rest[..1 + next],
+                kind: Kind::Text,
+            }
+        };
+        let atom = if in_fence {
+            if rest.starts_with("\n```") || line_start && rest.starts_with("```") {
+                in_fence = false;
+                let len = if c == '\n' { 4 } else { 3 };
+                Atom {
+                    text: &rest[..len],
+                    kind: Kind::Close,
+                }
+            } else if c == '\\' {
+                escaped()
+            } else {
+                char_atom(&rest[..c.len_utf8()])
+            }
+        } else if in_code {
+            match c {
+                '`' => {
+                    in_code = false;
+                    Atom {
+                        text: &rest[..1],
+                        kind: Kind::Close,
+                    }
+                }
+                '\\' => escaped(),
+                _ => char_atom(&rest[..c.len_utf8()]),
+            }
+        } else if line_start && rest.starts_with("```") {
+            in_fence = true;
+            Atom {
+                text: &rest[..rest.find('\n').map_or(rest.len(), |end| end + 1)],
+                kind: Kind::Open {
+                    close: "\n```",
+                    class: Class::Pre,
+                },
+            }
+        } else {
+            match c {
+                '\\' => escaped(),
+                '`' => {
+                    in_code = true;
+                    Atom {
+                        text: &rest[..1],
+                        kind: Kind::Open {
+                            close: "`",
+                            class: Class::Atomic,
+                        },
+                    }
+                }
+                '[' => markdown_link(rest).unwrap_or_else(|| Atom {
+                    text: &rest[..1],
+                    kind: Kind::Text,
+                }),
+                '*' | '_' | '~' => {
+                    let marker = if rest.starts_with("__") {
+                        "__"
+                    } else {
+                        &rest[..1]
+                    };
+                    let kind = if styles.last() == Some(&marker) {
+                        styles.pop();
+                        Kind::Close
+                    } else {
+                        styles.push(marker);
+                        Kind::Open {
+                            close: markdown_marker(marker),
+                            class: Class::Inline,
+                        }
+                    };
+                    Atom { text: marker, kind }
+                }
+                _ => char_atom(&rest[..c.len_utf8()]),
+            }
+        };
+        pos += atom.text.len();
+        atoms.push(atom);
+    }
+    atoms
+}
+
+fn markdown_marker(marker: &str) -> &'static str {
+    match marker {
+        "*" => "*",
+        "_" => "_",
+        "__" => "__",
+        _ => "~",
+    }
+}
+
+/// Link `[text](url)` as a single atom
+fn markdown_link(text: &str) -> Option<Atom<'_>> {
+    let mut escaped = false;
+    let mut in_url = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ']' if !in_url => {
+                if !text[i + 1..].starts_with('(') {
+                    return None;
+                }
+                in_url = true;
+            }
+            ')' if in_url => {
+                return Some(Atom {
+                    text: &text[..=i],
+                    kind: Kind::Text,
+                });
+            }
+            '\n' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Greedily fill parts with atoms, cutting at the best boundary
+fn split_atoms(atoms: &[Atom], max_length: usize) -> Vec<String> {
+    // Open formatting and character offset at each boundary between atoms
+    let mut stacks = Vec::with_capacity(atoms.len() + 1);
+    let mut offsets = Vec::with_capacity(atoms.len() + 1);
+    let mut stack: Vec<usize> = Vec::new();
+    let mut offset = 0;
+    stacks.push(stack.clone());
+    offsets.push(offset);
+    for (i, atom) in atoms.iter().enumerate() {
+        match atom.kind {
+            Kind::Open { .. } => stack.push(i),
+            Kind::Close => {
+                stack.pop();
+            }
+            _ => {}
+        }
+        offset += atom.text.chars().count();
+        stacks.push(stack.clone());
+        offsets.push(offset);
+    }
+    let opening = |b: usize| stacks[b].iter().map(|&i| atoms[i].text).collect::<String>();
+    let closing = |b: usize| {
+        stacks[b]
+            .iter()
+            .rev()
+            .map(|&i| match atoms[i].kind {
+                Kind::Open { close, .. } => close,
+                _ => "",
+            })
+            .collect::<String>()
+    };
+    let class = |i: usize| match atoms[i].kind {
+        Kind::Open { class, .. } => class,
+        _ => Class::Inline,
+    };
+    // Boundary preference, 0 if the boundary should be avoided
+    let level = |b: usize| -> u8 {
+        if stacks[b]
+            .iter()
+            .any(|&i| matches!(class(i), Class::Atomic | Class::Pre))
+        {
+            let in_pre = stacks[b].iter().any(|&i| class(i) == Class::Pre);
+            let before_line = matches!(atoms.get(b).map(|a| &a.kind), Some(Kind::Newline));
+            return u8::from(in_pre && before_line);
+        }
+        let closes_block = stacks[b - 1]
+            .last()
+            .is_some_and(|&i| matches!(class(i), Class::Block | Class::Pre));
+        let next = atoms.get(b).map(|a| &a.kind);
+        let before_block = matches!(
+            next,
+            Some(Kind::Open {
+                class: Class::Block | Class::Pre,
+                ..
+            })
+        );
+        match atoms[b - 1].kind {
+            Kind::Open { .. } => 0,
+            Kind::Close if closes_block => 4,
+            Kind::Newline if b >= 2 && matches!(atoms[b - 2].kind, Kind::Newline) => 4,
+            _ if before_block => 3,
+            Kind::Newline | Kind::Break => 3,
+            _ if matches!(next, Some(Kind::Newline)) => 3,
+            Kind::Space => 2,
+            _ if matches!(next, Some(Kind::Space)) => 2,
+            _ => 0,
+        }
+    };
+    let is_space = |i: usize| matches!(atoms[i].kind, Kind::Space | Kind::Newline);
+
+    let mut parts = Vec::new();
+    let mut start = 0;
+    while start < atoms.len() {
+        let prefix = opening(start);
+        let prefix_len = prefix.chars().count();
+        // Last boundary where the part with closing formatting fits
+        let mut fits = start;
+        for end in start + 1..=atoms.len() {
+            let len = prefix_len + offsets[end] - offsets[start];
+            if len > max_length {
+                break;
+            }
+            if len + closing(end).chars().count() <= max_length {
+                fits = end;
+            }
+        }
+        if fits == atoms.len() {
+            let body: String = atoms[start..].iter().map(|a| a.text).collect();
+            if !body.trim().is_empty() {
+                parts.push(prefix + &body);
+            }
+            break;
+        }
+        // Prefer boundaries out of the first quarter of the part to avoid tiny parts
+        let min_offset = offsets[start] + (offsets[fits] - offsets[start]) / 4;
+        let best = |min_offset: usize| {
+            (start + 1..=fits)
+                .filter(|&b| offsets[b] >= min_offset)
+                .map(|b| (level(b), b))
+                .max()
+                .filter(|(level, _)| *level > 0)
+        };
+        let (cut_level, cut) = best(min_offset).or_else(|| best(0)).unwrap_or_else(|| {
+            let cut = (start + 1..=fits)
+                .rev()
+                .find(|&b| !matches!(atoms[b - 1].kind, Kind::Open { .. }))
+                .unwrap_or(fits.max(start + 1));
+            (0, cut)
+        });
+        let mut body: String = atoms[start..cut].iter().map(|a| a.text).collect();
+        if cut_level >= 2 {
+            body.truncate(body.trim_end().len());
+        }
+        if !body.trim().is_empty() {
+            parts.push(prefix + &body + &closing(cut));
+        }
+        // Separator of the parts and formatting closed at the end of the part
+        start = cut;
+        if cut_level == 1 && matches!(atoms[start].kind, Kind::Newline) {
+            start += 1;
+        }
+        while start < atoms.len()
+            && (matches!(atoms[start].kind, Kind::Close) || cut_level >= 2 && is_space(start))
+        {
+            start += 1;
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html(text: &str, max_length: usize) -> Vec<String> {
+        split_text(text, Some(ParseMode::HTML), max_length)
+    }
+
+    #[test]
+    fn test_short_text_is_not_split() {
+        assert_eq!(html("<b>short</b>", 100), vec!["<b>short</b>"]);
+        assert_eq!(split_text("", None, 10), vec![""]);
+    }
+
+    #[test]
+    fn test_plain_text_splits_at_spaces() {
+        let parts = split_text("one two three four five", None, 10);
+        assert_eq!(parts, vec!["one two", "three four", "five"]);
+    }
+
+    #[test]
+    fn test_paragraph_preferred_over_space() {
+        let parts = html("first line\n\nsecond part of text", 25);
+        assert_eq!(parts, vec!["first line", "second part of text"]);
+    }
+
+    #[test]
+    fn test_tags_are_balanced() {
+        let parts = html("<b>bold text that is long</b> tail", 20);
+        assert_eq!(
+            parts,
+            vec!["<b>bold text</b>", "<b>that is long</b>", "tail"]
+        );
+    }
+
+    #[test]
+    fn test_entities_and_tags_are_not_split() {
+        let parts = html("a &lt;&gt; <i>b</i>", 9);
+        assert_eq!(parts, vec!["a", "&lt;&gt;", "<i>b</i>"]);
+    }
+
+    #[test]
+    fn test_split_before_pre_block() {
+        let parts = html("intro text<pre>let a = 1;\nlet b = 2;</pre>", 40);
+        assert_eq!(
+            parts,
+            vec!["intro text", "<pre>let a = 1;\nlet b = 2;</pre>"]
+        );
+    }
+
+    #[test]
+    fn test_long_pre_block_splits_at_lines() {
+        let parts = html(
+            "<pre class=\"rust\">line one\nline two\nline three</pre>",
+            42,
+        );
+        assert_eq!(
+            parts,
+            vec![
+                "<pre class=\"rust\">line one\nline two</pre>",
+                "<pre class=\"rust\">line three</pre>",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_items() {
+        let parts = html("<ol><li>first item</li><li>second item</li></ol>", 36);
+        assert_eq!(
+            parts,
+            vec![
+                "<ol><li>first item</li></ol>",
+                "<ol><li>second item</li></ol>",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_link_is_kept_whole() {
+        let text = "see <a href=\"https://example.com/\">the docs</a> now";
+        let parts = html(text, 44);
+        assert_eq!(
+            parts,
+            vec![
+                "see",
+                "<a href=\"https://example.com/\">the docs</a>",
+                "now"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markdown_styles_and_fences() {
+        let text = "*bold words here* and\n```rust\nlet a;\nlet b;\n```";
+        let parts = split_text(text, Some(ParseMode::MarkdownV2), 20);
+        assert_eq!(
+            parts,
+            vec![
+                "*bold words here*",
+                "and",
+                "```rust\nlet a;\n```",
+                "```rust\nlet b;\n```",
+            ]
+        );
+        let parts = split_text("*one two three*", Some(ParseMode::MarkdownV2), 10);
+        assert_eq!(parts, vec!["*one two*", "*three*"]);
+    }
+
+    #[test]
+    fn test_markdown_escapes_are_not_split() {
+        let parts = split_text("a\\.b\\.c\\.d", Some(ParseMode::MarkdownV2), 3);
+        assert_eq!(parts, vec!["a\\.", "b\\.", "c\\.", "d"]);
+    }
+
+    #[test]
+    fn test_every_part_fits() {
+        let text = "<b>word </b>".repeat(200) + &"<pre>code line\n</pre>".repeat(50);
+        for part in html(&text, 100) {
+            assert!(part.chars().count() <= 100, "{part}");
+            assert_eq!(part.matches("<b>").count(), part.matches("</b>").count());
+            assert_eq!(
+                part.matches("<pre>").count(),
+                part.matches("</pre>").count()
+            );
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! Bot builder with per-instance settings
+//!
+//! [`Bot::new`] and [`Bot::with_params`] take network, rate limit and event listener
+//! settings from the global configuration. [`BotBuilder`] allows to set them per bot,
+//! so several bots in one process can use different settings,
+//! and to provide a pre-built [`reqwest::Client`], e.g. with a proxy or a custom CA.
+//!
+//! ```no_run
+//! use vkteams_bot::prelude::*;
+//! use vkteams_bot::config::types::NetworkConfig;
+//!
+//! # fn main() -> Result<()> {
+//! let network = NetworkConfig {
+//!     retries: 5,
+//!     ..Default::default()
+//! };
+//! let bot = Bot::builder()
+//!     .token("your_bot_token")
+//!     .api_url("https://api.example.com")
+//!     .network(network)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::api::types::APIVersionUrl;
+use crate::bot::Bot;
+#[cfg(feature = "longpoll")]
+use crate::bot::checkpoint::Checkpoint;
+use crate::bot::net::ConnectionPool;
+#[cfg(feature = "ratelimit")]
+use crate::bot::ratelimit::RateLimiter;
+#[cfg(feature = "ratelimit")]
+use crate::bot::ratelimit_backend::RateLimitBackend;
+use crate::bot::upload_cache::UploadCache;
+use crate::config::CONFIG;
+#[cfg(feature = "longpoll")]
+use crate::config::types::EventListenerConfig;
+use crate::config::types::NetworkConfig;
+#[cfg(feature = "ratelimit")]
+use crate::config::types::RateLimit;
+use crate::error::{BotError, Result};
+use crate::secret::SecretString;
+use once_cell::sync::OnceCell;
+use reqwest::{Client, Url};
+use std::sync::Arc;
+use std::sync::atomic::AtomicU32;
+#[cfg(feature = "ratelimit")]
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// Builder for [`Bot`]
+///
+/// Unset values are taken from:
+/// - token - `VKTEAMS_BOT_API_TOKEN` environment variable
+/// - API URL - `VKTEAMS_BOT_API_URL` environment variable
+/// - settings - global configuration [`CONFIG`]
+#[derive(Debug, Clone, Default)]
+pub struct BotBuilder {
+    version: APIVersionUrl,
+    token: Option<SecretString>,
+    api_url: Option<String>,
+    network: Option<NetworkConfig>,
+    #[cfg(feature = "ratelimit")]
+    rate_limit: Option<RateLimit>,
+    #[cfg(feature = "ratelimit")]
+    rate_limit_backend: Option<Arc<dyn RateLimitBackend>>,
+    #[cfg(feature = "longpoll")]
+    listener: Option<EventListenerConfig>,
+    #[cfg(feature = "longpoll")]
+    checkpoint: Option<Checkpoint>,
+    upload_cache: Option<UploadCache>,
+    client: Option<Client>,
+}
+
+impl BotBuilder {
+    /// Create builder with API version V1
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set API version
+    pub fn version(mut self, version: APIVersionUrl) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set bot API token
+    pub fn token(mut self, token: impl Into<SecretString>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Set base API URL
+    pub fn api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = Some(api_url.into());
+        self
+    }
+
+    /// Set network settings: timeouts, retries, backoff and connection pool size
+    ///
+    /// Timeouts and pool size are not applied to a client set with [`client`](Self::client)
+    pub fn network(mut self, network: NetworkConfig) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Set rate limit settings
+    #[cfg(feature = "ratelimit")]
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Share the rate limit budget with other bot instances through the backend,
+    /// see [`ratelimit_backend`](crate::bot::ratelimit_backend)
+    #[cfg(feature = "ratelimit")]
+    pub fn rate_limit_backend(mut self, backend: impl RateLimitBackend + 'static) -> Self {
+        self.rate_limit_backend = Some(Arc::new(backend));
+        self
+    }
+
+    /// Set event listener settings
+    #[cfg(feature = "longpoll")]
+    pub fn listener(mut self, listener: EventListenerConfig) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    /// Set checkpoint of the last processed event ID for the event listeners
+    #[cfg(feature = "longpoll")]
+    pub fn checkpoint(mut self, checkpoint: Checkpoint) -> Self {
+        self.checkpoint = Some(checkpoint);
+        self
+    }
+
+    /// Set cache of uploaded file ids, so identical content is sent by `fileId` instead of uploaded again
+    pub fn upload_cache(mut self, upload_cache: UploadCache) -> Self {
+        self.upload_cache = Some(upload_cache);
+        self
+    }
+
+    /// Use pre-built HTTP client, e.g. with a proxy or a custom root certificate
+    ///
+    /// [`ConnectionPool::client_builder`] returns a builder with the settings used by default.
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Build bot
+    ///
+    /// ## Errors
+    /// - `BotError::Environment` - token or API URL is not set and not found in environment
+    /// - `BotError::Url` - URL parsing error or unsupported URL scheme
+    pub fn build(self) -> Result<Bot> {
+        debug!("Creating new bot with API version: {:?}", self.version);
+
+        let token = match self.token {
+            Some(token) => token,
+            None => std::env::var(crate::api::types::VKTEAMS_BOT_API_TOKEN)?.into(),
+        };
+        let api_url = match self.api_url {
+            Some(api_url) => api_url,
+            None => std::env::var(crate::api::types::VKTEAMS_BOT_API_URL)?,
+        };
+
+        let base_api_url = Url::parse(&api_url).map_err(BotError::Url)?;
+        match base_api_url.scheme() {
+            "http" | "https" => {
+                debug!("Base API URL scheme is valid: {}", base_api_url.scheme());
+            }
+            _ => {
+                return Err(BotError::Url(url::ParseError::InvalidIpv4Address));
+            }
+        }
+        debug!("API URL successfully parsed");
+
+        let base_api_path = self.version.to_string();
+        debug!("Set API base path: {}", base_api_path);
+
+        let network = self.network.unwrap_or_else(|| CONFIG.network.clone());
+        let connection_pool = match self.client {
+            Some(client) => {
+                debug!("Using provided HTTP client");
+                OnceCell::with_value(ConnectionPool::with_client(client, &network))
+            }
+            None => OnceCell::new(),
+        };
+
+        #[cfg(feature = "ratelimit")]
+        let rate_limit = self.rate_limit.unwrap_or_else(|| CONFIG.rate_limit.clone());
+        #[cfg(feature = "ratelimit")]
+        let rate_limiter = match self.rate_limit_backend {
+            Some(backend) => OnceCell::with_value(Arc::new(Mutex::new(RateLimiter::with_backend(
+                rate_limit.clone(),
+                backend,
+            )))),
+            None => OnceCell::new(),
+        };
+
+        Ok(Bot {
+            connection_pool,
+            token,
+            base_api_url,
+            base_api_path: Arc::<str>::from(base_api_path),
+            event_id: Arc::new(AtomicU32::new(0)),
+            network: Arc::new(network),
+            #[cfg(feature = "ratelimit")]
+            rate_limiter,
+            #[cfg(feature = "ratelimit")]
+            rate_limit: Arc::new(rate_limit),
+            #[cfg(feature = "longpoll")]
+            listener: Arc::new(self.listener.unwrap_or_else(|| CONFIG.listener.clone())),
+            #[cfg(feature = "longpoll")]
+            checkpoint: self.checkpoint,
+            upload_cache: self.upload_cache,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_defaults_from_config() {
+        let bot = BotBuilder::new()
+            .token("token")
+            .api_url("https://example.com")
+            .build()
+            .unwrap();
+        assert_eq!(bot.network_config(), &CONFIG.network);
+        #[cfg(feature = "ratelimit")]
+        assert_eq!(bot.rate_limit_config(), &CONFIG.rate_limit);
+        #[cfg(feature = "longpoll")]
+        assert_eq!(bot.listener_config(), &CONFIG.listener);
+        assert_eq!(bot.set_path("self/get"), "bot/v1/self/get");
+        assert!(bot.connection_pool.get().is_none());
+    }
+
+    #[test]
+    fn test_builder_custom_settings() {
+        let network = NetworkConfig {
+            retries: 7,
+            max_backoff_ms: 100,
+            ..Default::default()
+        };
+        let bot = Bot::builder()
+            .token("token")
+            .api_url("http://localhost:8080")
+            .network(network.clone())
+            .build()
+            .unwrap();
+        assert_eq!(bot.network_config(), &network);
+        assert_eq!(bot.connection_pool().retries, 7);
+        assert_eq!(
+            bot.connection_pool().max_backoff,
+            std::time::Duration::from_millis(100)
+        );
+    }
+
+    #[cfg(feature = "ratelimit")]
+    #[tokio::test]
+    async fn test_builder_rate_limit_backend() {
+        let bot = BotBuilder::new()
+            .token("token")
+            .api_url("https://example.com")
+            .rate_limit(RateLimit {
+                limit: 10,
+                lease_size: 2,
+                ..Default::default()
+            })
+            .rate_limit_backend(crate::bot::ratelimit_backend::MemoryRateLimitBackend::new())
+            .build()
+            .unwrap();
+        let limiter = bot.rate_limiter().lock().await.clone();
+        let chat_id = crate::api::types::ChatId::from("chat");
+        // Leased buckets start empty and take a lease from the backend
+        assert!(limiter.check_rate_limit(&chat_id).await);
+        assert_eq!(limiter.get_available_tokens(&chat_id).await, Some(1));
+    }
+
+    #[cfg(feature = "ratelimit")]
+    #[test]
+    fn test_builder_rate_limit() {
+        let rate_limit = RateLimit {
+            limit: 3,
+            ..Default::default()
+        };
+        let bot = Bot::builder()
+            .token("token")
+            .api_url("https://example.com")
+            .rate_limit(rate_limit.clone())
+            .build()
+            .unwrap();
+        assert_eq!(bot.rate_limit_config(), &rate_limit);
+    }
+
+    #[cfg(feature = "longpoll")]
+    #[test]
+    fn test_builder_listener() {
+        let listener = EventListenerConfig {
+            max_events_per_batch: 1,
+            ..Default::default()
+        };
+        let bot = Bot::builder()
+            .token("token")
+            .api_url("https://example.com")
+            .listener(listener.clone())
+            .build()
+            .unwrap();
+        assert_eq!(bot.listener_config(), &listener);
+    }
+
+    #[test]
+    fn test_builder_with_client() {
+        let network = NetworkConfig {
+            retries: 1,
+            ..Default::default()
+        };
+        let bot = Bot::builder()
+            .token("token")
+            .api_url("https://example.com")
+            .network(network)
+            .client(Client::new())
+            .build()
+            .unwrap();
+        let pool = bot.connection_pool.get().expect("pool must be initialized");
+        assert_eq!(pool.retries, 1);
+    }
+
+    #[test]
+    fn test_builder_invalid_url() {
+        let res = Bot::builder()
+            .token("token")
+            .api_url("ftp://example.com")
+            .build();
+        assert!(matches!(res, Err(BotError::Url(_))));
+        let res = Bot::builder().token("token").api_url("not a url").build();
+        assert!(matches!(res, Err(BotError::Url(_))));
+    }
+
+    #[test]
+    fn test_clones_share_settings() {
+        let bot = Bot::builder()
+            .token("token")
+            .api_url("https://example.com")
+            .build()
+            .unwrap();
+        let clone = bot.clone();
+        assert!(Arc::ptr_eq(&bot.network, &clone.network));
+    }
+}
@@ -0,0 +1,338 @@
+//! # Event checkpoints
+//! Durable storage of the last processed event id for crash-safe long polling.
+//!
+//! Without a checkpoint the last event id lives only in memory, so after a restart
+//! events are replayed from the beginning or lost. With a [`Checkpoint`] set on the bot,
+//! [`Bot::event_listener`] and [`Bot::event_listener_parallel`] load the event id on startup
+//! and save it after each batch according to [`CheckpointMode`].
+//!
+//! Storage backends implement [`CheckpointStore`]:
+//! - [`MemoryCheckpointStore`] - in-process storage
+//! - [`FileCheckpointStore`] - file with the event id, replaced atomically
+//! - `PostgresCheckpointStore` - PostgreSQL storage (`storage` feature)
+//!
+//! ```no_run
+//! use vkteams_bot::prelude::*;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let checkpoint = Checkpoint::new(FileCheckpointStore::new("bot.checkpoint"))
+//!     .with_mode(CheckpointMode::AfterSuccess);
+//! let bot = Bot::builder().checkpoint(checkpoint).build()?;
+//! bot.event_listener(|_bot, events| async move {
+//!     println!("{events:?}");
+//!     Ok(())
+//! })
+//! .await
+//! # }
+//! ```
+//!
+//! [`Bot::event_listener`]: crate::Bot::event_listener
+//! [`Bot::event_listener_parallel`]: crate::Bot::event_listener_parallel
+use crate::api::types::EventId;
+use crate::error::{BotError, Result};
+use async_trait::async_trait;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::Mutex;
+use tracing::{debug, error};
+
+/// When the event id is saved relative to the handler call
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CheckpointMode {
+    /// Save after the handler succeeds: at-least-once delivery,
+    /// events of an interrupted batch are received again after restart.
+    /// The handler must return the error of a failed event: the default [`Dispatcher`]
+    /// logs and drops it, [`Bot::run_dispatcher`] propagates errors in this mode.
+    ///
+    /// [`Dispatcher`]: crate::bot::dispatcher::Dispatcher
+    /// [`Bot::run_dispatcher`]: crate::Bot::run_dispatcher
+    #[default]
+    AfterSuccess,
+    /// Save before the handler is called: at-most-once delivery,
+    /// events of an interrupted batch are not received again
+    BeforeHandling,
+}
+
+/// Storage of the last processed event id
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    /// Load saved event id, `None` if nothing was saved yet
+    async fn load(&self) -> Result<Option<EventId>>;
+    /// Save event id
+    async fn save(&self, event_id: EventId) -> Result<()>;
+}
+
+/// In-memory checkpoint store
+#[derive(Debug, Default)]
+pub struct MemoryCheckpointStore {
+    event_id: Mutex<Option<EventId>>,
+}
+
+impl MemoryCheckpointStore {
+    /// Create new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for MemoryCheckpointStore {
+    async fn load(&self) -> Result<Option<EventId>> {
+        Ok(*self.event_id.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    async fn save(&self, event_id: EventId) -> Result<()> {
+        *self.event_id.lock().unwrap_or_else(|e| e.into_inner()) = Some(event_id);
+        Ok(())
+    }
+}
+
+/// File checkpoint store
+///
+/// The event id is written as text to a temporary file next to the target,
+/// which is then renamed over it, so a crash never leaves a partially written checkpoint.
+#[derive(Debug, Clone)]
+pub struct FileCheckpointStore {
+    path: PathBuf,
+}
+
+impl FileCheckpointStore {
+    /// Create store for the file, parent directories are created on first save
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Checkpoint file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for FileCheckpointStore {
+    async fn load(&self) -> Result<Option<EventId>> {
+        let content = match tokio::fs::read_to_string(&self.path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(BotError::Io(e)),
+        };
+        content.trim().parse().map(Some).map_err(|e| {
+            BotError::Validation(format!(
+                "Invalid checkpoint file {}: {e}",
+                self.path.display()
+            ))
+        })
+    }
+
+    async fn save(&self, event_id: EventId) -> Result<()> {
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        {
+            use tokio::io::AsyncWriteExt;
+            let mut file = tokio::fs::File::create(&tmp).await?;
+            file.write_all(event_id.to_string().as_bytes()).await?;
+            file.sync_all().await?;
+        }
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+}
+
+/// PostgreSQL checkpoint store
+///
+/// Checkpoints are stored in the `event_checkpoints` table under a name,
+/// so several bots can share the table. Call [`PostgresCheckpointStore::initialize`] to create it.
+#[cfg(feature = "storage")]
+#[derive(Debug, Clone)]
+pub struct PostgresCheckpointStore {
+    pool: sqlx::PgPool,
+    name: String,
+}
+
+#[cfg(feature = "storage")]
+impl PostgresCheckpointStore {
+    /// Create store on top of an existing pool,
+    /// e.g. [`crate::storage::StorageManager::pool`]
+    pub fn new(pool: sqlx::PgPool, name: impl Into<String>) -> Self {
+        Self {
+            pool,
+            name: name.into(),
+        }
+    }
+
+    /// Create `event_checkpoints` table if it does not exist
+    ///
+    /// The table isn't created by the storage migrations, only by this method.
+    ///
+    /// ## Errors
+    /// - `BotError::System` - database error
+    pub async fn initialize(&self) -> Result<()> {
+        sqlx::raw_sql(include_str!("../storage/schema/event_checkpoints.sql"))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage")]
+#[async_trait]
+impl CheckpointStore for PostgresCheckpointStore {
+    async fn load(&self) -> Result<Option<EventId>> {
+        let row: Option<(i64,)> =
+            sqlx::query_as("SELECT event_id FROM event_checkpoints WHERE name = $1")
+                .bind(&self.name)
+                .fetch_optional(&self.pool)
+                .await?;
+        row.map(|(event_id,)| {
+            EventId::try_from(event_id)
+                .map_err(|_| BotError::Validation(format!("Invalid checkpoint: {event_id}")))
+        })
+        .transpose()
+    }
+
+    async fn save(&self, event_id: EventId) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO event_checkpoints (name, event_id, updated_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (name) DO UPDATE
+            SET event_id = EXCLUDED.event_id,
+                updated_at = EXCLUDED.updated_at
+            "#,
+        )
+        .bind(&self.name)
+        .bind(i64::from(event_id))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Checkpoint store with the mode, set on the bot with [`crate::bot::BotBuilder::checkpoint`]
+#[derive(Clone)]
+pub struct Checkpoint {
+    store: Arc<dyn CheckpointStore>,
+    mode: CheckpointMode,
+}
+
+impl fmt::Debug for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Checkpoint")
+            .field("store", &"<store>")
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+impl Checkpoint {
+    /// Create checkpoint with [`CheckpointMode::AfterSuccess`] mode
+    pub fn new(store: impl CheckpointStore + 'static) -> Self {
+        Self::from_arc(Arc::new(store))
+    }
+
+    /// Create checkpoint with a shared store
+    pub fn from_arc(store: Arc<dyn CheckpointStore>) -> Self {
+        Self {
+            store,
+            mode: CheckpointMode::default(),
+        }
+    }
+
+    /// Set checkpoint mode
+    pub fn with_mode(mut self, mode: CheckpointMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Checkpoint mode
+    pub fn mode(&self) -> CheckpointMode {
+        self.mode
+    }
+
+    /// Checkpoint store
+    pub fn store(&self) -> &Arc<dyn CheckpointStore> {
+        &self.store
+    }
+
+    /// Load saved event id
+    ///
+    /// ## Errors
+    /// - store errors
+    pub async fn load(&self) -> Result<Option<EventId>> {
+        self.store.load().await
+    }
+
+    /// Save event id if the mode matches, store errors are logged
+    ///
+    /// A failed save is not fatal: events are received again or skipped after restart,
+    /// depending on the mode.
+    pub(crate) async fn save_if(&self, mode: CheckpointMode, event_id: EventId) {
+        if self.mode != mode {
+            return;
+        }
+        match self.store.save(event_id).await {
+            Ok(()) => debug!("Checkpoint saved: {}", event_id),
+            Err(e) => error!("Failed to save checkpoint {}: {}", event_id, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        let store = MemoryCheckpointStore::new();
+        assert_eq!(store.load().await.unwrap(), None);
+        store.save(42).await.unwrap();
+        assert_eq!(store.load().await.unwrap(), Some(42));
+    }
+
+    #[tokio::test]
+    async fn test_file_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileCheckpointStore::new(dir.path().join("nested").join("checkpoint"));
+        assert_eq!(store.load().await.unwrap(), None);
+
+        store.save(7).await.unwrap();
+        store.save(12).await.unwrap();
+        assert_eq!(store.load().await.unwrap(), Some(12));
+        assert_eq!(
+            std::fs::read_to_string(store.path()).unwrap(),
+            "12".to_string()
+        );
+
+        // Store reopened after restart
+        let store = FileCheckpointStore::new(store.path());
+        assert_eq!(store.load().await.unwrap(), Some(12));
+    }
+
+    #[tokio::test]
+    async fn test_file_store_invalid_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint");
+        std::fs::write(&path, "not a number").unwrap();
+        let store = FileCheckpointStore::new(&path);
+        assert!(matches!(store.load().await, Err(BotError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_save_if_mode() {
+        let store = Arc::new(MemoryCheckpointStore::new());
+        let checkpoint =
+            Checkpoint::from_arc(store.clone()).with_mode(CheckpointMode::BeforeHandling);
+        checkpoint.save_if(CheckpointMode::AfterSuccess, 1).await;
+        assert_eq!(store.load().await.unwrap(), None);
+        checkpoint.save_if(CheckpointMode::BeforeHandling, 2).await;
+        assert_eq!(checkpoint.load().await.unwrap(), Some(2));
+    }
+}
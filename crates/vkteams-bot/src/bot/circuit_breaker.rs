@@ -0,0 +1,457 @@
+//! # Circuit breaker
+//! Fail fast while the API is unavailable instead of retrying every request.
+//!
+//! [`ConnectionPool`] keeps a circuit per API host or per API method,
+//! see [`CircuitBreakerScope`]. A circuit is:
+//! - [`CircuitState::Closed`] - requests are sent, failures are counted in a window of `window_secs`
+//! - [`CircuitState::Open`] - opened when at least `min_requests` were sent in the window
+//!   and the share of failures reached `failure_ratio`. Requests fail at once
+//!   with `BotError::CircuitOpen` for `open_secs`
+//! - [`CircuitState::HalfOpen`] - up to `half_open_probes` probe requests are sent,
+//!   the circuit is closed when all of them succeed and opened again on the first failure
+//!
+//! Network errors and server errors count as failures,
+//! API errors and `429 Too Many Requests` don't: the server is responding.
+//! State changes are logged, [`Bot::circuit_stats`] reports the current state.
+//!
+//! The circuit breaker is disabled by default: with the host scope, timeouts of long-poll
+//! `events/get` requests during an outage also open the circuit of all other requests.
+//!
+//! ```toml
+//! [network.circuit_breaker]
+//! enabled = true
+//! scope = "method"
+//! failure_ratio = 0.5
+//! min_requests = 10
+//! open_secs = 30
+//! ```
+//!
+//! [`ConnectionPool`]: crate::bot::net::ConnectionPool
+//! [`CircuitBreakerScope`]: crate::config::types::CircuitBreakerScope
+//! [`Bot::circuit_stats`]: crate::bot::Bot::circuit_stats
+use crate::config::types::{CircuitBreakerConfig, CircuitBreakerScope};
+use crate::error::{BotError, Result};
+use reqwest::Url;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// State of a circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent
+    Closed,
+    /// Requests fail without being sent
+    Open,
+    /// Probe requests are sent
+    HalfOpen,
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitState::Closed => write!(f, "closed"),
+            CircuitState::Open => write!(f, "open"),
+            CircuitState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+/// Statistics of a circuit
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitStats {
+    /// Host or API method
+    pub key: String,
+    pub state: CircuitState,
+    /// Requests in the current window
+    pub requests: usize,
+    /// Failed requests in the current window
+    pub failures: usize,
+    /// Time until probe requests are let through, for an open circuit
+    pub retry_in: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct Circuit {
+    state: CircuitState,
+    window_start: Instant,
+    requests: usize,
+    failures: usize,
+    opened_at: Instant,
+    probes: usize,
+    probe_successes: usize,
+}
+
+impl Circuit {
+    fn new(now: Instant) -> Self {
+        Self {
+            state: CircuitState::Closed,
+            window_start: now,
+            requests: 0,
+            failures: 0,
+            opened_at: now,
+            probes: 0,
+            probe_successes: 0,
+        }
+    }
+
+    fn reset_window(&mut self, now: Instant) {
+        self.window_start = now;
+        self.requests = 0;
+        self.failures = 0;
+    }
+}
+
+/// Circuits of the connection pool
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(CircuitBreakerConfig::default())
+    }
+}
+
+impl CircuitBreaker {
+    /// Create circuit breaker with the settings
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            circuits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Circuit breaker settings
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
+    /// Circuit key of the request URL: host or host with the API method path
+    pub fn key(&self, url: &Url) -> String {
+        let host = url.host_str().unwrap_or_default();
+        match self.config.scope {
+            CircuitBreakerScope::Host => host.to_string(),
+            CircuitBreakerScope::Method => format!("{host}{}", url.path()),
+        }
+    }
+
+    /// Send the request through the circuit of the URL
+    ///
+    /// ## Errors
+    /// - `BotError::CircuitOpen` - circuit is open, the request was not sent
+    /// - errors of the request
+    pub async fn call<F, T>(&self, url: &Url, request: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        if !self.config.enabled {
+            return request.await;
+        }
+        let mut permit = self.acquire(self.key(url))?;
+        let result = request.await;
+        permit.record(result.as_ref().err().is_none_or(|e| !is_failure(e)));
+        result
+    }
+
+    /// Statistics of all circuits, sorted by key
+    pub fn stats(&self) -> Vec<CircuitStats> {
+        let circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let mut stats: Vec<_> = circuits
+            .iter()
+            .map(|(key, circuit)| CircuitStats {
+                key: key.clone(),
+                state: circuit.state,
+                requests: circuit.requests,
+                failures: circuit.failures,
+                retry_in: (circuit.state == CircuitState::Open).then(|| {
+                    (circuit.opened_at + self.open_duration()).saturating_duration_since(now)
+                }),
+            })
+            .collect();
+        stats.sort_by(|a, b| a.key.cmp(&b.key));
+        stats
+    }
+
+    fn open_duration(&self) -> Duration {
+        Duration::from_secs(self.config.open_secs)
+    }
+
+    /// Let the request through or fail with `BotError::CircuitOpen`
+    fn acquire(&self, key: String) -> Result<Permit<'_>> {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let circuit = circuits
+            .entry(key.clone())
+            .or_insert_with(|| Circuit::new(now));
+        if circuit.state == CircuitState::Open
+            && now.duration_since(circuit.opened_at) >= self.open_duration()
+        {
+            info!("Circuit {} is half-open, sending probe requests", key);
+            circuit.state = CircuitState::HalfOpen;
+            circuit.probes = 0;
+            circuit.probe_successes = 0;
+        }
+        let probe = match circuit.state {
+            CircuitState::Closed => {
+                if now.duration_since(circuit.window_start) >= self.window() {
+                    circuit.reset_window(now);
+                }
+                false
+            }
+            CircuitState::HalfOpen if circuit.probes < self.config.half_open_probes.max(1) => {
+                circuit.probes += 1;
+                true
+            }
+            _ => return Err(BotError::CircuitOpen(key)),
+        };
+        Ok(Permit {
+            breaker: self,
+            key,
+            probe,
+            recorded: false,
+        })
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.config.window_secs)
+    }
+
+    /// Count the result of a request let through by [`CircuitBreaker::acquire`]
+    fn record(&self, key: &str, probe: bool, success: bool) {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(circuit) = circuits.get_mut(key) else {
+            return;
+        };
+        let now = Instant::now();
+        match circuit.state {
+            CircuitState::Closed => {
+                circuit.requests += 1;
+                if !success {
+                    circuit.failures += 1;
+                }
+                if circuit.requests >= self.config.min_requests.max(1)
+                    && circuit.failures as f64
+                        >= circuit.requests as f64 * self.config.failure_ratio
+                {
+                    warn!(
+                        "Circuit {} is open for {:?}: {} of {} requests failed",
+                        key,
+                        self.open_duration(),
+                        circuit.failures,
+                        circuit.requests
+                    );
+                    circuit.state = CircuitState::Open;
+                    circuit.opened_at = now;
+                }
+            }
+            CircuitState::HalfOpen if probe => {
+                if !success {
+                    warn!("Probe request failed, circuit {} is open again", key);
+                    circuit.state = CircuitState::Open;
+                    circuit.opened_at = now;
+                    return;
+                }
+                circuit.probe_successes += 1;
+                if circuit.probe_successes >= self.config.half_open_probes.max(1) {
+                    info!("Circuit {} is closed", key);
+                    circuit.state = CircuitState::Closed;
+                    circuit.reset_window(now);
+                }
+            }
+            // Requests sent before the circuit opened
+            _ => {}
+        }
+    }
+
+    /// Free the probe slot of a cancelled request
+    fn release(&self, key: &str) {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(circuit) = circuits.get_mut(key)
+            && circuit.state == CircuitState::HalfOpen
+        {
+            circuit.probes = circuit.probes.saturating_sub(1);
+        }
+    }
+}
+
+/// Request let through a circuit, frees the probe slot if dropped without a result
+struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    key: String,
+    probe: bool,
+    recorded: bool,
+}
+
+impl Permit<'_> {
+    fn record(&mut self, success: bool) {
+        self.recorded = true;
+        self.breaker.record(&self.key, self.probe, success);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if !self.recorded && self.probe {
+            self.breaker.release(&self.key);
+        }
+    }
+}
+
+/// Errors caused by the unavailable server
+fn is_failure(err: &BotError) -> bool {
+    match err {
+        BotError::Network(e) => !e.is_builder() && !e.is_decode(),
+        // Server and unexpected status errors from `validate_response`
+        BotError::Server(_) | BotError::System(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(scope: CircuitBreakerScope) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            enabled: true,
+            scope,
+            min_requests: 4,
+            open_secs: 0,
+            half_open_probes: 2,
+            ..Default::default()
+        })
+    }
+
+    async fn send(breaker: &CircuitBreaker, url: &str, ok: bool) -> Result<()> {
+        let url = Url::parse(url).unwrap();
+        breaker
+            .call(&url, async move {
+                if ok {
+                    Ok(())
+                } else {
+                    Err(BotError::Server(reqwest::StatusCode::BAD_GATEWAY))
+                }
+            })
+            .await
+    }
+
+    #[test]
+    fn test_key_scope() {
+        let url = Url::parse("https://api.example.com/bot/v1/messages/sendText?chatId=1").unwrap();
+        assert_eq!(
+            breaker(CircuitBreakerScope::Host).key(&url),
+            "api.example.com"
+        );
+        assert_eq!(
+            breaker(CircuitBreakerScope::Method).key(&url),
+            "api.example.com/bot/v1/messages/sendText"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_on_failure_ratio() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            enabled: true,
+            min_requests: 4,
+            ..Default::default()
+        });
+        let url = "https://api.example.com/bot/v1/self/get";
+        send(&breaker, url, true).await.unwrap();
+        send(&breaker, url, true).await.unwrap();
+        assert!(send(&breaker, url, false).await.is_err());
+        assert_eq!(breaker.stats()[0].state, CircuitState::Closed);
+        assert!(matches!(
+            send(&breaker, url, false).await,
+            Err(BotError::Server(_))
+        ));
+
+        let stats = &breaker.stats()[0];
+        assert_eq!(
+            (stats.state, stats.requests, stats.failures),
+            (CircuitState::Open, 4, 2)
+        );
+        assert!(stats.retry_in.unwrap() > Duration::from_secs(25));
+        match send(&breaker, url, true).await {
+            Err(BotError::CircuitOpen(key)) => assert_eq!(key, "api.example.com"),
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert_eq!(breaker.stats()[0].requests, 4);
+    }
+
+    #[tokio::test]
+    async fn test_half_open_probes() {
+        let breaker = breaker(CircuitBreakerScope::Method);
+        let url = "https://api.example.com/bot/v1/self/get";
+        for _ in 0..4 {
+            send(&breaker, url, false).await.unwrap_err();
+        }
+        assert_eq!(breaker.stats()[0].state, CircuitState::Open);
+
+        // A failed probe opens the circuit again
+        send(&breaker, url, false).await.unwrap_err();
+        assert_eq!(breaker.stats()[0].state, CircuitState::Open);
+
+        send(&breaker, url, true).await.unwrap();
+        assert_eq!(breaker.stats()[0].state, CircuitState::HalfOpen);
+        send(&breaker, url, true).await.unwrap();
+        let stats = &breaker.stats()[0];
+        assert_eq!((stats.state, stats.requests), (CircuitState::Closed, 0));
+
+        // Other methods have their own circuits
+        send(
+            &breaker,
+            "https://api.example.com/bot/v1/chats/getInfo",
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(breaker.stats().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_half_open_limits_probes() {
+        let breaker = breaker(CircuitBreakerScope::Host);
+        let url = Url::parse("https://api.example.com/bot/v1/self/get").unwrap();
+        for _ in 0..4 {
+            send(&breaker, url.as_str(), false).await.unwrap_err();
+        }
+        let first = breaker.acquire(breaker.key(&url)).unwrap();
+        let second = breaker.acquire(breaker.key(&url)).unwrap();
+        assert!(matches!(
+            breaker.acquire(breaker.key(&url)),
+            Err(BotError::CircuitOpen(_))
+        ));
+        // Cancelled probes free their slots
+        drop(first);
+        drop(second);
+        assert!(breaker.acquire(breaker.key(&url)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_and_ignored_errors() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            enabled: false,
+            min_requests: 1,
+            ..Default::default()
+        });
+        let url = "https://api.example.com/bot/v1/self/get";
+        send(&breaker, url, false).await.unwrap_err();
+        send(&breaker, url, false).await.unwrap_err();
+        assert!(breaker.stats().is_empty());
+
+        assert!(!is_failure(&BotError::RateLimited(None)));
+        assert!(!is_failure(&BotError::Validation(
+            "HTTP error: 404".to_string()
+        )));
+        assert!(is_failure(&BotError::Server(
+            reqwest::StatusCode::SERVICE_UNAVAILABLE
+        )));
+    }
+}
@@ -0,0 +1,696 @@
+//! # Event dispatcher
+//! Routes incoming events to handlers registered with [`Filter`]s.
+//!
+//! Every event goes to the first route whose filters all match it.
+//! Events that match no route go to the fallback handler, if one is set.
+//! The same [`Dispatcher`] can be used with the long polling listener
+//! ([`Bot::run_dispatcher`]) and with the webhook server
+//! (`DispatcherWebhook` with the `webhook` feature).
+//!
+//! ```no_run
+//! use vkteams_bot::prelude::*;
+//!
+//! async fn start(bot: Bot, event: EventMessage) -> Result<()> {
+//!     if let Some(chat) = event.event_type.chat() {
+//!         let req = RequestMessagesSendText::new(chat.chat_id.clone())
+//!             .set_text(MessageTextParser::new().add(MessageTextFormat::Plain("Hi!".into())))?;
+//!         bot.send_api_request(req).await?;
+//!     }
+//!     Ok(())
+//! }
+//!
+//! async fn other(_bot: Bot, _event: EventMessage) -> Result<()> {
+//!     Ok(())
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let bot = Bot::default();
+//! let dispatcher = Dispatcher::new()
+//!     .on(Filter::command("/start"), start)
+//!     .fallback(other);
+//! bot.run_dispatcher(dispatcher).await
+//! # }
+//! ```
+use crate::api::events::get::ResponseEventsGet;
+use crate::api::types::{ChatId, ChatType, EventKind, EventMessage};
+use crate::bot::Bot;
+use crate::bot::checkpoint::CheckpointMode;
+use crate::bot::fsm::{StateKey, StateManager};
+use crate::error::{BotError, Result};
+use regex::Regex;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+#[cfg(feature = "longpoll")]
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, trace};
+
+/// Boxed future returned by [`Handler`]
+pub type HandlerFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+/// Event handler
+///
+/// Implemented for any `Fn(Bot, EventMessage) -> impl Future<Output = Result<()>>`
+pub trait Handler: Send + Sync + 'static {
+    /// Handle event
+    fn handle(&self, bot: Bot, event: EventMessage) -> HandlerFuture;
+}
+
+impl<F, X> Handler for F
+where
+    F: Fn(Bot, EventMessage) -> X + Send + Sync + 'static,
+    X: Future<Output = Result<()>> + Send + 'static,
+{
+    fn handle(&self, bot: Bot, event: EventMessage) -> HandlerFuture {
+        Box::pin(self(bot, event))
+    }
+}
+
+/// Event filter
+#[derive(Clone)]
+pub enum Filter {
+    /// Event of the given kind
+    Kind(EventKind),
+    /// Event from the given chat
+    ChatId(ChatId),
+    /// Event from a chat of the given type
+    ChatType(ChatType),
+    /// Message text starts with the command, e.g. `/start`
+    Command(String),
+    /// Message text matches the regular expression
+    Regex(Regex),
+    /// Callback data starts with the prefix
+    CallbackPrefix(String),
+    /// Sender's conversation is in the state, see [`Dispatcher::with_states`]
+    State(String),
+    /// Sender's conversation is in any state, see [`Dispatcher::with_states`]
+    AnyState,
+    /// All filters match
+    All(Vec<Filter>),
+    /// Any of the filters matches
+    Any(Vec<Filter>),
+    /// Filter does not match
+    Not(Box<Filter>),
+    /// Custom predicate
+    Custom(Arc<dyn Fn(&EventMessage) -> bool + Send + Sync>),
+}
+
+impl fmt::Debug for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::Kind(kind) => f.debug_tuple("Kind").field(kind).finish(),
+            Filter::ChatId(chat_id) => f.debug_tuple("ChatId").field(chat_id).finish(),
+            Filter::ChatType(chat_type) => f.debug_tuple("ChatType").field(chat_type).finish(),
+            Filter::Command(cmd) => f.debug_tuple("Command").field(cmd).finish(),
+            Filter::Regex(re) => f.debug_tuple("Regex").field(&re.as_str()).finish(),
+            Filter::CallbackPrefix(p) => f.debug_tuple("CallbackPrefix").field(p).finish(),
+            Filter::State(state) => f.debug_tuple("State").field(state).finish(),
+            Filter::AnyState => f.write_str("AnyState"),
+            Filter::All(filters) => f.debug_tuple("All").field(filters).finish(),
+            Filter::Any(filters) => f.debug_tuple("Any").field(filters).finish(),
+            Filter::Not(filter) => f.debug_tuple("Not").field(filter).finish(),
+            Filter::Custom(_) => f.write_str("Custom(<fn>)"),
+        }
+    }
+}
+
+impl Filter {
+    /// Match events of the given kind
+    pub fn kind(kind: EventKind) -> Self {
+        Filter::Kind(kind)
+    }
+
+    /// Match events from the given chat
+    pub fn chat_id(chat_id: impl Into<ChatId>) -> Self {
+        Filter::ChatId(chat_id.into())
+    }
+
+    /// Match events from chats of the given type
+    pub fn chat_type(chat_type: ChatType) -> Self {
+        Filter::ChatType(chat_type)
+    }
+
+    /// Match messages starting with the command, e.g. `/start` or `/start@bot`
+    pub fn command(command: impl Into<String>) -> Self {
+        let command = command.into();
+        let command = command.trim_start_matches('/');
+        Filter::Command(format!("/{command}"))
+    }
+
+    /// Match messages with text matching the regular expression
+    ///
+    /// ## Errors
+    /// - `BotError::Validation` - invalid regular expression
+    pub fn regex(pattern: &str) -> Result<Self> {
+        Regex::new(pattern)
+            .map(Filter::Regex)
+            .map_err(|e| BotError::Validation(format!("Invalid regex filter: {e}")))
+    }
+
+    /// Match callback queries with callback data starting with the prefix
+    pub fn callback_prefix(prefix: impl Into<String>) -> Self {
+        Filter::CallbackPrefix(prefix.into())
+    }
+
+    /// Match events from users whose conversation is in the state
+    ///
+    /// Never matches if the dispatcher has no [`StateManager`].
+    pub fn state(state: impl Into<String>) -> Self {
+        Filter::State(state.into())
+    }
+
+    /// Match events from users whose conversation is in any state
+    ///
+    /// Never matches if the dispatcher has no [`StateManager`].
+    pub fn any_state() -> Self {
+        Filter::AnyState
+    }
+
+    /// Match events for which the predicate returns `true`
+    pub fn custom<F>(predicate: F) -> Self
+    where
+        F: Fn(&EventMessage) -> bool + Send + Sync + 'static,
+    {
+        Filter::Custom(Arc::new(predicate))
+    }
+
+    /// Combine with another filter, both must match
+    pub fn and(self, other: Filter) -> Self {
+        match self {
+            Filter::All(mut filters) => {
+                filters.push(other);
+                Filter::All(filters)
+            }
+            filter => Filter::All(vec![filter, other]),
+        }
+    }
+
+    /// Combine with another filter, any must match
+    pub fn or(self, other: Filter) -> Self {
+        match self {
+            Filter::Any(mut filters) => {
+                filters.push(other);
+                Filter::Any(filters)
+            }
+            filter => Filter::Any(vec![filter, other]),
+        }
+    }
+
+    /// Check if the event matches the filter, state filters never match
+    pub fn matches(&self, event: &EventMessage) -> bool {
+        self.matches_state(event, None)
+    }
+
+    /// Check if the event matches the filter with the sender's conversation state
+    pub fn matches_state(&self, event: &EventMessage, state: Option<&str>) -> bool {
+        let event_type = &event.event_type;
+        match self {
+            Filter::Kind(kind) => event_type.kind() == *kind,
+            Filter::ChatId(chat_id) => event_type
+                .chat()
+                .is_some_and(|chat| chat.chat_id == *chat_id),
+            Filter::ChatType(chat_type) => event_type
+                .chat()
+                .is_some_and(|chat| chat.chat_type.eq_ignore_ascii_case(chat_type.as_str())),
+            Filter::Command(command) => event_type
+                .text()
+                .is_some_and(|text| is_command(text, command)),
+            Filter::Regex(re) => event_type.text().is_some_and(|text| re.is_match(text)),
+            Filter::CallbackPrefix(prefix) => event_type
+                .callback_data()
+                .is_some_and(|data| data.starts_with(prefix.as_str())),
+            Filter::State(expected) => state == Some(expected.as_str()),
+            Filter::AnyState => state.is_some(),
+            Filter::All(filters) => filters.iter().all(|f| f.matches_state(event, state)),
+            Filter::Any(filters) => filters.iter().any(|f| f.matches_state(event, state)),
+            Filter::Not(filter) => !filter.matches_state(event, state),
+            Filter::Custom(predicate) => predicate(event),
+        }
+    }
+}
+
+impl std::ops::Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Self::Output {
+        Filter::Not(Box::new(self))
+    }
+}
+
+/// Check that the first word of the text is the command,
+/// optionally addressed to a bot as `/command@bot`
+fn is_command(text: &str, command: &str) -> bool {
+    let Some(word) = text.split_whitespace().next() else {
+        return false;
+    };
+    let name = word.split_once('@').map_or(word, |(name, _)| name);
+    name == command
+}
+
+/// Handler with filters
+#[derive(Clone)]
+struct Route {
+    filter: Filter,
+    handler: Arc<dyn Handler>,
+}
+
+/// Event dispatcher
+///
+/// Routes are checked in registration order, the first matching route handles the event.
+#[derive(Clone, Default)]
+pub struct Dispatcher {
+    routes: Vec<Route>,
+    fallback: Option<Arc<dyn Handler>>,
+    states: Option<StateManager>,
+    propagate_errors: bool,
+}
+
+impl fmt::Debug for Dispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dispatcher")
+            .field(
+                "routes",
+                &self.routes.iter().map(|r| &r.filter).collect::<Vec<_>>(),
+            )
+            .field("fallback", &self.fallback.is_some())
+            .field("states", &self.states)
+            .field("propagate_errors", &self.propagate_errors)
+            .finish()
+    }
+}
+
+impl Dispatcher {
+    /// Create new empty dispatcher
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register handler for events matching the filter
+    pub fn on<H>(mut self, filter: Filter, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        self.routes.push(Route {
+            filter,
+            handler: Arc::new(handler),
+        });
+        self
+    }
+
+    /// Register handler for events matching all filters
+    pub fn on_all<H>(self, filters: Vec<Filter>, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        self.on(Filter::All(filters), handler)
+    }
+
+    /// Set handler for events not matched by any route
+    pub fn fallback<H>(mut self, handler: H) -> Self
+    where
+        H: Handler,
+    {
+        self.fallback = Some(Arc::new(handler));
+        self
+    }
+
+    /// Use conversation states for [`Filter::State`] and [`Filter::AnyState`]
+    ///
+    /// The sender's state is loaded before matching each event.
+    pub fn with_states(mut self, states: StateManager) -> Self {
+        self.states = Some(states);
+        self
+    }
+
+    /// Conversation state manager
+    pub fn states(&self) -> Option<&StateManager> {
+        self.states.as_ref()
+    }
+
+    /// Return handler errors to the caller instead of logging them.
+    ///
+    /// When enabled, a failed handler stops processing of the remaining events in the batch
+    /// and the error is returned from [`Dispatcher::dispatch_events`].
+    /// Disabled by default: a failed event is logged and dropped, the batch counts as handled.
+    /// [`Bot::run_dispatcher`] enables it for a bot with a [`CheckpointMode::AfterSuccess`]
+    /// checkpoint, so failed events are received again.
+    pub fn propagate_errors(mut self, propagate: bool) -> Self {
+        self.propagate_errors = propagate;
+        self
+    }
+
+    /// Number of registered routes
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Check if no routes are registered
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Find handler for the event
+    fn find_handler(&self, event: &EventMessage, state: Option<&str>) -> Option<&Arc<dyn Handler>> {
+        self.routes
+            .iter()
+            .position(|route| route.filter.matches_state(event, state))
+            .map(|idx| {
+                trace!("Event {} matched route {}", event.event_id, idx);
+                &self.routes[idx].handler
+            })
+            .or(self.fallback.as_ref())
+    }
+
+    /// Dispatch single event to the first matching handler
+    ///
+    /// Returns `Ok(false)` if neither a route nor the fallback handled the event.
+    ///
+    /// ## Errors
+    /// - any error returned by the handler
+    /// - state storage error
+    pub async fn dispatch(&self, bot: Bot, event: EventMessage) -> Result<bool> {
+        let state = match (&self.states, StateKey::from_event(&event)) {
+            (Some(states), Some(key)) => states.get(&key).await?.map(|record| record.state),
+            _ => None,
+        };
+        match self.find_handler(&event, state.as_deref()) {
+            Some(handler) => {
+                handler.handle(bot, event).await?;
+                Ok(true)
+            }
+            None => {
+                debug!("No handler for event {}", event.event_id);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Dispatch batch of events in order
+    ///
+    /// ## Errors
+    /// - handler error, if [`Dispatcher::propagate_errors`] is enabled
+    pub async fn dispatch_events(&self, bot: Bot, events: ResponseEventsGet) -> Result<()> {
+        for event in events.events {
+            let event_id = event.event_id;
+            if let Err(e) = self.dispatch(bot.clone(), event).await {
+                if self.propagate_errors {
+                    return Err(e);
+                }
+                error!("Error handling event {}: {}", event_id, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "longpoll")]
+impl Bot {
+    /// Listen for events and route them through the [`Dispatcher`]
+    ///
+    /// With a [`CheckpointMode::AfterSuccess`] checkpoint handler errors are always propagated,
+    /// see [`Dispatcher::propagate_errors`].
+    ///
+    /// ## Errors
+    /// - `BotError::Api` - API error when getting events
+    /// - `BotError::Network` - network error when getting events
+    /// - `BotError::Serialization` - response deserialization error
+    /// - handler error, if [`Dispatcher::propagate_errors`] is enabled
+    pub async fn run_dispatcher(&self, dispatcher: Dispatcher) -> Result<()> {
+        let dispatcher = self.checkpointed_dispatcher(dispatcher);
+        self.event_listener(move |bot, events| {
+            let dispatcher = dispatcher.clone();
+            async move { dispatcher.dispatch_events(bot, events).await }
+        })
+        .await
+    }
+
+    /// Listen for events and route them through the [`Dispatcher`] until the token is cancelled,
+    /// see [`Bot::event_listener_with_shutdown`]
+    ///
+    /// ## Errors
+    /// - handler error, if [`Dispatcher::propagate_errors`] is enabled
+    pub async fn run_dispatcher_with_shutdown(
+        &self,
+        dispatcher: Dispatcher,
+        shutdown: CancellationToken,
+    ) -> Result<()> {
+        let dispatcher = self.checkpointed_dispatcher(dispatcher);
+        self.event_listener_with_shutdown(
+            move |bot, events| {
+                let dispatcher = dispatcher.clone();
+                async move { dispatcher.dispatch_events(bot, events).await }
+            },
+            shutdown,
+        )
+        .await
+    }
+
+    /// Propagate handler errors with a [`CheckpointMode::AfterSuccess`] checkpoint,
+    /// otherwise the checkpoint is saved past the failed events
+    fn checkpointed_dispatcher(&self, dispatcher: Dispatcher) -> Arc<Dispatcher> {
+        let after_success = self
+            .checkpoint()
+            .is_some_and(|checkpoint| checkpoint.mode() == CheckpointMode::AfterSuccess);
+        if after_success && !dispatcher.propagate_errors {
+            debug!("Propagating handler errors to save the checkpoint after success only");
+            return Arc::new(dispatcher.propagate_errors(true));
+        }
+        Arc::new(dispatcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::types::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn bot() -> Bot {
+        Bot::with_params(&APIVersionUrl::V1, "test_token", "https://example.com").unwrap()
+    }
+
+    fn chat(id: &'static str, chat_type: &str) -> Chat {
+        Chat {
+            chat_id: ChatId::from(id),
+            title: None,
+            chat_type: chat_type.to_string(),
+        }
+    }
+
+    fn message(text: &str) -> EventMessage {
+        EventMessage {
+            event_id: 1,
+            event_type: EventType::NewMessage(Box::new(EventPayloadNewMessage {
+                text: text.to_string(),
+                chat: chat("chat@chat.agent", "group"),
+                ..Default::default()
+            })),
+        }
+    }
+
+    fn callback(data: &str) -> EventMessage {
+        EventMessage {
+            event_id: 2,
+            event_type: EventType::CallbackQuery(Box::new(EventPayloadCallbackQuery {
+                query_id: QueryId("q".to_string()),
+                from: From::default(),
+                chat: Chat::default(),
+                message: EventPayloadNewMessage {
+                    chat: chat("user@example.com", "private"),
+                    ..Default::default()
+                },
+                callback_data: data.to_string(),
+            })),
+        }
+    }
+
+    fn counter() -> (Arc<AtomicUsize>, impl Handler) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let count_clone = count.clone();
+        let handler = move |_bot: Bot, _event: EventMessage| {
+            let count = count_clone.clone();
+            async move {
+                count.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        };
+        (count, handler)
+    }
+
+    #[test]
+    fn test_command_filter() {
+        let filter = Filter::command("start");
+        assert!(filter.matches(&message("/start")));
+        assert!(filter.matches(&message("  /start arg1 arg2")));
+        assert!(filter.matches(&message("/start@my_bot")));
+        assert!(!filter.matches(&message("/started")));
+        assert!(!filter.matches(&message("start")));
+        assert!(!filter.matches(&message("")));
+        assert!(!filter.matches(&callback("/start")));
+    }
+
+    #[test]
+    fn test_kind_chat_filters() {
+        assert!(Filter::kind(EventKind::NewMessage).matches(&message("hi")));
+        assert!(!Filter::kind(EventKind::CallbackQuery).matches(&message("hi")));
+        assert!(Filter::chat_id("chat@chat.agent").matches(&message("hi")));
+        assert!(!Filter::chat_id("other").matches(&message("hi")));
+        assert!(Filter::chat_type(ChatType::Group).matches(&message("hi")));
+        assert!(Filter::chat_type(ChatType::Private).matches(&callback("x")));
+        assert!(!Filter::chat_type(ChatType::Channel).matches(&message("hi")));
+    }
+
+    #[test]
+    fn test_regex_and_callback_filters() {
+        let filter = Filter::regex(r"^\d{4}-\d{2}-\d{2}$").unwrap();
+        assert!(filter.matches(&message("2024-01-31")));
+        assert!(!filter.matches(&message("tomorrow")));
+        assert!(Filter::regex("(").is_err());
+
+        let filter = Filter::callback_prefix("vote:");
+        assert!(filter.matches(&callback("vote:yes")));
+        assert!(!filter.matches(&callback("menu:yes")));
+        assert!(!filter.matches(&message("vote:yes")));
+    }
+
+    #[test]
+    fn test_combined_filters() {
+        let filter = Filter::kind(EventKind::NewMessage).and(Filter::chat_type(ChatType::Group));
+        assert!(filter.matches(&message("hi")));
+        assert!(!filter.matches(&callback("hi")));
+
+        let filter = Filter::command("/a").or(Filter::command("/b"));
+        assert!(filter.matches(&message("/b")));
+        assert!(!filter.matches(&message("/c")));
+
+        let filter = !Filter::command("/a");
+        assert!(!filter.matches(&message("/a")));
+        assert!(filter.matches(&message("/c")));
+
+        let filter = Filter::custom(|event| event.event_id == 2);
+        assert!(filter.matches(&callback("x")));
+        assert!(!filter.matches(&message("x")));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_first_match_and_fallback() {
+        let (start_count, start) = counter();
+        let (any_count, any) = counter();
+        let (fallback_count, fallback) = counter();
+        let dispatcher = Dispatcher::new()
+            .on(Filter::command("/start"), start)
+            .on(Filter::kind(EventKind::NewMessage), any)
+            .fallback(fallback);
+        assert_eq!(dispatcher.len(), 2);
+
+        assert!(dispatcher.dispatch(bot(), message("/start")).await.unwrap());
+        assert!(dispatcher.dispatch(bot(), message("hello")).await.unwrap());
+        assert!(dispatcher.dispatch(bot(), callback("x")).await.unwrap());
+
+        assert_eq!(start_count.load(Ordering::SeqCst), 1);
+        assert_eq!(any_count.load(Ordering::SeqCst), 1);
+        assert_eq!(fallback_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_unknown_event() {
+        let unknown = EventMessage {
+            event_id: 3,
+            event_type: EventType::Unknown {
+                event_type: "changedChatInfo".to_string(),
+                payload: serde_json::json!({"chat": {"chatId": "chat@chat.agent"}}),
+            },
+        };
+        assert!(Filter::kind(EventKind::Unknown).matches(&unknown));
+        assert!(!Filter::command("/start").matches(&unknown));
+        assert!(!Filter::chat_id("chat@chat.agent").matches(&unknown));
+
+        let (count, handler) = counter();
+        let (fallback_count, fallback) = counter();
+        let dispatcher = Dispatcher::new()
+            .on(Filter::kind(EventKind::NewMessage), handler)
+            .fallback(fallback);
+        assert!(dispatcher.dispatch(bot(), unknown).await.unwrap());
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        assert_eq!(fallback_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_without_fallback() {
+        let (count, handler) = counter();
+        let dispatcher = Dispatcher::new().on(Filter::callback_prefix("a"), handler);
+        assert!(!dispatcher.dispatch(bot(), message("a")).await.unwrap());
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_state_filters() {
+        let (date_count, date) = counter();
+        let (busy_count, busy) = counter();
+        let (fallback_count, fallback) = counter();
+        let states = StateManager::in_memory();
+        let dispatcher = Dispatcher::new()
+            .with_states(states.clone())
+            .on(Filter::state("await_date"), date)
+            .on(Filter::any_state().and(Filter::command("/help")), busy)
+            .fallback(fallback);
+
+        let mut event = message("2024-01-31");
+        if let EventType::NewMessage(payload) = &mut event.event_type {
+            payload.from.user_id = UserId("user".to_string());
+        }
+        let key = StateKey::from_event(&event).unwrap();
+
+        dispatcher.dispatch(bot(), event.clone()).await.unwrap();
+        assert_eq!(fallback_count.load(Ordering::SeqCst), 1);
+
+        states
+            .set(&key, crate::bot::fsm::StateRecord::new("await_date"))
+            .await
+            .unwrap();
+        dispatcher.dispatch(bot(), event).await.unwrap();
+        assert_eq!(date_count.load(Ordering::SeqCst), 1);
+
+        states
+            .set(&key, crate::bot::fsm::StateRecord::new("other"))
+            .await
+            .unwrap();
+        let mut help = message("/help");
+        if let EventType::NewMessage(payload) = &mut help.event_type {
+            payload.from.user_id = UserId("user".to_string());
+        }
+        dispatcher.dispatch(bot(), help.clone()).await.unwrap();
+        assert_eq!(busy_count.load(Ordering::SeqCst), 1);
+
+        assert!(!Filter::state("other").matches(&help));
+        assert!(Filter::state("other").matches_state(&help, Some("other")));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_events_error_policy() {
+        let (count, handler) = counter();
+        let failing = |_bot: Bot, _event: EventMessage| async move {
+            Err(BotError::System("fail".to_string()))
+        };
+        let events = ResponseEventsGet {
+            events: vec![message("/fail"), message("ok"), message("ok")],
+        };
+
+        let dispatcher = Dispatcher::new()
+            .on(Filter::command("/fail"), failing)
+            .fallback(handler);
+        assert!(
+            dispatcher
+                .dispatch_events(bot(), events.clone())
+                .await
+                .is_ok()
+        );
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+
+        let dispatcher = dispatcher.propagate_errors(true);
+        assert!(dispatcher.dispatch_events(bot(), events).await.is_err());
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+}
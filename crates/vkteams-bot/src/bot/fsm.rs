@@ -0,0 +1,622 @@
+//! # Conversation state machine
+//! Per-user conversation state for multi-step dialogs.
+//!
+//! State is keyed by [`StateKey`] (chat id + user id), so the same user can run
+//! independent dialogs in different chats. Each state has a name and optional JSON data.
+//! States expire after [`StateManager::with_timeout`] (30 minutes by default).
+//!
+//! Storage backends implement [`StateStorage`]:
+//! - [`MemoryStateStorage`] - in-process storage
+//! - `PostgresStateStorage` - PostgreSQL storage (`storage` feature)
+//!
+//! ```no_run
+//! use vkteams_bot::prelude::*;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let states = StateManager::in_memory();
+//! let on_start = {
+//!     let states = states.clone();
+//!     move |_bot: Bot, event: EventMessage| {
+//!         let states = states.clone();
+//!         async move {
+//!             if let Some(ctx) = states.context(&event) {
+//!                 ctx.transition("await_date").await?;
+//!             }
+//!             Ok(())
+//!         }
+//!     }
+//! };
+//! let on_date = {
+//!     let states = states.clone();
+//!     move |_bot: Bot, event: EventMessage| {
+//!         let states = states.clone();
+//!         async move {
+//!             if let Some(ctx) = states.context(&event) {
+//!                 ctx.finish().await?;
+//!             }
+//!             Ok(())
+//!         }
+//!     }
+//! };
+//! let dispatcher = Dispatcher::new()
+//!     .with_states(states)
+//!     .on(Filter::command("/start"), on_start)
+//!     .on(Filter::state("await_date"), on_date);
+//! Bot::default().run_dispatcher(dispatcher).await
+//! # }
+//! ```
+use crate::api::types::{ChatId, EventMessage, UserId};
+use crate::error::Result;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::trace;
+
+/// Default state timeout
+pub const DEFAULT_STATE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Conversation key: user in a chat
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StateKey {
+    pub chat_id: ChatId,
+    pub user_id: UserId,
+}
+
+impl StateKey {
+    /// Create new key
+    pub fn new(chat_id: impl Into<ChatId>, user_id: UserId) -> Self {
+        Self {
+            chat_id: chat_id.into(),
+            user_id,
+        }
+    }
+
+    /// Get key from the event chat and sender
+    ///
+    /// Returns `None` for events without chat or sender.
+    pub fn from_event(event: &EventMessage) -> Option<Self> {
+        let chat = event.event_type.chat()?;
+        let from = event.event_type.from()?;
+        Some(Self::new(chat.chat_id.clone(), from.user_id.clone()))
+    }
+}
+
+/// Conversation state: name and data
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StateRecord {
+    pub state: String,
+    #[serde(default)]
+    pub data: Value,
+}
+
+impl StateRecord {
+    /// Create state without data
+    pub fn new(state: impl Into<String>) -> Self {
+        Self {
+            state: state.into(),
+            data: Value::Null,
+        }
+    }
+
+    /// Set state data
+    ///
+    /// ## Errors
+    /// - `BotError::Serialization` - data serialization error
+    pub fn with_data<T: Serialize>(mut self, data: &T) -> Result<Self> {
+        self.data = serde_json::to_value(data)?;
+        Ok(self)
+    }
+
+    /// Get state data as `T`
+    ///
+    /// ## Errors
+    /// - `BotError::Serialization` - data deserialization error
+    pub fn data_as<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_value(self.data.clone())?)
+    }
+}
+
+/// Conversation state storage backend
+#[async_trait]
+pub trait StateStorage: Send + Sync {
+    /// Get state, `None` if not set or expired
+    async fn get(&self, key: &StateKey) -> Result<Option<StateRecord>>;
+    /// Set state, replacing the previous one. `ttl` of `None` never expires.
+    async fn set(&self, key: &StateKey, record: &StateRecord, ttl: Option<Duration>) -> Result<()>;
+    /// Remove state
+    async fn remove(&self, key: &StateKey) -> Result<()>;
+    /// Remove expired states, returns number of removed states
+    async fn remove_expired(&self) -> Result<u64>;
+}
+
+#[derive(Debug, Clone)]
+struct MemoryEntry {
+    record: StateRecord,
+    expires_at: Option<Instant>,
+}
+
+impl MemoryEntry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+/// In-memory state storage
+///
+/// Expired states are removed lazily on access and by [`StateStorage::remove_expired`].
+#[derive(Debug, Default)]
+pub struct MemoryStateStorage {
+    states: DashMap<StateKey, MemoryEntry>,
+}
+
+impl MemoryStateStorage {
+    /// Create new empty storage
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of stored states, including expired but not yet removed
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Check if storage is empty
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+}
+
+#[async_trait]
+impl StateStorage for MemoryStateStorage {
+    async fn get(&self, key: &StateKey) -> Result<Option<StateRecord>> {
+        let now = Instant::now();
+        match self.states.get(key) {
+            Some(entry) if !entry.is_expired(now) => return Ok(Some(entry.record.clone())),
+            Some(_) => {}
+            None => return Ok(None),
+        }
+        self.states.remove_if(key, |_, entry| entry.is_expired(now));
+        Ok(None)
+    }
+
+    async fn set(&self, key: &StateKey, record: &StateRecord, ttl: Option<Duration>) -> Result<()> {
+        let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+        self.states.insert(
+            key.clone(),
+            MemoryEntry {
+                record: record.clone(),
+                expires_at,
+            },
+        );
+        Ok(())
+    }
+
+    async fn remove(&self, key: &StateKey) -> Result<()> {
+        self.states.remove(key);
+        Ok(())
+    }
+
+    async fn remove_expired(&self) -> Result<u64> {
+        let now = Instant::now();
+        let before = self.states.len();
+        self.states.retain(|_, entry| !entry.is_expired(now));
+        Ok(before.saturating_sub(self.states.len()) as u64)
+    }
+}
+
+/// PostgreSQL state storage
+///
+/// States are stored in the `conversation_states` table,
+/// call [`PostgresStateStorage::initialize`] to create it.
+#[cfg(feature = "storage")]
+#[derive(Debug, Clone)]
+pub struct PostgresStateStorage {
+    pool: sqlx::PgPool,
+}
+
+#[cfg(feature = "storage")]
+impl PostgresStateStorage {
+    /// Create storage on top of an existing pool,
+    /// e.g. [`crate::storage::StorageManager::pool`]
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create `conversation_states` table if it does not exist
+    ///
+    /// The table isn't created by the storage migrations, only by this method.
+    ///
+    /// ## Errors
+    /// - `BotError::System` - database error
+    pub async fn initialize(&self) -> Result<()> {
+        sqlx::raw_sql(include_str!("../storage/schema/conversation_states.sql"))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage")]
+#[async_trait]
+impl StateStorage for PostgresStateStorage {
+    async fn get(&self, key: &StateKey) -> Result<Option<StateRecord>> {
+        let row: Option<(String, Value)> = sqlx::query_as(
+            r#"
+            SELECT state, data FROM conversation_states
+            WHERE chat_id = $1 AND user_id = $2
+              AND (expires_at IS NULL OR expires_at > NOW())
+            "#,
+        )
+        .bind(key.chat_id.as_ref())
+        .bind(&key.user_id.0)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|(state, data)| StateRecord { state, data }))
+    }
+
+    async fn set(&self, key: &StateKey, record: &StateRecord, ttl: Option<Duration>) -> Result<()> {
+        let ttl_secs = ttl.map(|ttl| ttl.as_secs_f64());
+        sqlx::query(
+            r#"
+            INSERT INTO conversation_states (chat_id, user_id, state, data, updated_at, expires_at)
+            VALUES ($1, $2, $3, $4, NOW(), NOW() + make_interval(secs => $5))
+            ON CONFLICT (chat_id, user_id) DO UPDATE
+            SET state = EXCLUDED.state,
+                data = EXCLUDED.data,
+                updated_at = EXCLUDED.updated_at,
+                expires_at = EXCLUDED.expires_at
+            "#,
+        )
+        .bind(key.chat_id.as_ref())
+        .bind(&key.user_id.0)
+        .bind(&record.state)
+        .bind(&record.data)
+        .bind(ttl_secs)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn remove(&self, key: &StateKey) -> Result<()> {
+        sqlx::query("DELETE FROM conversation_states WHERE chat_id = $1 AND user_id = $2")
+            .bind(key.chat_id.as_ref())
+            .bind(&key.user_id.0)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_expired(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM conversation_states WHERE expires_at <= NOW()")
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// Conversation state manager
+///
+/// Cheap to clone, clones share the same storage.
+#[derive(Clone)]
+pub struct StateManager {
+    storage: Arc<dyn StateStorage>,
+    timeout: Option<Duration>,
+}
+
+impl fmt::Debug for StateManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateManager")
+            .field("storage", &"<storage>")
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl Default for StateManager {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl StateManager {
+    /// Create manager with the storage backend and default timeout
+    pub fn new<S: StateStorage + 'static>(storage: S) -> Self {
+        Self {
+            storage: Arc::new(storage),
+            timeout: Some(DEFAULT_STATE_TIMEOUT),
+        }
+    }
+
+    /// Create manager with [`MemoryStateStorage`]
+    pub fn in_memory() -> Self {
+        Self::new(MemoryStateStorage::new())
+    }
+
+    /// Set state timeout, `None` to keep states until finished
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// State timeout
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Get state
+    ///
+    /// ## Errors
+    /// - storage error
+    pub async fn get(&self, key: &StateKey) -> Result<Option<StateRecord>> {
+        self.storage.get(key).await
+    }
+
+    /// Set state, the timeout starts over
+    ///
+    /// ## Errors
+    /// - storage error
+    pub async fn set(&self, key: &StateKey, record: StateRecord) -> Result<()> {
+        trace!(
+            "Set state {} for {}:{}",
+            record.state, key.chat_id, key.user_id.0
+        );
+        self.storage.set(key, &record, self.timeout).await
+    }
+
+    /// Move to another state, keeping the current data
+    ///
+    /// ## Errors
+    /// - storage error
+    pub async fn transition(&self, key: &StateKey, state: impl Into<String>) -> Result<()> {
+        let data = self
+            .get(key)
+            .await?
+            .map(|record| record.data)
+            .unwrap_or_default();
+        self.set(
+            key,
+            StateRecord {
+                state: state.into(),
+                data,
+            },
+        )
+        .await
+    }
+
+    /// Finish conversation and remove state
+    ///
+    /// ## Errors
+    /// - storage error
+    pub async fn finish(&self, key: &StateKey) -> Result<()> {
+        trace!("Finish state for {}:{}", key.chat_id, key.user_id.0);
+        self.storage.remove(key).await
+    }
+
+    /// Remove expired states
+    ///
+    /// ## Errors
+    /// - storage error
+    pub async fn remove_expired(&self) -> Result<u64> {
+        self.storage.remove_expired().await
+    }
+
+    /// Get state context for the event sender,
+    /// `None` for events without chat or sender
+    pub fn context(&self, event: &EventMessage) -> Option<StateContext> {
+        StateKey::from_event(event).map(|key| StateContext {
+            manager: self.clone(),
+            key,
+        })
+    }
+}
+
+/// State of a single conversation, for use in handlers
+#[derive(Debug, Clone)]
+pub struct StateContext {
+    manager: StateManager,
+    key: StateKey,
+}
+
+impl StateContext {
+    /// Conversation key
+    pub fn key(&self) -> &StateKey {
+        &self.key
+    }
+
+    /// Get state
+    ///
+    /// ## Errors
+    /// - storage error
+    pub async fn get(&self) -> Result<Option<StateRecord>> {
+        self.manager.get(&self.key).await
+    }
+
+    /// Get state name
+    ///
+    /// ## Errors
+    /// - storage error
+    pub async fn state(&self) -> Result<Option<String>> {
+        Ok(self.get().await?.map(|record| record.state))
+    }
+
+    /// Get state data as `T`, `None` if no state is set
+    ///
+    /// ## Errors
+    /// - storage error
+    /// - `BotError::Serialization` - data deserialization error
+    pub async fn data<T: DeserializeOwned>(&self) -> Result<Option<T>> {
+        self.get().await?.map(|record| record.data_as()).transpose()
+    }
+
+    /// Set state
+    ///
+    /// ## Errors
+    /// - storage error
+    pub async fn set(&self, record: StateRecord) -> Result<()> {
+        self.manager.set(&self.key, record).await
+    }
+
+    /// Move to another state, keeping the current data
+    ///
+    /// ## Errors
+    /// - storage error
+    pub async fn transition(&self, state: impl Into<String>) -> Result<()> {
+        self.manager.transition(&self.key, state).await
+    }
+
+    /// Move to another state with new data
+    ///
+    /// ## Errors
+    /// - storage error
+    /// - `BotError::Serialization` - data serialization error
+    pub async fn transition_with<T: Serialize>(
+        &self,
+        state: impl Into<String>,
+        data: &T,
+    ) -> Result<()> {
+        self.set(StateRecord::new(state).with_data(data)?).await
+    }
+
+    /// Finish conversation and remove state
+    ///
+    /// ## Errors
+    /// - storage error
+    pub async fn finish(&self) -> Result<()> {
+        self.manager.finish(&self.key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::types::*;
+
+    fn key(chat: &'static str, user: &str) -> StateKey {
+        StateKey::new(chat, UserId(user.to_string()))
+    }
+
+    fn message(chat: &'static str, user: &str) -> EventMessage {
+        EventMessage {
+            event_id: 1,
+            event_type: EventType::NewMessage(Box::new(EventPayloadNewMessage {
+                chat: Chat {
+                    chat_id: ChatId::from(chat),
+                    title: None,
+                    chat_type: "group".to_string(),
+                },
+                from: From {
+                    first_name: "Test".to_string(),
+                    last_name: None,
+                    user_id: UserId(user.to_string()),
+                },
+                ..Default::default()
+            })),
+        }
+    }
+
+    #[test]
+    fn test_state_key_from_event() {
+        let key = StateKey::from_event(&message("chat1", "user1")).unwrap();
+        assert_eq!(key.chat_id, ChatId::from("chat1"));
+        assert_eq!(key.user_id, UserId("user1".to_string()));
+
+        let event = EventMessage {
+            event_id: 2,
+            event_type: EventType::None,
+        };
+        assert!(StateKey::from_event(&event).is_none());
+    }
+
+    #[test]
+    fn test_state_record_data() {
+        let record = StateRecord::new("step")
+            .with_data(&vec!["a".to_string()])
+            .unwrap();
+        assert_eq!(record.data_as::<Vec<String>>().unwrap(), vec!["a"]);
+        assert!(record.data_as::<u32>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_memory_storage_keys_are_independent() {
+        let storage = MemoryStateStorage::new();
+        storage
+            .set(&key("chat1", "user1"), &StateRecord::new("a"), None)
+            .await
+            .unwrap();
+        storage
+            .set(&key("chat2", "user1"), &StateRecord::new("b"), None)
+            .await
+            .unwrap();
+
+        let a = storage.get(&key("chat1", "user1")).await.unwrap().unwrap();
+        let b = storage.get(&key("chat2", "user1")).await.unwrap().unwrap();
+        assert_eq!(a.state, "a");
+        assert_eq!(b.state, "b");
+        assert!(storage.get(&key("chat1", "user2")).await.unwrap().is_none());
+
+        storage.remove(&key("chat1", "user1")).await.unwrap();
+        assert!(storage.get(&key("chat1", "user1")).await.unwrap().is_none());
+        assert_eq!(storage.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_memory_storage_expiry() {
+        let storage = MemoryStateStorage::new();
+        let ttl = Some(Duration::from_millis(10));
+        storage
+            .set(&key("chat", "user1"), &StateRecord::new("a"), ttl)
+            .await
+            .unwrap();
+        storage
+            .set(&key("chat", "user2"), &StateRecord::new("a"), ttl)
+            .await
+            .unwrap();
+        storage
+            .set(&key("chat", "user3"), &StateRecord::new("a"), None)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert!(storage.get(&key("chat", "user1")).await.unwrap().is_none());
+        assert_eq!(storage.len(), 2);
+        assert_eq!(storage.remove_expired().await.unwrap(), 1);
+        assert_eq!(storage.len(), 1);
+        assert!(storage.get(&key("chat", "user3")).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_state_context_flow() {
+        let states = StateManager::in_memory();
+        let ctx = states.context(&message("chat", "user")).unwrap();
+        assert!(ctx.state().await.unwrap().is_none());
+
+        ctx.transition_with("await_name", &42u32).await.unwrap();
+        ctx.transition("await_date").await.unwrap();
+        assert_eq!(ctx.state().await.unwrap().as_deref(), Some("await_date"));
+        assert_eq!(ctx.data::<u32>().await.unwrap(), Some(42));
+
+        ctx.finish().await.unwrap();
+        assert!(ctx.get().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_state_manager_timeout() {
+        let states = StateManager::in_memory().with_timeout(Some(Duration::from_millis(10)));
+        let key = key("chat", "user");
+        states.set(&key, StateRecord::new("a")).await.unwrap();
+        assert!(states.get(&key).await.unwrap().is_some());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(states.get(&key).await.unwrap().is_none());
+
+        assert_eq!(
+            StateManager::default().timeout(),
+            Some(DEFAULT_STATE_TIMEOUT)
+        );
+    }
+}
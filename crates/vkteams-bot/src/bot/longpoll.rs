@@ -1,8 +1,11 @@
 use crate::api::events::get::{RequestEventsGet, ResponseEventsGet};
-use crate::api::types::{BotRequest, EventMessage, POLL_TIME};
+use crate::api::types::{BotRequest, EventId, EventMessage, POLL_TIME};
 use crate::bot::Bot;
-use crate::config::CONFIG;
+use crate::bot::checkpoint::CheckpointMode;
+use crate::bot::metrics::metrics;
 use crate::error::{BotError, Result};
+use futures::Stream;
+use std::collections::VecDeque;
 use std::future::Future;
 use std::sync::Arc;
 #[cfg(test)]
@@ -10,6 +13,7 @@ use std::sync::atomic::AtomicU32;
 use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
 use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, trace, warn};
 
 /// Listen for events and execute callback function
@@ -25,29 +29,53 @@ impl Bot {
     /// - `BotError::Network` - network error when getting events
     /// - `BotError::Serialization` - response deserialization error
     /// - `BotError::System` - error when executing callback function
+    /// - checkpoint store errors when loading the last event id
+    ///
+    /// Runs until an error occurs. Stops on Ctrl+C or SIGTERM only when
+    /// [`handle_signals`](crate::config::types::EventListenerConfig::handle_signals) is enabled,
+    /// use [`event_listener_with_shutdown`](Self::event_listener_with_shutdown)
+    /// to stop it programmatically.
     pub async fn event_listener<F, X>(&self, func: F) -> Result<()>
     where
         F: Fn(Bot, ResponseEventsGet) -> X,
-        X: Future<Output = Result<()>> + Send + Sync + 'static,
+        X: Future<Output = Result<()>> + Send + 'static,
     {
-        let cfg = &CONFIG.listener;
-        // Create a channel to signal shutdown
-        let (shutdown_tx, mut shutdown_rx) = tokio::sync::broadcast::channel::<()>(1);
+        let shutdown = self.listener_shutdown();
+        let _guard = shutdown.clone().drop_guard();
+        self.event_listener_with_shutdown(func, shutdown).await
+    }
 
-        // Setup shutdown signal handler
-        let shutdown_tx_clone = shutdown_tx.clone();
-        tokio::spawn(async move {
-            crate::bot::net::shutdown_signal().await;
-            info!("Received stop signal, gracefully stopping event listener...");
-            let _ = shutdown_tx_clone.send(());
-        });
+    /// Listen for events and execute callback function until the token is cancelled
+    ///
+    /// On cancellation the pending poll is abandoned immediately, the batch being processed
+    /// gets [`shutdown_timeout_ms`](crate::config::types::EventListenerConfig::shutdown_timeout_ms)
+    /// to finish and is abandoned after that.
+    /// ## Parameters
+    /// - `func` - callback function with [`Result`] type and [`ResponseEventsGet`] argument
+    /// - `shutdown` - [`CancellationToken`] to stop the listener
+    ///
+    /// ## Errors
+    /// - `BotError::System` - error when executing callback function
+    /// - checkpoint store errors when loading the last event id
+    pub async fn event_listener_with_shutdown<F, X>(
+        &self,
+        func: F,
+        shutdown: CancellationToken,
+    ) -> Result<()>
+    where
+        F: Fn(Bot, ResponseEventsGet) -> X,
+        X: Future<Output = Result<()>> + Send + 'static,
+    {
+        let cfg = self.listener_config();
+        let drain_timeout = Duration::from_millis(cfg.shutdown_timeout_ms);
+        self.restore_checkpoint().await?;
 
         let mut current_backoff = cfg.empty_backoff_ms;
         let mut consecutive_empty_polls = 0u32;
 
         'event_loop: loop {
-            // Check if we received a shutdown signal
-            if shutdown_rx.try_recv().is_ok() {
+            // Check if shutdown was requested
+            if shutdown.is_cancelled() {
                 info!("Processing shutdown request");
                 break 'event_loop;
             }
@@ -58,15 +86,25 @@ impl Bot {
             let req = RequestEventsGet::new(self.get_last_event_id()).with_poll_time(POLL_TIME);
 
             // Get response, with error handling for network issues
-            let res = match self.send_api_request::<RequestEventsGet>(req).await {
-                Ok(res) => res,
+            let res = tokio::select! {
+                res = self.send_api_request::<RequestEventsGet>(req) => res,
+                _ = shutdown.cancelled() => {
+                    info!("Processing shutdown request");
+                    break 'event_loop;
+                }
+            };
+            let res = match res {
+                Ok(res) => {
+                    metrics().record_longpoll(&res.events);
+                    res
+                }
                 Err(e) => {
                     error!("Error getting events: {}", e);
 
                     // Apply backoff before retrying
                     let backoff = Duration::from_millis(current_backoff);
                     warn!("Backing off for {:?} before retrying", backoff);
-                    sleep(backoff).await;
+                    pause(backoff, &shutdown).await;
 
                     // Increase backoff time for next failure, with maximum limit
                     if cfg.use_exponential_backoff {
@@ -85,8 +123,17 @@ impl Bot {
                 current_backoff = cfg.empty_backoff_ms;
                 consecutive_empty_polls = 0;
 
-                // Process events
-                self.process_event_batch(res, &func).await?;
+                // Process events, on shutdown wait for the batch until the deadline
+                match drain(
+                    self.process_event_batch(res, &func),
+                    &shutdown,
+                    drain_timeout,
+                )
+                .await
+                {
+                    Some(result) => result?,
+                    None => break 'event_loop,
+                }
             } else {
                 debug!("No events received, continuing to wait");
                 consecutive_empty_polls += 1;
@@ -101,7 +148,7 @@ impl Bot {
                     if elapsed < backoff_time {
                         let sleep_time = backoff_time - elapsed;
                         debug!("Backing off for {:?}", sleep_time);
-                        sleep(sleep_time).await;
+                        pause(sleep_time, &shutdown).await;
                     }
 
                     // Increase backoff time for next empty poll, with maximum limit
@@ -122,9 +169,9 @@ impl Bot {
     async fn process_event_batch<F, X>(&self, events: ResponseEventsGet, func: &F) -> Result<()>
     where
         F: Fn(Bot, ResponseEventsGet) -> X,
-        X: Future<Output = Result<()>> + Send + Sync + 'static,
+        X: Future<Output = Result<()>> + Send + 'static,
     {
-        let cfg = &CONFIG.listener;
+        let cfg = self.listener_config();
         // Calculate approximate memory usage of events
         let memory_usage = if cfg.max_memory_usage > 0 {
             events.events.len() * 1024 // Assume 1KB per event as estimate
@@ -162,14 +209,14 @@ impl Bot {
                 let last_event_id = batch_events.events[batch_events.events.len() - 1].event_id;
 
                 // Update last event ID
-                self.set_last_event_id(last_event_id);
-                debug!("Updated last event ID: {}", last_event_id);
+                self.start_handling(last_event_id).await;
 
                 // Process this batch of events
                 if let Err(e) = func(self.clone(), batch_events).await {
                     error!("Error processing events batch: {}", e);
                     return Err(e);
                 }
+                self.finish_handling(last_event_id).await;
 
                 // Brief pause between batches to allow GC to run
                 sleep(Duration::from_millis(10)).await;
@@ -178,14 +225,14 @@ impl Bot {
             // Process all events at once (original behavior)
             // Update last event id
             let last_event_id = events.events[events.events.len() - 1].event_id;
-            self.set_last_event_id(last_event_id);
-            debug!("Updated last event ID: {}", last_event_id);
+            self.start_handling(last_event_id).await;
 
             // Execute callback function
             if let Err(e) = func(self.clone(), events).await {
                 error!("Error processing events: {}", e);
                 return Err(e);
             }
+            self.finish_handling(last_event_id).await;
         }
 
         Ok(())
@@ -193,13 +240,38 @@ impl Bot {
 
     /// Listen for events with parallel processing
     /// Enhanced version that processes events in parallel batches
+    ///
+    /// Stops on Ctrl+C or SIGTERM only when
+    /// [`handle_signals`](crate::config::types::EventListenerConfig::handle_signals) is enabled,
+    /// use [`event_listener_parallel_with_shutdown`](Self::event_listener_parallel_with_shutdown)
+    /// to stop it programmatically.
     pub async fn event_listener_parallel<F, X>(&self, func: F) -> Result<()>
     where
         F: Fn(Bot, ResponseEventsGet) -> X + Send + Sync + Clone + 'static,
         X: Future<Output = Result<()>> + Send + 'static,
     {
-        let cfg = &CONFIG.listener;
+        let shutdown = self.listener_shutdown();
+        let _guard = shutdown.clone().drop_guard();
+        self.event_listener_parallel_with_shutdown(func, shutdown)
+            .await
+    }
+
+    /// Listen for events with parallel processing until the token is cancelled
+    ///
+    /// Shutdown works as in [`event_listener_with_shutdown`](Self::event_listener_with_shutdown).
+    pub async fn event_listener_parallel_with_shutdown<F, X>(
+        &self,
+        func: F,
+        shutdown: CancellationToken,
+    ) -> Result<()>
+    where
+        F: Fn(Bot, ResponseEventsGet) -> X + Send + Sync + Clone + 'static,
+        X: Future<Output = Result<()>> + Send + 'static,
+    {
+        let cfg = self.listener_config();
+        let drain_timeout = Duration::from_millis(cfg.shutdown_timeout_ms);
         info!("Starting parallel event listener...");
+        self.restore_checkpoint().await?;
 
         // Initialize parallel processor and adaptive backoff
         let processor = ParallelEventProcessor::new(
@@ -215,20 +287,9 @@ impl Bot {
         // Initialize event stream buffer for zero-copy processing (future use)
         // let mut event_stream = ZeroCopyEventStream::new(cfg.max_events_per_batch * 10);
 
-        // Create a channel to signal shutdown
-        let (shutdown_tx, mut shutdown_rx) = tokio::sync::broadcast::channel::<()>(1);
-
-        // Setup shutdown signal handler
-        let shutdown_tx_clone = shutdown_tx.clone();
-        tokio::spawn(async move {
-            crate::bot::net::shutdown_signal().await;
-            info!("Received stop signal, gracefully stopping parallel event listener...");
-            let _ = shutdown_tx_clone.send(());
-        });
-
         'event_loop: loop {
-            // Check if we received a shutdown signal
-            if shutdown_rx.try_recv().is_ok() {
+            // Check if shutdown was requested
+            if shutdown.is_cancelled() {
                 info!("Processing shutdown request");
                 break 'event_loop;
             }
@@ -239,15 +300,25 @@ impl Bot {
             let req = RequestEventsGet::new(self.get_last_event_id()).with_poll_time(POLL_TIME);
 
             // Send request and handle response
-            let res = match self.send_api_request::<RequestEventsGet>(req).await {
-                Ok(res) => res,
+            let res = tokio::select! {
+                res = self.send_api_request::<RequestEventsGet>(req) => res,
+                _ = shutdown.cancelled() => {
+                    info!("Processing shutdown request");
+                    break 'event_loop;
+                }
+            };
+            let res = match res {
+                Ok(res) => {
+                    metrics().record_longpoll(&res.events);
+                    res
+                }
                 Err(e) => {
                     error!("Error getting events: {}", e);
 
                     // Apply adaptive backoff on error
                     let delay = backoff.calculate_delay(0);
                     warn!("Error occurred, backing off for {:?}", delay);
-                    sleep(delay).await;
+                    pause(delay, &shutdown).await;
                     continue;
                 }
             };
@@ -258,21 +329,22 @@ impl Bot {
 
                 // Update last event ID from the most recent event
                 let last_event_id = res.events[res.events.len() - 1].event_id;
-                self.set_last_event_id(last_event_id);
-                debug!("Updated last event ID: {}", last_event_id);
+                self.start_handling(last_event_id).await;
 
-                // Process events in parallel
+                // Process events in parallel, on shutdown wait for them until the deadline
                 let processing_start = Instant::now();
-                match processor
-                    .process_events_parallel(self.clone(), res, func.clone())
-                    .await
-                {
+                let processing = processor.process_events_parallel(self.clone(), res, func.clone());
+                let Some(result) = drain(processing, &shutdown, drain_timeout).await else {
+                    break 'event_loop;
+                };
+                match result {
                     Ok(_) => {
                         let processing_duration = processing_start.elapsed();
                         trace!("Parallel processing completed in {:?}", processing_duration);
 
                         // Reset backoff on successful processing
                         backoff.calculate_delay(1);
+                        self.finish_handling(last_event_id).await;
                     }
                     Err(e) => {
                         error!("Error in parallel processing: {}", e);
@@ -290,7 +362,7 @@ impl Bot {
                 if elapsed < delay {
                     let sleep_time = delay - elapsed;
                     trace!("Adaptive backoff: sleeping for {:?}", sleep_time);
-                    sleep(sleep_time).await;
+                    pause(sleep_time, &shutdown).await;
                 }
             }
         } // End of event_loop
@@ -298,6 +370,205 @@ impl Bot {
         info!("Parallel event listener stopped gracefully");
         Ok(())
     }
+
+    /// Stream of events
+    ///
+    /// Polls the API while the stream is consumed, with [`AdaptiveBackoff`] between
+    /// empty polls and after errors. The last event id is advanced as events are yielded.
+    /// Dropping the stream stops polling, no background tasks are left behind.
+    ///
+    /// API and network errors are yielded as items, polling continues on the next call
+    /// after a backoff, so the consumer decides whether to stop.
+    ///
+    /// With a checkpoint set on the bot, polling resumes from it. In the
+    /// [`CheckpointMode::AfterSuccess`] mode an event id is saved when the next item is requested,
+    /// i.e. after the consumer is done with the event.
+    ///
+    /// ```no_run
+    /// use futures::StreamExt;
+    /// use std::time::Duration;
+    /// use vkteams_bot::prelude::*;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// let bot = Bot::default();
+    /// let mut events = std::pin::pin!(bot.events());
+    /// while let Ok(Some(event)) = tokio::time::timeout(Duration::from_secs(60), events.next()).await {
+    ///     println!("{:?}", event?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn events(&self) -> impl Stream<Item = Result<EventMessage>> + Send + 'static {
+        let cfg = self.listener_config();
+        let state = EventStreamState {
+            bot: self.clone(),
+            backoff: AdaptiveBackoff::new(
+                Duration::from_millis(cfg.empty_backoff_ms),
+                Duration::from_millis(cfg.max_backoff_ms),
+            ),
+            buffer: VecDeque::new(),
+            delay: None,
+            handled: None,
+            restored: false,
+        };
+        futures::stream::unfold(state, |mut state| async move {
+            let item = state.next_event().await;
+            Some((item, state))
+        })
+    }
+
+    /// Shutdown token for the listeners without an explicit token,
+    /// cancelled on Ctrl+C or SIGTERM if signal handling is enabled
+    fn listener_shutdown(&self) -> CancellationToken {
+        let shutdown = CancellationToken::new();
+        if self.listener_config().handle_signals {
+            let token = shutdown.clone();
+            tokio::spawn(async move {
+                tokio::select! {
+                    _ = crate::bot::net::shutdown_signal() => {
+                        info!("Received stop signal, gracefully stopping event listener...");
+                        token.cancel();
+                    }
+                    // Listener stopped on its own
+                    _ = token.cancelled() => {}
+                }
+            });
+        }
+        shutdown
+    }
+
+    /// Resume from the checkpoint if it is ahead of the current last event id
+    async fn restore_checkpoint(&self) -> Result<()> {
+        let Some(checkpoint) = self.checkpoint() else {
+            return Ok(());
+        };
+        if let Some(event_id) = checkpoint.load().await?
+            && event_id > self.get_last_event_id()
+        {
+            info!("Resuming from checkpoint, last event ID: {}", event_id);
+            self.set_last_event_id(event_id);
+        }
+        Ok(())
+    }
+
+    /// Save the checkpoint if it is set with the mode
+    async fn save_checkpoint(&self, mode: CheckpointMode, event_id: EventId) {
+        if let Some(checkpoint) = self.checkpoint() {
+            checkpoint.save_if(mode, event_id).await;
+        }
+    }
+
+    /// Events up to the id are passed to the handler.
+    /// In the [`CheckpointMode::AfterSuccess`] mode the last event id is not advanced
+    /// until [`Bot::finish_handling`], so events of a failed handler are polled again.
+    async fn start_handling(&self, event_id: EventId) {
+        let after_success = self
+            .checkpoint()
+            .is_some_and(|checkpoint| checkpoint.mode() == CheckpointMode::AfterSuccess);
+        if !after_success {
+            self.set_last_event_id(event_id);
+            debug!("Updated last event ID: {}", event_id);
+        }
+        self.save_checkpoint(CheckpointMode::BeforeHandling, event_id)
+            .await;
+    }
+
+    /// Events up to the id are handled successfully
+    async fn finish_handling(&self, event_id: EventId) {
+        self.set_last_event_id(event_id);
+        debug!("Updated last event ID: {}", event_id);
+        self.save_checkpoint(CheckpointMode::AfterSuccess, event_id)
+            .await;
+    }
+}
+
+/// Sleep, waking up early on shutdown
+async fn pause(duration: Duration, shutdown: &CancellationToken) {
+    tokio::select! {
+        _ = sleep(duration) => {}
+        _ = shutdown.cancelled() => {}
+    }
+}
+
+/// Wait for in-flight work, once shutdown is requested wait at most `timeout`.
+/// Returns `None` if the work was abandoned.
+async fn drain<T>(
+    work: impl Future<Output = T>,
+    shutdown: &CancellationToken,
+    timeout: Duration,
+) -> Option<T> {
+    tokio::pin!(work);
+    tokio::select! {
+        biased;
+        res = &mut work => Some(res),
+        _ = shutdown.cancelled() => {
+            info!("Shutdown requested, waiting up to {:?} for in-flight events", timeout);
+            match tokio::time::timeout(timeout, work).await {
+                Ok(res) => Some(res),
+                Err(_) => {
+                    warn!("In-flight events were not processed before the shutdown deadline");
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// State of the [`Bot::events`] stream
+struct EventStreamState {
+    bot: Bot,
+    backoff: AdaptiveBackoff,
+    buffer: VecDeque<EventMessage>,
+    /// Delay before the next poll
+    delay: Option<Duration>,
+    /// Last yielded event id, handled once the next item is requested
+    handled: Option<EventId>,
+    restored: bool,
+}
+
+impl EventStreamState {
+    async fn next_event(&mut self) -> Result<EventMessage> {
+        if let Some(event_id) = self.handled.take() {
+            self.bot.finish_handling(event_id).await;
+        }
+        if !self.restored {
+            self.restored = true;
+            self.bot.restore_checkpoint().await?;
+        }
+        loop {
+            if let Some(event) = self.buffer.pop_front() {
+                self.bot.start_handling(event.event_id).await;
+                self.handled = Some(event.event_id);
+                return Ok(event);
+            }
+            if let Some(delay) = self.delay.take() {
+                trace!("Event stream: sleeping for {:?}", delay);
+                sleep(delay).await;
+            }
+
+            let start_time = Instant::now();
+            let req = RequestEventsGet::new(self.bot.get_last_event_id()).with_poll_time(POLL_TIME);
+            match self.bot.send_api_request::<RequestEventsGet>(req).await {
+                Ok(res) => {
+                    metrics().record_longpoll(&res.events);
+                    let delay = self.backoff.calculate_delay(res.events.len());
+                    if res.events.is_empty() {
+                        debug!("No events received, applying adaptive backoff");
+                        self.delay = delay.checked_sub(start_time.elapsed());
+                    } else {
+                        debug!("Received {} events", res.events.len());
+                    }
+                    self.buffer.extend(res.events);
+                }
+                Err(e) => {
+                    error!("Error getting events: {}", e);
+                    self.delay = Some(self.backoff.calculate_delay(0));
+                    return Err(e);
+                }
+            }
+        }
+    }
 }
 
 /// Parallel event processor for concurrent batch processing
@@ -869,4 +1140,44 @@ mod tests {
         let _processor = ParallelEventProcessor::new(5, 10);
         // Can't directly test internal fields, but constructor should not panic
     }
+
+    #[tokio::test]
+    async fn test_drain_completes_before_deadline() {
+        let shutdown = CancellationToken::new();
+        shutdown.cancel();
+        let work = async {
+            sleep(Duration::from_millis(20)).await;
+            42
+        };
+        assert_eq!(
+            drain(work, &shutdown, Duration::from_secs(5)).await,
+            Some(42)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_drain_abandons_after_deadline() {
+        let shutdown = CancellationToken::new();
+        shutdown.cancel();
+        let started = Instant::now();
+        let work = std::future::pending::<()>();
+        assert_eq!(
+            drain(work, &shutdown, Duration::from_millis(50)).await,
+            None
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_pause_wakes_up_on_shutdown() {
+        let shutdown = CancellationToken::new();
+        let started = Instant::now();
+        let token = shutdown.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(20)).await;
+            token.cancel();
+        });
+        pause(Duration::from_secs(30), &shutdown).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
 }
@@ -0,0 +1,652 @@
+//! # Metrics
+//! Built-in metrics of API requests, retries, the rate limiter and the event listener.
+//!
+//! Every bot records into the process-wide registry returned by [`metrics`]:
+//! - `vkteams_bot_api_requests_total{method, outcome}` - API requests by method and outcome:
+//!   `ok`, `api_error`, `rate_limited`, `circuit_open` or `error`
+//! - `vkteams_bot_api_request_duration_seconds{method}` - latency of API requests, retries included.
+//!   Long-poll `events/get` requests wait for events on the server and aren't observed
+//! - `vkteams_bot_http_retries_total{reason}` - attempts retried by [`ConnectionPool`]:
+//!   `network`, `status`, `api` or `download`
+//! - `vkteams_bot_rate_limit_requests_total{result}` - rate limiter decisions: `allowed` or `limited`
+//! - `vkteams_bot_longpoll_batch_size` - events received by a long-poll request
+//! - `vkteams_bot_longpoll_lag_seconds` - age of the oldest event of the last long-poll batch
+//!
+//! The metrics are exposed:
+//! - in the Prometheus text format, see [`Metrics::render_prometheus`].
+//!   With the `prometheus` feature, enabled by `webhook`, `MetricsRouter::route_metrics`
+//!   mounts `GET /metrics` on an [`axum`] router and `serve_metrics` runs a standalone endpoint
+//! - with the OTLP exporter configured by [`crate::otlp::init`] (`otlp` feature)
+//!
+//! ```no_run
+//! use vkteams_bot::bot::metrics::MetricsRouter;
+//!
+//! let app: axum::Router = axum::Router::new().route_metrics();
+//! ```
+//!
+//! [`ConnectionPool`]: crate::bot::net::ConnectionPool
+//! [`axum`]: https://docs.rs/axum
+use crate::api::types::EventMessage;
+use crate::error::{BotError, Result};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const API_REQUESTS: &str = "vkteams_bot_api_requests_total";
+const API_REQUEST_DURATION: &str = "vkteams_bot_api_request_duration_seconds";
+const HTTP_RETRIES: &str = "vkteams_bot_http_retries_total";
+const RATE_LIMIT_REQUESTS: &str = "vkteams_bot_rate_limit_requests_total";
+const LONGPOLL_BATCH_SIZE: &str = "vkteams_bot_longpoll_batch_size";
+const LONGPOLL_LAG: &str = "vkteams_bot_longpoll_lag_seconds";
+
+/// Long-poll method, its requests are counted but their latency isn't observed
+const LONGPOLL_METHOD: &str = "events/get";
+
+/// Buckets of the API request latency in seconds
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+/// Buckets of the long-poll batch size
+const BATCH_SIZE_BUCKETS: &[f64] = &[0.0, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0];
+
+/// Content type of the Prometheus text format
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+/// Process-wide metrics registry
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// Histogram with fixed buckets
+#[derive(Debug)]
+struct Histogram {
+    bounds: &'static [f64],
+    /// Observations per bucket, not cumulative
+    buckets: Vec<AtomicU64>,
+    count: AtomicU64,
+    /// Sum of the observations, `f64` bits
+    sum: AtomicU64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: bounds.iter().map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0f64.to_bits()),
+        }
+    }
+
+    fn observe(&self, value: f64) {
+        if let Some(bucket) = self.bounds.iter().position(|bound| value <= *bound) {
+            self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let _ = self
+            .sum
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |sum| {
+                Some((f64::from_bits(sum) + value).to_bits())
+            });
+    }
+
+    fn render(&self, out: &mut String, name: &str, label: Option<(&str, &str)>) {
+        let labels = label
+            .map(|(key, value)| format!("{key}=\"{value}\","))
+            .unwrap_or_default();
+        let mut cumulative = 0;
+        for (bound, bucket) in self.bounds.iter().zip(&self.buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{name}_bucket{{{labels}le=\"{bound}\"}} {cumulative}");
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let sum = f64::from_bits(self.sum.load(Ordering::Relaxed));
+        let _ = writeln!(out, "{name}_bucket{{{labels}le=\"+Inf\"}} {count}");
+        let labels = labels.trim_end_matches(',');
+        if labels.is_empty() {
+            let _ = writeln!(out, "{name}_sum {sum}");
+            let _ = writeln!(out, "{name}_count {count}");
+        } else {
+            let _ = writeln!(out, "{name}_sum{{{labels}}} {sum}");
+            let _ = writeln!(out, "{name}_count{{{labels}}} {count}");
+        }
+    }
+}
+
+fn render_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Outcome label of the API request result
+fn outcome<T>(result: &Result<T>) -> &'static str {
+    match result {
+        Ok(_) => "ok",
+        Err(BotError::Api(_)) => "api_error",
+        Err(BotError::RateLimited(_)) => "rate_limited",
+        Err(BotError::CircuitOpen(_)) => "circuit_open",
+        Err(_) => "error",
+    }
+}
+
+/// Reason label of the retried attempt
+pub(crate) fn retry_reason(err: &BotError) -> &'static str {
+    match err {
+        BotError::Network(_) => "network",
+        BotError::Api(_) | BotError::RateLimited(_) => "api",
+        _ => "status",
+    }
+}
+
+/// Seconds since the Unix epoch
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
+
+/// Metrics registry
+#[derive(Debug)]
+pub struct Metrics {
+    api_requests: DashMap<(Cow<'static, str>, &'static str), AtomicU64>,
+    api_latency: DashMap<Cow<'static, str>, Histogram>,
+    retries: DashMap<&'static str, AtomicU64>,
+    rate_limit_allowed: AtomicU64,
+    rate_limit_limited: AtomicU64,
+    longpoll_batch_size: Histogram,
+    /// Lag of the last long-poll batch in seconds, `f64` bits
+    longpoll_lag: AtomicU64,
+    #[cfg(feature = "otlp")]
+    otlp: once_cell::sync::OnceCell<otlp::Instruments>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// Create empty registry, bots record into the one returned by [`metrics`]
+    pub fn new() -> Self {
+        Self {
+            api_requests: DashMap::new(),
+            api_latency: DashMap::new(),
+            retries: DashMap::new(),
+            rate_limit_allowed: AtomicU64::new(0),
+            rate_limit_limited: AtomicU64::new(0),
+            longpoll_batch_size: Histogram::new(BATCH_SIZE_BUCKETS),
+            longpoll_lag: AtomicU64::new(0f64.to_bits()),
+            #[cfg(feature = "otlp")]
+            otlp: once_cell::sync::OnceCell::new(),
+        }
+    }
+
+    /// Record the result and latency of the API request
+    ///
+    /// The latency of long-poll requests isn't observed.
+    pub fn record_api_request<T>(
+        &self,
+        method: impl Into<Cow<'static, str>>,
+        result: &Result<T>,
+        elapsed: Duration,
+    ) {
+        let method = method.into();
+        let outcome = outcome(result);
+        let elapsed = (method != LONGPOLL_METHOD).then_some(elapsed);
+        #[cfg(feature = "otlp")]
+        if let Some(otlp) = self.otlp.get() {
+            otlp.record_api_request(method.clone(), outcome, elapsed);
+        }
+        if let Some(elapsed) = elapsed {
+            self.api_latency
+                .entry(method.clone())
+                .or_insert_with(|| Histogram::new(LATENCY_BUCKETS))
+                .observe(elapsed.as_secs_f64());
+        }
+        self.api_requests
+            .entry((method, outcome))
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record the attempt retried by the connection pool
+    pub fn record_retry(&self, reason: &'static str) {
+        self.retries
+            .entry(reason)
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "otlp")]
+        if let Some(otlp) = self.otlp.get() {
+            otlp.record_retry(reason);
+        }
+    }
+
+    /// Record the rate limiter decision
+    pub fn record_rate_limit(&self, allowed: bool) {
+        let counter = if allowed {
+            &self.rate_limit_allowed
+        } else {
+            &self.rate_limit_limited
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "otlp")]
+        if let Some(otlp) = self.otlp.get() {
+            otlp.record_rate_limit(allowed);
+        }
+    }
+
+    /// Record the batch size of the long-poll response and the age of its oldest event
+    pub fn record_longpoll(&self, events: &[EventMessage]) {
+        self.longpoll_batch_size.observe(events.len() as f64);
+        let lag = events
+            .iter()
+            .filter_map(|event| event.event_type.timestamp())
+            .map(|timestamp| timestamp.0)
+            .min()
+            .map(|oldest| now_secs().saturating_sub(oldest as u64) as f64);
+        if let Some(lag) = lag {
+            self.longpoll_lag.store(lag.to_bits(), Ordering::Relaxed);
+        }
+        #[cfg(feature = "otlp")]
+        if let Some(otlp) = self.otlp.get() {
+            otlp.record_longpoll(events.len(), lag);
+        }
+    }
+
+    /// API requests of the method with the outcome
+    pub fn api_requests(&self, method: &str, outcome: &str) -> u64 {
+        self.api_requests
+            .iter()
+            .find(|entry| entry.key().0 == method && entry.key().1 == outcome)
+            .map(|entry| entry.value().load(Ordering::Relaxed))
+            .unwrap_or_default()
+    }
+
+    /// Attempts retried for the reason
+    pub fn retries(&self, reason: &str) -> u64 {
+        self.retries
+            .get(reason)
+            .map(|retries| retries.load(Ordering::Relaxed))
+            .unwrap_or_default()
+    }
+
+    /// Render the metrics in the Prometheus text format
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        render_header(
+            &mut out,
+            API_REQUESTS,
+            "counter",
+            "API requests by method and outcome",
+        );
+        let mut requests: Vec<_> = self
+            .api_requests
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().load(Ordering::Relaxed)))
+            .collect();
+        requests.sort_unstable();
+        for ((method, outcome), count) in requests {
+            let _ = writeln!(
+                out,
+                "{API_REQUESTS}{{method=\"{method}\",outcome=\"{outcome}\"}} {count}"
+            );
+        }
+
+        render_header(
+            &mut out,
+            API_REQUEST_DURATION,
+            "histogram",
+            "Latency of API requests including retries",
+        );
+        let mut methods: Vec<_> = self
+            .api_latency
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+        methods.sort_unstable();
+        for method in methods {
+            if let Some(histogram) = self.api_latency.get(&method) {
+                histogram.render(&mut out, API_REQUEST_DURATION, Some(("method", &method)));
+            }
+        }
+
+        render_header(
+            &mut out,
+            HTTP_RETRIES,
+            "counter",
+            "HTTP attempts retried by the connection pool",
+        );
+        let mut retries: Vec<_> = self
+            .retries
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().load(Ordering::Relaxed)))
+            .collect();
+        retries.sort_unstable();
+        for (reason, count) in retries {
+            let _ = writeln!(out, "{HTTP_RETRIES}{{reason=\"{reason}\"}} {count}");
+        }
+
+        render_header(
+            &mut out,
+            RATE_LIMIT_REQUESTS,
+            "counter",
+            "Rate limiter decisions",
+        );
+        for (result, counter) in [
+            ("allowed", &self.rate_limit_allowed),
+            ("limited", &self.rate_limit_limited),
+        ] {
+            let count = counter.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{RATE_LIMIT_REQUESTS}{{result=\"{result}\"}} {count}");
+        }
+
+        render_header(
+            &mut out,
+            LONGPOLL_BATCH_SIZE,
+            "histogram",
+            "Events received by a long-poll request",
+        );
+        self.longpoll_batch_size
+            .render(&mut out, LONGPOLL_BATCH_SIZE, None);
+
+        render_header(
+            &mut out,
+            LONGPOLL_LAG,
+            "gauge",
+            "Age of the oldest event of the last long-poll batch",
+        );
+        let lag = f64::from_bits(self.longpoll_lag.load(Ordering::Relaxed));
+        let _ = writeln!(out, "{LONGPOLL_LAG} {lag}");
+
+        out
+    }
+
+    /// Export the metrics with the OpenTelemetry meter as well, called by [`crate::otlp::init`]
+    #[cfg(feature = "otlp")]
+    pub fn enable_otlp(&self, meter: &opentelemetry::metrics::Meter) {
+        let _ = self.otlp.set(otlp::Instruments::new(meter));
+    }
+}
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use super::LATENCY_BUCKETS;
+    use opentelemetry::KeyValue;
+    use opentelemetry::metrics::{Counter, Gauge, Histogram, Meter};
+    use std::borrow::Cow;
+    use std::time::Duration;
+
+    /// OpenTelemetry instruments of the metrics
+    #[derive(Debug)]
+    pub(super) struct Instruments {
+        api_requests: Counter<u64>,
+        api_request_duration: Histogram<f64>,
+        retries: Counter<u64>,
+        rate_limit_requests: Counter<u64>,
+        longpoll_batch_size: Histogram<u64>,
+        longpoll_lag: Gauge<f64>,
+    }
+
+    impl Instruments {
+        pub(super) fn new(meter: &Meter) -> Self {
+            Self {
+                api_requests: meter
+                    .u64_counter("vkteams_bot.api.requests")
+                    .with_description("API requests by method and outcome")
+                    .build(),
+                api_request_duration: meter
+                    .f64_histogram("vkteams_bot.api.request.duration")
+                    .with_description("Latency of API requests including retries")
+                    .with_unit("s")
+                    .with_boundaries(LATENCY_BUCKETS.to_vec())
+                    .build(),
+                retries: meter
+                    .u64_counter("vkteams_bot.http.retries")
+                    .with_description("HTTP attempts retried by the connection pool")
+                    .build(),
+                rate_limit_requests: meter
+                    .u64_counter("vkteams_bot.rate_limit.requests")
+                    .with_description("Rate limiter decisions")
+                    .build(),
+                longpoll_batch_size: meter
+                    .u64_histogram("vkteams_bot.longpoll.batch_size")
+                    .with_description("Events received by a long-poll request")
+                    .build(),
+                longpoll_lag: meter
+                    .f64_gauge("vkteams_bot.longpoll.lag")
+                    .with_description("Age of the oldest event of the last long-poll batch")
+                    .with_unit("s")
+                    .build(),
+            }
+        }
+
+        pub(super) fn record_api_request(
+            &self,
+            method: Cow<'static, str>,
+            outcome: &'static str,
+            elapsed: Option<Duration>,
+        ) {
+            let attributes = [
+                KeyValue::new("method", method),
+                KeyValue::new("outcome", outcome),
+            ];
+            self.api_requests.add(1, &attributes);
+            if let Some(elapsed) = elapsed {
+                self.api_request_duration
+                    .record(elapsed.as_secs_f64(), &attributes[..1]);
+            }
+        }
+
+        pub(super) fn record_retry(&self, reason: &'static str) {
+            self.retries.add(1, &[KeyValue::new("reason", reason)]);
+        }
+
+        pub(super) fn record_rate_limit(&self, allowed: bool) {
+            let result = if allowed { "allowed" } else { "limited" };
+            self.rate_limit_requests
+                .add(1, &[KeyValue::new("result", result)]);
+        }
+
+        pub(super) fn record_longpoll(&self, batch_size: usize, lag: Option<f64>) {
+            self.longpoll_batch_size.record(batch_size as u64, &[]);
+            if let Some(lag) = lag {
+                self.longpoll_lag.record(lag, &[]);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "prometheus")]
+pub use server::{METRICS_PATH, MetricsRouter, serve_metrics};
+
+#[cfg(feature = "prometheus")]
+mod server {
+    use super::{PROMETHEUS_CONTENT_TYPE, metrics};
+    use crate::error::Result;
+    use axum::{Router, http::header::CONTENT_TYPE, response::IntoResponse, routing::get};
+    use tokio::net::ToSocketAddrs;
+    use tokio_util::sync::CancellationToken;
+    use tracing::info;
+
+    /// Path of the Prometheus endpoint
+    pub const METRICS_PATH: &str = "/metrics";
+
+    /// Inherit Router with the Prometheus endpoint
+    pub trait MetricsRouter<S> {
+        fn route_metrics(self) -> Self;
+    }
+
+    /// Implement MetricsRouter for Router
+    impl<S> MetricsRouter<S> for Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        fn route_metrics(self) -> Self {
+            self.route(METRICS_PATH, get(metrics_handler))
+        }
+    }
+
+    async fn metrics_handler() -> impl IntoResponse {
+        (
+            [(CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+            metrics().render_prometheus(),
+        )
+    }
+
+    /// Serve the Prometheus endpoint until the token is cancelled
+    ///
+    /// ## Errors
+    /// - `BotError::Io` - unable to bind the address or serve requests
+    pub async fn serve_metrics(
+        addr: impl ToSocketAddrs,
+        shutdown: CancellationToken,
+    ) -> Result<()> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        info!(
+            "Metrics endpoint started on http://{}{METRICS_PATH}",
+            listener.local_addr()?
+        );
+        axum::serve(listener, Router::new().route_metrics())
+            .with_graceful_shutdown(shutdown.cancelled_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::types::{EventPayloadNewMessage, EventType, Timestamp};
+    use crate::error::ApiError;
+
+    #[test]
+    fn test_api_request_metrics() {
+        let metrics = Metrics::new();
+        metrics.record_api_request("messages/sendText", &Ok(()), Duration::from_millis(20));
+        metrics.record_api_request("messages/sendText", &Ok(()), Duration::from_millis(300));
+        metrics.record_api_request::<()>(
+            "messages/sendText",
+            &Err(BotError::Api(ApiError {
+                description: "Chat not found".to_string(),
+            })),
+            Duration::from_millis(40),
+        );
+        metrics.record_retry("network");
+
+        assert_eq!(metrics.api_requests("messages/sendText", "ok"), 2);
+        assert_eq!(metrics.api_requests("messages/sendText", "api_error"), 1);
+        assert_eq!(metrics.api_requests("chats/getInfo", "ok"), 0);
+        assert_eq!(metrics.retries("network"), 1);
+
+        let text = metrics.render_prometheus();
+        assert!(text.contains(
+            "vkteams_bot_api_requests_total{method=\"messages/sendText\",outcome=\"ok\"} 2"
+        ));
+        assert!(text.contains(
+            "vkteams_bot_api_request_duration_seconds_bucket{method=\"messages/sendText\",le=\"0.025\"} 1"
+        ));
+        assert!(text.contains(
+            "vkteams_bot_api_request_duration_seconds_bucket{method=\"messages/sendText\",le=\"0.05\"} 2"
+        ));
+        assert!(text.contains(
+            "vkteams_bot_api_request_duration_seconds_bucket{method=\"messages/sendText\",le=\"+Inf\"} 3"
+        ));
+        assert!(text.contains(
+            "vkteams_bot_api_request_duration_seconds_count{method=\"messages/sendText\"} 3"
+        ));
+        assert!(text.contains("vkteams_bot_http_retries_total{reason=\"network\"} 1"));
+        assert!(text.contains("# TYPE vkteams_bot_api_request_duration_seconds histogram"));
+    }
+
+    #[test]
+    fn test_longpoll_and_owned_method_metrics() {
+        let metrics = Metrics::new();
+        metrics.record_api_request("events/get", &Ok(()), Duration::from_secs(30));
+        metrics.record_api_request(
+            String::from("chats/sendActions"),
+            &Ok(()),
+            Duration::from_millis(20),
+        );
+
+        assert_eq!(metrics.api_requests("events/get", "ok"), 1);
+        assert_eq!(metrics.api_requests("chats/sendActions", "ok"), 1);
+        let text = metrics.render_prometheus();
+        assert!(
+            text.contains("vkteams_bot_api_requests_total{method=\"events/get\",outcome=\"ok\"} 1")
+        );
+        assert!(
+            !text.contains("vkteams_bot_api_request_duration_seconds_count{method=\"events/get\"}")
+        );
+        assert!(text.contains(
+            "vkteams_bot_api_request_duration_seconds_count{method=\"chats/sendActions\"} 1"
+        ));
+    }
+
+    #[test]
+    fn test_rate_limit_and_longpoll_metrics() {
+        let metrics = Metrics::new();
+        metrics.record_rate_limit(true);
+        metrics.record_rate_limit(true);
+        metrics.record_rate_limit(false);
+
+        let message = |timestamp: u64| EventMessage {
+            event_id: 1,
+            event_type: EventType::NewMessage(Box::new(EventPayloadNewMessage {
+                timestamp: Timestamp(timestamp as u32),
+                ..Default::default()
+            })),
+        };
+        metrics.record_longpoll(&[]);
+        metrics.record_longpoll(&[message(now_secs() - 5), message(now_secs() - 2)]);
+
+        let text = metrics.render_prometheus();
+        assert!(text.contains("vkteams_bot_rate_limit_requests_total{result=\"allowed\"} 2"));
+        assert!(text.contains("vkteams_bot_rate_limit_requests_total{result=\"limited\"} 1"));
+        assert!(text.contains("vkteams_bot_longpoll_batch_size_bucket{le=\"0\"} 1"));
+        assert!(text.contains("vkteams_bot_longpoll_batch_size_bucket{le=\"2\"} 2"));
+        assert!(text.contains("vkteams_bot_longpoll_batch_size_sum 2"));
+        assert!(text.contains("vkteams_bot_longpoll_batch_size_count 2"));
+        let lag: f64 = text
+            .lines()
+            .find_map(|line| line.strip_prefix("vkteams_bot_longpoll_lag_seconds "))
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((5.0..7.0).contains(&lag));
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[tokio::test]
+    async fn test_metrics_router() {
+        use tower::ServiceExt;
+
+        metrics().record_retry("status");
+        let app: axum::Router = axum::Router::new().route_metrics();
+        let response = app
+            .oneshot(
+                axum::http::Request::get(METRICS_PATH)
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert_eq!(
+            response.headers()[axum::http::header::CONTENT_TYPE],
+            PROMETHEUS_CONTENT_TYPE
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("vkteams_bot_http_retries_total{reason=\"status\"}"));
+    }
+}
@@ -1,17 +1,40 @@
+pub mod builder;
+#[cfg(feature = "longpoll")]
+pub mod checkpoint;
+pub mod circuit_breaker;
+pub mod dispatcher;
+pub mod fsm;
 #[cfg(feature = "grpc")]
 pub mod grpc;
 #[cfg(feature = "longpoll")]
 pub mod longpoll;
+pub mod metrics;
 pub mod net;
+pub mod outbox;
 #[cfg(feature = "ratelimit")]
 pub mod ratelimit;
+#[cfg(feature = "ratelimit")]
+pub mod ratelimit_backend;
+pub mod upload_cache;
 #[cfg(feature = "webhook")]
 pub mod webhook;
 
+use crate::api::files::get_info::ResponseFilesGetInfo;
 use crate::api::types::*;
+pub use crate::bot::builder::BotBuilder;
+#[cfg(feature = "longpoll")]
+use crate::bot::checkpoint::Checkpoint;
+use crate::bot::circuit_breaker::CircuitStats;
 #[cfg(feature = "ratelimit")]
 use crate::bot::ratelimit::RateLimiter;
+use crate::bot::upload_cache::UploadCache;
+#[cfg(feature = "longpoll")]
+use crate::config::types::EventListenerConfig;
+use crate::config::types::NetworkConfig;
+#[cfg(feature = "ratelimit")]
+use crate::config::types::RateLimit;
 use crate::error::{BotError, Result};
+use crate::secret::SecretString;
 use net::ConnectionPool;
 use net::*;
 use once_cell::sync::OnceCell;
@@ -20,28 +43,45 @@ use serde::Serialize;
 use std::fmt;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
 #[cfg(feature = "ratelimit")]
 use tokio::sync::Mutex;
-use tracing::debug;
+use tracing::{debug, warn};
 
 #[derive(Clone)]
 /// Bot class with attributes
 /// - `connection_pool`: [`ConnectionPool`] - Pool of HTTP connections for API requests
-/// - `token`: [`String`] - Bot API token
+/// - `token`: [`SecretString`] - Bot API token, never printed
 /// - `base_api_url`: [`reqwest::Url`] - Base API URL
 /// - `base_api_path`: [`String`] - Base API path
 /// - `event_id`: [`std::sync::Arc<_>`] - Last event ID
+/// - `network`: [`NetworkConfig`] - Network settings
+/// - `rate_limit`: [`RateLimit`] - Rate limit settings
+/// - `listener`: [`EventListenerConfig`] - Event listener settings
+/// - `checkpoint`: [`Checkpoint`] - Optional durable storage of the last event ID
+/// - `upload_cache`: [`UploadCache`] - Optional reuse of uploaded files by content hash
+///
+/// Use [`Bot::builder`] to set the settings per bot,
+/// otherwise they are taken from the global configuration.
 ///
 /// [`reqwest::Url`]: https://docs.rs/reqwest/latest/reqwest/struct.Url.html
 /// [`std::sync::Arc<_>`]: https://doc.rust-lang.org/std/sync/struct.Arc.html
 pub struct Bot {
     pub(crate) connection_pool: OnceCell<ConnectionPool>,
-    pub(crate) token: Arc<str>,
+    pub(crate) token: SecretString,
     pub(crate) base_api_url: Url,
     pub(crate) base_api_path: Arc<str>,
     pub(crate) event_id: Arc<AtomicU32>,
+    pub(crate) network: Arc<NetworkConfig>,
     #[cfg(feature = "ratelimit")]
     pub(crate) rate_limiter: OnceCell<Arc<Mutex<RateLimiter>>>,
+    #[cfg(feature = "ratelimit")]
+    pub(crate) rate_limit: Arc<RateLimit>,
+    #[cfg(feature = "longpoll")]
+    pub(crate) listener: Arc<EventListenerConfig>,
+    #[cfg(feature = "longpoll")]
+    pub(crate) checkpoint: Option<Checkpoint>,
+    pub(crate) upload_cache: Option<UploadCache>,
 }
 
 impl fmt::Debug for Bot {
@@ -52,6 +92,7 @@ impl fmt::Debug for Bot {
             .field("base_api_url", &self.base_api_url)
             .field("base_api_path", &self.base_api_path)
             .field("event_id", &self.event_id)
+            .field("network", &self.network)
             .finish()
     }
 }
@@ -130,33 +171,78 @@ impl Bot {
     /// For most cases, consider using [`with_default_version`](#method.with_default_version)
     /// which uses V1 API version and has a simpler signature.
     pub fn with_params(version: &APIVersionUrl, token: &str, api_url: &str) -> Result<Self> {
-        debug!("Creating new bot with API version: {:?}", version);
         debug!("Using provided token and API URL");
+        BotBuilder::new()
+            .version(version.clone())
+            .token(token)
+            .api_url(api_url)
+            .build()
+    }
 
-        let base_api_url = Url::parse(api_url).map_err(BotError::Url)?;
+    /// Creates a [`BotBuilder`] to set network, rate limit and event listener settings
+    /// or a pre-built HTTP client for this bot
+    pub fn builder() -> BotBuilder {
+        BotBuilder::new()
+    }
 
-        match base_api_url.scheme() {
-            "http" | "https" => {
-                debug!("Base API URL scheme is valid: {}", base_api_url.scheme());
-            }
-            _ => {
-                return Err(BotError::Url(url::ParseError::InvalidIpv4Address));
-            }
-        }
-        debug!("API URL successfully parsed");
+    /// Network settings of this bot
+    pub fn network_config(&self) -> &NetworkConfig {
+        &self.network
+    }
 
-        let base_api_path = version.to_string();
-        debug!("Set API base path: {}", base_api_path);
+    /// Rate limit settings of this bot
+    #[cfg(feature = "ratelimit")]
+    pub fn rate_limit_config(&self) -> &RateLimit {
+        &self.rate_limit
+    }
 
-        Ok(Self {
-            connection_pool: OnceCell::new(),
-            token: Arc::<str>::from(token),
-            base_api_url,
-            base_api_path: Arc::<str>::from(base_api_path),
-            event_id: Arc::new(AtomicU32::new(0)),
-            #[cfg(feature = "ratelimit")]
-            rate_limiter: OnceCell::new(),
-        })
+    /// Use the rate limiter, e.g. one created with [`RateLimiter::with_backend`]
+    #[cfg(feature = "ratelimit")]
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limit = Arc::new(rate_limiter.config().clone());
+        self.rate_limiter = OnceCell::with_value(Arc::new(Mutex::new(rate_limiter)));
+        self
+    }
+
+    /// Event listener settings of this bot
+    #[cfg(feature = "longpoll")]
+    pub fn listener_config(&self) -> &EventListenerConfig {
+        &self.listener
+    }
+
+    /// Checkpoint of the last processed event ID used by the event listeners
+    #[cfg(feature = "longpoll")]
+    pub fn checkpoint(&self) -> Option<&Checkpoint> {
+        self.checkpoint.as_ref()
+    }
+
+    /// Set checkpoint of the last processed event ID, see [`checkpoint`](crate::bot::checkpoint)
+    #[cfg(feature = "longpoll")]
+    pub fn with_checkpoint(mut self, checkpoint: Checkpoint) -> Self {
+        self.checkpoint = Some(checkpoint);
+        self
+    }
+
+    /// Cache of uploaded file ids used by `messages/sendFile` and `messages/sendVoice`
+    pub fn upload_cache(&self) -> Option<&UploadCache> {
+        self.upload_cache.as_ref()
+    }
+
+    /// Set cache of uploaded file ids, see [`upload_cache`](crate::bot::upload_cache)
+    pub fn with_upload_cache(mut self, upload_cache: UploadCache) -> Self {
+        self.upload_cache = Some(upload_cache);
+        self
+    }
+
+    /// State of the circuits of API requests, see [`circuit_breaker`](crate::bot::circuit_breaker)
+    pub fn circuit_stats(&self) -> Vec<CircuitStats> {
+        self.connection_pool().circuit_breaker().stats()
+    }
+
+    /// Connection pool, created on first use from the network settings
+    pub(crate) fn connection_pool(&self) -> &ConnectionPool {
+        self.connection_pool
+            .get_or_init(|| ConnectionPool::from_config(&self.network))
     }
 
     /// Get last event id (lock-free)
@@ -191,7 +277,8 @@ impl Bot {
         let mut url = self.base_api_url.clone();
         url.set_path(&path);
         url.set_query(Some(&query));
-        url.query_pairs_mut().append_pair("token", &self.token);
+        url.query_pairs_mut()
+            .append_pair("token", self.token.expose_secret());
         Ok(url)
     }
 
@@ -214,30 +301,222 @@ impl Bot {
     ///
     #[tracing::instrument(skip(self, message))]
     pub async fn send_api_request<Rq>(&self, message: Rq) -> Result<<Rq>::ResponseType>
+    where
+        Rq: BotRequest + Serialize + std::fmt::Debug,
+    {
+        self.send_request(message, None).await
+    }
+
+    /// Send request with an explicit upload, get response
+    /// The file part of a POST request is taken from `upload` instead of the request multipart.
+    /// Use it to stream from an [`tokio::io::AsyncRead`] or to track upload progress.
+    /// - `message`: generic type `Rq` - request type
+    /// - `upload`: [`RetryableMultipartForm`] - file to upload, reopened on every retry
+    ///
+    /// ## Errors
+    /// - the same as [`Bot::send_api_request`]
+    #[tracing::instrument(skip(self, message, upload))]
+    pub async fn send_api_request_with_upload<Rq>(
+        &self,
+        message: Rq,
+        upload: RetryableMultipartForm,
+    ) -> Result<<Rq>::ResponseType>
+    where
+        Rq: BotRequest + Serialize + std::fmt::Debug,
+    {
+        self.send_request(message, Some(upload)).await
+    }
+
+    /// Download a file to the writer, streaming it in chunks
+    /// The size reported by `files/getInfo` is checked unless `options` already expects a size.
+    /// - `info`: [`ResponseFilesGetInfo`] - file info with the download URL
+    /// - `writer`: destination of the file content
+    /// - `options`: [`DownloadOptions`] - hash check and progress callback
+    ///
+    /// ## Errors
+    /// - the same as [`ConnectionPool::download_to_writer`]
+    #[tracing::instrument(skip(self, writer, options))]
+    pub async fn download_file_to_writer<W>(
+        &self,
+        info: &ResponseFilesGetInfo,
+        writer: &mut W,
+        options: DownloadOptions,
+    ) -> Result<DownloadedFile>
+    where
+        W: tokio::io::AsyncWrite + Unpin + Send,
+    {
+        self.connection_pool()
+            .download_to_writer(
+                info.download_url()?,
+                writer,
+                &info.download_options(options),
+            )
+            .await
+    }
+
+    /// Download a file to the path, resuming a partially downloaded file
+    /// The size reported by `files/getInfo` is checked unless `options` already expects a size.
+    /// - `info`: [`ResponseFilesGetInfo`] - file info with the download URL
+    /// - `path`: destination file path
+    /// - `options`: [`DownloadOptions`] - hash check and progress callback
+    ///
+    /// ## Errors
+    /// - the same as [`ConnectionPool::download_to_path`]
+    #[tracing::instrument(skip(self, info, options))]
+    pub async fn download_file_to_path(
+        &self,
+        info: &ResponseFilesGetInfo,
+        path: &std::path::Path,
+        options: DownloadOptions,
+    ) -> Result<DownloadedFile> {
+        self.connection_pool()
+            .download_to_path(info.download_url()?, path, &info.download_options(options))
+            .await
+    }
+
+    /// Send request, retrying requests rejected with too many requests after the
+    /// `Retry-After` delay or a backoff. The chat rate limiter is slowed down on every rejection.
+    async fn send_request<Rq>(
+        &self,
+        message: Rq,
+        upload: Option<RetryableMultipartForm>,
+    ) -> Result<<Rq>::ResponseType>
     where
         Rq: BotRequest + Serialize + std::fmt::Debug,
     {
         debug!("Starting send_api_request");
-        // Check rate limit for this chat
-        #[cfg(feature = "ratelimit")]
-        {
-            if let Some(chat_id) = message.get_chat_id() {
-                let mut rate_limiter = self
-                    .rate_limiter
-                    .get_or_init(|| Arc::new(Mutex::new(RateLimiter::default())))
-                    .lock()
-                    .await;
-                if !rate_limiter.wait_if_needed(chat_id).await {
-                    return Err(BotError::Validation(
-                        "Rate limit exceeded for this chat".to_string(),
-                    ));
+        let started = std::time::Instant::now();
+        let result = self
+            .send_with_retries(<Rq>::METHOD, message.get_chat_id(), || {
+                self.dispatch_request(&message, upload.clone())
+            })
+            .await;
+        metrics::metrics().record_api_request(<Rq>::METHOD, &result, started.elapsed());
+        result
+    }
+
+    /// Send a GET request to the API method with an already serialized query,
+    /// used to deliver requests stored in the [`outbox`](crate::bot::outbox)
+    ///
+    /// ## Errors
+    /// - the same as [`Bot::send_api_request`]
+    pub(crate) async fn send_raw_request(
+        &self,
+        method: &str,
+        query: &str,
+        chat_id: Option<&ChatId>,
+    ) -> Result<serde_json::Value> {
+        let started = std::time::Instant::now();
+        let result = self
+            .send_with_retries(method, chat_id, || async {
+                let url = self.get_parsed_url(self.set_path(method), query.to_string())?;
+                let body = self.connection_pool().get_text(url).await?;
+                let response: serde_json::Value = serde_json::from_str(&body)?;
+                if response.get("ok") == Some(&serde_json::Value::Bool(false)) {
+                    let description = response
+                        .get("description")
+                        .and_then(serde_json::Value::as_str)
+                        .unwrap_or("Unspecified error")
+                        .to_string();
+                    return ApiResponseWrapper::Error {
+                        ok: false,
+                        description,
+                    }
+                    .into();
                 }
-            } else {
-                debug!("No chat_id found in message");
+                Ok(response)
+            })
+            .await;
+        metrics::metrics().record_api_request(method.to_owned(), &result, started.elapsed());
+        result
+    }
+
+    /// Run the attempt, waiting for the rate limits before each one and retrying
+    /// attempts rejected with too many requests or a temporary API error
+    async fn send_with_retries<T, F, Fut>(
+        &self,
+        method: &str,
+        chat_id: Option<&ChatId>,
+        attempt: F,
+    ) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let mut attempts = 0;
+        loop {
+            #[cfg(feature = "ratelimit")]
+            self.wait_rate_limit(method, chat_id).await?;
+
+            let err = match attempt().await {
+                Err(e @ (BotError::RateLimited(_) | BotError::Api(_))) if e.is_retryable() => e,
+                result => return result,
+            };
+            let retry_after = err.retry_after();
+            #[cfg(feature = "ratelimit")]
+            if let (BotError::RateLimited(_), Some(chat_id)) = (&err, chat_id) {
+                self.rate_limiter()
+                    .lock()
+                    .await
+                    .throttle(chat_id, retry_after);
             }
+            #[cfg(not(feature = "ratelimit"))]
+            let _ = chat_id;
+
+            attempts += 1;
+            if attempts > self.network.retries
+                || retry_after.is_some_and(|delay| delay > MAX_RETRY_AFTER)
+            {
+                return Err(err);
+            }
+            let delay = retry_after.unwrap_or_else(|| {
+                calculate_backoff_duration(
+                    attempts,
+                    Duration::from_millis(self.network.max_backoff_ms),
+                )
+            });
+            warn!(
+                "Request to {} failed, retrying in {:?} (attempt {} of {}): {}",
+                method, delay, attempts, self.network.retries, err
+            );
+            tokio::time::sleep(delay).await;
         }
+    }
 
-        let query = serde_url_params::to_string(&message)?;
+    /// Rate limiter of this bot, created on first use from the rate limit settings
+    #[cfg(feature = "ratelimit")]
+    pub fn rate_limiter(&self) -> &Arc<Mutex<RateLimiter>> {
+        self.rate_limiter.get_or_init(|| {
+            Arc::new(Mutex::new(RateLimiter::with_config(
+                self.rate_limit.as_ref().clone(),
+            )))
+        })
+    }
+
+    /// Wait for the chat, API method and bot-wide rate limits of the request
+    ///
+    /// ## Errors
+    /// - `BotError::RateLimited` - rate limit is still exceeded after all attempts
+    #[cfg(feature = "ratelimit")]
+    async fn wait_rate_limit(&self, method: &str, chat_id: Option<&ChatId>) -> Result<()> {
+        // Buckets are shared between clones, so the lock is not held while waiting
+        let limiter = self.rate_limiter().lock().await.clone();
+        if !limiter.wait_for_request(method, chat_id).await {
+            return Err(BotError::RateLimited(None));
+        }
+        Ok(())
+    }
+
+    /// Single attempt to send the request
+    async fn dispatch_request<Rq>(
+        &self,
+        message: &Rq,
+        upload: Option<RetryableMultipartForm>,
+    ) -> Result<<Rq>::ResponseType>
+    where
+        Rq: BotRequest + Serialize + std::fmt::Debug,
+    {
+        let query = serde_url_params::to_string(message)?;
         let url = self.get_parsed_url(self.set_path(<Rq>::METHOD), query.to_owned())?;
 
         debug!("Request URL: {}", url.path());
@@ -249,25 +528,75 @@ impl Bot {
                     message,
                     message.get_multipart()
                 );
-                let form = file_to_multipart(message.get_multipart()).await?;
+                let form = match upload {
+                    Some(form) => form,
+                    None => file_to_retryable_multipart(message.get_multipart()).await?,
+                };
+
+                if let Some(cache) = &self.upload_cache
+                    && upload_cache::supports_file_id(<Rq>::METHOD)
+                {
+                    return self.send_cached_upload::<Rq>(cache, url, &form).await;
+                }
 
-                self.connection_pool
-                    .get_or_init(ConnectionPool::optimized)
-                    .post_file(url, form)
+                self.connection_pool()
+                    .post_file_retryable(url, &form)
                     .await?
             }
             HTTPMethod::GET => {
                 debug!("Sending GET request");
-                self.connection_pool
-                    .get_or_init(ConnectionPool::optimized)
-                    .get_text(url)
-                    .await?
+                self.connection_pool().get_text(url).await?
             }
         };
 
         let response: ApiResponseWrapper<<Rq>::ResponseType> = serde_json::from_str(&body)?;
         response.into()
     }
+
+    /// Send the file by the cached `fileId` of identical content, or upload it and cache the id
+    ///
+    /// A cached id rejected by the API as unknown or invalid is removed and the file is uploaded again.
+    async fn send_cached_upload<Rq>(
+        &self,
+        cache: &UploadCache,
+        url: Url,
+        form: &RetryableMultipartForm,
+    ) -> Result<<Rq>::ResponseType>
+    where
+        Rq: BotRequest,
+    {
+        let key = upload_cache::cache_key(<Rq>::METHOD, &form.sha256().await?);
+
+        if let Some(file_id) = cache.lookup(&key).await {
+            debug!("Sending cached fileId {} for {}", file_id, form.filename);
+            let mut cached_url = url.clone();
+            cached_url.query_pairs_mut().append_pair("fileId", &file_id);
+            let body = self.connection_pool().get_text(cached_url).await?;
+            let value: serde_json::Value = serde_json::from_str(&body)?;
+            match upload_cache::file_id_rejection(&value) {
+                Some(e) => {
+                    debug!("Cached fileId {} rejected: {}", file_id, e.description);
+                    cache.forget(&key).await;
+                }
+                None => {
+                    let response: ApiResponseWrapper<<Rq>::ResponseType> =
+                        serde_json::from_value(value)?;
+                    return response.into();
+                }
+            }
+        }
+
+        let body = self
+            .connection_pool()
+            .post_file_retryable(url, form)
+            .await?;
+        let value: serde_json::Value = serde_json::from_str(&body)?;
+        if let Some(file_id) = value.get("fileId").and_then(serde_json::Value::as_str) {
+            cache.remember(&key, file_id).await;
+        }
+        let response: ApiResponseWrapper<<Rq>::ResponseType> = serde_json::from_value(value)?;
+        response.into()
+    }
 }
 
 impl Default for Bot {
@@ -328,7 +657,7 @@ mod tests {
     #[test]
     fn test_bot_with_params_valid() {
         let url = Url::parse("https://example.com/api").unwrap();
-        let token: Arc<str> = Arc::from("test_token");
+        let token = SecretString::new("test_token");
         let path: Arc<str> = Arc::from("/api");
         let event_id = Arc::new(AtomicU32::new(0u32));
         let bot = Bot {
@@ -337,10 +666,18 @@ mod tests {
             base_api_url: url.clone(),
             base_api_path: path.clone(),
             event_id: event_id.clone(),
+            network: Arc::default(),
             #[cfg(feature = "ratelimit")]
             rate_limiter: OnceCell::new(),
+            #[cfg(feature = "ratelimit")]
+            rate_limit: Arc::default(),
+            #[cfg(feature = "longpoll")]
+            listener: Arc::default(),
+            #[cfg(feature = "longpoll")]
+            checkpoint: None,
+            upload_cache: None,
         };
-        assert_eq!(bot.token.as_ref(), "test_token");
+        assert_eq!(bot.token.expose_secret(), "test_token");
         assert_eq!(bot.base_api_url, url);
         assert_eq!(bot.base_api_path.as_ref(), "/api");
     }
@@ -354,17 +691,25 @@ mod tests {
     #[test]
     fn test_bot_with_default_version_valid() {
         let url = Url::parse("https://example.com/api").unwrap();
-        let token: Arc<str> = Arc::from("test_token");
+        let token = SecretString::new("test_token");
         let bot = Bot {
             connection_pool: OnceCell::new(),
             token: token.clone(),
             base_api_url: url.clone(),
             base_api_path: Arc::from("/api"),
             event_id: Arc::new(AtomicU32::new(0u32)),
+            network: Arc::default(),
             #[cfg(feature = "ratelimit")]
             rate_limiter: OnceCell::new(),
+            #[cfg(feature = "ratelimit")]
+            rate_limit: Arc::default(),
+            #[cfg(feature = "longpoll")]
+            listener: Arc::default(),
+            #[cfg(feature = "longpoll")]
+            checkpoint: None,
+            upload_cache: None,
         };
-        assert_eq!(bot.token.as_ref(), "test_token");
+        assert_eq!(bot.token.expose_secret(), "test_token");
     }
 
     #[test]
@@ -376,15 +721,23 @@ mod tests {
     #[test]
     fn test_set_and_get_last_event_id() {
         let url = Url::parse("https://example.com/api").unwrap();
-        let token: Arc<str> = Arc::from("test_token");
+        let token = SecretString::new("test_token");
         let bot = Bot {
             connection_pool: OnceCell::new(),
             token: token.clone(),
             base_api_url: url.clone(),
             base_api_path: Arc::from("/api"),
             event_id: Arc::new(AtomicU32::new(0u32)),
+            network: Arc::default(),
             #[cfg(feature = "ratelimit")]
             rate_limiter: OnceCell::new(),
+            #[cfg(feature = "ratelimit")]
+            rate_limit: Arc::default(),
+            #[cfg(feature = "longpoll")]
+            listener: Arc::default(),
+            #[cfg(feature = "longpoll")]
+            checkpoint: None,
+            upload_cache: None,
         };
 
         // Test atomic operations
@@ -471,7 +824,8 @@ mod tests {
         let debug_str = format!("{bot:?}");
 
         assert!(debug_str.contains("Bot"));
-        assert!(debug_str.contains("debug_token"));
+        assert!(!debug_str.contains("debug_token"));
+        assert!(debug_str.contains("[REDACTED]"));
         assert!(debug_str.contains("debug.example.com"));
         assert!(debug_str.contains("<pool>"));
     }
@@ -497,7 +851,7 @@ mod tests {
         assert!(result.is_ok());
 
         let bot = result.unwrap();
-        assert_eq!(bot.token.as_ref(), "default_token");
+        assert_eq!(bot.token.expose_secret(), "default_token");
         assert_eq!(bot.base_api_path.as_ref(), "bot/v1/");
         assert_eq!(bot.base_api_url.as_str(), "https://default.example.com/");
     }
@@ -528,7 +882,30 @@ mod tests {
         assert!(result.is_ok()); // Empty token is allowed, validation happens at API level
 
         let bot = result.unwrap();
-        assert_eq!(bot.token.as_ref(), "");
+        assert_eq!(bot.token.expose_secret(), "");
+    }
+
+    #[tokio::test]
+    async fn test_network_error_does_not_expose_token() {
+        const TOKEN: &str = "001.9988776655.4433221100:1000000004";
+        let bot = Bot::builder()
+            .token(TOKEN)
+            .api_url("http://127.0.0.1:1")
+            .network(NetworkConfig {
+                retries: 0,
+                ..NetworkConfig::default()
+            })
+            .build()
+            .unwrap();
+
+        let err = bot
+            .send_api_request(crate::api::myself::get::RequestSelfGet::new(()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BotError::Network(_)));
+        assert!(!err.to_string().contains(TOKEN));
+        assert!(!format!("{err:?}").contains(TOKEN));
+        assert!(!format!("{bot:?}").contains(TOKEN));
     }
 
     #[tokio::test]
@@ -1,15 +1,28 @@
 //! Network module
 use crate::api::types::*;
+use crate::bot::circuit_breaker::CircuitBreaker;
+use crate::bot::metrics::{metrics, retry_reason};
 use crate::config::CONFIG;
+use crate::config::types::NetworkConfig;
 use crate::error::{BotError, Result};
-use bytes::Bytes;
+use crate::secret::redact_url;
+use bytes::{Bytes, BytesMut};
+use futures::future::BoxFuture;
+use futures::{Stream, StreamExt, TryStreamExt, stream};
 use rand::Rng;
 use reqwest::{
-    Body, Client, ClientBuilder, StatusCode, Url,
+    Body, Client, ClientBuilder, Response, StatusCode, Url,
+    header::{HeaderMap, RANGE, RETRY_AFTER},
     multipart::{Form, Part},
 };
-use std::time::Duration;
-use tokio::fs::File;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::signal;
 use tokio::time::sleep;
 use tokio_util::codec::{BytesCodec, FramedRead};
@@ -17,19 +30,15 @@ use tracing::{debug, error, trace, warn};
 /// Connection pool for managing HTTP connections
 #[derive(Debug, Clone)]
 pub struct ConnectionPool {
-    client: Client,
-    retries: usize,
-    max_backoff: Duration,
+    pub(crate) client: Client,
+    pub(crate) retries: usize,
+    pub(crate) max_backoff: Duration,
+    pub(crate) circuit_breaker: Arc<CircuitBreaker>,
 }
 
 impl Default for ConnectionPool {
     fn default() -> Self {
-        let cfg = &CONFIG.network;
-        Self::new(
-            Client::new(),
-            cfg.retries,
-            Duration::from_millis(cfg.max_backoff_ms),
-        )
+        Self::with_client(Client::new(), &CONFIG.network)
     }
 }
 
@@ -40,30 +49,65 @@ impl ConnectionPool {
             client,
             retries,
             max_backoff,
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
         }
     }
 
+    /// Replace the circuit breaker of API requests
+    pub fn with_circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Arc::new(circuit_breaker);
+        self
+    }
+
+    /// Circuit breaker of API requests
+    pub fn circuit_breaker(&self) -> &CircuitBreaker {
+        &self.circuit_breaker
+    }
+
     /// Create a connection pool with optimized settings for the VK Teams Bot API
     pub fn optimized() -> Self {
-        let cfg = &CONFIG.network;
-        let client = build_optimized_client().unwrap_or_else(|e| {
+        Self::from_config(&CONFIG.network)
+    }
+
+    /// Create a connection pool with optimized client built from the network settings
+    pub fn from_config(cfg: &NetworkConfig) -> Self {
+        let client = Self::client_builder(cfg).build().unwrap_or_else(|e| {
             warn!(
                 "Failed to build optimized client. Use default instead: {}",
                 e
             );
             Client::new()
         });
-        let retries = cfg.retries;
-        let max_backoff = Duration::from_millis(cfg.max_backoff_ms);
+        Self::with_client(client, cfg)
+    }
 
-        Self {
+    /// Create a connection pool with a pre-built client,
+    /// retries, backoff and circuit breaker are taken from the network settings
+    pub fn with_client(client: Client, cfg: &NetworkConfig) -> Self {
+        Self::new(
             client,
-            retries,
-            max_backoff,
-        }
+            cfg.retries,
+            Duration::from_millis(cfg.max_backoff_ms),
+        )
+        .with_circuit_breaker(CircuitBreaker::new(cfg.circuit_breaker.clone()))
+    }
+
+    /// Client builder with optimized settings for the API.
+    /// Use it to customize the client, e.g. add a proxy or a root certificate.
+    pub fn client_builder(cfg: &NetworkConfig) -> ClientBuilder {
+        ClientBuilder::new()
+            .timeout(Duration::from_secs(cfg.request_timeout_secs))
+            .connect_timeout(Duration::from_secs(cfg.connect_timeout_secs))
+            .pool_idle_timeout(Duration::from_secs(cfg.pool_idle_timeout_secs))
+            .tcp_nodelay(true)
+            .pool_max_idle_per_host(cfg.max_idle_connections)
+            .use_rustls_tls()
     }
 
-    /// Execute a request with exponential backoff retry strategy
+    /// Execute a request with exponential backoff retry strategy.
+    /// Errors classified by [`BotError::is_retryable`] are retried,
+    /// except too many requests left to the caller to slow down
+    /// and an open circuit that fails fast.
     pub async fn execute_with_retry<F, Fut, T>(&self, operation: F) -> Result<T>
     where
         F: Fn() -> Fut + Send + Sync,
@@ -77,59 +121,64 @@ impl ConnectionPool {
             match operation().await {
                 Ok(result) => return Ok(result),
                 Err(e) => {
-                    if let BotError::Network(ref req_err) = e {
-                        if !should_retry(req_err) || retries >= self.retries {
-                            return Err(e);
-                        }
-
-                        retries += 1;
-                        let jitter = rand::random::<u64>() % 100;
-                        let delay = Duration::from_millis(backoff_ms + jitter);
-
-                        warn!(
-                            "Request failed, retrying ({}/{}): {} after {:?}",
-                            retries, self.retries, req_err, delay
-                        );
-
-                        sleep(delay).await;
-                        backoff_ms =
-                            std::cmp::min(backoff_ms * 2, self.max_backoff.as_millis() as u64);
-                    } else {
+                    if !e.is_retryable()
+                        || matches!(e, BotError::RateLimited(_) | BotError::CircuitOpen(_))
+                        || retries >= self.retries
+                    {
                         return Err(e);
                     }
+
+                    retries += 1;
+                    metrics().record_retry(retry_reason(&e));
+                    let jitter = rand::random::<u64>() % 100;
+                    let delay = Duration::from_millis(backoff_ms + jitter);
+
+                    warn!(
+                        "Request failed, retrying ({}/{}): {} after {:?}",
+                        retries, self.retries, e, delay
+                    );
+
+                    sleep(delay).await;
+                    backoff_ms = std::cmp::min(backoff_ms * 2, self.max_backoff.as_millis() as u64);
                 }
             }
         }
     }
 
-    /// Get text response from API with retry capability
-    #[tracing::instrument(skip(self))]
+    /// Get text response from API with retry capability.
+    /// Every attempt goes through the circuit breaker.
+    ///
+    /// ## Errors
+    /// - `BotError::CircuitOpen` - circuit of the host or method is open
+    #[tracing::instrument(skip(self, url), fields(url = %redact_url(&url)))]
     pub async fn get_text(&self, url: Url) -> Result<String> {
-        debug!("Getting response from API at path {}...", url);
+        debug!("Getting response from API at path {}...", redact_url(&url));
 
-        let url_str = url.as_str().to_string();
+        let url = &url;
         self.execute_with_retry(move || {
             let client = self.client.clone();
-            let url_str = url_str.clone();
 
-            async move {
-                let response = client.get(&url_str).send().await?;
+            self.circuit_breaker.call(url, async move {
+                let response = client.get(url.as_str()).send().await?;
                 trace!("Response status: {}", response.status());
 
-                validate_response(&response.status())?;
+                check_response(&response)?;
 
                 let text = response.text().await?;
                 trace!("Response body length: {} bytes", text.len());
                 Ok(text)
-            }
+            })
         })
         .await
     }
 
     /// Get bytes response from API with retry capability
-    #[tracing::instrument(skip(self))]
+    #[tracing::instrument(skip(self, url), fields(url = %redact_url(&url)))]
     pub async fn get_bytes(&self, url: Url) -> Result<Vec<u8>> {
-        debug!("Getting binary response from API at path {}...", url);
+        debug!(
+            "Getting binary response from API at path {}...",
+            redact_url(&url)
+        );
 
         let url_str = url.as_str().to_string();
         self.execute_with_retry(move || {
@@ -140,7 +189,7 @@ impl ConnectionPool {
                 let response = client.get(&url_str).send().await?;
                 trace!("Response status: {}", response.status());
 
-                validate_response(&response.status())?;
+                check_response(&response)?;
 
                 let bytes = response.bytes().await?;
                 trace!("Response body size: {} bytes", bytes.len());
@@ -151,7 +200,7 @@ impl ConnectionPool {
     }
 
     /// Post file to API with retry capability using retryable form
-    #[tracing::instrument(skip(self, retryable_form))]
+    #[tracing::instrument(skip(self, url, retryable_form), fields(url = %redact_url(&url)))]
     pub async fn post_file_retryable(
         &self,
         url: Url,
@@ -159,7 +208,7 @@ impl ConnectionPool {
     ) -> Result<String> {
         debug!(
             "Sending file to API at path {} (size: {} bytes)...",
-            url,
+            redact_url(&url),
             retryable_form.size()
         );
 
@@ -170,46 +219,42 @@ impl ConnectionPool {
             attempts += 1;
 
             // Create fresh form for each attempt
-            let form = retryable_form.to_form();
+            let form = retryable_form.to_form().await?;
 
             trace!("Attempt {} of {}", attempts, max_attempts);
 
-            let response = self.client.post(url.as_str()).multipart(form).send().await;
+            let mut status = None;
+            let response = self
+                .circuit_breaker
+                .call(&url, async {
+                    let response = self
+                        .client
+                        .post(url.as_str())
+                        .multipart(form)
+                        .send()
+                        .await?;
+                    trace!("Response status: {}", response.status());
+                    status = Some(response.status());
+                    check_response(&response)?;
+                    Ok(response)
+                })
+                .await;
 
             match response {
                 Ok(response) => {
-                    trace!("Response status: {}", response.status());
-
-                    // Validate the response
-                    if let Err(e) = validate_response(&response.status()) {
-                        if attempts >= max_attempts || !should_retry_status(&response.status()) {
-                            return Err(e);
-                        }
-
-                        let backoff = calculate_backoff_duration(attempts, self.max_backoff);
-                        warn!(
-                            "HTTP error {}, retrying in {:?} (attempt {} of {})",
-                            response.status(),
-                            backoff,
-                            attempts,
-                            max_attempts
-                        );
-                        sleep(backoff).await;
-                        continue;
-                    }
-
                     // Get the response text
                     let text = response.text().await?;
                     trace!("Response body length: {} bytes", text.len());
                     debug!("File uploaded successfully after {} attempt(s)", attempts);
                     return Ok(text);
                 }
-                Err(err) => {
+                Err(BotError::Network(err)) => {
                     if attempts >= max_attempts || !should_retry(&err) {
                         error!("File upload failed after {} attempt(s): {}", attempts, err);
                         return Err(BotError::Network(err));
                     }
 
+                    metrics().record_retry("network");
                     let backoff = calculate_backoff_duration(attempts, self.max_backoff);
                     warn!(
                         "File upload failed, retrying in {:?} (attempt {} of {}): {}",
@@ -217,43 +262,286 @@ impl ConnectionPool {
                     );
                     sleep(backoff).await;
                 }
+                Err(e) => {
+                    // Open circuit and HTTP errors that can't be retried,
+                    // too many requests are left to the caller to slow down
+                    let Some(status) = status.filter(should_retry_status) else {
+                        return Err(e);
+                    };
+                    if attempts >= max_attempts || matches!(e, BotError::RateLimited(_)) {
+                        return Err(e);
+                    }
+
+                    metrics().record_retry("status");
+                    let backoff = calculate_backoff_duration(attempts, self.max_backoff);
+                    warn!(
+                        "HTTP error {}, retrying in {:?} (attempt {} of {})",
+                        status, backoff, attempts, max_attempts
+                    );
+                    sleep(backoff).await;
+                }
             }
         }
     }
 
     /// Post file to API with retry capability (legacy method for backward compatibility)
-    #[tracing::instrument(skip(self, form))]
+    #[tracing::instrument(skip(self, url, form), fields(url = %redact_url(&url)))]
     pub async fn post_file(&self, url: Url, form: Form) -> Result<String> {
         debug!(
             "Sending file to API at path {} (legacy method, no retry)...",
-            url
+            redact_url(&url)
         );
 
-        let response = self.client.post(url.as_str()).multipart(form).send().await;
-
-        match response {
-            Ok(response) => {
+        self.circuit_breaker
+            .call(&url, async {
+                let response = self
+                    .client
+                    .post(url.as_str())
+                    .multipart(form)
+                    .send()
+                    .await
+                    .map_err(BotError::from)
+                    .inspect_err(|err| warn!("File upload failed (no retry available): {}", err))?;
                 trace!("Response status: {}", response.status());
-                validate_response(&response.status())?;
+                check_response(&response)?;
                 let text = response.text().await?;
                 trace!("Response body length: {} bytes", text.len());
                 Ok(text)
+            })
+            .await
+    }
+
+    /// Download file to the writer, streaming the body in chunks.
+    /// An interrupted download is resumed with the HTTP `Range` header,
+    /// bytes already written are skipped if the server ignores the range.
+    /// - `url` - file URL
+    /// - `writer` - destination of the file content
+    /// - `options` - [`DownloadOptions`] with expected size, hash and progress callback
+    ///
+    /// ## Errors
+    /// - `BotError::Network` - network error after all retries
+    /// - `BotError::Io` - error writing the file content
+    /// - `BotError::Validation` - HTTP client error, size or hash mismatch
+    #[tracing::instrument(skip(self, url, writer, options), fields(url = %redact_url(&url)))]
+    pub async fn download_to_writer<W>(
+        &self,
+        url: Url,
+        writer: &mut W,
+        options: &DownloadOptions,
+    ) -> Result<DownloadedFile>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        let downloaded = self
+            .download_from(&url, writer, 0, Sha256::new(), options)
+            .await?;
+        options.verify(&downloaded)?;
+        Ok(downloaded)
+    }
+
+    /// Download file to the path, streaming the body in chunks.
+    /// The content is written to `<path>.part` first and renamed after the checks pass,
+    /// so a partial file left by an interrupted download is resumed with the HTTP `Range` header.
+    /// - `url` - file URL
+    /// - `path` - destination file path
+    /// - `options` - [`DownloadOptions`] with expected size, hash and progress callback
+    ///
+    /// ## Errors
+    /// - `BotError::Network` - network error after all retries
+    /// - `BotError::Io` - error working with the file
+    /// - `BotError::Validation` - invalid path, HTTP client error, size or hash mismatch
+    #[tracing::instrument(skip(self, url, options), fields(url = %redact_url(&url)))]
+    pub async fn download_to_path(
+        &self,
+        url: Url,
+        path: &Path,
+        options: &DownloadOptions,
+    ) -> Result<DownloadedFile> {
+        let partial = partial_download_path(path)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&partial)
+            .await?;
+        let mut offset = file.metadata().await?.len();
+        if options.expected_size.is_some_and(|size| offset > size) {
+            // The partial file can't belong to this download
+            file.set_len(0).await?;
+            offset = 0;
+        }
+        let hasher = if offset > 0 {
+            debug!(
+                "Resuming download of {} from {} bytes",
+                path.display(),
+                offset
+            );
+            hash_file(&partial).await?
+        } else {
+            Sha256::new()
+        };
+
+        let downloaded = self
+            .download_from(&url, &mut file, offset, hasher, options)
+            .await?;
+        drop(file);
+        if let Err(e) = options.verify(&downloaded) {
+            // Corrupted content can't be resumed
+            tokio::fs::remove_file(&partial).await?;
+            return Err(e);
+        }
+        tokio::fs::rename(&partial, path).await?;
+        Ok(downloaded)
+    }
+
+    /// Stream the file content to the writer starting from `offset` with retries
+    async fn download_from<W>(
+        &self,
+        url: &Url,
+        writer: &mut W,
+        offset: u64,
+        mut hasher: Sha256,
+        options: &DownloadOptions,
+    ) -> Result<DownloadedFile>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        let mut written = offset;
+        if let Some(progress) = &options.progress {
+            progress(written);
+        }
+
+        let mut attempts = 0;
+        let max_attempts = self.retries + 1;
+        // Nothing is left to download when the partial file is already complete
+        while options.expected_size != Some(written) {
+            attempts += 1;
+            trace!("Attempt {} of {}", attempts, max_attempts);
+
+            let before = written;
+            match self
+                .download_attempt(url, writer, &mut written, &mut hasher, options)
+                .await
+            {
+                Ok(()) => break,
+                Err(e) if attempts < max_attempts && should_retry_download(&e) => {
+                    metrics().record_retry("download");
+                    let backoff = match e {
+                        BotError::RateLimited(Some(retry_after)) => retry_after,
+                        _ => calculate_backoff_duration(attempts, self.max_backoff),
+                    };
+                    warn!(
+                        "Download interrupted after {} bytes, retrying in {:?} (attempt {} of {}): {}",
+                        written - before,
+                        backoff,
+                        attempts,
+                        max_attempts,
+                        e
+                    );
+                    sleep(backoff).await;
+                }
+                Err(e) => {
+                    error!("Download failed after {} attempt(s): {}", attempts, e);
+                    return Err(e);
+                }
             }
-            Err(err) => {
-                warn!("File upload failed (no retry available): {}", err);
-                Err(BotError::Network(err))
+        }
+        writer.flush().await?;
+
+        Ok(DownloadedFile {
+            size: written,
+            resumed_from: offset,
+            sha256: format!("{:x}", hasher.finalize()),
+        })
+    }
+
+    /// Single download request, writes the body after the `written` bytes
+    async fn download_attempt<W>(
+        &self,
+        url: &Url,
+        writer: &mut W,
+        written: &mut u64,
+        hasher: &mut Sha256,
+        options: &DownloadOptions,
+    ) -> Result<()>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        let mut request = self.client.get(url.as_str());
+        if *written > 0 {
+            request = request.header(RANGE, format!("bytes={written}-"));
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        trace!("Response status: {}", status);
+        if status == StatusCode::RANGE_NOT_SATISFIABLE && *written > 0 {
+            // The server has no bytes after the offset
+            return Ok(());
+        }
+        check_response(&response)?;
+
+        // The server ignored the range and sends the file from the start
+        let mut skip = if status == StatusCode::PARTIAL_CONTENT {
+            0
+        } else {
+            *written
+        };
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let mut chunk = chunk?;
+            if skip > 0 {
+                let skipped = skip.min(chunk.len() as u64);
+                skip -= skipped;
+                chunk = chunk.slice(skipped as usize..);
+            }
+            if chunk.is_empty() {
+                continue;
+            }
+            writer.write_all(&chunk).await?;
+            hasher.update(&chunk);
+            *written += chunk.len() as u64;
+            if let Some(progress) = &options.progress {
+                progress(*written);
             }
         }
+        Ok(())
     }
 }
 
+/// Longest `Retry-After` delay waited before retrying a request,
+/// requests throttled for longer fail with `BotError::RateLimited`
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Validate HTTP response, `429 Too Many Requests` is returned as
+/// `BotError::RateLimited` with the delay from the `Retry-After` header
+fn check_response(response: &Response) -> Result<()> {
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = retry_after(response.headers());
+        warn!("Too many requests, retry after {:?}", retry_after);
+        return Err(BotError::RateLimited(retry_after));
+    }
+    validate_response(&response.status())
+}
+
+/// Parse the `Retry-After` header: delay in seconds or HTTP date
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
 /// Validate HTTP response status
 fn validate_response(status: &StatusCode) -> Result<()> {
     if status.is_success() {
         Ok(())
     } else if status.is_server_error() {
         warn!("Server error: {}", status);
-        Err(BotError::System(format!("Server error: HTTP {status}")))
+        Err(BotError::Server(*status))
     } else if status.is_client_error() {
         error!("Client error: {}", status);
         Err(BotError::Validation(format!("HTTP error: {status}")))
@@ -266,13 +554,51 @@ fn validate_response(status: &StatusCode) -> Result<()> {
 }
 
 /// Determine if the request should be retried based on the error
-fn should_retry(err: &reqwest::Error) -> bool {
+pub(crate) fn should_retry(err: &reqwest::Error) -> bool {
     err.is_timeout()
         || err.is_connect()
         || err.is_request()
         || (err.status().is_some_and(|s| s.is_server_error()))
 }
 
+/// Determine if an interrupted download should be resumed
+fn should_retry_download(err: &BotError) -> bool {
+    match err {
+        BotError::Network(err) => should_retry(err) || err.is_body(),
+        // Server and unexpected status errors from `validate_response`
+        BotError::Server(_) | BotError::System(_) => true,
+        BotError::RateLimited(retry_after) => retry_after.is_none_or(|d| d <= MAX_RETRY_AFTER),
+        _ => false,
+    }
+}
+
+/// Path of the partially downloaded file: `<path>.part`
+///
+/// ## Errors
+/// - `BotError::Validation` - path has no file name
+fn partial_download_path(path: &Path) -> Result<PathBuf> {
+    let file_name = path.file_name().ok_or_else(|| {
+        BotError::Validation(format!("Invalid download path: {}", path.display()))
+    })?;
+    let mut partial = file_name.to_os_string();
+    partial.push(".part");
+    Ok(path.with_file_name(partial))
+}
+
+/// Hash the content of a partially downloaded file
+async fn hash_file(path: &Path) -> Result<Sha256> {
+    let mut file = File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; UPLOAD_CHUNK_SIZE];
+    loop {
+        let read = file.read(&mut buf).await?;
+        if read == 0 {
+            return Ok(hasher);
+        }
+        hasher.update(&buf[..read]);
+    }
+}
+
 /// Check if HTTP status code should trigger a retry
 pub fn should_retry_status(status: &StatusCode) -> bool {
     match status.as_u16() {
@@ -305,19 +631,6 @@ pub fn calculate_backoff_duration(attempt: usize, max_backoff: Duration) -> Dura
     Duration::from_millis(final_duration)
 }
 
-/// Build a client with optimized settings for the API
-fn build_optimized_client() -> Result<Client> {
-    let cfg = &CONFIG.network;
-    let builder = ClientBuilder::new()
-        .timeout(Duration::from_secs(cfg.request_timeout_secs))
-        .connect_timeout(Duration::from_secs(cfg.connect_timeout_secs))
-        .pool_idle_timeout(Duration::from_secs(cfg.pool_idle_timeout_secs))
-        .tcp_nodelay(true)
-        .pool_max_idle_per_host(cfg.max_idle_connections)
-        .use_rustls_tls();
-
-    builder.build().map_err(BotError::Network)
-}
 /// Get bytes response from API
 /// Send request with [`Client`] `get` method and get body with [`reqwest::Response`] `bytes` method
 /// - `url` - file URL
@@ -326,9 +639,12 @@ fn build_optimized_client() -> Result<Client> {
 /// - `BotError::Network` - network error when sending request or receiving response
 ///
 /// @deprecated Use ConnectionPool::get_bytes instead
-#[tracing::instrument(skip(client))]
+#[tracing::instrument(skip(client, url), fields(url = %redact_url(&url)))]
 pub async fn get_bytes_response(client: Client, url: Url) -> Result<Vec<u8>> {
-    debug!("Getting binary response from API at path {}...", url);
+    debug!(
+        "Getting binary response from API at path {}...",
+        redact_url(&url)
+    );
     let response = client.get(url.as_str()).send().await?;
     trace!("Response status: {}", response.status());
     let bytes = response.bytes().await?;
@@ -478,8 +794,8 @@ mod tests {
     async fn test_validate_response_server_error() {
         let err = validate_response(&StatusCode::INTERNAL_SERVER_ERROR).unwrap_err();
         match err {
-            BotError::System(msg) => assert!(msg.contains("Server error")),
-            _ => panic!("Expected System error"),
+            BotError::Server(status) => assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR),
+            _ => panic!("Expected Server error"),
         }
     }
 
@@ -521,7 +837,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_build_optimized_client() {
-        let result = build_optimized_client();
+        let result = ConnectionPool::client_builder(&CONFIG.network).build();
         assert!(
             result.is_ok(),
             "Failed to build optimized client: {:?}",
@@ -540,6 +856,21 @@ mod tests {
         assert!(pool.max_backoff > Duration::from_millis(0));
     }
 
+    #[tokio::test]
+    async fn test_connection_pool_from_config() {
+        let cfg = NetworkConfig {
+            retries: 9,
+            max_backoff_ms: 250,
+            ..Default::default()
+        };
+        let pool = ConnectionPool::from_config(&cfg);
+        assert_eq!(pool.retries, 9);
+        assert_eq!(pool.max_backoff, Duration::from_millis(250));
+
+        let pool = ConnectionPool::with_client(reqwest::Client::new(), &cfg);
+        assert_eq!(pool.retries, 9);
+    }
+
     #[tokio::test]
     async fn test_connection_pool_execute_with_retry_success() {
         let pool = ConnectionPool::new(reqwest::Client::new(), 2, Duration::from_millis(100));
@@ -821,8 +1152,8 @@ mod tests {
             let result = validate_response(code);
             assert!(result.is_err(), "Status code {code:?} should be error");
             match result.unwrap_err() {
-                BotError::System(_) => {} // Expected
-                _ => panic!("Expected System error for code {code:?}"),
+                BotError::Server(status) => assert_eq!(status, *code),
+                _ => panic!("Expected Server error for code {code:?}"),
             }
         }
     }
@@ -869,6 +1200,177 @@ mod tests {
         let result = signal_task.await.unwrap();
         assert!(result.is_err()); // Should timeout
     }
+
+    #[tokio::test]
+    async fn test_retryable_form_reopens_reader_for_every_attempt() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let opened = Arc::new(AtomicUsize::new(0));
+        let counter = opened.clone();
+        let form = RetryableMultipartForm::from_reader(
+            "log.txt".to_string(),
+            "file".to_string(),
+            move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async { Ok(std::io::Cursor::new(b"log line".to_vec())) }
+            },
+        )
+        .with_size(8);
+        assert_eq!(form.size(), 8);
+        assert_eq!(opened.load(Ordering::SeqCst), 0);
+
+        form.to_form().await.unwrap();
+        form.to_form().await.unwrap();
+        assert_eq!(opened.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retryable_form_from_file_path_does_not_buffer() {
+        let mut path = std::env::temp_dir();
+        path.push(format!("vkteams_stream_{}.bin", std::process::id()));
+        tokio::fs::write(&path, vec![7u8; 1024]).await.unwrap();
+
+        let form = RetryableMultipartForm::from_file_path(path.to_string_lossy().to_string())
+            .await
+            .unwrap();
+        assert_eq!(form.size(), 1024);
+        assert!(matches!(form.source, UploadSource::Path(_)));
+        form.to_form().await.unwrap();
+
+        // The file is reopened on every attempt, so a removed file is an error
+        tokio::fs::remove_file(&path).await.unwrap();
+        assert!(matches!(form.to_form().await, Err(BotError::Io(_))));
+    }
+
+    #[tokio::test]
+    async fn test_retryable_form_reader_open_error() {
+        let form = RetryableMultipartForm::from_reader(
+            "log.txt".to_string(),
+            "file".to_string(),
+            || async { Err::<std::io::Cursor<Vec<u8>>, _>(std::io::Error::other("gone")) },
+        );
+        assert_eq!(form.size(), 0);
+        assert!(matches!(form.to_form().await, Err(BotError::Io(_))));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, "5".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(5)));
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(30));
+        headers.insert(RETRY_AFTER, date.parse().unwrap());
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(25) && delay <= Duration::from_secs(30));
+        headers.insert(
+            RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+        headers.insert(RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_check_response_too_many_requests() {
+        let response = Response::from(
+            http::Response::builder()
+                .status(StatusCode::TOO_MANY_REQUESTS)
+                .header(RETRY_AFTER, "2")
+                .body("")
+                .unwrap(),
+        );
+        assert!(matches!(
+            check_response(&response),
+            Err(BotError::RateLimited(Some(d))) if d == Duration::from_secs(2)
+        ));
+        let response = Response::from(
+            http::Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body("")
+                .unwrap(),
+        );
+        assert!(matches!(
+            check_response(&response),
+            Err(BotError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_should_retry_download_rate_limited() {
+        assert!(should_retry_download(&BotError::RateLimited(None)));
+        assert!(should_retry_download(&BotError::RateLimited(Some(
+            Duration::from_secs(1)
+        ))));
+        assert!(!should_retry_download(&BotError::RateLimited(Some(
+            MAX_RETRY_AFTER + Duration::from_secs(1)
+        ))));
+    }
+
+    #[tokio::test]
+    async fn test_retryable_form_sha256() {
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let form = RetryableMultipartForm::from_content(
+            "a.txt".to_string(),
+            "file".to_string(),
+            b"abc".to_vec(),
+        );
+        assert_eq!(form.sha256().await.unwrap(), abc);
+        let form = RetryableMultipartForm::from_reader(
+            "b.txt".to_string(),
+            "file".to_string(),
+            || async { Ok(std::io::Cursor::new(b"abc".to_vec())) },
+        );
+        assert_eq!(form.sha256().await.unwrap(), abc);
+    }
+
+    #[test]
+    fn test_download_options_verify() {
+        let file = DownloadedFile {
+            size: 3,
+            resumed_from: 0,
+            sha256: "ba7816bf".to_string(),
+        };
+        assert!(DownloadOptions::new().verify(&file).is_ok());
+        assert!(
+            DownloadOptions::new()
+                .with_expected_size(3)
+                .with_sha256("BA7816BF")
+                .verify(&file)
+                .is_ok()
+        );
+        assert!(matches!(
+            DownloadOptions::new().with_expected_size(4).verify(&file),
+            Err(BotError::Validation(_))
+        ));
+        assert!(matches!(
+            DownloadOptions::new().with_sha256("00").verify(&file),
+            Err(BotError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_partial_download_path() {
+        assert_eq!(
+            partial_download_path(Path::new("/tmp/archive.log")).unwrap(),
+            PathBuf::from("/tmp/archive.log.part")
+        );
+        assert!(partial_download_path(Path::new("/")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hash_file() {
+        let mut path = std::env::temp_dir();
+        path.push(format!("vkteams_hash_{}.txt", std::process::id()));
+        tokio::fs::write(&path, b"abc").await.unwrap();
+        let hasher = hash_file(&path).await.unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+        assert_eq!(
+            format!("{:x}", hasher.finalize()),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
 }
 /// Validate file path for security and correctness
 ///
@@ -1005,31 +1507,84 @@ fn validate_filename(filename: &str) -> Result<()> {
 
     Ok(())
 }
-/// Retryable multipart form that can be recreated for retry attempts
-#[derive(Debug, Clone)]
+/// Size of the chunks used to stream in-memory content when progress is reported
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Upload progress callback, receives the number of bytes sent in the current attempt
+pub type UploadProgress = Arc<dyn Fn(u64) + Send + Sync>;
+
+/// Reader returned by the opener of a streamed upload
+pub type UploadReader = Box<dyn AsyncRead + Send + Unpin>;
+
+type ReaderOpener =
+    Arc<dyn Fn() -> BoxFuture<'static, std::io::Result<UploadReader>> + Send + Sync>;
+
+/// Content of a [`RetryableMultipartForm`], opened again for every attempt
+#[derive(Clone)]
+enum UploadSource {
+    /// In-memory content, shared between attempts without copying
+    Bytes(Bytes),
+    /// File on disk, reopened and streamed on every attempt
+    Path(PathBuf),
+    /// Any [`AsyncRead`], created by the opener on every attempt
+    Reader(ReaderOpener),
+}
+
+impl std::fmt::Debug for UploadSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bytes(bytes) => f.debug_tuple("Bytes").field(&bytes.len()).finish(),
+            Self::Path(path) => f.debug_tuple("Path").field(path).finish(),
+            Self::Reader(_) => f.write_str("Reader"),
+        }
+    }
+}
+
+/// Retryable multipart form that can be recreated for retry attempts.
+///
+/// Files and readers are streamed in chunks: every attempt reopens the source
+/// instead of keeping the whole content in memory.
+#[derive(Clone)]
 pub struct RetryableMultipartForm {
-    file_data: Bytes,
+    source: UploadSource,
     pub filename: String,
     field_name: String,
+    size: Option<u64>,
+    progress: Option<UploadProgress>,
+}
+
+impl std::fmt::Debug for RetryableMultipartForm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryableMultipartForm")
+            .field("source", &self.source)
+            .field("filename", &self.filename)
+            .field("field_name", &self.field_name)
+            .field("size", &self.size)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
 }
 
 impl RetryableMultipartForm {
     /// Create new retryable form from file content
     pub fn from_content(filename: String, field_name: String, content: Vec<u8>) -> Self {
+        let size = Some(content.len() as u64);
         Self {
-            file_data: Bytes::from(content),
+            source: UploadSource::Bytes(Bytes::from(content)),
             filename,
             field_name,
+            size,
+            progress: None,
         }
     }
 
-    /// Create new retryable form from file path (loads content into memory)
+    /// Create new retryable form from file path.
+    /// The file is streamed from disk and reopened on every attempt.
     pub async fn from_file_path(path: String) -> Result<Self> {
         // Validate file path first
         validate_file_path_async(&path).await?;
 
-        // Read file content into memory for retry capability
-        let content = tokio::fs::read(&path).await.map_err(BotError::Io)?;
+        let metadata = tokio::fs::metadata(&path).await.map_err(BotError::Io)?;
 
         let filename = std::path::Path::new(&path)
             .file_name()
@@ -1037,21 +1592,229 @@ impl RetryableMultipartForm {
             .unwrap_or(&path)
             .to_string();
 
-        Ok(Self::from_content(filename.clone(), filename, content))
+        Ok(Self {
+            source: UploadSource::Path(PathBuf::from(&path)),
+            filename: filename.clone(),
+            field_name: filename,
+            size: Some(metadata.len()),
+            progress: None,
+        })
+    }
+
+    /// Create new retryable form from any [`AsyncRead`].
+    /// `open` is called on every attempt and must return a reader positioned at the start.
+    pub fn from_reader<F, Fut, R>(filename: String, field_name: String, open: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = std::io::Result<R>> + Send + 'static,
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        let opener: ReaderOpener = Arc::new(move || {
+            let reader = open();
+            Box::pin(async move { reader.await.map(|r| Box::new(r) as UploadReader) })
+        });
+        Self {
+            source: UploadSource::Reader(opener),
+            filename,
+            field_name,
+            size: None,
+            progress: None,
+        }
     }
 
-    /// Convert to reqwest Form for sending
-    pub fn to_form(&self) -> Form {
-        let part = Part::bytes(self.file_data.clone().to_vec()).file_name(self.filename.clone());
-        Form::new().part(self.field_name.clone(), part)
+    /// Set the content length when it is known in advance (used for readers)
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
     }
 
-    /// Get file size for logging/validation
+    /// Set the callback receiving the number of bytes sent in the current attempt.
+    /// It starts from zero again when the upload is retried.
+    pub fn with_progress<F>(mut self, progress: F) -> Self
+    where
+        F: Fn(u64) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Convert to reqwest Form for sending, opening the source again
+    ///
+    /// ## Errors
+    /// - `BotError::Io` - error opening the file or the reader
+    pub async fn to_form(&self) -> Result<Form> {
+        let (body, size) = match &self.source {
+            UploadSource::Bytes(bytes) if self.progress.is_none() => {
+                (Body::from(bytes.clone()), self.size)
+            }
+            UploadSource::Bytes(bytes) => {
+                let chunks: Vec<std::io::Result<Bytes>> = (0..bytes.len())
+                    .step_by(UPLOAD_CHUNK_SIZE)
+                    .map(|start| Ok(bytes.slice(start..bytes.len().min(start + UPLOAD_CHUNK_SIZE))))
+                    .collect();
+                (self.stream_body(stream::iter(chunks)), self.size)
+            }
+            UploadSource::Path(path) => {
+                let file = File::open(path).await?;
+                // The file may have changed since the form was created
+                let size = file.metadata().await?.len();
+                (self.stream_body(read_chunks(file)), Some(size))
+            }
+            UploadSource::Reader(open) => {
+                let reader = open().await?;
+                (self.stream_body(read_chunks(reader)), self.size)
+            }
+        };
+        let part = match size {
+            Some(size) => Part::stream_with_length(body, size),
+            None => Part::stream(body),
+        }
+        .file_name(self.filename.clone());
+        Ok(Form::new().part(self.field_name.clone(), part))
+    }
+
+    /// Wrap the chunk stream into a request body, reporting progress if requested
+    fn stream_body<S>(&self, chunks: S) -> Body
+    where
+        S: Stream<Item = std::io::Result<Bytes>> + Send + 'static,
+    {
+        match self.progress.clone() {
+            Some(progress) => {
+                let mut sent = 0;
+                Body::wrap_stream(chunks.inspect_ok(move |chunk| {
+                    sent += chunk.len() as u64;
+                    progress(sent);
+                }))
+            }
+            None => Body::wrap_stream(chunks),
+        }
+    }
+
+    /// Get file size for logging/validation, `0` if the size of a reader is unknown
     pub fn size(&self) -> usize {
-        self.file_data.len()
+        self.size.unwrap_or_default() as usize
+    }
+
+    /// SHA-256 hash of the content as a lowercase hex string.
+    /// Files and readers are opened and read once more, without buffering.
+    ///
+    /// ## Errors
+    /// - `BotError::Io` - error opening or reading the file or the reader
+    pub async fn sha256(&self) -> Result<String> {
+        let hasher = match &self.source {
+            UploadSource::Bytes(bytes) => Sha256::new().chain_update(bytes),
+            UploadSource::Path(path) => hash_file(path).await?,
+            UploadSource::Reader(open) => {
+                let mut chunks = read_chunks(open().await?);
+                let mut hasher = Sha256::new();
+                while let Some(chunk) = chunks.try_next().await? {
+                    hasher.update(&chunk);
+                }
+                hasher
+            }
+        };
+        Ok(format!("{:x}", hasher.finalize()))
     }
 }
 
+/// Read chunks from an [`AsyncRead`]
+fn read_chunks<R>(reader: R) -> impl Stream<Item = std::io::Result<Bytes>> + Send + 'static
+where
+    R: AsyncRead + Send + Unpin + 'static,
+{
+    FramedRead::new(reader, BytesCodec::new()).map_ok(BytesMut::freeze)
+}
+
+/// Download progress callback, receives the number of bytes written so far
+pub type DownloadProgress = Arc<dyn Fn(u64) + Send + Sync>;
+
+/// Checks and progress reporting of a streamed download
+#[derive(Clone, Default)]
+pub struct DownloadOptions {
+    expected_size: Option<u64>,
+    expected_sha256: Option<String>,
+    progress: Option<DownloadProgress>,
+}
+
+impl std::fmt::Debug for DownloadOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DownloadOptions")
+            .field("expected_size", &self.expected_size)
+            .field("expected_sha256", &self.expected_sha256)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+impl DownloadOptions {
+    /// Create options without checks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check the size of the downloaded file
+    pub fn with_expected_size(mut self, size: u64) -> Self {
+        self.expected_size = Some(size);
+        self
+    }
+
+    /// Check the SHA-256 hash of the downloaded file, given as a hex string
+    pub fn with_sha256(mut self, sha256: impl Into<String>) -> Self {
+        self.expected_sha256 = Some(sha256.into().to_lowercase());
+        self
+    }
+
+    /// Set the callback receiving the number of bytes written so far,
+    /// including the bytes of a resumed partial file
+    pub fn with_progress<F>(mut self, progress: F) -> Self
+    where
+        F: Fn(u64) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Expected size of the downloaded file
+    pub fn expected_size(&self) -> Option<u64> {
+        self.expected_size
+    }
+
+    /// Check the downloaded file against the expected size and hash
+    ///
+    /// ## Errors
+    /// - `BotError::Validation` - size or hash mismatch
+    fn verify(&self, file: &DownloadedFile) -> Result<()> {
+        if let Some(size) = self.expected_size
+            && size != file.size
+        {
+            return Err(BotError::Validation(format!(
+                "Downloaded {} bytes, expected {size}",
+                file.size
+            )));
+        }
+        if let Some(sha256) = &self.expected_sha256
+            && sha256 != &file.sha256
+        {
+            return Err(BotError::Validation(format!(
+                "SHA-256 mismatch: got {}, expected {sha256}",
+                file.sha256
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Result of a streamed download
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DownloadedFile {
+    /// Size of the whole file in bytes
+    pub size: u64,
+    /// Bytes of a partial file downloaded before, `0` if the download started from scratch
+    pub resumed_from: u64,
+    /// SHA-256 hash of the whole file as a lowercase hex string
+    pub sha256: String,
+}
+
 /// Validate file path asynchronously for security and correctness
 ///
 /// ## Errors
@@ -0,0 +1,938 @@
+//! # Outbox
+//! Persistent queue of outbound requests delivered by a background worker.
+//!
+//! [`Bot::send_api_request`] gives up after a few retries, so a message sent while the API
+//! is unavailable is lost. Requests enqueued to an [`Outbox`] are saved to the store first
+//! and delivered by [`Outbox::run`], which retries failed deliveries with a backoff
+//! until they succeed, the attempts run out or the deadline passes.
+//! Errors classified by [`BotError::is_permanent`] fail the entry at once.
+//! Deliveries go through [`Bot::send_api_request`] retries and rate limiter,
+//! due entries are taken by priority, then by enqueue time.
+//!
+//! Every entry has an idempotency key: enqueueing a request with a key that is already
+//! in the store does nothing, so a request retried by the caller is enqueued once.
+//! Delivery is at-least-once: a request is sent again if the worker stops after sending it
+//! but before saving the result, or if a claim of a shared store expires during the attempt.
+//! Finished entries are kept with the delivery result until [`Outbox::prune`] removes them.
+//! Delivery results are returned by [`Outbox::get`] and broadcast to [`Outbox::subscribe`].
+//!
+//! Only requests without a file upload can be enqueued.
+//!
+//! Storage backends implement [`OutboxStore`]:
+//! - [`MemoryOutboxStore`] - in-process storage
+//! - [`FileOutboxStore`] - JSON file with all entries, replaced atomically, for small queues
+//! - `PostgresOutboxStore` - PostgreSQL storage (`storage` feature)
+//!
+//! ```no_run
+//! use vkteams_bot::prelude::*;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let bot = Bot::builder().build()?;
+//! let outbox = Outbox::new(FileOutboxStore::new("outbox.json"));
+//! let worker = tokio::spawn({
+//!     let (outbox, bot) = (outbox.clone(), bot.clone());
+//!     async move { outbox.run(&bot).await }
+//! });
+//!
+//! let request = RequestMessagesSendText::new(ChatId::from("chat_id"))
+//!     .with_text("Disk is full".to_string());
+//! let message = OutboxMessage::new("alert-disk-full", &request)?
+//!     .with_priority(OutboxPriority::High)
+//!     .with_deadline(Duration::from_secs(3600));
+//! outbox.enqueue(message).await?;
+//! # worker.await.unwrap()
+//! # }
+//! ```
+//!
+//! [`Bot::send_api_request`]: crate::Bot::send_api_request
+use crate::api::types::{BotRequest, ChatId, HTTPMethod};
+use crate::bot::Bot;
+use crate::error::{BotError, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, warn};
+
+/// Number of entries taken from the store in one delivery pass
+const DELIVERY_BATCH: usize = 100;
+/// Time a worker owns due entries taken from a shared store
+#[cfg(feature = "storage")]
+const CLAIM_TIMEOUT: Duration = Duration::from_secs(600);
+/// Capacity of the delivery result channel
+const EVENTS_CAPACITY: usize = 256;
+
+/// Current time in milliseconds since the Unix epoch
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Delivery priority, higher priorities are delivered first
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Critical,
+}
+
+/// Delivery state of an outbox entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxStatus {
+    /// Waiting for the first or the next attempt
+    Pending,
+    /// Delivered, the response is saved in the entry
+    Delivered,
+    /// Rejected by the API or out of attempts
+    Failed,
+    /// Not delivered before the deadline
+    Expired,
+}
+
+impl OutboxStatus {
+    /// Check if the entry will not be delivered again
+    pub fn is_finished(self) -> bool {
+        self != Self::Pending
+    }
+}
+
+/// Request stored in the outbox with its delivery state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    /// Idempotency key
+    pub key: String,
+    /// API method, e.g. `messages/sendText`
+    pub method: String,
+    /// Request serialized to URL query parameters
+    pub query: String,
+    /// Chat of the request for the rate limiter
+    pub chat_id: Option<String>,
+    pub priority: OutboxPriority,
+    pub status: OutboxStatus,
+    /// Enqueue time in milliseconds since the Unix epoch
+    pub created_at: u64,
+    /// Time in milliseconds since the Unix epoch after which the entry expires
+    pub deadline: Option<u64>,
+    /// Delivery attempts made
+    pub attempts: u32,
+    /// Time of the next attempt in milliseconds since the Unix epoch
+    pub next_attempt_at: u64,
+    /// Error of the last failed attempt
+    pub last_error: Option<String>,
+    /// API response of the delivered request
+    pub response: Option<serde_json::Value>,
+}
+
+/// Request to enqueue with [`Outbox::enqueue`]
+#[derive(Debug, Clone)]
+pub struct OutboxMessage {
+    key: String,
+    method: &'static str,
+    query: String,
+    chat_id: Option<String>,
+    priority: OutboxPriority,
+    deadline: Option<Duration>,
+}
+
+impl OutboxMessage {
+    /// Serialize the request with the idempotency key, normal priority and without deadline
+    ///
+    /// ## Errors
+    /// - `BotError::Validation` - the request uploads a file
+    /// - `BotError::UrlParams` - URL parameters serialization error
+    pub fn new<Rq>(key: impl Into<String>, request: &Rq) -> Result<Self>
+    where
+        Rq: BotRequest + Serialize,
+    {
+        if matches!(<Rq>::HTTP_METHOD, HTTPMethod::POST) {
+            return Err(BotError::Validation(format!(
+                "{} uploads a file and can't be sent through the outbox",
+                <Rq>::METHOD
+            )));
+        }
+        Ok(Self {
+            key: key.into(),
+            method: <Rq>::METHOD,
+            query: serde_url_params::to_string(request)?,
+            chat_id: request.get_chat_id().map(|chat_id| chat_id.0.to_string()),
+            priority: OutboxPriority::default(),
+            deadline: None,
+        })
+    }
+
+    /// Set delivery priority
+    pub fn with_priority(mut self, priority: OutboxPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Expire the request if it is not delivered within the time after enqueueing
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    fn into_entry(self, now: u64) -> OutboxEntry {
+        OutboxEntry {
+            key: self.key,
+            method: self.method.to_string(),
+            query: self.query,
+            chat_id: self.chat_id,
+            priority: self.priority,
+            status: OutboxStatus::Pending,
+            created_at: now,
+            deadline: self
+                .deadline
+                .map(|deadline| now.saturating_add(deadline.as_millis() as u64)),
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+            response: None,
+        }
+    }
+}
+
+/// Storage of outbox entries
+#[async_trait]
+pub trait OutboxStore: Send + Sync {
+    /// Add the entry, `false` if an entry with the same key exists
+    async fn insert(&self, entry: &OutboxEntry) -> Result<bool>;
+    /// Get entry by the idempotency key
+    async fn get(&self, key: &str) -> Result<Option<OutboxEntry>>;
+    /// Replace the entry with the same key, releasing its claim
+    async fn update(&self, entry: &OutboxEntry) -> Result<()>;
+    /// Pending entries with the next attempt due at the time,
+    /// by priority, then by enqueue time
+    ///
+    /// Stores shared by several workers claim the returned entries until they are updated
+    /// or the claim expires, so other workers don't take them meanwhile.
+    async fn due(&self, now: u64, limit: usize) -> Result<Vec<OutboxEntry>>;
+    /// Remove finished entries enqueued before the time, returns the number of removed entries
+    async fn prune(&self, before: u64) -> Result<usize>;
+}
+
+/// Pending entries due at the time in delivery order
+fn due_entries<'a>(
+    entries: impl Iterator<Item = &'a OutboxEntry>,
+    now: u64,
+    limit: usize,
+) -> Vec<OutboxEntry> {
+    let mut due: Vec<_> = entries
+        .filter(|entry| entry.status == OutboxStatus::Pending && entry.next_attempt_at <= now)
+        .cloned()
+        .collect();
+    due.sort_by(|a, b| {
+        b.priority
+            .cmp(&a.priority)
+            .then(a.created_at.cmp(&b.created_at))
+    });
+    due.truncate(limit);
+    due
+}
+
+/// Remove finished entries enqueued before the time
+fn prune_entries(entries: &mut HashMap<String, OutboxEntry>, before: u64) -> usize {
+    let len = entries.len();
+    entries.retain(|_, entry| !entry.status.is_finished() || entry.created_at >= before);
+    len - entries.len()
+}
+
+/// In-process outbox store
+#[derive(Debug, Default)]
+pub struct MemoryOutboxStore {
+    entries: Mutex<HashMap<String, OutboxEntry>>,
+}
+
+impl MemoryOutboxStore {
+    /// Create new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl OutboxStore for MemoryOutboxStore {
+    async fn insert(&self, entry: &OutboxEntry) -> Result<bool> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.contains_key(&entry.key) {
+            return Ok(false);
+        }
+        entries.insert(entry.key.clone(), entry.clone());
+        Ok(true)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<OutboxEntry>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(entries.get(key).cloned())
+    }
+
+    async fn update(&self, entry: &OutboxEntry) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(entry.key.clone(), entry.clone());
+        Ok(())
+    }
+
+    async fn due(&self, now: u64, limit: usize) -> Result<Vec<OutboxEntry>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(due_entries(entries.values(), now, limit))
+    }
+
+    async fn prune(&self, before: u64) -> Result<usize> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(prune_entries(&mut entries, before))
+    }
+}
+
+/// File outbox store
+///
+/// All entries are kept in one JSON file. Changes are written to a temporary file next to
+/// the target, which is then renamed over it, so a crash never leaves a partially written outbox.
+///
+/// Every enqueue and delivery attempt reads and rewrites the whole file, so the store is meant
+/// for small queues of a single worker, e.g. alerts of a CLI or a small bot. Prune finished
+/// entries regularly with [`Outbox::prune`], and use `PostgresOutboxStore` for larger queues.
+#[derive(Debug)]
+pub struct FileOutboxStore {
+    path: PathBuf,
+    /// Serializes read-modify-write cycles of this process
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl FileOutboxStore {
+    /// Create store for the file, parent directories are created on first save
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Outbox file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    async fn load(&self) -> Result<HashMap<String, OutboxEntry>> {
+        let content = match tokio::fs::read(&self.path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(BotError::Io(e)),
+        };
+        serde_json::from_slice(&content).map_err(|e| {
+            BotError::Validation(format!("Invalid outbox file {}: {e}", self.path.display()))
+        })
+    }
+
+    async fn store(&self, entries: &HashMap<String, OutboxEntry>) -> Result<()> {
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        {
+            use tokio::io::AsyncWriteExt;
+            let mut file = tokio::fs::File::create(&tmp).await?;
+            file.write_all(&serde_json::to_vec(entries)?).await?;
+            file.sync_all().await?;
+        }
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl OutboxStore for FileOutboxStore {
+    async fn insert(&self, entry: &OutboxEntry) -> Result<bool> {
+        let _guard = self.lock.lock().await;
+        let mut entries = self.load().await?;
+        if entries.contains_key(&entry.key) {
+            return Ok(false);
+        }
+        entries.insert(entry.key.clone(), entry.clone());
+        self.store(&entries).await?;
+        Ok(true)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<OutboxEntry>> {
+        let _guard = self.lock.lock().await;
+        Ok(self.load().await?.remove(key))
+    }
+
+    async fn update(&self, entry: &OutboxEntry) -> Result<()> {
+        let _guard = self.lock.lock().await;
+        let mut entries = self.load().await?;
+        entries.insert(entry.key.clone(), entry.clone());
+        self.store(&entries).await
+    }
+
+    async fn due(&self, now: u64, limit: usize) -> Result<Vec<OutboxEntry>> {
+        let _guard = self.lock.lock().await;
+        Ok(due_entries(self.load().await?.values(), now, limit))
+    }
+
+    async fn prune(&self, before: u64) -> Result<usize> {
+        let _guard = self.lock.lock().await;
+        let mut entries = self.load().await?;
+        let removed = prune_entries(&mut entries, before);
+        if removed > 0 {
+            self.store(&entries).await?;
+        }
+        Ok(removed)
+    }
+}
+
+/// PostgreSQL outbox store
+///
+/// Entries are stored in the `outbox_entries` table under a name,
+/// so several bots can share the table. Call [`PostgresOutboxStore::initialize`] to create it.
+///
+/// Several workers can deliver from one outbox: due entries are claimed with
+/// `FOR UPDATE SKIP LOCKED` until they are updated, and a claim of a worker that stopped
+/// expires after the claim timeout of 10 minutes.
+#[cfg(feature = "storage")]
+#[derive(Debug, Clone)]
+pub struct PostgresOutboxStore {
+    pool: sqlx::PgPool,
+    name: String,
+    claim_timeout: Duration,
+}
+
+#[cfg(feature = "storage")]
+impl PostgresOutboxStore {
+    /// Create store on top of an existing pool,
+    /// e.g. [`crate::storage::StorageManager::pool`]
+    pub fn new(pool: sqlx::PgPool, name: impl Into<String>) -> Self {
+        Self {
+            pool,
+            name: name.into(),
+            claim_timeout: CLAIM_TIMEOUT,
+        }
+    }
+
+    /// Set time after which entries claimed by a worker can be taken by another one,
+    /// it should exceed the delivery time of a whole batch
+    pub fn with_claim_timeout(mut self, claim_timeout: Duration) -> Self {
+        self.claim_timeout = claim_timeout;
+        self
+    }
+
+    /// Create `outbox_entries` table if it does not exist
+    ///
+    /// The table isn't created by the storage migrations, only by this method.
+    ///
+    /// ## Errors
+    /// - `BotError::System` - database error
+    pub async fn initialize(&self) -> Result<()> {
+        sqlx::raw_sql(include_str!("../storage/schema/outbox_entries.sql"))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage")]
+fn status_name(status: OutboxStatus) -> &'static str {
+    match status {
+        OutboxStatus::Pending => "pending",
+        OutboxStatus::Delivered => "delivered",
+        OutboxStatus::Failed => "failed",
+        OutboxStatus::Expired => "expired",
+    }
+}
+
+#[cfg(feature = "storage")]
+#[async_trait]
+impl OutboxStore for PostgresOutboxStore {
+    async fn insert(&self, entry: &OutboxEntry) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO outbox_entries
+                (name, key, status, priority, created_at, next_attempt_at, entry)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (name, key) DO NOTHING
+            "#,
+        )
+        .bind(&self.name)
+        .bind(&entry.key)
+        .bind(status_name(entry.status))
+        .bind(entry.priority as i16)
+        .bind(i64::try_from(entry.created_at).unwrap_or(i64::MAX))
+        .bind(i64::try_from(entry.next_attempt_at).unwrap_or(i64::MAX))
+        .bind(sqlx::types::Json(entry))
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<OutboxEntry>> {
+        let row: Option<(sqlx::types::Json<OutboxEntry>,)> =
+            sqlx::query_as("SELECT entry FROM outbox_entries WHERE name = $1 AND key = $2")
+                .bind(&self.name)
+                .bind(key)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(row.map(|(entry,)| entry.0))
+    }
+
+    async fn update(&self, entry: &OutboxEntry) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE outbox_entries
+            SET status = $3, next_attempt_at = $4, entry = $5, claimed_until = 0
+            WHERE name = $1 AND key = $2
+            "#,
+        )
+        .bind(&self.name)
+        .bind(&entry.key)
+        .bind(status_name(entry.status))
+        .bind(i64::try_from(entry.next_attempt_at).unwrap_or(i64::MAX))
+        .bind(sqlx::types::Json(entry))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn due(&self, now: u64, limit: usize) -> Result<Vec<OutboxEntry>> {
+        let claimed_until = now.saturating_add(self.claim_timeout.as_millis() as u64);
+        let rows: Vec<(sqlx::types::Json<OutboxEntry>,)> = sqlx::query_as(
+            r#"
+            UPDATE outbox_entries
+            SET claimed_until = $3
+            WHERE (name, key) IN (
+                SELECT name, key FROM outbox_entries
+                WHERE name = $1 AND status = 'pending' AND next_attempt_at <= $2
+                    AND claimed_until <= $2
+                ORDER BY priority DESC, created_at
+                LIMIT $4
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING entry
+            "#,
+        )
+        .bind(&self.name)
+        .bind(i64::try_from(now).unwrap_or(i64::MAX))
+        .bind(i64::try_from(claimed_until).unwrap_or(i64::MAX))
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await?;
+        // RETURNING keeps no order
+        let entries: Vec<_> = rows.into_iter().map(|(entry,)| entry.0).collect();
+        Ok(due_entries(entries.iter(), now, limit))
+    }
+
+    async fn prune(&self, before: u64) -> Result<usize> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM outbox_entries
+            WHERE name = $1 AND status <> 'pending' AND created_at < $2
+            "#,
+        )
+        .bind(&self.name)
+        .bind(i64::try_from(before).unwrap_or(i64::MAX))
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() as usize)
+    }
+}
+
+/// Outbox store with the delivery settings
+#[derive(Clone)]
+pub struct Outbox {
+    store: Arc<dyn OutboxStore>,
+    events: broadcast::Sender<OutboxEntry>,
+    max_attempts: u32,
+    retry_delay: Duration,
+    max_retry_delay: Duration,
+    poll_interval: Duration,
+}
+
+impl fmt::Debug for Outbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Outbox")
+            .field("store", &"<store>")
+            .field("max_attempts", &self.max_attempts)
+            .field("retry_delay", &self.retry_delay)
+            .field("max_retry_delay", &self.max_retry_delay)
+            .field("poll_interval", &self.poll_interval)
+            .finish()
+    }
+}
+
+impl Outbox {
+    /// Create outbox with 10 attempts per entry, retried after 1s doubled up to 5 minutes,
+    /// checking for due entries every second
+    pub fn new(store: impl OutboxStore + 'static) -> Self {
+        Self::from_arc(Arc::new(store))
+    }
+
+    /// Create outbox with a shared store
+    pub fn from_arc(store: Arc<dyn OutboxStore>) -> Self {
+        Self {
+            store,
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+            max_attempts: 10,
+            retry_delay: Duration::from_secs(1),
+            max_retry_delay: Duration::from_secs(300),
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Set delivery attempts per entry
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set delay after the first failed attempt, doubled after every next one up to `max_delay`
+    pub fn with_retry_delay(mut self, delay: Duration, max_delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self.max_retry_delay = max_delay.max(delay);
+        self
+    }
+
+    /// Set interval between checks for due entries in [`run`](Self::run)
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Outbox store
+    pub fn store(&self) -> &Arc<dyn OutboxStore> {
+        &self.store
+    }
+
+    /// Save the request for delivery
+    ///
+    /// Returns `false` if an entry with the same idempotency key is already in the outbox.
+    ///
+    /// ## Errors
+    /// - store errors
+    pub async fn enqueue(&self, message: OutboxMessage) -> Result<bool> {
+        let entry = message.into_entry(now_millis());
+        let inserted = self.store.insert(&entry).await?;
+        if inserted {
+            debug!("Enqueued {} as {}", entry.method, entry.key);
+        } else {
+            debug!("Outbox entry {} already exists", entry.key);
+        }
+        Ok(inserted)
+    }
+
+    /// Get entry with its delivery state by the idempotency key
+    ///
+    /// ## Errors
+    /// - store errors
+    pub async fn get(&self, key: &str) -> Result<Option<OutboxEntry>> {
+        self.store.get(key).await
+    }
+
+    /// Receive entries after every delivery attempt of this outbox
+    pub fn subscribe(&self) -> broadcast::Receiver<OutboxEntry> {
+        self.events.subscribe()
+    }
+
+    /// Remove finished entries enqueued more than `age` ago, returns the number of removed entries
+    ///
+    /// ## Errors
+    /// - store errors
+    pub async fn prune(&self, age: Duration) -> Result<usize> {
+        self.store
+            .prune(now_millis().saturating_sub(age.as_millis() as u64))
+            .await
+    }
+
+    /// Deliver entries due now, returns the number of delivery attempts
+    ///
+    /// ## Errors
+    /// - store errors
+    pub async fn deliver_due(&self, bot: &Bot) -> Result<usize> {
+        let due = self.store.due(now_millis(), DELIVERY_BATCH).await?;
+        let count = due.len();
+        for entry in due {
+            let entry = self.deliver(bot, entry).await;
+            self.store.update(&entry).await?;
+            let _ = self.events.send(entry);
+        }
+        Ok(count)
+    }
+
+    /// Deliver due entries until the process is stopped
+    ///
+    /// Store errors are logged and the delivery is retried after the poll interval.
+    pub async fn run(&self, bot: &Bot) -> Result<()> {
+        self.run_with_shutdown(bot, CancellationToken::new()).await
+    }
+
+    /// Deliver due entries until the token is cancelled,
+    /// an attempt in progress is finished before returning
+    pub async fn run_with_shutdown(&self, bot: &Bot, shutdown: CancellationToken) -> Result<()> {
+        while !shutdown.is_cancelled() {
+            let delivered = match self.deliver_due(bot).await {
+                Ok(delivered) => delivered,
+                Err(e) => {
+                    error!("Outbox delivery failed: {}", e);
+                    0
+                }
+            };
+            if delivered == 0 {
+                tokio::select! {
+                    _ = shutdown.cancelled() => {}
+                    _ = tokio::time::sleep(self.poll_interval) => {}
+                }
+            }
+        }
+        debug!("Outbox worker stopped");
+        Ok(())
+    }
+
+    /// Make one delivery attempt and update the entry with the result
+    async fn deliver(&self, bot: &Bot, mut entry: OutboxEntry) -> OutboxEntry {
+        let now = now_millis();
+        if entry.deadline.is_some_and(|deadline| deadline <= now) {
+            warn!("Outbox entry {} expired", entry.key);
+            entry.status = OutboxStatus::Expired;
+            return entry;
+        }
+
+        entry.attempts += 1;
+        let chat_id = entry.chat_id.clone().map(ChatId::from);
+        match bot
+            .send_raw_request(&entry.method, &entry.query, chat_id.as_ref())
+            .await
+        {
+            Ok(response) => {
+                debug!("Outbox entry {} delivered", entry.key);
+                entry.status = OutboxStatus::Delivered;
+                entry.response = Some(response);
+                entry.last_error = None;
+            }
+            Err(e) => {
+                entry.last_error = Some(e.to_string());
+                if e.is_permanent() || entry.attempts >= self.max_attempts {
+                    error!(
+                        "Outbox entry {} failed after {} attempts: {}",
+                        entry.key, entry.attempts, e
+                    );
+                    entry.status = OutboxStatus::Failed;
+                } else {
+                    let delay = e
+                        .retry_after()
+                        .unwrap_or_else(|| self.retry_delay_for(entry.attempts));
+                    warn!(
+                        "Outbox entry {} attempt {} failed, retrying in {:?}: {}",
+                        entry.key, entry.attempts, delay, e
+                    );
+                    entry.next_attempt_at = now.saturating_add(delay.as_millis() as u64);
+                }
+            }
+        }
+        entry
+    }
+
+    /// Delay after the failed attempt
+    fn retry_delay_for(&self, attempts: u32) -> Duration {
+        self.retry_delay
+            .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+            .min(self.max_retry_delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    fn entry(key: &str, priority: OutboxPriority, created_at: u64) -> OutboxEntry {
+        let request = RequestMessagesSendText::new(ChatId::from("chat"));
+        let mut entry = OutboxMessage::new(key, &request)
+            .unwrap()
+            .with_priority(priority)
+            .into_entry(created_at);
+        entry.next_attempt_at = created_at;
+        entry
+    }
+
+    #[test]
+    fn test_message_serializes_request() {
+        let request =
+            RequestMessagesSendText::new(ChatId::from("chat")).with_text("hello".to_string());
+        let entry = OutboxMessage::new("key", &request)
+            .unwrap()
+            .with_deadline(Duration::from_secs(1))
+            .into_entry(1000);
+        assert_eq!(entry.method, "messages/sendText");
+        assert_eq!(entry.chat_id.as_deref(), Some("chat"));
+        assert!(entry.query.contains("text=hello"));
+        assert_eq!(entry.deadline, Some(2000));
+        assert_eq!(entry.status, OutboxStatus::Pending);
+    }
+
+    #[test]
+    fn test_message_rejects_uploads() {
+        let request = RequestMessagesSendFile::new((
+            ChatId::from("chat"),
+            MultipartName::FilePath("file.txt".to_string()),
+        ));
+        assert!(matches!(
+            OutboxMessage::new("key", &request),
+            Err(BotError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_due_entries_order() {
+        let mut later = entry("later", OutboxPriority::Normal, 10);
+        later.next_attempt_at = 100;
+        let mut delivered = entry("delivered", OutboxPriority::Critical, 1);
+        delivered.status = OutboxStatus::Delivered;
+        let entries = [
+            entry("normal-old", OutboxPriority::Normal, 1),
+            entry("normal-new", OutboxPriority::Normal, 2),
+            entry("high", OutboxPriority::High, 3),
+            later,
+            delivered,
+        ];
+        let keys: Vec<_> = due_entries(entries.iter(), 50, 10)
+            .into_iter()
+            .map(|entry| entry.key)
+            .collect();
+        assert_eq!(keys, ["high", "normal-old", "normal-new"]);
+        assert_eq!(due_entries(entries.iter(), 50, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_retry_delay_for() {
+        let outbox = Outbox::new(MemoryOutboxStore::new())
+            .with_retry_delay(Duration::from_secs(1), Duration::from_secs(5));
+        assert_eq!(outbox.retry_delay_for(1), Duration::from_secs(1));
+        assert_eq!(outbox.retry_delay_for(3), Duration::from_secs(4));
+        assert_eq!(outbox.retry_delay_for(10), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_idempotency_and_prune() {
+        let store = MemoryOutboxStore::new();
+        assert!(
+            store
+                .insert(&entry("a", OutboxPriority::Low, 1))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !store
+                .insert(&entry("a", OutboxPriority::High, 2))
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            store.get("a").await.unwrap().unwrap().priority,
+            OutboxPriority::Low
+        );
+
+        let mut delivered = entry("b", OutboxPriority::Low, 1);
+        delivered.status = OutboxStatus::Delivered;
+        store.insert(&delivered).await.unwrap();
+        assert_eq!(store.prune(10).await.unwrap(), 1);
+        assert!(store.get("a").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_file_store_persists_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("outbox.json");
+        let store = FileOutboxStore::new(&path);
+        assert!(
+            store
+                .insert(&entry("a", OutboxPriority::Low, 1))
+                .await
+                .unwrap()
+        );
+        let mut updated = entry("a", OutboxPriority::Low, 1);
+        updated.attempts = 2;
+        store.update(&updated).await.unwrap();
+
+        let reopened = FileOutboxStore::new(&path);
+        assert_eq!(reopened.get("a").await.unwrap(), Some(updated));
+        assert_eq!(reopened.due(1, 10).await.unwrap().len(), 1);
+        assert!(
+            !reopened
+                .insert(&entry("a", OutboxPriority::Low, 1))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_entry_is_not_sent() {
+        let bot = Bot::builder()
+            .token("token")
+            .api_url("http://127.0.0.1:9")
+            .build()
+            .unwrap();
+        let outbox = Outbox::new(MemoryOutboxStore::new());
+        let mut events = outbox.subscribe();
+        let mut expired = entry("expired", OutboxPriority::Normal, 1);
+        expired.deadline = Some(1);
+        outbox.store().insert(&expired).await.unwrap();
+
+        assert_eq!(outbox.deliver_due(&bot).await.unwrap(), 1);
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.status, OutboxStatus::Expired);
+        assert_eq!(event.attempts, 0);
+    }
+
+    #[cfg(feature = "ratelimit")]
+    #[tokio::test]
+    async fn test_exhausted_rate_limit_is_retried() {
+        use crate::config::types::RateLimit;
+
+        let bot = Bot::builder()
+            .token("token")
+            .api_url("http://127.0.0.1:9")
+            .rate_limit(RateLimit {
+                limit: 1,
+                duration: 3600,
+                retry_attempts: 1,
+                retry_delay: 1,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let chat_id = ChatId::from("chat");
+        let limiter = bot.rate_limiter().lock().await.clone();
+        assert!(
+            limiter
+                .check_request("messages/sendText", Some(&chat_id))
+                .await
+        );
+
+        let outbox = Outbox::new(MemoryOutboxStore::new());
+        outbox
+            .store()
+            .insert(&entry("limited", OutboxPriority::Normal, 1))
+            .await
+            .unwrap();
+        assert_eq!(outbox.deliver_due(&bot).await.unwrap(), 1);
+        let entry = outbox.get("limited").await.unwrap().unwrap();
+        assert_eq!((entry.status, entry.attempts), (OutboxStatus::Pending, 1));
+        assert!(entry.next_attempt_at > 1);
+    }
+}
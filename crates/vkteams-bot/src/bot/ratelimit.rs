@@ -1,10 +1,14 @@
+use crate::bot::metrics::metrics;
+use crate::bot::ratelimit_backend::RateLimitBackend;
 use crate::config::CONFIG;
+use crate::config::types::{RateLimit, RateLimitPolicy};
 use crate::prelude::ChatId;
 use async_trait::async_trait;
 use crossbeam_utils::CachePadded;
 use dashmap::DashMap;
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::time::{Duration, sleep};
 use tracing::{debug, info, warn};
@@ -27,16 +31,45 @@ pub struct LockFreeTokenBucket {
     last_refill: CachePadded<Arc<AtomicU64>>,
     /// Bucket capacity (read-only after creation)
     capacity: u32,
-    /// Tokens refilled per second (read-only after creation)
-    refill_rate: u32,
+    /// Tokens refilled per 1000 seconds (read-only after creation)
+    refill_rate_milli: u64,
     /// Statistics packed: High 32 bits = rate_limited_count, Low 32 bits = allowed_count
     /// CachePadded prevents false sharing with other atomic fields
     stats: CachePadded<Arc<AtomicU64>>,
+    /// Refill rate factor in permille set by the last [`throttle`](Self::throttle)
+    penalty_factor: AtomicU32,
+    /// End of the last throttle in microseconds since UNIX epoch, `0` if never throttled
+    penalty_until: AtomicU64,
 }
 
+/// Full refill rate factor in permille
+const FULL_RATE: u32 = 1000;
+/// Lowest refill rate factor in permille after repeated throttling
+const MIN_RATE: u32 = 50;
+/// Time to recover from zero to the full refill rate after a throttle
+const RATE_RECOVERY: Duration = Duration::from_secs(60);
+
 impl LockFreeTokenBucket {
-    /// Create a new lock-free token bucket
+    /// Create a new lock-free token bucket refilling `refill_rate` tokens per second
     pub fn new(capacity: u32, refill_rate: u32) -> Self {
+        Self::with_refill_milli(capacity, refill_rate as u64 * 1000)
+    }
+
+    /// Create a new lock-free token bucket refilling its whole capacity over the window
+    pub fn with_window(capacity: u32, window: Duration) -> Self {
+        let window_millis = window.as_millis().max(1) as u64;
+        Self::with_refill_milli(capacity, capacity as u64 * 1_000_000 / window_millis)
+    }
+
+    /// Create a new empty token bucket refilled only with [`add_tokens`](Self::add_tokens),
+    /// e.g. with tokens leased from a [`RateLimitBackend`]
+    pub fn leased(capacity: u32) -> Self {
+        let bucket = Self::with_refill_milli(capacity, 0);
+        bucket.state.store(0, Ordering::Release);
+        bucket
+    }
+
+    fn with_refill_milli(capacity: u32, refill_rate_milli: u64) -> Self {
         let now_micros = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -46,8 +79,10 @@ impl LockFreeTokenBucket {
             state: CachePadded::new(Arc::new(AtomicU64::new(Self::pack_state(0, capacity)))),
             last_refill: CachePadded::new(Arc::new(AtomicU64::new(now_micros))),
             capacity,
-            refill_rate,
+            refill_rate_milli,
             stats: CachePadded::new(Arc::new(AtomicU64::new(0))),
+            penalty_factor: AtomicU32::new(FULL_RATE),
+            penalty_until: AtomicU64::new(0),
         }
     }
 
@@ -92,9 +127,11 @@ impl LockFreeTokenBucket {
             // Calculate tokens to add based on time passed
             let last_refill = self.last_refill.load(Ordering::Acquire);
             let time_passed = now.saturating_sub(last_refill);
-            let tokens_to_add = ((time_passed * self.refill_rate as u64) / 1_000_000)
-                .min((self.capacity - available_tokens) as u64)
-                as u32;
+            let rate_factor = self.rate_factor_at(now) as u64;
+            let tokens_to_add =
+                ((time_passed as u128 * self.refill_rate_milli as u128 * rate_factor as u128)
+                    / (1_000_000_000 * FULL_RATE as u128))
+                    .min((self.capacity - available_tokens) as u128) as u32;
 
             let new_available = (available_tokens + tokens_to_add).min(self.capacity);
 
@@ -145,6 +182,95 @@ impl LockFreeTokenBucket {
         available_tokens
     }
 
+    /// Bucket capacity
+    #[inline]
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Add tokens up to the capacity
+    pub fn add_tokens(&self, tokens: u32) {
+        let _ = self
+            .state
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |state| {
+                let (total_requests, available_tokens) = Self::unpack_state(state);
+                Some(Self::pack_state(
+                    total_requests,
+                    available_tokens.saturating_add(tokens).min(self.capacity),
+                ))
+            });
+    }
+
+    /// Give back a token taken by [`try_consume`](Self::try_consume) for a request
+    /// that was not sent after all
+    pub fn refund(&self) {
+        let _ = self
+            .state
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |state| {
+                let (total_requests, available_tokens) = Self::unpack_state(state);
+                Some(Self::pack_state(
+                    total_requests.saturating_sub(1),
+                    (available_tokens + 1).min(self.capacity),
+                ))
+            });
+        let _ = self
+            .stats
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |stats| {
+                let (rate_limited, allowed) = Self::unpack_state(stats);
+                Some(Self::pack_stats(rate_limited, allowed.saturating_sub(1)))
+            });
+    }
+
+    /// Slow down after the server rejected a request with too many requests
+    ///
+    /// Available tokens are dropped, no tokens are refilled until `retry_after` passes,
+    /// and the refill rate is halved. The rate then recovers linearly to the full rate.
+    pub fn throttle(&self, retry_after: Option<Duration>) {
+        let now = Self::current_time_micros();
+        let until = now + retry_after.unwrap_or_default().as_micros() as u64;
+        let factor = (self.rate_factor_at(now) / 2).max(MIN_RATE);
+
+        self.penalty_factor.store(factor, Ordering::Relaxed);
+        self.penalty_until.fetch_max(until, Ordering::AcqRel);
+        // Refill starts after the delay
+        self.last_refill.fetch_max(until, Ordering::AcqRel);
+        let _ = self
+            .state
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |state| {
+                let (total_requests, _) = Self::unpack_state(state);
+                Some(Self::pack_state(total_requests, 0))
+            });
+        debug!(
+            "Token bucket throttled for {:?}, refill rate {}‰",
+            retry_after, factor
+        );
+    }
+
+    /// Current refill rate relative to the configured one, from `0.05` to `1.0`
+    pub fn rate_factor(&self) -> f64 {
+        self.rate_factor_at(Self::current_time_micros()) as f64 / FULL_RATE as f64
+    }
+
+    /// Refill rate factor in permille at the time
+    #[inline]
+    fn rate_factor_at(&self, now: u64) -> u32 {
+        let until = self.penalty_until.load(Ordering::Acquire);
+        if until == 0 {
+            return FULL_RATE;
+        }
+        let recovered =
+            now.saturating_sub(until) * FULL_RATE as u64 / RATE_RECOVERY.as_micros() as u64;
+        (self.penalty_factor.load(Ordering::Relaxed) as u64 + recovered).min(FULL_RATE as u64)
+            as u32
+    }
+
+    /// Time left until the refill starts again after [`throttle`](Self::throttle)
+    pub fn throttled_for(&self) -> Duration {
+        let now = Self::current_time_micros();
+        let last_refill = self.last_refill.load(Ordering::Acquire);
+        Duration::from_micros(last_refill.saturating_sub(now))
+    }
+
     /// Get lock-free statistics
     pub fn get_stats(&self) -> (u32, u32, u32) {
         let state = self.state.load(Ordering::Relaxed);
@@ -234,6 +360,7 @@ impl LockFreeGlobalStats {
         } else {
             self.rate_limited_requests.fetch_add(1, Ordering::Relaxed);
         }
+        metrics().record_rate_limit(was_allowed);
     }
 
     /// Update active bucket count
@@ -250,6 +377,8 @@ impl LockFreeGlobalStats {
             allowed_requests: self.allowed_requests.load(Ordering::Relaxed),
             last_access: SystemTime::now(),
             max_tokens_used: 0, // Not tracked in global stats
+            available_tokens: 0,
+            capacity: 0,
         }
     }
 }
@@ -267,6 +396,46 @@ pub struct BucketStats {
     pub last_access: SystemTime,
     /// Maximum tokens used in a single window
     pub max_tokens_used: u32,
+    /// Tokens left in the bucket, `0` for the global statistics
+    pub available_tokens: u32,
+    /// Bucket capacity, `0` for the global statistics
+    pub capacity: u32,
+}
+
+impl From<&LockFreeTokenBucket> for BucketStats {
+    fn from(bucket: &LockFreeTokenBucket) -> Self {
+        let (total, allowed, rate_limited) = bucket.get_stats();
+        Self {
+            total_requests: total as u64,
+            rate_limited_requests: rate_limited as u64,
+            allowed_requests: allowed as u64,
+            last_access: SystemTime::now(),
+            max_tokens_used: 0, // Not tracked per bucket
+            available_tokens: bucket.available_tokens(),
+            capacity: bucket.capacity(),
+        }
+    }
+}
+
+/// Layer of the hierarchical rate limit a bucket belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RateLimitLayer {
+    /// Bot-wide ceiling
+    Global,
+    /// Budget of an API method
+    Method(String),
+    /// Budget of a chat
+    Chat(ChatId),
+}
+
+impl std::fmt::Display for RateLimitLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Global => write!(f, "global"),
+            Self::Method(method) => write!(f, "method {method}"),
+            Self::Chat(chat_id) => write!(f, "chat {chat_id}"),
+        }
+    }
 }
 
 impl Default for BucketStats {
@@ -277,12 +446,21 @@ impl Default for BucketStats {
             allowed_requests: 0,
             last_access: SystemTime::now(),
             max_tokens_used: 0,
+            available_tokens: 0,
+            capacity: 0,
         }
     }
 }
 
 /// Optimized RateLimiter with lock-free operations
 ///
+/// Requests are limited by up to three layers of token buckets: the chat
+/// (with per-chat overrides), the API method and a bot-wide ceiling.
+/// A request takes a token from every layer it belongs to or from none of them.
+///
+/// With a [`RateLimitBackend`] the buckets are refilled with tokens leased from
+/// buckets shared between bot instances, see [`ratelimit_backend`](crate::bot::ratelimit_backend).
+///
 /// Performance improvements:
 /// - Lock-free token buckets using atomic operations
 /// - Proactive memory management for inactive buckets
@@ -292,10 +470,22 @@ impl Default for BucketStats {
 pub struct RateLimiter {
     /// Map of chat_id to lock-free token bucket
     chat_buckets: Arc<DashMap<ChatId, Arc<LockFreeTokenBucket>>>,
+    /// Buckets of the API methods with a configured budget
+    method_buckets: Arc<BTreeMap<String, Arc<LockFreeTokenBucket>>>,
+    /// Bot-wide bucket, if configured
+    global_bucket: Option<Arc<LockFreeTokenBucket>>,
+    /// Shared buckets the local ones lease tokens from
+    backend: Option<Arc<dyn RateLimitBackend>>,
+    /// Time-refilled buckets by layer, used instead of the backend while it is unavailable
+    fallback_buckets: Arc<DashMap<String, Arc<LockFreeTokenBucket>>>,
+    /// Backend failed on the last lease
+    backend_down: Arc<AtomicBool>,
     /// Lock-free global statistics
     global_stats: Arc<LockFreeGlobalStats>,
     /// Proactive cleanup manager
     cleanup_manager: Arc<ProactiveCleanup>,
+    /// Rate limit settings
+    config: Arc<RateLimit>,
 }
 
 impl Default for RateLimiter {
@@ -304,25 +494,78 @@ impl Default for RateLimiter {
     }
 }
 
+/// Token bucket for the policy, refilled by time or only with leased tokens
+fn policy_bucket(policy: RateLimitPolicy, leased: bool) -> Arc<LockFreeTokenBucket> {
+    let capacity = u32::try_from(policy.limit)
+        .unwrap_or_else(|_| panic!("Rate limit capacity too large: {}", policy.limit));
+    if leased {
+        return Arc::new(LockFreeTokenBucket::leased(capacity));
+    }
+    Arc::new(LockFreeTokenBucket::with_window(
+        capacity,
+        Duration::from_secs(policy.duration.max(1)),
+    ))
+}
+
+/// Bucket of a rate limit layer with the policy it was created from
+struct LayerBucket {
+    layer: RateLimitLayer,
+    policy: RateLimitPolicy,
+    bucket: Arc<LockFreeTokenBucket>,
+}
+
 impl RateLimiter {
     /// Create a new high-performance RateLimiter with lock-free operations
+    /// using the settings from the global configuration
     #[tracing::instrument]
     pub fn new() -> Self {
+        Self::with_config(CONFIG.rate_limit.clone())
+    }
+
+    /// Create a new RateLimiter with the given settings
+    #[tracing::instrument]
+    pub fn with_config(cfg: RateLimit) -> Self {
+        Self::build(cfg, None)
+    }
+
+    /// Create a new RateLimiter sharing its budget with other bot instances through the backend
+    #[tracing::instrument]
+    pub fn with_backend(cfg: RateLimit, backend: Arc<dyn RateLimitBackend>) -> Self {
+        Self::build(cfg, Some(backend))
+    }
+
+    fn build(cfg: RateLimit, backend: Option<Arc<dyn RateLimitBackend>>) -> Self {
         debug!("Creating lock-free high-performance RateLimiter");
-        let cfg = &CONFIG.rate_limit;
+        let leased = backend.is_some();
         let capacity = u32::try_from(cfg.init_bucket)
             .unwrap_or_else(|_| panic!("Rate limit capacity too large: {}", cfg.init_bucket));
 
         Self {
             chat_buckets: Arc::new(DashMap::with_capacity(capacity as usize)),
+            method_buckets: Arc::new(
+                cfg.methods
+                    .iter()
+                    .map(|(method, policy)| (method.clone(), policy_bucket(*policy, leased)))
+                    .collect(),
+            ),
+            global_bucket: cfg.global.map(|policy| policy_bucket(policy, leased)),
+            backend,
+            fallback_buckets: Arc::new(DashMap::new()),
+            backend_down: Arc::new(AtomicBool::new(false)),
             global_stats: Arc::new(LockFreeGlobalStats::new()),
             cleanup_manager: Arc::new(ProactiveCleanup::new(
                 cfg.init_bucket * std::mem::size_of::<LockFreeTokenBucket>(),
                 Duration::from_secs(cfg.cleanup_interval),
             )),
+            config: Arc::new(cfg),
         }
     }
 
+    /// Rate limit settings
+    pub fn config(&self) -> &RateLimit {
+        &self.config
+    }
+
     /// Get global rate limit statistics (lock-free)
     pub async fn get_global_stats(&self) -> BucketStats {
         self.global_stats.get_snapshot()
@@ -330,18 +573,43 @@ impl RateLimiter {
 
     /// Get statistics for a specific chat_id (lock-free)
     pub async fn get_chat_stats(&self, chat_id: &ChatId) -> Option<BucketStats> {
-        if let Some(bucket) = self.chat_buckets.get(chat_id) {
-            let (total, allowed, rate_limited) = bucket.get_stats();
-            Some(BucketStats {
-                total_requests: total as u64,
-                rate_limited_requests: rate_limited as u64,
-                allowed_requests: allowed as u64,
-                last_access: SystemTime::now(),
-                max_tokens_used: 0, // Not tracked per bucket
+        self.chat_buckets
+            .get(chat_id)
+            .map(|bucket| BucketStats::from(bucket.as_ref()))
+    }
+
+    /// Get statistics for every bucket: the bot-wide one, the API methods and the active chats
+    pub async fn get_layer_stats(&self) -> Vec<(RateLimitLayer, BucketStats)> {
+        let global = self
+            .global_bucket
+            .iter()
+            .map(|bucket| (RateLimitLayer::Global, BucketStats::from(bucket.as_ref())));
+        let methods = self.method_buckets.iter().map(|(method, bucket)| {
+            (
+                RateLimitLayer::Method(method.clone()),
+                BucketStats::from(bucket.as_ref()),
+            )
+        });
+        let mut chats: Vec<_> = self
+            .chat_buckets
+            .iter()
+            .map(|entry| {
+                (
+                    entry.key().clone(),
+                    BucketStats::from(entry.value().as_ref()),
+                )
             })
-        } else {
-            None
-        }
+            .collect();
+        chats.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+
+        global
+            .chain(methods)
+            .chain(
+                chats
+                    .into_iter()
+                    .map(|(chat_id, stats)| (RateLimitLayer::Chat(chat_id), stats)),
+            )
+            .collect()
     }
 
     /// Proactive cleanup of inactive buckets (non-blocking)
@@ -354,8 +622,9 @@ impl RateLimiter {
                 .chat_buckets
                 .iter()
                 .filter_map(|entry| {
-                    // Simple heuristic: remove buckets with zero tokens that haven't been used
-                    if entry.value().available_tokens() == 0 {
+                    // Simple heuristic: remove buckets with zero tokens that haven't been used,
+                    // buckets slowed down by the server keep their penalty until it recovers
+                    if entry.value().available_tokens() == 0 && entry.value().rate_factor() >= 1.0 {
                         Some(entry.key().clone())
                     } else {
                         None
@@ -381,8 +650,145 @@ impl RateLimiter {
         }
     }
 
+    /// Get or create bucket with lock-free access
+    fn bucket(&self, chat_id: &ChatId) -> Arc<LockFreeTokenBucket> {
+        self.chat_buckets
+            .entry(chat_id.clone())
+            .or_insert_with(|| {
+                debug!(
+                    "Creating new lock-free token bucket for chat_id: {}",
+                    chat_id.0
+                );
+                policy_bucket(self.config.chat_policy(&chat_id.0), self.backend.is_some())
+            })
+            .clone()
+    }
+
+    /// Chat layer of a request
+    fn chat_layer(&self, chat_id: &ChatId) -> LayerBucket {
+        LayerBucket {
+            layer: RateLimitLayer::Chat(chat_id.clone()),
+            policy: self.config.chat_policy(&chat_id.0),
+            bucket: self.bucket(chat_id),
+        }
+    }
+
+    /// Layers a request is limited by, from the most specific one to the bot-wide one
+    fn layers(&self, method: &str, chat_id: Option<&ChatId>) -> Vec<LayerBucket> {
+        let method_layer = self
+            .method_buckets
+            .get(method)
+            .zip(self.config.methods.get(method))
+            .map(|(bucket, policy)| LayerBucket {
+                layer: RateLimitLayer::Method(method.to_string()),
+                policy: *policy,
+                bucket: bucket.clone(),
+            });
+        let global_layer =
+            self.global_bucket
+                .as_ref()
+                .zip(self.config.global)
+                .map(|(bucket, policy)| LayerBucket {
+                    layer: RateLimitLayer::Global,
+                    policy,
+                    bucket: bucket.clone(),
+                });
+        chat_id
+            .map(|chat_id| self.chat_layer(chat_id))
+            .into_iter()
+            .chain(method_layer)
+            .chain(global_layer)
+            .collect()
+    }
+
+    /// Lease tokens from the backend for the empty buckets
+    ///
+    /// A bucket throttled by the server is not refilled until the throttle ends.
+    /// While the backend is unavailable, tokens are taken from a local bucket refilled
+    /// by time at the policy rate, so requests are neither blocked nor unlimited.
+    async fn lease(&self, layers: &[LayerBucket]) {
+        let Some(backend) = &self.backend else {
+            return;
+        };
+        for LayerBucket {
+            layer,
+            policy,
+            bucket,
+        } in layers
+        {
+            if bucket.available_tokens() > 0 || !bucket.throttled_for().is_zero() {
+                continue;
+            }
+            let tokens = self.config.lease_size.clamp(1, bucket.capacity().max(1));
+            match backend.acquire(&layer.to_string(), *policy, tokens).await {
+                Ok(leased) => {
+                    if self.backend_down.swap(false, Ordering::AcqRel) {
+                        info!("Shared rate limit is available again");
+                        self.fallback_buckets.clear();
+                    }
+                    debug!("Leased {} of {} tokens for {}", leased, tokens, layer);
+                    bucket.add_tokens(leased);
+                }
+                Err(e) => {
+                    if self.backend_down.swap(true, Ordering::AcqRel) {
+                        debug!("Shared rate limit for {} is unavailable: {}", layer, e);
+                    } else {
+                        warn!(
+                            "Shared rate limit is unavailable, falling back to local limits: {}",
+                            e
+                        );
+                    }
+                    let fallback = self
+                        .fallback_buckets
+                        .entry(layer.to_string())
+                        .or_insert_with(|| policy_bucket(*policy, false))
+                        .clone();
+                    if fallback.try_consume() {
+                        bucket.add_tokens(1);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Take a token from every layer or from none of them
+    async fn consume_all(&self, layers: &[LayerBucket]) -> bool {
+        self.lease(layers).await;
+        for (consumed, layer) in layers.iter().enumerate() {
+            if !layer.bucket.try_consume() {
+                layers[..consumed]
+                    .iter()
+                    .for_each(|layer| layer.bucket.refund());
+                self.global_stats.record_request(false);
+                return false;
+            }
+        }
+        self.global_stats.record_request(true);
+        true
+    }
+
+    /// Slow down requests to the chat after the server answered with too many requests,
+    /// see [`LockFreeTokenBucket::throttle`]
+    pub fn throttle(&self, chat_id: &ChatId, retry_after: Option<Duration>) {
+        warn!(
+            "Server rate limit hit for chat_id {}, retry after {:?}",
+            chat_id.0, retry_after
+        );
+        self.bucket(chat_id).throttle(retry_after);
+    }
+
+    /// Current refill rate of the chat relative to the configured one,
+    /// `None` if the chat has no bucket yet
+    pub fn get_rate_factor(&self, chat_id: &ChatId) -> Option<f64> {
+        self.chat_buckets
+            .get(chat_id)
+            .map(|bucket| bucket.rate_factor())
+    }
+
     /// High-Performance Rate Limit Check (completely lock-free)
     ///
+    /// Checks the chat layer only, see [`check_request`](Self::check_request) for all layers.
+    ///
     /// Optimizations:
     /// - Lock-free bucket lookup using DashMap
     /// - Atomic token consumption without mutexes
@@ -394,29 +800,8 @@ impl RateLimiter {
         // Occasionally clean up inactive buckets (non-blocking)
         self.maybe_cleanup_inactive_buckets();
 
-        // Get or create bucket with lock-free access
-        let bucket = self
-            .chat_buckets
-            .entry(chat_id.clone())
-            .or_insert_with(|| {
-                debug!(
-                    "Creating new lock-free token bucket for chat_id: {}",
-                    chat_id.0
-                );
-                let cfg = &CONFIG.rate_limit;
-                let capacity = u32::try_from(cfg.limit)
-                    .unwrap_or_else(|_| panic!("Rate limit capacity too large: {}", cfg.limit));
-                let refill_rate = u32::try_from(capacity as u64 / cfg.duration.max(1)).unwrap_or(1);
-
-                Arc::new(LockFreeTokenBucket::new(capacity, refill_rate))
-            })
-            .clone();
-
         // Consume token (completely lock-free operation)
-        let result = bucket.try_consume();
-
-        // Update global stats (lock-free)
-        self.global_stats.record_request(result);
+        let result = self.consume_all(&[self.chat_layer(chat_id)]).await;
 
         debug!(
             "Rate limit check for chat_id {}: {}",
@@ -427,20 +812,64 @@ impl RateLimiter {
         result
     }
 
+    /// Check the chat, API method and bot-wide limits of a request
+    ///
+    /// Requests without a chat are limited by the method and bot-wide layers only.
+    #[tracing::instrument(skip(self))]
+    pub async fn check_request(&self, method: &str, chat_id: Option<&ChatId>) -> bool {
+        self.maybe_cleanup_inactive_buckets();
+
+        let result = self.consume_all(&self.layers(method, chat_id)).await;
+
+        debug!(
+            "Rate limit check for {} in chat_id {:?}: {}",
+            method,
+            chat_id.map(|chat_id| &chat_id.0),
+            if result { "allowed" } else { "limited" }
+        );
+
+        result
+    }
+
     /// Check and wait if request limit is exceeded with adaptive backoff
     #[tracing::instrument(skip(self))]
     pub async fn wait_if_needed(&mut self, chat_id: &ChatId) -> bool {
-        let cfg = &CONFIG.rate_limit;
+        self.wait_for(&format!("chat_id {}", chat_id.0), || {
+            vec![self.chat_layer(chat_id)]
+        })
+        .await
+    }
+
+    /// Wait until the chat, API method and bot-wide limits of a request allow it
+    /// with adaptive backoff
+    #[tracing::instrument(skip(self))]
+    pub async fn wait_for_request(&self, method: &str, chat_id: Option<&ChatId>) -> bool {
+        let target = match chat_id {
+            Some(chat_id) => format!("{method} in chat_id {}", chat_id.0),
+            None => method.to_string(),
+        };
+        self.wait_for(&target, || self.layers(method, chat_id))
+            .await
+    }
+
+    /// Retry taking a token from the layers until the retry attempts run out
+    async fn wait_for<F>(&self, target: &str, layers: F) -> bool
+    where
+        F: Fn() -> Vec<LayerBucket>,
+    {
+        let cfg = &self.config;
         let mut attempts = 0;
         let base_retry_delay = Duration::from_millis(cfg.retry_delay);
 
         while attempts < cfg.retry_attempts {
-            if self.check_rate_limit(chat_id).await {
+            self.maybe_cleanup_inactive_buckets();
+            let layers = layers();
+            if self.consume_all(&layers).await {
                 if attempts > 0 {
                     debug!(
-                        "Rate limit passed after {} attempts for chat_id: {}",
+                        "Rate limit passed after {} attempts for {}",
                         attempts + 1,
-                        chat_id.0
+                        target
                     );
                 }
                 return true;
@@ -454,6 +883,11 @@ impl RateLimiter {
             let retry_delay = Duration::from_millis(
                 (base_retry_delay.as_millis() as f64 * factor) as u64 + jitter_ms,
             );
+            // Wait out the server throttle instead of spending the attempts
+            let retry_delay = layers
+                .iter()
+                .map(|layer| layer.bucket.throttled_for())
+                .fold(retry_delay, Duration::max);
 
             debug!(
                 "Rate limit exceeded, attempt {}/{}, backing off for {:?}",
@@ -464,8 +898,8 @@ impl RateLimiter {
         }
 
         warn!(
-            "Rate limit exceeded after {} attempts for chat_id: {}",
-            attempts, chat_id.0
+            "Rate limit exceeded after {} attempts for {}",
+            attempts, target
         );
 
         false
@@ -487,15 +921,11 @@ impl RateLimiter {
             .map(|bucket| bucket.available_tokens())
     }
 
-    /// Get bucket capacity for a specific chat
+    /// Get bucket capacity for a specific chat, including per-chat overrides
     pub async fn get_bucket_capacity(&self, chat_id: &ChatId) -> Option<u32> {
-        // All buckets have the same capacity from config
-        if self.chat_buckets.contains_key(chat_id) {
-            let cfg = &CONFIG.rate_limit;
-            Some(u32::try_from(cfg.limit).unwrap_or(100))
-        } else {
-            None
-        }
+        self.chat_buckets
+            .get(chat_id)
+            .map(|bucket| bucket.capacity())
     }
 
     /// Get number of active buckets (atomic)
@@ -558,6 +988,293 @@ mod tests {
         LockFreeTokenBucket::new(capacity, refill_rate)
     }
 
+    #[tokio::test]
+    async fn test_rate_limiter_with_config() {
+        let limiter = RateLimiter::with_config(RateLimit {
+            limit: 2,
+            ..Default::default()
+        });
+        assert_eq!(limiter.config().limit, 2);
+
+        let chat_id = ChatId::from("config_chat");
+        assert!(limiter.check_rate_limit(&chat_id).await);
+        assert!(limiter.check_rate_limit(&chat_id).await);
+        assert!(!limiter.check_rate_limit(&chat_id).await);
+        assert_eq!(limiter.get_bucket_capacity(&chat_id).await, Some(2));
+    }
+
+    #[test]
+    fn test_bucket_throttle() {
+        let bucket = create_test_bucket(10, 1000);
+        assert_eq!(bucket.rate_factor(), 1.0);
+
+        bucket.throttle(Some(Duration::from_millis(200)));
+        assert_eq!(bucket.available_tokens(), 0);
+        assert!(!bucket.try_consume());
+        assert!(bucket.throttled_for() > Duration::from_millis(100));
+        assert_eq!(bucket.rate_factor(), 0.5);
+
+        // Repeated throttling slows down further, but not below the minimum rate
+        for _ in 0..10 {
+            bucket.throttle(None);
+        }
+        assert_eq!(bucket.rate_factor(), MIN_RATE as f64 / FULL_RATE as f64);
+        let (total, _, rate_limited) = bucket.get_stats();
+        assert_eq!((total, rate_limited), (0, 1));
+    }
+
+    #[test]
+    fn test_bucket_rate_recovers() {
+        let bucket = create_test_bucket(10, 1000);
+        bucket.throttle(None);
+        let until = bucket.penalty_until.load(Ordering::Relaxed);
+        let recovery = RATE_RECOVERY.as_micros() as u64;
+        assert_eq!(bucket.rate_factor_at(until), 500);
+        assert_eq!(bucket.rate_factor_at(until + recovery / 4), 750);
+        assert_eq!(bucket.rate_factor_at(until + recovery), FULL_RATE);
+    }
+
+    #[tokio::test]
+    async fn test_throttled_bucket_refills_after_delay() {
+        let bucket = create_test_bucket(10, 1000);
+        bucket.throttle(Some(Duration::from_millis(50)));
+        assert!(!bucket.try_consume());
+        sleep(Duration::from_millis(100)).await;
+        // Half rate: about 25 tokens per 50ms, capped by the capacity
+        assert!(bucket.try_consume());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_throttle() {
+        let limiter = RateLimiter::with_config(RateLimit {
+            limit: 10,
+            ..Default::default()
+        });
+        let chat_id = ChatId::from("throttled_chat");
+        assert_eq!(limiter.get_rate_factor(&chat_id), None);
+        limiter.throttle(&chat_id, Some(Duration::from_secs(5)));
+        assert_eq!(limiter.get_rate_factor(&chat_id), Some(0.5));
+        assert!(!limiter.check_rate_limit(&chat_id).await);
+        assert_eq!(limiter.get_available_tokens(&chat_id).await, Some(0));
+    }
+
+    fn layered_config() -> RateLimit {
+        RateLimit {
+            limit: 3,
+            retry_attempts: 1,
+            retry_delay: 1,
+            global: Some(RateLimitPolicy {
+                limit: 5,
+                duration: 60,
+            }),
+            methods: [(
+                "messages/sendFile".to_string(),
+                RateLimitPolicy {
+                    limit: 1,
+                    duration: 60,
+                },
+            )]
+            .into(),
+            chats: [(
+                "channel".to_string(),
+                RateLimitPolicy {
+                    limit: 1,
+                    duration: 60,
+                },
+            )]
+            .into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bucket_refund() {
+        let bucket = create_test_bucket(2, 1);
+        assert!(bucket.try_consume());
+        bucket.refund();
+        assert_eq!(bucket.available_tokens(), 2);
+        assert_eq!(bucket.get_stats(), (0, 0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_bucket_with_window_refills_fractional_rate() {
+        // One token per 50ms would round down to zero tokens per second
+        let bucket = LockFreeTokenBucket::with_window(1, Duration::from_millis(50));
+        assert!(bucket.try_consume());
+        assert!(!bucket.try_consume());
+        sleep(Duration::from_millis(80)).await;
+        assert!(bucket.try_consume());
+    }
+
+    #[tokio::test]
+    async fn test_check_request_method_layer() {
+        let limiter = RateLimiter::with_config(layered_config());
+        let chat_id = ChatId::from("dm");
+
+        assert!(
+            limiter
+                .check_request("messages/sendFile", Some(&chat_id))
+                .await
+        );
+        assert!(
+            !limiter
+                .check_request("messages/sendFile", Some(&chat_id))
+                .await
+        );
+        // The chat token taken before the method layer denied is given back
+        assert_eq!(limiter.get_available_tokens(&chat_id).await, Some(2));
+        assert!(
+            limiter
+                .check_request("messages/sendText", Some(&chat_id))
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_request_chat_override_and_global() {
+        let limiter = RateLimiter::with_config(layered_config());
+        let channel = ChatId::from("channel");
+
+        assert!(
+            limiter
+                .check_request("messages/sendText", Some(&channel))
+                .await
+        );
+        assert!(
+            !limiter
+                .check_request("messages/sendText", Some(&channel))
+                .await
+        );
+        assert_eq!(limiter.get_bucket_capacity(&channel).await, Some(1));
+
+        // Requests without a chat are limited by the bot-wide ceiling
+        for _ in 0..4 {
+            assert!(limiter.check_request("self/get", None).await);
+        }
+        assert!(!limiter.check_request("self/get", None).await);
+        assert!(!limiter.wait_for_request("self/get", None).await);
+    }
+
+    #[tokio::test]
+    async fn test_layer_stats() {
+        let limiter = RateLimiter::with_config(layered_config());
+        let chat_id = ChatId::from("dm");
+        limiter
+            .check_request("messages/sendFile", Some(&chat_id))
+            .await;
+        limiter
+            .check_request("messages/sendFile", Some(&chat_id))
+            .await;
+
+        let stats = limiter.get_layer_stats().await;
+        let layers: Vec<_> = stats.iter().map(|(layer, _)| layer.to_string()).collect();
+        assert_eq!(layers, ["global", "method messages/sendFile", "chat dm"]);
+        let (_, global) = &stats[0];
+        assert_eq!((global.allowed_requests, global.capacity), (1, 5));
+        let (_, method) = &stats[1];
+        assert_eq!(
+            (method.allowed_requests, method.rate_limited_requests),
+            (1, 1)
+        );
+        let (_, chat) = &stats[2];
+        assert_eq!((chat.allowed_requests, chat.available_tokens), (1, 2));
+    }
+
+    #[test]
+    fn test_leased_bucket() {
+        let bucket = LockFreeTokenBucket::leased(3);
+        assert_eq!(bucket.available_tokens(), 0);
+        assert!(!bucket.try_consume());
+        bucket.add_tokens(5);
+        assert_eq!(bucket.available_tokens(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_replicas_share_backend_budget() {
+        let backend: Arc<dyn RateLimitBackend> =
+            Arc::new(crate::bot::ratelimit_backend::MemoryRateLimitBackend::new());
+        let cfg = RateLimit {
+            limit: 4,
+            lease_size: 3,
+            ..Default::default()
+        };
+        let first = RateLimiter::with_backend(cfg.clone(), backend.clone());
+        let second = RateLimiter::with_backend(cfg, backend);
+        let chat_id = ChatId::from("shared_chat");
+
+        assert!(
+            first
+                .check_request("messages/sendText", Some(&chat_id))
+                .await
+        );
+        // The first replica leased 3 of 4 tokens, one is left for the second one
+        assert_eq!(first.get_available_tokens(&chat_id).await, Some(2));
+        assert!(
+            second
+                .check_request("messages/sendText", Some(&chat_id))
+                .await
+        );
+        assert!(
+            !second
+                .check_request("messages/sendText", Some(&chat_id))
+                .await
+        );
+        assert!(
+            first
+                .check_request("messages/sendText", Some(&chat_id))
+                .await
+        );
+        assert!(
+            first
+                .check_request("messages/sendText", Some(&chat_id))
+                .await
+        );
+        assert!(
+            !first
+                .check_request("messages/sendText", Some(&chat_id))
+                .await
+        );
+    }
+
+    #[derive(Debug)]
+    struct FailingBackend;
+
+    #[async_trait]
+    impl RateLimitBackend for FailingBackend {
+        async fn acquire(
+            &self,
+            _key: &str,
+            _policy: RateLimitPolicy,
+            _tokens: u32,
+        ) -> crate::error::Result<u32> {
+            Err(crate::error::BotError::System("down".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backend_outage_falls_back_to_local_limit() {
+        let cfg = RateLimit {
+            limit: 2,
+            duration: 60,
+            ..Default::default()
+        };
+        let limiter = RateLimiter::with_backend(cfg, Arc::new(FailingBackend));
+        let chat_id = ChatId::from("outage_chat");
+        for _ in 0..2 {
+            assert!(
+                limiter
+                    .check_request("messages/sendText", Some(&chat_id))
+                    .await
+            );
+        }
+        assert!(
+            !limiter
+                .check_request("messages/sendText", Some(&chat_id))
+                .await
+        );
+        assert!(limiter.backend_down.load(Ordering::Acquire));
+    }
+
     #[tokio::test]
     async fn test_bucket_created_with_full_capacity() {
         let bucket = create_test_bucket(10, 60);
@@ -0,0 +1,196 @@
+//! # Shared rate limit budget
+//! Token buckets shared by several bot instances using the same token.
+//!
+//! Every [`RateLimiter`] keeps its own buckets, so replicas of one bot together exceed
+//! the API quota. A limiter created with [`RateLimiter::with_backend`] keeps local buckets
+//! as a fast path, but they are not refilled by time: when a local bucket runs dry,
+//! a lease of up to [`RateLimit::lease_size`] tokens is taken from the shared bucket
+//! of the same layer in the backend. Requests are only sent with leased tokens,
+//! so all replicas share one budget per chat, API method and bot.
+//!
+//! Bigger leases mean fewer round-trips, but tokens leased by an idle replica
+//! are not available to the others.
+//!
+//! Backends implement [`RateLimitBackend`]:
+//! - [`MemoryRateLimitBackend`] - in-process buckets, e.g. for several bots in one process
+//! - `PostgresRateLimitBackend` - PostgreSQL buckets (`storage` feature)
+//!
+//! ```no_run
+//! use vkteams_bot::prelude::*;
+//! use vkteams_bot::bot::ratelimit_backend::PostgresRateLimitBackend;
+//! use vkteams_bot::storage::StorageManager;
+//! use vkteams_bot::config::CONFIG;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! let storage = StorageManager::new(&CONFIG.get_storage_config()).await?;
+//! let backend = PostgresRateLimitBackend::new(storage.pool().clone(), "my-bot");
+//! backend.initialize().await?;
+//! let bot = Bot::builder().rate_limit_backend(backend).build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`RateLimiter`]: crate::bot::ratelimit::RateLimiter
+//! [`RateLimiter::with_backend`]: crate::bot::ratelimit::RateLimiter::with_backend
+//! [`RateLimit::lease_size`]: crate::config::types::RateLimit::lease_size
+use crate::config::types::RateLimitPolicy;
+use crate::error::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Storage of the token buckets shared between bot instances
+#[async_trait]
+pub trait RateLimitBackend: Send + Sync + std::fmt::Debug {
+    /// Take up to `tokens` tokens from the bucket `key`, refilled according to the policy
+    ///
+    /// A new bucket starts full. Returns the number of tokens taken, `0` if the bucket is empty.
+    async fn acquire(&self, key: &str, policy: RateLimitPolicy, tokens: u32) -> Result<u32>;
+}
+
+/// Tokens in a bucket after `elapsed_secs` of refill, capped by the policy limit
+fn refill(tokens: f64, elapsed_secs: f64, policy: RateLimitPolicy) -> f64 {
+    let limit = policy.limit as f64;
+    (tokens + elapsed_secs.max(0.0) * limit / policy.duration.max(1) as f64).min(limit)
+}
+
+/// Whole tokens that can be taken from the available ones
+fn take(available: f64, tokens: u32) -> u32 {
+    (available.floor() as u32).min(tokens)
+}
+
+/// In-process shared buckets
+#[derive(Debug, Default)]
+pub struct MemoryRateLimitBackend {
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+impl MemoryRateLimitBackend {
+    /// Create new backend without buckets
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RateLimitBackend for MemoryRateLimitBackend {
+    async fn acquire(&self, key: &str, policy: RateLimitPolicy, tokens: u32) -> Result<u32> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let (available, refilled_at) = buckets
+            .entry(key.to_string())
+            .or_insert((policy.limit as f64, now));
+        let refilled = refill(
+            *available,
+            now.duration_since(*refilled_at).as_secs_f64(),
+            policy,
+        );
+        let taken = take(refilled, tokens);
+        *available = refilled - taken as f64;
+        *refilled_at = now;
+        Ok(taken)
+    }
+}
+
+/// PostgreSQL shared buckets
+///
+/// Buckets are stored in the `rate_limit_buckets` table under a namespace,
+/// so several bots can share the table. Every lease is a single upsert, which refills
+/// the bucket row under its lock with the database clock, so replica clocks don't matter.
+/// Call [`PostgresRateLimitBackend::initialize`] to create the table.
+#[cfg(feature = "storage")]
+#[derive(Debug, Clone)]
+pub struct PostgresRateLimitBackend {
+    pool: sqlx::PgPool,
+    namespace: String,
+}
+
+#[cfg(feature = "storage")]
+impl PostgresRateLimitBackend {
+    /// Create backend on top of an existing pool,
+    /// e.g. [`crate::storage::StorageManager::pool`]
+    pub fn new(pool: sqlx::PgPool, namespace: impl Into<String>) -> Self {
+        Self {
+            pool,
+            namespace: namespace.into(),
+        }
+    }
+
+    /// Create `rate_limit_buckets` table if it does not exist
+    ///
+    /// The table isn't created by the storage migrations, only by this method.
+    ///
+    /// ## Errors
+    /// - `BotError::System` - database error
+    pub async fn initialize(&self) -> Result<()> {
+        sqlx::raw_sql(include_str!("../storage/schema/rate_limit_buckets.sql"))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage")]
+#[async_trait]
+impl RateLimitBackend for PostgresRateLimitBackend {
+    async fn acquire(&self, key: &str, policy: RateLimitPolicy, tokens: u32) -> Result<u32> {
+        // Same refill as `refill` and `take`, done in one statement under the row lock
+        let (taken,): (i32,) = sqlx::query_as(
+            r#"
+            INSERT INTO rate_limit_buckets AS bucket
+                (namespace, bucket_key, tokens, taken, refilled_at)
+            VALUES ($1, $2, $3 - LEAST(FLOOR($3), $4), LEAST(FLOOR($3), $4), CLOCK_TIMESTAMP())
+            ON CONFLICT (namespace, bucket_key) DO UPDATE
+            SET (tokens, taken, refilled_at) = (
+                SELECT available - LEAST(FLOOR(available), $4),
+                    LEAST(FLOOR(available), $4),
+                    GREATEST(bucket.refilled_at, EXCLUDED.refilled_at)
+                FROM (
+                    SELECT LEAST($3, bucket.tokens + GREATEST(EXTRACT(EPOCH FROM
+                        EXCLUDED.refilled_at - bucket.refilled_at)::DOUBLE PRECISION, 0) * $3 / $5)
+                        AS available
+                ) refill
+            )
+            RETURNING taken
+            "#,
+        )
+        .bind(&self.namespace)
+        .bind(key)
+        .bind(policy.limit as f64)
+        .bind(tokens as f64)
+        .bind(policy.duration.max(1) as f64)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(taken.max(0) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: RateLimitPolicy = RateLimitPolicy {
+        limit: 4,
+        duration: 2,
+    };
+
+    #[test]
+    fn test_refill() {
+        assert_eq!(refill(0.0, 1.0, POLICY), 2.0);
+        assert_eq!(refill(3.0, 10.0, POLICY), 4.0);
+        assert_eq!(refill(1.0, -1.0, POLICY), 1.0);
+        assert_eq!(take(2.7, 5), 2);
+        assert_eq!(take(2.7, 1), 1);
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_shares_budget() {
+        let backend = MemoryRateLimitBackend::new();
+        assert_eq!(backend.acquire("chat a", POLICY, 3).await.unwrap(), 3);
+        assert_eq!(backend.acquire("chat a", POLICY, 3).await.unwrap(), 1);
+        assert_eq!(backend.acquire("chat a", POLICY, 3).await.unwrap(), 0);
+        assert_eq!(backend.acquire("chat b", POLICY, 3).await.unwrap(), 3);
+    }
+}
@@ -0,0 +1,510 @@
+//! # Upload cache
+//! Reuse of uploaded files by the hash of their content.
+//!
+//! Every `messages/sendFile` and `messages/sendVoice` call with a multipart uploads the content again.
+//! With an [`UploadCache`] set on the bot, the SHA-256 hash of the content is looked up before
+//! the upload: on a hit the file is sent with the `fileId` returned by the first upload,
+//! otherwise the file is uploaded and the returned `fileId` is saved.
+//! A cached id rejected by the API as unknown or invalid is removed and the file is uploaded again.
+//!
+//! Entries are keyed by the API method and the content, so a voice message is never sent
+//! with the id of a file, and a file sent with a cached id keeps the name it was first uploaded with.
+//!
+//! Storage backends implement [`UploadCacheStore`]:
+//! - [`MemoryUploadCacheStore`] - in-process storage
+//! - [`FileUploadCacheStore`] - JSON file with all entries, replaced atomically
+//! - `PostgresUploadCacheStore` - PostgreSQL storage (`storage` feature)
+//!
+//! ```no_run
+//! use vkteams_bot::prelude::*;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let cache = UploadCache::new(FileUploadCacheStore::new("upload-cache.json"))
+//!     .with_ttl(Duration::from_secs(7 * 24 * 3600));
+//! let bot = Bot::builder().upload_cache(cache).build()?;
+//! let request = RequestMessagesSendFile::new((
+//!     ChatId::from("chat_id"),
+//!     MultipartName::FilePath("report.png".to_string()),
+//! ));
+//! // Only the first call uploads the file
+//! bot.send_api_request(request.clone()).await?;
+//! bot.send_api_request(request).await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::error::{ApiError, ApiErrorKind, BotError, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, error};
+
+/// Methods accepting the `fileId` of an uploaded file instead of a multipart
+const FILE_ID_METHODS: [&str; 2] = ["messages/sendFile", "messages/sendVoice"];
+
+/// Check if the API method can send a cached `fileId` instead of uploading the file
+pub(crate) fn supports_file_id(method: &str) -> bool {
+    FILE_ID_METHODS.contains(&method)
+}
+
+/// Key of the cache entry for the content hash sent by the API method
+pub(crate) fn cache_key(method: &str, hash: &str) -> String {
+    format!("{method}:{hash}")
+}
+
+/// Get the error of the API response rejecting the cached `fileId` itself,
+/// `None` on success and on other errors, which keep the entry
+///
+/// The raw response is checked because payloads with only optional fields
+/// swallow the error description when deserialized.
+pub(crate) fn file_id_rejection(response: &serde_json::Value) -> Option<ApiError> {
+    if response.get("ok").and_then(serde_json::Value::as_bool) != Some(false) {
+        return None;
+    }
+    let error = ApiError {
+        description: response.get("description")?.as_str()?.to_string(),
+    };
+    (error.kind() == ApiErrorKind::FileNotFound
+        || error.description.to_lowercase().contains("fileid"))
+    .then_some(error)
+}
+
+/// File id returned by the API for uploaded content
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedUpload {
+    /// `fileId` returned by the API
+    pub file_id: String,
+    /// Upload time in seconds since the Unix epoch
+    pub uploaded_at: u64,
+}
+
+impl CachedUpload {
+    /// Create entry uploaded now
+    pub fn new(file_id: impl Into<String>) -> Self {
+        Self {
+            file_id: file_id.into(),
+            uploaded_at: unix_now(),
+        }
+    }
+
+    /// Time passed since the upload
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(unix_now().saturating_sub(self.uploaded_at))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Storage of file ids by content hash
+#[async_trait]
+pub trait UploadCacheStore: Send + Sync {
+    /// Get entry for the content hash, `None` if the content was not uploaded yet
+    async fn get(&self, hash: &str) -> Result<Option<CachedUpload>>;
+    /// Save entry for the content hash, replacing the previous one
+    async fn put(&self, hash: &str, upload: CachedUpload) -> Result<()>;
+    /// Remove entry for the content hash
+    async fn remove(&self, hash: &str) -> Result<()>;
+}
+
+/// In-memory upload cache store
+#[derive(Debug, Default)]
+pub struct MemoryUploadCacheStore {
+    entries: Mutex<HashMap<String, CachedUpload>>,
+}
+
+impl MemoryUploadCacheStore {
+    /// Create new empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl UploadCacheStore for MemoryUploadCacheStore {
+    async fn get(&self, hash: &str) -> Result<Option<CachedUpload>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(entries.get(hash).cloned())
+    }
+
+    async fn put(&self, hash: &str, upload: CachedUpload) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(hash.to_string(), upload);
+        Ok(())
+    }
+
+    async fn remove(&self, hash: &str) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.remove(hash);
+        Ok(())
+    }
+}
+
+/// File upload cache store
+///
+/// All entries are kept in one JSON file. Changes are written to a temporary file next to
+/// the target, which is then renamed over it, so a crash never leaves a partially written cache.
+#[derive(Debug)]
+pub struct FileUploadCacheStore {
+    path: PathBuf,
+    /// Serializes read-modify-write cycles of this process
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl FileUploadCacheStore {
+    /// Create store for the file, parent directories are created on first save
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Cache file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    async fn load(&self) -> Result<HashMap<String, CachedUpload>> {
+        let content = match tokio::fs::read(&self.path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(BotError::Io(e)),
+        };
+        serde_json::from_slice(&content).map_err(|e| {
+            BotError::Validation(format!(
+                "Invalid upload cache file {}: {e}",
+                self.path.display()
+            ))
+        })
+    }
+
+    async fn store(&self, entries: &HashMap<String, CachedUpload>) -> Result<()> {
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        {
+            use tokio::io::AsyncWriteExt;
+            let mut file = tokio::fs::File::create(&tmp).await?;
+            file.write_all(&serde_json::to_vec(entries)?).await?;
+            file.sync_all().await?;
+        }
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UploadCacheStore for FileUploadCacheStore {
+    async fn get(&self, hash: &str) -> Result<Option<CachedUpload>> {
+        let _guard = self.lock.lock().await;
+        Ok(self.load().await?.remove(hash))
+    }
+
+    async fn put(&self, hash: &str, upload: CachedUpload) -> Result<()> {
+        let _guard = self.lock.lock().await;
+        let mut entries = self.load().await?;
+        entries.insert(hash.to_string(), upload);
+        self.store(&entries).await
+    }
+
+    async fn remove(&self, hash: &str) -> Result<()> {
+        let _guard = self.lock.lock().await;
+        let mut entries = self.load().await?;
+        if entries.remove(hash).is_some() {
+            self.store(&entries).await?;
+        }
+        Ok(())
+    }
+}
+
+/// PostgreSQL upload cache store
+///
+/// Entries are stored in the `upload_cache` table.
+/// Call [`PostgresUploadCacheStore::initialize`] to create it.
+#[cfg(feature = "storage")]
+#[derive(Debug, Clone)]
+pub struct PostgresUploadCacheStore {
+    pool: sqlx::PgPool,
+}
+
+#[cfg(feature = "storage")]
+impl PostgresUploadCacheStore {
+    /// Create store on top of an existing pool,
+    /// e.g. [`crate::storage::StorageManager::pool`]
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create `upload_cache` table if it does not exist
+    ///
+    /// The table isn't created by the storage migrations, only by this method.
+    ///
+    /// ## Errors
+    /// - `BotError::System` - database error
+    pub async fn initialize(&self) -> Result<()> {
+        sqlx::raw_sql(include_str!("../storage/schema/upload_cache.sql"))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage")]
+#[async_trait]
+impl UploadCacheStore for PostgresUploadCacheStore {
+    async fn get(&self, hash: &str) -> Result<Option<CachedUpload>> {
+        let row: Option<(String, i64)> =
+            sqlx::query_as("SELECT file_id, uploaded_at FROM upload_cache WHERE content_hash = $1")
+                .bind(hash)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(row.map(|(file_id, uploaded_at)| CachedUpload {
+            file_id,
+            uploaded_at: u64::try_from(uploaded_at).unwrap_or_default(),
+        }))
+    }
+
+    async fn put(&self, hash: &str, upload: CachedUpload) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO upload_cache (content_hash, file_id, uploaded_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (content_hash) DO UPDATE
+            SET file_id = EXCLUDED.file_id,
+                uploaded_at = EXCLUDED.uploaded_at
+            "#,
+        )
+        .bind(hash)
+        .bind(&upload.file_id)
+        .bind(i64::try_from(upload.uploaded_at).unwrap_or(i64::MAX))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn remove(&self, hash: &str) -> Result<()> {
+        sqlx::query("DELETE FROM upload_cache WHERE content_hash = $1")
+            .bind(hash)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Upload cache store with the entry lifetime,
+/// set on the bot with [`crate::bot::BotBuilder::upload_cache`]
+#[derive(Clone)]
+pub struct UploadCache {
+    store: Arc<dyn UploadCacheStore>,
+    ttl: Option<Duration>,
+}
+
+impl fmt::Debug for UploadCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UploadCache")
+            .field("store", &"<store>")
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+impl UploadCache {
+    /// Create cache without entry expiration
+    pub fn new(store: impl UploadCacheStore + 'static) -> Self {
+        Self::from_arc(Arc::new(store))
+    }
+
+    /// Create cache with a shared store
+    pub fn from_arc(store: Arc<dyn UploadCacheStore>) -> Self {
+        Self { store, ttl: None }
+    }
+
+    /// Set entry lifetime, older entries are removed on lookup and the file is uploaded again
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Entry lifetime, `None` if entries do not expire
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Upload cache store
+    pub fn store(&self) -> &Arc<dyn UploadCacheStore> {
+        &self.store
+    }
+
+    /// Get file id for the content hash, expired entries are removed
+    ///
+    /// ## Errors
+    /// - store errors
+    pub async fn get(&self, hash: &str) -> Result<Option<String>> {
+        let Some(upload) = self.store.get(hash).await? else {
+            return Ok(None);
+        };
+        if self.ttl.is_some_and(|ttl| upload.age() > ttl) {
+            debug!("Upload cache entry {} expired", hash);
+            self.store.remove(hash).await?;
+            return Ok(None);
+        }
+        Ok(Some(upload.file_id))
+    }
+
+    /// Save file id for the content hash
+    ///
+    /// ## Errors
+    /// - store errors
+    pub async fn insert(&self, hash: &str, file_id: impl Into<String>) -> Result<()> {
+        self.store.put(hash, CachedUpload::new(file_id)).await
+    }
+
+    /// Remove file id for the content hash, e.g. after the API rejected it
+    ///
+    /// ## Errors
+    /// - store errors
+    pub async fn invalidate(&self, hash: &str) -> Result<()> {
+        self.store.remove(hash).await
+    }
+
+    /// Get file id, store errors are logged and treated as a miss
+    pub(crate) async fn lookup(&self, hash: &str) -> Option<String> {
+        self.get(hash).await.unwrap_or_else(|e| {
+            error!("Failed to read upload cache entry {}: {}", hash, e);
+            None
+        })
+    }
+
+    /// Save file id, store errors are logged
+    ///
+    /// A failed save is not fatal: the file is uploaded again next time.
+    pub(crate) async fn remember(&self, hash: &str, file_id: &str) {
+        match self.insert(hash, file_id).await {
+            Ok(()) => debug!("Upload cached: {} -> {}", hash, file_id),
+            Err(e) => error!("Failed to save upload cache entry {}: {}", hash, e),
+        }
+    }
+
+    /// Remove file id, store errors are logged
+    pub(crate) async fn forget(&self, hash: &str) {
+        if let Err(e) = self.invalidate(hash).await {
+            error!("Failed to remove upload cache entry {}: {}", hash, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_id_rejection() {
+        let response =
+            |description: &str| serde_json::json!({ "ok": false, "description": description });
+        assert!(file_id_rejection(&response("File not found: f1")).is_some());
+        assert!(file_id_rejection(&response("Invalid fileId")).is_some());
+        assert!(file_id_rejection(&response("Chat not found")).is_none());
+        assert!(file_id_rejection(&response("Too many requests")).is_none());
+        assert!(file_id_rejection(&serde_json::json!({ "ok": true, "fileId": "f1" })).is_none());
+    }
+
+    #[test]
+    fn test_cache_key_includes_method() {
+        assert_ne!(
+            cache_key("messages/sendFile", "h1"),
+            cache_key("messages/sendVoice", "h1")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        let store = MemoryUploadCacheStore::new();
+        assert_eq!(store.get("h1").await.unwrap(), None);
+        let upload = CachedUpload::new("file1");
+        store.put("h1", upload.clone()).await.unwrap();
+        assert_eq!(store.get("h1").await.unwrap(), Some(upload));
+        store.remove("h1").await.unwrap();
+        assert_eq!(store.get("h1").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileUploadCacheStore::new(dir.path().join("nested").join("cache.json"));
+        assert_eq!(store.get("h1").await.unwrap(), None);
+
+        store.put("h1", CachedUpload::new("file1")).await.unwrap();
+        store.put("h2", CachedUpload::new("file2")).await.unwrap();
+        store.remove("h1").await.unwrap();
+
+        // Store reopened after restart
+        let store = FileUploadCacheStore::new(store.path());
+        assert_eq!(store.get("h1").await.unwrap(), None);
+        assert_eq!(store.get("h2").await.unwrap().unwrap().file_id, "file2");
+    }
+
+    #[tokio::test]
+    async fn test_file_store_invalid_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        std::fs::write(&path, "not json").unwrap();
+        let store = FileUploadCacheStore::new(&path);
+        assert!(matches!(
+            store.get("h1").await,
+            Err(BotError::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cache_ttl() {
+        let store = Arc::new(MemoryUploadCacheStore::new());
+        let cache = UploadCache::from_arc(store.clone()).with_ttl(Duration::from_secs(60));
+        cache.insert("fresh", "file1").await.unwrap();
+        store
+            .put(
+                "stale",
+                CachedUpload {
+                    file_id: "file2".to_string(),
+                    uploaded_at: unix_now() - 120,
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(cache.get("fresh").await.unwrap().as_deref(), Some("file1"));
+        assert_eq!(cache.get("stale").await.unwrap(), None);
+        // Expired entry is removed from the store
+        assert_eq!(store.get("stale").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_cache_invalidate() {
+        let cache = UploadCache::new(MemoryUploadCacheStore::new());
+        cache.remember("h1", "file1").await;
+        assert_eq!(cache.lookup("h1").await.as_deref(), Some("file1"));
+        cache.forget("h1").await;
+        assert_eq!(cache.lookup("h1").await, None);
+    }
+
+    #[test]
+    fn test_supports_file_id() {
+        assert!(supports_file_id("messages/sendFile"));
+        assert!(supports_file_id("messages/sendVoice"));
+        assert!(!supports_file_id("chats/avatar/set"));
+    }
+}
@@ -19,6 +19,10 @@
 //! - `VKTEAMS_BOT_API_URL` - bot api url
 //! - `VKTEAMS_PROXY` - proxy url (optional)
 //! - `VKTEAMS_BOT_SERVER_PORT` - server port (default: 3333)
+use crate::api::events::get::ResponseEventsGet;
+use crate::api::types::EventMessage;
+use crate::bot::Bot;
+use crate::bot::dispatcher::Dispatcher;
 #[cfg(feature = "grpc")]
 use crate::bot::grpc::GRPCRouter;
 use crate::bot::net::shutdown_signal;
@@ -32,6 +36,7 @@ use axum::{
     response::IntoResponse,
     routing::post,
 };
+use serde::Deserialize;
 use serde::de::DeserializeOwned;
 use std::time::Duration;
 use tower_http::cors::{AllowOrigin, CorsLayer};
@@ -81,6 +86,80 @@ pub trait WebhookState: Clone + Send + Sync + 'static {
     async fn handler(&self, msg: Self::WebhookType) -> Result<()>;
 }
 
+/// Webhook payload accepted by [`DispatcherWebhook`]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum WebhookEvents {
+    /// Batch of events in `events/get` format
+    Batch(ResponseEventsGet),
+    /// Single event
+    Single(EventMessage),
+}
+
+/// Webhook state that routes incoming events through a [`Dispatcher`]
+#[derive(Debug, Clone)]
+pub struct DispatcherWebhook {
+    bot: Option<Bot>,
+    dispatcher: Dispatcher,
+    path: String,
+}
+
+impl Default for DispatcherWebhook {
+    fn default() -> Self {
+        Self {
+            bot: None,
+            dispatcher: Dispatcher::default(),
+            path: String::from("/"),
+        }
+    }
+}
+
+impl DispatcherWebhook {
+    /// Create webhook state for the bot and dispatcher, listening on `/`
+    pub fn new(bot: Bot, dispatcher: Dispatcher) -> Self {
+        Self {
+            bot: Some(bot),
+            dispatcher,
+            ..Default::default()
+        }
+    }
+
+    /// Set webhook path
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+}
+
+#[async_trait]
+impl WebhookState for DispatcherWebhook {
+    type WebhookType = WebhookEvents;
+
+    fn get_path(&self) -> Result<String> {
+        Ok(self.path.clone())
+    }
+
+    async fn handler(&self, msg: Self::WebhookType) -> Result<()> {
+        let bot = self
+            .bot
+            .clone()
+            .ok_or_else(|| BotError::Config("Bot is not set for dispatcher webhook".to_string()))?;
+        let events = match msg {
+            WebhookEvents::Batch(events) => events,
+            WebhookEvents::Single(event) => ResponseEventsGet {
+                events: vec![event],
+            },
+        };
+        self.dispatcher.dispatch_events(bot, events).await
+    }
+}
+
+impl FromRef<AppState<DispatcherWebhook>> for DispatcherWebhook {
+    fn from_ref(state: &AppState<DispatcherWebhook>) -> DispatcherWebhook {
+        state.ext.clone()
+    }
+}
+
 /// Trait for bot router
 pub trait BotRouter<S> {
     fn route_bot(self) -> Self;
@@ -91,11 +170,11 @@ where
     S: Clone + Send + Sync + 'static,
 {
     fn route_bot(self) -> Self {
-        if cfg!(feature = "grpc") {
-            self.route_grpc_probe()
-        } else {
-            self
-        }
+        #[cfg(feature = "grpc")]
+        let router = self.route_grpc_probe();
+        #[cfg(not(feature = "grpc"))]
+        let router = self;
+        router
     }
 }
 
@@ -487,6 +566,63 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
     }
 
+    #[tokio::test]
+    async fn test_dispatcher_webhook() {
+        use crate::api::types::APIVersionUrl;
+        use crate::bot::dispatcher::Filter;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let count_clone = count.clone();
+        let dispatcher = Dispatcher::new().on(
+            Filter::command("/start"),
+            move |_bot: Bot, _event: EventMessage| {
+                let count = count_clone.clone();
+                async move {
+                    count.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            },
+        );
+        let bot = Bot::with_params(&APIVersionUrl::V1, "token", "https://example.com").unwrap();
+        let state = DispatcherWebhook::new(bot, dispatcher).with_path("/hook");
+        let router = build_router(state).unwrap();
+
+        let event = serde_json::json!({
+            "eventId": 1,
+            "type": "newMessage",
+            "payload": {
+                "msgId": "1",
+                "text": "/start",
+                "chat": {"chatId": "c", "type": "private"},
+                "from": {"firstName": "A", "userId": "u"},
+                "timestamp": 1
+            }
+        });
+        for payload in [
+            event.clone(),
+            serde_json::json!({ "events": [event.clone(), event] }),
+        ] {
+            let req = Request::builder()
+                .method("POST")
+                .uri("/hook")
+                .header("content-type", "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap();
+            let resp = router.clone().oneshot(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_dispatcher_webhook_without_bot() {
+        let state = DispatcherWebhook::default();
+        let msg = WebhookEvents::Batch(ResponseEventsGet { events: vec![] });
+        assert!(matches!(state.handler(msg).await, Err(BotError::Config(_))));
+    }
+
     #[test]
     fn test_bot_router_trait() {
         // Test that BotRouter trait can be used with a concrete state type
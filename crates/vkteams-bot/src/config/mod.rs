@@ -21,7 +21,7 @@ impl Config {
         if let Ok(database_url) =
             std::env::var("DATABASE_URL").or_else(|_| std::env::var("VKTEAMS_BOT_DATABASE_URL"))
         {
-            storage_config.database.url = database_url;
+            storage_config.database.url = crate::secret::SecretString::url(database_url);
         }
 
         storage_config
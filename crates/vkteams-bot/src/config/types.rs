@@ -1,6 +1,8 @@
 use once_cell::sync::Lazy;
 use serde::{self, Deserialize, Serialize};
 use std::borrow::Cow;
+#[cfg(feature = "ratelimit")]
+use std::collections::BTreeMap;
 
 pub static APP_FOLDER: &str = "VKTEAMS_BOT_CONFIG";
 pub static CONFIG: Lazy<Config> = Lazy::new(Config::new);
@@ -158,6 +160,42 @@ pub struct RateLimit {
     pub cleanup_interval: u64,
     #[serde(default = "default_bucket_lifetime")]
     pub bucket_lifetime: u64,
+    /// Bot-wide ceiling shared by all requests, unlimited if not set
+    #[serde(default)]
+    pub global: Option<RateLimitPolicy>,
+    /// Budgets per API method, e.g. `messages/sendFile`
+    #[serde(default)]
+    pub methods: BTreeMap<String, RateLimitPolicy>,
+    /// Per-chat overrides of `limit` and `duration` by chat id
+    #[serde(default)]
+    pub chats: BTreeMap<String, RateLimitPolicy>,
+    /// Tokens taken at once from a shared backend,
+    /// see [`ratelimit_backend`](crate::bot::ratelimit_backend)
+    #[serde(default = "default_lease_size")]
+    pub lease_size: u32,
+}
+
+/// Number of requests allowed per time window for one rate limit layer
+#[cfg(feature = "ratelimit")]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub struct RateLimitPolicy {
+    /// Requests per window
+    pub limit: usize,
+    /// Window in seconds
+    #[serde(default = "default_duration")]
+    pub duration: u64,
+}
+
+#[cfg(feature = "ratelimit")]
+impl RateLimit {
+    /// Policy of the chat layer: the chat override or the default `limit` and `duration`
+    pub fn chat_policy(&self, chat_id: &str) -> RateLimitPolicy {
+        self.chats.get(chat_id).copied().unwrap_or(RateLimitPolicy {
+            limit: self.limit,
+            duration: self.duration,
+        })
+    }
 }
 
 #[cfg(feature = "ratelimit")]
@@ -171,6 +209,10 @@ impl Default for RateLimit {
             init_bucket: default_init_bucket(),
             cleanup_interval: default_cleanup_interval(),
             bucket_lifetime: default_bucket_lifetime(),
+            global: None,
+            methods: BTreeMap::new(),
+            chats: BTreeMap::new(),
+            lease_size: default_lease_size(),
         }
     }
 }
@@ -202,6 +244,10 @@ fn default_cleanup_interval() -> u64 {
 fn default_bucket_lifetime() -> u64 {
     3600
 }
+#[cfg(feature = "ratelimit")]
+fn default_lease_size() -> u32 {
+    5
+}
 
 /// Configuration for event listener
 #[cfg(feature = "longpoll")]
@@ -223,6 +269,12 @@ pub struct EventListenerConfig {
     /// Maximum memory usage for event processing in bytes (0 means no limit)
     #[serde(default = "default_max_memory_usage")]
     pub max_memory_usage: usize,
+    /// Time in milliseconds given to in-flight events to finish on shutdown
+    #[serde(default = "default_shutdown_timeout_ms")]
+    pub shutdown_timeout_ms: u64,
+    /// Whether listeners without a shutdown token stop on Ctrl+C and SIGTERM
+    #[serde(default)]
+    pub handle_signals: bool,
 }
 
 #[cfg(feature = "longpoll")]
@@ -234,6 +286,8 @@ impl Default for EventListenerConfig {
             max_backoff_ms: default_max_backoff_ms(),
             use_exponential_backoff: default_use_exponential_backoff(),
             max_memory_usage: default_max_memory_usage(),
+            shutdown_timeout_ms: default_shutdown_timeout_ms(),
+            handle_signals: false,
         }
     }
 }
@@ -257,6 +311,10 @@ fn default_use_exponential_backoff() -> bool {
 fn default_max_memory_usage() -> usize {
     0
 }
+#[cfg(feature = "longpoll")]
+fn default_shutdown_timeout_ms() -> u64 {
+    30000
+}
 
 /// Network configuration
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -280,6 +338,9 @@ pub struct NetworkConfig {
     /// Maximum number of idle connections per host
     #[serde(default = "default_max_idle_connections")]
     pub max_idle_connections: usize,
+    /// Circuit breaker of API requests
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
 }
 
 impl Default for NetworkConfig {
@@ -291,6 +352,7 @@ impl Default for NetworkConfig {
             connect_timeout_secs: default_connect_timeout_secs(),
             pool_idle_timeout_secs: default_pool_idle_timeout_secs(),
             max_idle_connections: default_max_idle_connections(),
+            circuit_breaker: CircuitBreakerConfig::default(),
         }
     }
 }
@@ -311,6 +373,77 @@ fn default_max_idle_connections() -> usize {
     10
 }
 
+/// Circuit breaker configuration, see [`circuit_breaker`](crate::bot::circuit_breaker)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct CircuitBreakerConfig {
+    /// Fail fast while the API is unavailable, disabled by default
+    #[serde(default = "default_circuit_enabled")]
+    pub enabled: bool,
+    /// Requests sharing one circuit
+    #[serde(default)]
+    pub scope: CircuitBreakerScope,
+    /// Share of failed requests in the window that opens the circuit
+    #[serde(default = "default_failure_ratio")]
+    pub failure_ratio: f64,
+    /// Minimum number of requests in the window before the circuit may open
+    #[serde(default = "default_min_requests")]
+    pub min_requests: usize,
+    /// Window of counted requests in seconds
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+    /// Time in seconds the circuit stays open before probe requests are let through
+    #[serde(default = "default_open_secs")]
+    pub open_secs: u64,
+    /// Successful probe requests closing a half-open circuit
+    #[serde(default = "default_half_open_probes")]
+    pub half_open_probes: usize,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_circuit_enabled(),
+            scope: CircuitBreakerScope::default(),
+            failure_ratio: default_failure_ratio(),
+            min_requests: default_min_requests(),
+            window_secs: default_window_secs(),
+            open_secs: default_open_secs(),
+            half_open_probes: default_half_open_probes(),
+        }
+    }
+}
+
+/// Key of a circuit
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitBreakerScope {
+    /// One circuit per API host
+    #[default]
+    Host,
+    /// One circuit per API method, e.g. `messages/sendText`
+    Method,
+}
+
+fn default_circuit_enabled() -> bool {
+    false
+}
+fn default_failure_ratio() -> f64 {
+    0.5
+}
+fn default_min_requests() -> usize {
+    10
+}
+fn default_window_secs() -> u64 {
+    60
+}
+fn default_open_secs() -> u64 {
+    30
+}
+fn default_half_open_probes() -> usize {
+    1
+}
+
 #[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum LogFormat {
@@ -391,4 +524,51 @@ mod tests {
     fn test_default_retry_delay() {
         assert_eq!(default_retry_delay(), 1000);
     }
+
+    #[cfg(feature = "ratelimit")]
+    #[test]
+    fn test_rate_limit_layers_from_toml() {
+        let cfg: RateLimit = toml::from_str(
+            r#"
+            limit = 5
+            [global]
+            limit = 100
+            [methods."messages/sendFile"]
+            limit = 2
+            duration = 10
+            [chats."channel@chat.agent"]
+            limit = 50
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cfg.global.unwrap().limit, 100);
+        assert_eq!(cfg.global.unwrap().duration, 60);
+        assert_eq!(
+            cfg.methods["messages/sendFile"],
+            RateLimitPolicy {
+                limit: 2,
+                duration: 10
+            }
+        );
+        assert_eq!(cfg.chat_policy("channel@chat.agent").limit, 50);
+        assert_eq!(cfg.chat_policy("dm@chat.agent").limit, 5);
+    }
+
+    #[test]
+    fn test_circuit_breaker_from_toml() {
+        let cfg: NetworkConfig = toml::from_str(
+            r#"
+            [circuit_breaker]
+            enabled = true
+            scope = "method"
+            failure_ratio = 0.25
+            "#,
+        )
+        .unwrap();
+        assert!(cfg.circuit_breaker.enabled);
+        assert!(!NetworkConfig::default().circuit_breaker.enabled);
+        assert_eq!(cfg.circuit_breaker.scope, CircuitBreakerScope::Method);
+        assert_eq!(cfg.circuit_breaker.failure_ratio, 0.25);
+        assert_eq!(cfg.circuit_breaker.min_requests, 10);
+    }
 }
@@ -1,6 +1,8 @@
+use crate::secret::{redact, redact_url};
 use serde::{Deserialize, Serialize};
 use std::env::VarError;
 use std::fmt;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiError {
@@ -15,6 +17,136 @@ impl fmt::Display for ApiError {
 
 impl std::error::Error for ApiError {}
 
+impl ApiError {
+    /// Known error of the API response.
+    /// The API returns no error codes, the kind is recognized by the description.
+    pub fn kind(&self) -> ApiErrorKind {
+        ApiErrorKind::from_description(&self.description)
+    }
+
+    /// Check if the API rejected the request because too many requests were sent
+    pub fn is_rate_limited(&self) -> bool {
+        self.kind() == ApiErrorKind::RateLimited
+    }
+}
+
+/// Known errors of the VK Teams Bot API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiErrorKind {
+    /// Bot token is invalid or revoked
+    InvalidToken,
+    /// Required parameter is missing or has invalid value
+    InvalidParameters,
+    /// Chat does not exist or is not visible to the bot
+    ChatNotFound,
+    /// User does not exist
+    UserNotFound,
+    /// Message does not exist or was deleted
+    MessageNotFound,
+    /// File does not exist or has expired
+    FileNotFound,
+    /// Bot is not a member of the chat
+    NotChatMember,
+    /// Bot has no rights for the action in the chat
+    PermissionDenied,
+    /// Too many requests were sent
+    RateLimited,
+    /// API server failed to process the request
+    Unavailable,
+    /// Error not recognized by the description
+    Other,
+}
+
+impl ApiErrorKind {
+    /// Patterns of the error descriptions in lowercase, the first match wins
+    const PATTERNS: &[(&[&str], ApiErrorKind)] = &[
+        (
+            &["too many requests", "rate limit"],
+            ApiErrorKind::RateLimited,
+        ),
+        (
+            &["invalid token", "unauthorized", "token is not valid"],
+            ApiErrorKind::InvalidToken,
+        ),
+        (
+            &["not a member", "not member", "not in chat"],
+            ApiErrorKind::NotChatMember,
+        ),
+        (
+            &[
+                "permission denied",
+                "not enough rights",
+                "forbidden",
+                "access denied",
+                "not allowed",
+            ],
+            ApiErrorKind::PermissionDenied,
+        ),
+        (&["chat not found"], ApiErrorKind::ChatNotFound),
+        (&["user not found"], ApiErrorKind::UserNotFound),
+        (&["message not found"], ApiErrorKind::MessageNotFound),
+        (&["file not found"], ApiErrorKind::FileNotFound),
+        (
+            &[
+                "internal error",
+                "service unavailable",
+                "temporarily unavailable",
+                "try again",
+                "timeout",
+            ],
+            ApiErrorKind::Unavailable,
+        ),
+        (
+            &[
+                "missing required",
+                "invalid param",
+                "invalid value",
+                "bad request",
+            ],
+            ApiErrorKind::InvalidParameters,
+        ),
+    ];
+
+    /// Recognize the error by the description of the API response
+    pub fn from_description(description: &str) -> Self {
+        let description = description.to_lowercase();
+        Self::PATTERNS
+            .iter()
+            .find(|(patterns, _)| patterns.iter().any(|p| description.contains(p)))
+            .map_or(ApiErrorKind::Other, |(_, kind)| *kind)
+    }
+
+    /// Same request may succeed later
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ApiErrorKind::RateLimited | ApiErrorKind::Unavailable)
+    }
+
+    /// Same request fails until the request, the chat or the bot settings are changed
+    pub fn is_permanent(&self) -> bool {
+        !self.is_retryable() && *self != ApiErrorKind::Other
+    }
+}
+
+impl fmt::Display for ApiErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            ApiErrorKind::InvalidToken => "invalid token",
+            ApiErrorKind::InvalidParameters => "invalid parameters",
+            ApiErrorKind::ChatNotFound => "chat not found",
+            ApiErrorKind::UserNotFound => "user not found",
+            ApiErrorKind::MessageNotFound => "message not found",
+            ApiErrorKind::FileNotFound => "file not found",
+            ApiErrorKind::NotChatMember => "not a chat member",
+            ApiErrorKind::PermissionDenied => "permission denied",
+            ApiErrorKind::RateLimited => "rate limited",
+            ApiErrorKind::Unavailable => "unavailable",
+            ApiErrorKind::Other => "other",
+        };
+        write!(f, "{kind}")
+    }
+}
+
 #[derive(Debug)]
 pub struct OtlpError {
     pub message: String,
@@ -72,13 +204,19 @@ pub enum BotError {
     Environment(std::env::VarError),
     /// Otlp Error
     Otlp(OtlpError),
+    /// Too many requests: HTTP 429 or API error, with the delay from `Retry-After` if provided
+    RateLimited(Option<Duration>),
+    /// Circuit breaker is open for the host or API method, the request was not sent
+    CircuitOpen(String),
+    /// Server error: HTTP 5xx response
+    Server(reqwest::StatusCode),
 }
 
 impl fmt::Display for BotError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             BotError::Api(e) => write!(f, "API Error: {e}"),
-            BotError::Network(e) => write!(f, "Network Error: {e}"),
+            BotError::Network(e) => write!(f, "Network Error: {}", redact(&e.to_string())),
             #[cfg(feature = "grpc")]
             BotError::Grpc(e) => write!(f, "gRPC Error: {e}"),
             BotError::Serialization(e) => write!(f, "Serialization Error: {e}"),
@@ -92,6 +230,57 @@ impl fmt::Display for BotError {
             BotError::System(e) => write!(f, "System Error: {e}"),
             BotError::Environment(e) => write!(f, "Environment Error: {e}"),
             BotError::Otlp(e) => write!(f, "Otlp Error: {e}"),
+            BotError::RateLimited(Some(retry_after)) => {
+                write!(f, "Rate Limited: retry after {retry_after:?}")
+            }
+            BotError::RateLimited(None) => write!(f, "Rate Limited"),
+            BotError::CircuitOpen(key) => {
+                write!(f, "Circuit Open: requests to {key} are suspended")
+            }
+            BotError::Server(status) => write!(f, "Server Error: HTTP {status}"),
+        }
+    }
+}
+
+impl BotError {
+    /// Same request may succeed later: network failures, server errors, too many requests,
+    /// open circuit breaker and temporary API errors
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            BotError::Network(e) => crate::bot::net::should_retry(e),
+            BotError::Api(e) => e.kind().is_retryable(),
+            BotError::RateLimited(_) | BotError::CircuitOpen(_) | BotError::Server(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Same request fails until it is changed: invalid request,
+    /// unknown chat or message, missing rights or invalid token
+    pub fn is_permanent(&self) -> bool {
+        match self {
+            BotError::Api(e) => e.kind().is_permanent(),
+            BotError::Validation(_)
+            | BotError::Url(_)
+            | BotError::UrlParams(_)
+            | BotError::Config(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Delay before the request may be sent again, requested by the server
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            BotError::RateLimited(retry_after) => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Kind of the API error
+    pub fn api_error_kind(&self) -> Option<ApiErrorKind> {
+        match self {
+            BotError::Api(e) => Some(e.kind()),
+            BotError::RateLimited(_) => Some(ApiErrorKind::RateLimited),
+            _ => None,
         }
     }
 }
@@ -114,16 +303,30 @@ impl std::error::Error for BotError {
             BotError::System(_) => None,
             BotError::Environment(e) => Some(e),
             BotError::Otlp(e) => Some(e),
+            BotError::RateLimited(_) => None,
+            BotError::CircuitOpen(_) => None,
+            BotError::Server(_) => None,
         }
     }
 }
 
+/// The request URL is redacted, it contains the bot token
 impl From<reqwest::Error> for BotError {
-    fn from(err: reqwest::Error) -> Self {
+    fn from(mut err: reqwest::Error) -> Self {
+        if let Some(url) = err.url_mut() {
+            *url = redact_url(url);
+        }
         BotError::Network(err)
     }
 }
 
+#[cfg(feature = "storage")]
+impl From<sqlx::Error> for BotError {
+    fn from(err: sqlx::Error) -> Self {
+        BotError::System(format!("Storage error: {err}"))
+    }
+}
+
 impl From<serde_json::Error> for BotError {
     fn from(err: serde_json::Error) -> Self {
         BotError::Serialization(err)
@@ -195,6 +398,104 @@ mod tests {
         assert_eq!(format!("{err}"), "API Error: fail");
     }
 
+    #[test]
+    fn test_api_error_is_rate_limited() {
+        let err = |description: &str| ApiError {
+            description: description.to_string(),
+        };
+        assert!(err("Too Many Requests").is_rate_limited());
+        assert!(err("rate limit exceeded").is_rate_limited());
+        assert!(!err("Chat not found").is_rate_limited());
+    }
+
+    #[test]
+    fn test_api_error_kind() {
+        let kind = |description: &str| {
+            ApiError {
+                description: description.to_string(),
+            }
+            .kind()
+        };
+        assert_eq!(kind("Chat not found"), ApiErrorKind::ChatNotFound);
+        assert_eq!(
+            kind("Bot is not a member of the chat"),
+            ApiErrorKind::NotChatMember
+        );
+        assert_eq!(kind("Permission denied"), ApiErrorKind::PermissionDenied);
+        assert_eq!(kind("Invalid token"), ApiErrorKind::InvalidToken);
+        assert_eq!(
+            kind("Missing required parameter chatId"),
+            ApiErrorKind::InvalidParameters
+        );
+        assert_eq!(kind("Service unavailable"), ApiErrorKind::Unavailable);
+        assert_eq!(kind("Something went wrong"), ApiErrorKind::Other);
+        assert_eq!(ApiErrorKind::NotChatMember.to_string(), "not a chat member");
+
+        assert!(ApiErrorKind::Unavailable.is_retryable());
+        assert!(!ApiErrorKind::Unavailable.is_permanent());
+        assert!(ApiErrorKind::ChatNotFound.is_permanent());
+        assert!(!ApiErrorKind::Other.is_retryable());
+        assert!(!ApiErrorKind::Other.is_permanent());
+    }
+
+    #[test]
+    fn test_bot_error_classification() {
+        let api = |description: &str| {
+            BotError::Api(ApiError {
+                description: description.to_string(),
+            })
+        };
+        assert!(api("Chat not found").is_permanent());
+        assert!(!api("Chat not found").is_retryable());
+        assert!(api("Internal error").is_retryable());
+        assert!(!api("Unknown failure").is_retryable());
+        assert!(!api("Unknown failure").is_permanent());
+        assert!(BotError::Validation("HTTP error: 400".to_string()).is_permanent());
+        assert!(BotError::CircuitOpen("api.example.com".to_string()).is_retryable());
+        assert!(!BotError::System("Server error".to_string()).is_permanent());
+        let server = BotError::Server(reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        assert!(server.is_retryable());
+        assert!(!server.is_permanent());
+        assert_eq!(
+            server.to_string(),
+            "Server Error: HTTP 503 Service Unavailable"
+        );
+
+        let rate_limited = BotError::RateLimited(Some(Duration::from_secs(3)));
+        assert!(rate_limited.is_retryable());
+        assert_eq!(rate_limited.retry_after(), Some(Duration::from_secs(3)));
+        assert_eq!(
+            rate_limited.api_error_kind(),
+            Some(ApiErrorKind::RateLimited)
+        );
+        assert_eq!(api("Chat not found").retry_after(), None);
+        assert_eq!(
+            api("Message not found").api_error_kind(),
+            Some(ApiErrorKind::MessageNotFound)
+        );
+        assert_eq!(
+            BotError::Config("invalid".to_string()).api_error_kind(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rate_limited_display() {
+        assert_eq!(
+            BotError::RateLimited(Some(Duration::from_secs(2))).to_string(),
+            "Rate Limited: retry after 2s"
+        );
+        assert_eq!(BotError::RateLimited(None).to_string(), "Rate Limited");
+    }
+
+    #[test]
+    fn test_circuit_open_display() {
+        assert_eq!(
+            BotError::CircuitOpen("api.example.com".to_string()).to_string(),
+            "Circuit Open: requests to api.example.com are suspended"
+        );
+    }
+
     #[test]
     fn test_otlp_error_display_and_from() {
         let err = OtlpError {
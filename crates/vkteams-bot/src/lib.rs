@@ -98,6 +98,7 @@ pub mod error;
 #[cfg(feature = "otlp")]
 pub mod otlp;
 pub mod prelude;
+pub mod secret;
 #[cfg(feature = "storage")]
 pub mod storage;
 /// API methods
@@ -120,6 +121,8 @@ mod api {
     pub mod myself {
         pub mod get;
     }
+    /// API `/threads/` methods
+    pub mod threads;
 }
 
 pub use self::bot::Bot;
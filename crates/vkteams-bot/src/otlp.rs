@@ -1,16 +1,26 @@
+use crate::bot::metrics::metrics;
 use crate::config::{CONFIG, LogFormat};
-use opentelemetry::{KeyValue, global, trace::TracerProvider as _};
+use crate::secret::{RedactingMakeWriter, redact};
+use opentelemetry::{
+    Context, KeyValue, StringValue, Value, global,
+    trace::{Status, TracerProvider as _},
+};
 use opentelemetry_otlp::{Protocol, WithExportConfig};
 use opentelemetry_sdk::{
     Resource,
+    error::OTelSdkResult,
     metrics::{PeriodicReader, SdkMeterProvider},
     propagation::TraceContextPropagator,
-    trace::{RandomIdGenerator, Sampler, SdkTracerProvider},
+    trace::{
+        BatchSpanProcessor, RandomIdGenerator, Sampler, SdkTracerProvider, Span, SpanData,
+        SpanProcessor,
+    },
 };
 use opentelemetry_semantic_conventions::{
     attribute::{DEPLOYMENT_ENVIRONMENT_NAME, SERVICE_VERSION},
     resource::{SERVICE_INSTANCE_ID, SERVICE_NAMESPACE},
 };
+use std::borrow::Cow;
 use std::result::Result;
 use tokio::time::Duration;
 use tracing_opentelemetry::{MetricsLayer, OpenTelemetryLayer};
@@ -54,9 +64,79 @@ fn init_traces() -> Result<SdkTracerProvider, Box<dyn std::error::Error>> {
         ))))
         .with_id_generator(RandomIdGenerator::default())
         .with_resource(get_resource())
-        .with_batch_exporter(exporter)
+        .with_span_processor(RedactingSpanProcessor::new(
+            BatchSpanProcessor::builder(exporter).build(),
+        ))
         .build())
 }
+/// Span processor that redacts secrets in the span name, attributes,
+/// events and status before passing the span to the wrapped processor
+#[derive(Debug)]
+pub struct RedactingSpanProcessor<P> {
+    inner: P,
+}
+
+impl<P: SpanProcessor> RedactingSpanProcessor<P> {
+    /// Wrap the processor
+    pub fn new(inner: P) -> Self {
+        Self { inner }
+    }
+}
+
+/// Redacted text, `None` if there is nothing to redact
+fn redacted(text: &str) -> Option<String> {
+    match redact(text) {
+        Cow::Borrowed(_) => None,
+        Cow::Owned(text) => Some(text),
+    }
+}
+
+fn redact_attributes(attributes: &mut [KeyValue]) {
+    for attribute in attributes {
+        if let Value::String(value) = &attribute.value
+            && let Some(text) = redacted(value.as_str())
+        {
+            attribute.value = Value::String(StringValue::from(text));
+        }
+    }
+}
+
+impl<P: SpanProcessor> SpanProcessor for RedactingSpanProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, mut span: SpanData) {
+        if let Some(name) = redacted(&span.name) {
+            span.name = name.into();
+        }
+        redact_attributes(&mut span.attributes);
+        for event in &mut span.events.events {
+            if let Some(name) = redacted(&event.name) {
+                event.name = name.into();
+            }
+            redact_attributes(&mut event.attributes);
+        }
+        if let Status::Error { description } = &span.status
+            && let Some(description) = redacted(description)
+        {
+            span.status = Status::error(description);
+        }
+        self.inner.on_end(span);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
 /// Initialize OpenTelemetry metrics
 fn init_metrics() -> Result<SdkMeterProvider, Box<dyn std::error::Error>> {
     let cfg = &CONFIG.otlp;
@@ -85,6 +165,7 @@ fn init_metrics() -> Result<SdkMeterProvider, Box<dyn std::error::Error>> {
         .build();
 
     global::set_meter_provider(meter_provider.clone());
+    metrics().enable_otlp(&global::meter(env!("CARGO_PKG_NAME")));
     Ok(meter_provider)
 }
 /// Create a filter for the OpenTelemetry subscriber
@@ -132,7 +213,7 @@ pub fn init() -> Result<OtelGuard, Box<dyn std::error::Error>> {
         let base_subscriber = base_subscriber.with(
             tracing_subscriber::fmt::layer()
                 .json()
-                .with_writer(std::io::stderr)
+                .with_writer(RedactingMakeWriter::new(std::io::stderr))
                 .with_filter(fmt_filter()?),
         );
         // Add appropriate layers depending on initialization results
@@ -159,7 +240,7 @@ pub fn init() -> Result<OtelGuard, Box<dyn std::error::Error>> {
             tracing_subscriber::fmt::layer()
                 .pretty()
                 .with_ansi(cfg.fmt_ansi)
-                .with_writer(std::io::stderr)
+                .with_writer(RedactingMakeWriter::new(std::io::stderr))
                 .with_thread_names(true)
                 .with_line_number(true)
                 .with_thread_ids(true)
@@ -187,7 +268,7 @@ pub fn init() -> Result<OtelGuard, Box<dyn std::error::Error>> {
         let base_subscriber = base_subscriber.with(
             tracing_subscriber::fmt::layer()
                 .with_ansi(cfg.fmt_ansi)
-                .with_writer(std::io::stderr)
+                .with_writer(RedactingMakeWriter::new(std::io::stderr))
                 .with_thread_names(true)
                 .with_line_number(true)
                 .with_thread_ids(true)
@@ -243,6 +324,62 @@ impl Drop for OtelGuard {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use opentelemetry::trace::{Span as _, Tracer as _};
+    use std::sync::{Arc, Mutex};
+
+    /// Processor that keeps the finished spans
+    #[derive(Debug, Clone, Default)]
+    struct CapturingProcessor {
+        spans: Arc<Mutex<Vec<SpanData>>>,
+    }
+
+    impl SpanProcessor for CapturingProcessor {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+        fn on_end(&self, span: SpanData) {
+            self.spans.lock().unwrap().push(span);
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn shutdown_with_timeout(&self, _timeout: Duration) -> OTelSdkResult {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_redacting_span_processor() {
+        const TOKEN: &str = "001.5566778899.0011223344:1000000002";
+        crate::secret::register_secret(TOKEN);
+
+        let capture = CapturingProcessor::default();
+        let provider = SdkTracerProvider::builder()
+            .with_span_processor(RedactingSpanProcessor::new(capture.clone()))
+            .build();
+        let tracer = provider.tracer("test");
+        let url = format!("https://api.example.com/bot/v1/self/get?token={TOKEN}");
+        let mut span = tracer.start(format!("GET {url}"));
+        span.set_attribute(KeyValue::new("url", url.clone()));
+        span.set_attribute(KeyValue::new("attempts", 2));
+        span.add_event(
+            "request failed",
+            vec![KeyValue::new(
+                "error",
+                format!("error sending request for url ({url})"),
+            )],
+        );
+        span.set_status(Status::error(format!("Network Error: {TOKEN}")));
+        span.end();
+
+        let spans = capture.spans.lock().unwrap();
+        assert_eq!(spans.len(), 1);
+        let exported = format!("{:?}", spans[0]);
+        assert!(!exported.contains(TOKEN));
+        assert!(exported.contains("[REDACTED]"));
+        assert!(spans[0].attributes.contains(&KeyValue::new("attempts", 2)));
+    }
 
     #[test]
     fn test_get_resource() {
@@ -5,12 +5,34 @@ pub use crate::api::events::get::*;
 pub use crate::api::files::get_info::*;
 pub use crate::api::messages::*;
 pub use crate::api::myself::get::*;
+pub use crate::api::threads::*;
 pub use crate::api::types::*;
 pub use crate::api::utils::*;
 pub use crate::api::*;
-pub use crate::bot::net::ConnectionPool;
+#[cfg(feature = "longpoll")]
+pub use crate::bot::checkpoint::{
+    Checkpoint, CheckpointMode, CheckpointStore, FileCheckpointStore, MemoryCheckpointStore,
+};
+pub use crate::bot::circuit_breaker::{CircuitBreaker, CircuitState, CircuitStats};
+pub use crate::bot::dispatcher::{Dispatcher, Filter, Handler};
+pub use crate::bot::fsm::{StateContext, StateKey, StateManager, StateRecord};
+pub use crate::bot::metrics::{Metrics, metrics};
+#[cfg(feature = "prometheus")]
+pub use crate::bot::metrics::{MetricsRouter, serve_metrics};
+pub use crate::bot::net::{
+    ConnectionPool, DownloadOptions, DownloadedFile, RetryableMultipartForm,
+};
+pub use crate::bot::outbox::{
+    FileOutboxStore, MemoryOutboxStore, Outbox, OutboxEntry, OutboxMessage, OutboxPriority,
+    OutboxStatus, OutboxStore,
+};
 #[cfg(feature = "ratelimit")]
-pub use crate::bot::ratelimit::RateLimiter;
+pub use crate::bot::ratelimit::{BucketStats, RateLimitLayer, RateLimiter};
+#[cfg(feature = "ratelimit")]
+pub use crate::bot::ratelimit_backend::{MemoryRateLimitBackend, RateLimitBackend};
+pub use crate::bot::upload_cache::{
+    CachedUpload, FileUploadCacheStore, MemoryUploadCacheStore, UploadCache, UploadCacheStore,
+};
 #[cfg(feature = "grpc")]
 pub use crate::bot::webhook::*;
 pub use crate::bot::*;
@@ -18,3 +40,6 @@ pub use crate::bot_api_method;
 pub use crate::error::*;
 #[cfg(feature = "otlp")]
 pub use crate::otlp::{self, OtelGuard, init};
+pub use crate::secret::SecretString;
+#[cfg(feature = "longpoll")]
+pub use tokio_util::sync::CancellationToken;
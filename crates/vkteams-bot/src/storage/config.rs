@@ -1,5 +1,6 @@
 //! Storage configuration types
 
+use crate::secret::SecretString;
 use serde::{Deserialize, Serialize};
 
 /// Complete storage configuration
@@ -23,8 +24,9 @@ pub struct StorageConfig {
 /// Database connection configuration
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct DatabaseConfig {
-    /// PostgreSQL connection URL
-    pub url: String,
+    /// PostgreSQL connection URL, may contain the password
+    #[serde(deserialize_with = "crate::secret::deserialize_url")]
+    pub url: SecretString,
 
     /// Maximum number of connections in pool
     #[serde(default = "default_max_connections")]
@@ -72,8 +74,9 @@ pub struct VectorConfig {
     #[serde(default = "default_vector_provider")]
     pub provider: String,
 
-    /// Connection URL for vector store
-    pub connection_url: String,
+    /// Connection URL for vector store, may contain the password
+    #[serde(deserialize_with = "crate::secret::deserialize_url")]
+    pub connection_url: SecretString,
 
     /// Collection/table name for vectors
     #[serde(default = "default_collection_name")]
@@ -246,7 +249,7 @@ impl Default for StorageConfig {
     fn default() -> Self {
         Self {
             database: DatabaseConfig {
-                url: "postgresql://localhost/vkteams_bot".to_string(),
+                url: SecretString::url("postgresql://localhost/vkteams_bot"),
                 max_connections: default_max_connections(),
                 connection_timeout: default_connection_timeout(),
                 auto_migrate: default_auto_migrate(),
@@ -255,7 +258,7 @@ impl Default for StorageConfig {
             #[cfg(feature = "vector-search")]
             vector: Some(VectorConfig {
                 provider: default_vector_provider(),
-                connection_url: "postgresql://localhost/vkteams_bot".to_string(),
+                connection_url: SecretString::url("postgresql://localhost/vkteams_bot"),
                 collection_name: default_collection_name(),
                 dimensions: default_dimensions(),
                 similarity_threshold: default_similarity_threshold(),
@@ -293,7 +293,7 @@ impl EmbeddingClient for OllamaEmbeddingClient {
         const MAX_CONCURRENT: usize = 4; // Limit concurrent requests for local Ollama
 
         let owned_texts: Vec<String> = texts.to_vec();
-        let results: Result<Vec<_>, _> = stream::iter(owned_texts.into_iter())
+        let results: Result<Vec<_>, _> = stream::iter(owned_texts)
             .map(|text| {
                 let client = self.clone();
                 async move { client.generate_embedding(&text).await }
@@ -1,6 +1,7 @@
 //! Storage manager that coordinates all storage backends
 
 use crate::api::types::EventMessage;
+use crate::api::utils::MessageTextHTMLParser;
 use crate::storage::{StorageConfig, StorageError, StorageResult};
 use chrono::{DateTime, Utc};
 use serde_json::Value;
@@ -53,7 +54,7 @@ impl StorageManager {
     pub async fn new(config: &StorageConfig) -> StorageResult<Self> {
         #[cfg(feature = "storage")]
         let relational = {
-            let store = SimpleRelationalStore::new(&config.database.url).await?;
+            let store = SimpleRelationalStore::new(config.database.url.expose_secret()).await?;
             if config.database.auto_migrate {
                 store.migrate().await?;
             }
@@ -65,7 +66,7 @@ impl StorageManager {
             if let Some(vector_config) = &config.vector {
                 let store = create_vector_store_with_ssl(
                     &vector_config.provider,
-                    &vector_config.connection_url,
+                    vector_config.connection_url.expose_secret(),
                     Some(vector_config.collection_name.clone()),
                     vector_config.dimensions,
                     vector_config.ivfflat_lists,
@@ -225,7 +226,7 @@ impl StorageManager {
                     let vector_docs: Vec<VectorDocument> = event_ids
                         .iter()
                         .zip(events.iter())
-                        .zip(embeddings.into_iter())
+                        .zip(embeddings)
                         .filter_map(|((event_id, event), embedding)| {
                             self.extract_text_content(event).map(|text| {
                                 let event_timestamp =
@@ -320,6 +321,16 @@ impl StorageManager {
         self.relational.search_messages(query, chat_id, limit).await
     }
 
+    /// Get a stored message by chat and message id
+    #[cfg(feature = "storage")]
+    pub async fn get_message(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+    ) -> StorageResult<Option<Message>> {
+        self.relational.get_message(chat_id, message_id).await
+    }
+
     /// Advanced search with multiple filters
     #[cfg(feature = "storage")]
     pub async fn search_events_advanced(
@@ -418,6 +429,13 @@ impl StorageManager {
         &self.config
     }
 
+    /// Get PostgreSQL connection pool, e.g. to share it with
+    /// [`crate::bot::fsm::PostgresStateStorage`]
+    #[cfg(feature = "storage")]
+    pub fn pool(&self) -> &sqlx::PgPool {
+        self.relational.pool()
+    }
+
     /// Extract message data from event (private helper)
     fn extract_message_data(
         &self,
@@ -515,6 +533,7 @@ impl StorageManager {
             crate::api::types::EventType::LeftChatMembers(_) => "leftChatMembers".to_string(),
             crate::api::types::EventType::CallbackQuery(_) => "callbackQuery".to_string(),
             crate::api::types::EventType::None => "none".to_string(),
+            crate::api::types::EventType::Unknown { event_type, .. } => event_type.clone(),
         }
     }
 
@@ -545,6 +564,11 @@ impl StorageManager {
             crate::api::types::EventType::CallbackQuery(payload) => {
                 Some(payload.message.chat.chat_id.0.to_string())
             }
+            // Best effort for event types not supported yet
+            crate::api::types::EventType::Unknown { payload, .. } => payload
+                .pointer("/chat/chatId")
+                .and_then(Value::as_str)
+                .map(str::to_string),
             _ => None,
         }
     }
@@ -561,6 +585,10 @@ impl StorageManager {
             crate::api::types::EventType::CallbackQuery(payload) => {
                 Some(payload.from.user_id.0.to_string())
             }
+            crate::api::types::EventType::Unknown { payload, .. } => payload
+                .pointer("/from/userId")
+                .and_then(Value::as_str)
+                .map(str::to_string),
             _ => None,
         }
     }
@@ -592,6 +620,10 @@ impl StorageManager {
                 DateTime::from_timestamp(payload.message.timestamp.0 as i64, 0)
             }
             crate::api::types::EventType::None => None,
+            crate::api::types::EventType::Unknown { payload, .. } => payload
+                .get("timestamp")
+                .and_then(Value::as_i64)
+                .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0)),
         }
     }
 
@@ -650,30 +682,22 @@ impl StorageManager {
         }
     }
 
-    /// Extract formatted text from message payload
+    /// Extract message text with formatting as HTML
     fn extract_formatted_text(
         &self,
         payload: &crate::api::types::EventPayloadNewMessage,
     ) -> Option<String> {
-        if let Some(format) = &payload.format {
-            // Convert MessageFormat to formatted text representation
-            serde_json::to_string(format).ok()
-        } else {
-            None
-        }
+        payload.format.as_ref()?;
+        payload.text_parser().parse().ok().map(|(html, _)| html)
     }
 
-    /// Extract formatted text from edited message payload
+    /// Extract edited message text with formatting as HTML
     fn extract_formatted_text_edited(
         &self,
         payload: &crate::api::types::EventPayloadEditedMessage,
     ) -> Option<String> {
-        if let Some(format) = &payload.format {
-            // Convert MessageFormat to formatted text representation
-            serde_json::to_string(format).ok()
-        } else {
-            None
-        }
+        payload.format.as_ref()?;
+        payload.text_parser().parse().ok().map(|(html, _)| html)
     }
 }
 
@@ -721,6 +745,10 @@ mod tests {
                     DateTime::from_timestamp(payload.message.timestamp.0 as i64, 0)
                 }
                 crate::api::types::EventType::None => None,
+                crate::api::types::EventType::Unknown { payload, .. } => payload
+                    .get("timestamp")
+                    .and_then(Value::as_i64)
+                    .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0)),
             }
         }
     }
@@ -745,6 +773,7 @@ mod tests {
             format: None,
             parts: vec![],
             timestamp: Timestamp(1700000000), // Unix timestamp
+            parent_topic: None,
         };
 
         let event = EventMessage {
@@ -780,6 +809,7 @@ mod tests {
             format: None,
             parts: vec![],
             timestamp: Timestamp(1700000000), // Unix timestamp
+            parent_topic: None,
         };
 
         let callback_payload = EventPayloadCallbackQuery {
@@ -871,6 +901,7 @@ mod tests {
                 format: None,
                 parts: vec![],
                 timestamp: Timestamp(timestamp_value),
+                parent_topic: None,
             };
 
             let event = EventMessage {
@@ -925,6 +956,13 @@ mod tests {
                 "callbackQuery",
             ),
             (EventType::None, "unknown"),
+            (
+                EventType::Unknown {
+                    event_type: "changedChatInfo".to_string(),
+                    payload: serde_json::Value::Null,
+                },
+                "changedChatInfo",
+            ),
         ];
 
         for (event_type, expected_string) in test_cases {
@@ -1024,12 +1062,12 @@ mod tests {
             }),
             parts: vec![],
             timestamp: Timestamp(1700000000),
+            parent_topic: None,
         };
 
         let result = manager.extract_formatted_text(&payload_with_format);
         assert!(result.is_some());
-        let formatted = result.unwrap();
-        assert!(formatted.contains("bold"));
+        assert_eq!(result.unwrap(), "<b>Test</b> message");
 
         // Test with no format
         let payload_without_format = EventPayloadNewMessage {
@@ -1040,6 +1078,7 @@ mod tests {
             format: None,
             parts: vec![],
             timestamp: Timestamp(1700000000),
+            parent_topic: None,
         };
 
         let result = manager.extract_formatted_text(&payload_without_format);
@@ -1073,12 +1112,12 @@ mod tests {
             }),
             timestamp: Timestamp(1700000000),
             edited_timestamp: Timestamp(1700000001),
+            parent_topic: None,
         };
 
         let result = manager.extract_formatted_text_edited(&payload_with_format);
         assert!(result.is_some());
-        let formatted = result.unwrap();
-        assert!(formatted.contains("italic"));
+        assert_eq!(result.unwrap(), "<i>Edited</i> message");
 
         // Test with no format
         let payload_without_format = EventPayloadEditedMessage {
@@ -1089,6 +1128,7 @@ mod tests {
             format: None,
             timestamp: Timestamp(1700000000),
             edited_timestamp: Timestamp(1700000001),
+            parent_topic: None,
         };
 
         let result = manager.extract_formatted_text_edited(&payload_without_format);
@@ -1154,6 +1194,7 @@ mod tests {
             format: None,
             parts: vec![],
             timestamp: Timestamp(1700000000),
+            parent_topic: None,
         }
     }
 
@@ -1166,6 +1207,7 @@ mod tests {
             format: None,
             timestamp: Timestamp(1700000000),
             edited_timestamp: Timestamp(1700000001),
+            parent_topic: None,
         }
     }
 
@@ -1251,6 +1293,7 @@ mod tests {
                 crate::api::types::EventType::LeftChatMembers(_) => "leftChatMembers".to_string(),
                 crate::api::types::EventType::CallbackQuery(_) => "callbackQuery".to_string(),
                 crate::api::types::EventType::None => "unknown".to_string(),
+                crate::api::types::EventType::Unknown { event_type, .. } => event_type.clone(),
             }
         }
 
@@ -1279,22 +1322,16 @@ mod tests {
         }
 
         fn extract_formatted_text(&self, payload: &EventPayloadNewMessage) -> Option<String> {
-            if let Some(format) = &payload.format {
-                serde_json::to_string(format).ok()
-            } else {
-                None
-            }
+            payload.format.as_ref()?;
+            payload.text_parser().parse().ok().map(|(html, _)| html)
         }
 
         fn extract_formatted_text_edited(
             &self,
             payload: &EventPayloadEditedMessage,
         ) -> Option<String> {
-            if let Some(format) = &payload.format {
-                serde_json::to_string(format).ok()
-            } else {
-                None
-            }
+            payload.format.as_ref()?;
+            payload.text_parser().parse().ok().map(|(html, _)| html)
         }
 
         fn extract_text_content(&self, event: &EventMessage) -> Option<String> {
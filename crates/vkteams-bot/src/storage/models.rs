@@ -48,6 +48,22 @@ pub struct Message {
     pub created_at: DateTime<Utc>,
 }
 
+impl Message {
+    /// File ids of the attachments stored with the message
+    pub fn attachment_file_ids(&self) -> Vec<String> {
+        let attachments = match &self.file_attachments {
+            Some(serde_json::Value::Array(items)) => items.iter().collect(),
+            Some(item @ serde_json::Value::Object(_)) => vec![item],
+            _ => Vec::new(),
+        };
+        attachments
+            .into_iter()
+            .filter_map(|item| item.get("file_id")?.as_str())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
 /// Context record for MCP server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "database", derive(FromRow))]
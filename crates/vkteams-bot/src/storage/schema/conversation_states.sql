@@ -0,0 +1,10 @@
+-- Conversation states of the dialog state machine
+CREATE TABLE IF NOT EXISTS conversation_states (
+    chat_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    state TEXT NOT NULL,
+    data JSONB NOT NULL DEFAULT 'null',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ,
+    PRIMARY KEY (chat_id, user_id)
+);
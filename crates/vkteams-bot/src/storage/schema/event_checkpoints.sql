@@ -0,0 +1,6 @@
+-- Last handled event id of each long-poll listener
+CREATE TABLE IF NOT EXISTS event_checkpoints (
+    name TEXT PRIMARY KEY,
+    event_id BIGINT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
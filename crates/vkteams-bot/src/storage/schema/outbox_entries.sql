@@ -0,0 +1,16 @@
+-- Messages of the persistent outbox
+CREATE TABLE IF NOT EXISTS outbox_entries (
+    name TEXT NOT NULL,
+    key TEXT NOT NULL,
+    status TEXT NOT NULL,
+    priority SMALLINT NOT NULL,
+    created_at BIGINT NOT NULL,
+    next_attempt_at BIGINT NOT NULL,
+    entry JSONB NOT NULL,
+    -- Time in milliseconds since the Unix epoch until which a worker owns the entry
+    claimed_until BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (name, key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_outbox_entries_due
+ON outbox_entries (name, status, next_attempt_at);
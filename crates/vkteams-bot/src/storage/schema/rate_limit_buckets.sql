@@ -0,0 +1,9 @@
+-- Token buckets of the rate limiter shared by several bot instances
+CREATE TABLE IF NOT EXISTS rate_limit_buckets (
+    namespace TEXT NOT NULL,
+    bucket_key TEXT NOT NULL,
+    tokens DOUBLE PRECISION NOT NULL,
+    taken INTEGER NOT NULL DEFAULT 0,
+    refilled_at TIMESTAMPTZ NOT NULL DEFAULT CLOCK_TIMESTAMP(),
+    PRIMARY KEY (namespace, bucket_key)
+);
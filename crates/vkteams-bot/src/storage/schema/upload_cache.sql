@@ -0,0 +1,6 @@
+-- File ids of uploaded content, keyed by API method and content hash
+CREATE TABLE IF NOT EXISTS upload_cache (
+    content_hash TEXT PRIMARY KEY,
+    file_id TEXT NOT NULL,
+    uploaded_at BIGINT NOT NULL
+);
@@ -18,6 +18,11 @@ impl SimpleRelationalStore {
         Ok(Self { pool })
     }
 
+    /// Underlying connection pool
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
     pub async fn initialize(&self) -> StorageResult<()> {
         // Basic table creation - simplified for compilation
         sqlx::query(
@@ -80,6 +85,23 @@ impl SimpleRelationalStore {
         Ok(vec![])
     }
 
+    /// Get a stored message by chat and message id
+    pub async fn get_message(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+    ) -> StorageResult<Option<Message>> {
+        let message = sqlx::query_as::<_, Message>(
+            "SELECT * FROM messages WHERE chat_id = $1 AND message_id = $2",
+        )
+        .bind(chat_id)
+        .bind(message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(message)
+    }
+
     pub async fn search_events_advanced(
         &self,
         _user_id: Option<&str>,
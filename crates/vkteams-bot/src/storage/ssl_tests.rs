@@ -17,7 +17,7 @@ mod tests {
     #[test]
     fn test_database_config_with_ssl() {
         let db_config = DatabaseConfig {
-            url: "postgresql://localhost/test".to_string(),
+            url: "postgresql://localhost/test".into(),
             max_connections: 10,
             connection_timeout: 30,
             auto_migrate: true,
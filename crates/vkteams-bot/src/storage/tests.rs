@@ -19,7 +19,7 @@ mod storage_tests {
     fn test_storage_config_creation() {
         let config = StorageConfig {
             database: DatabaseConfig {
-                url: "postgresql://test/db".to_string(),
+                url: "postgresql://test/db".into(),
                 max_connections: 10,
                 connection_timeout: 15,
                 auto_migrate: false,
@@ -126,6 +126,41 @@ mod storage_tests {
         }
     }
 
+    #[test]
+    fn test_message_attachment_file_ids() {
+        use crate::storage::Message;
+        use chrono::Utc;
+
+        let mut message = Message {
+            id: 1,
+            event_id: 1,
+            message_id: "msg_1".to_string(),
+            chat_id: "chat".to_string(),
+            user_id: "user".to_string(),
+            text: None,
+            formatted_text: None,
+            reply_to_message_id: None,
+            forward_from_chat_id: None,
+            forward_from_message_id: None,
+            file_attachments: None,
+            has_mentions: false,
+            mentions: None,
+            timestamp: Utc::now(),
+            created_at: Utc::now(),
+        };
+        assert!(message.attachment_file_ids().is_empty());
+
+        message.file_attachments = Some(serde_json::json!([
+            { "type": "file", "file_id": "file_1", "filename": "a.txt" },
+            { "type": "voice", "file_id": "voice_1" },
+            { "type": "file" }
+        ]));
+        assert_eq!(message.attachment_file_ids(), vec!["file_1", "voice_1"]);
+
+        message.file_attachments = Some(serde_json::json!({ "file_id": "file_2" }));
+        assert_eq!(message.attachment_file_ids(), vec!["file_2"]);
+    }
+
     #[test]
     fn test_storage_error_display() {
         use crate::storage::StorageError;
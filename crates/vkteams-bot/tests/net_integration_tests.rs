@@ -29,8 +29,8 @@ async fn test_retryable_multipart_form_integration() {
     assert_eq!(retryable_form.filename, "test_file.txt");
 
     // Test that we can create multiple forms (simulate retry)
-    let _form1 = retryable_form.to_form();
-    let _form2 = retryable_form.to_form();
+    let _form1 = retryable_form.to_form().await.unwrap();
+    let _form2 = retryable_form.to_form().await.unwrap();
     // Forms should be independently usable
 }
 
@@ -102,6 +102,7 @@ proptest! {
                 format: None,
                 parts: vec![],
                 timestamp: Timestamp(0),
+                parent_topic: None,
             })),
         };
         let ser = serde_json::to_string(&msg).unwrap();
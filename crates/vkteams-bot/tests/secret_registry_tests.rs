@@ -0,0 +1,47 @@
+//! Secret registry limit, in its own process to keep the registry of other tests small
+#![cfg(feature = "otlp")]
+
+use std::io::Write;
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::Duration;
+use vkteams_bot::secret::{RedactingMakeWriter, redact, register_secret};
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_register_secrets_over_limit_with_redacting_writer() {
+    let output = SharedBuffer::default();
+    let writer = output.clone();
+    let (done, finished) = mpsc::channel();
+    std::thread::spawn(move || {
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(RedactingMakeWriter::new(move || writer.clone()))
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            for i in 0..300 {
+                register_secret(&format!("registry_secret_{i:03}"));
+            }
+        });
+        done.send(()).unwrap();
+    });
+    finished
+        .recv_timeout(Duration::from_secs(10))
+        .expect("registering secrets over the limit must not block");
+
+    let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    assert!(output.contains("Secret is not registered"));
+    assert_eq!(redact("registry_secret_000"), "[REDACTED]");
+    assert_eq!(redact("registry_secret_299"), "registry_secret_299");
+}
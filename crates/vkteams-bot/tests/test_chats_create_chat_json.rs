@@ -0,0 +1,16 @@
+use vkteams_bot::prelude::{MemberAddStatus, ResponseChatsCreateChat};
+
+/// Integration test: deserializes ResponseChatsCreateChat from a real JSON file and checks key fields.
+#[test]
+fn test_response_chats_create_chat_from_real_json() {
+    let path = std::path::Path::new("tests/responds/chats_create_chat.json");
+    let data = std::fs::read_to_string(path).expect("Failed to read chats_create_chat.json");
+    let resp: ResponseChatsCreateChat =
+        serde_json::from_str(&data).expect("Failed to deserialize ResponseChatsCreateChat");
+    assert_eq!(resp.chat_id.0, "681869378@chat.agent");
+    assert_eq!(resp.members.len(), 2);
+    assert_eq!(resp.members[0].user_id.0, "u1");
+    assert_eq!(resp.members[0].status, MemberAddStatus::Added);
+    assert_eq!(resp.members[1].status, MemberAddStatus::Failed);
+    assert_eq!(resp.members[1].error.as_deref(), Some("User not found"));
+}
@@ -0,0 +1,16 @@
+use vkteams_bot::prelude::{MemberAddStatus, ResponseChatsMembersAdd};
+
+/// Integration test: deserializes ResponseChatsMembersAdd from a real JSON file and checks key fields.
+#[test]
+fn test_response_chats_members_add_from_real_json() {
+    let path = std::path::Path::new("tests/responds/chats_members_add.json");
+    let data = std::fs::read_to_string(path).expect("Failed to read chats_members_add.json");
+    let resp: ResponseChatsMembersAdd =
+        serde_json::from_str(&data).expect("Failed to deserialize ResponseChatsMembersAdd");
+    assert_eq!(resp.members.len(), 3);
+    assert_eq!(resp.members[0].user_id.0, "u1");
+    assert_eq!(resp.members[0].status, MemberAddStatus::Added);
+    assert_eq!(resp.members[1].status, MemberAddStatus::Pending);
+    assert_eq!(resp.members[2].status, MemberAddStatus::AlreadyMember);
+    assert_eq!(resp.members[2].error, None);
+}
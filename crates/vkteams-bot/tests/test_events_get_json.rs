@@ -1,4 +1,4 @@
-use vkteams_bot::prelude::{EventType, ResponseEventsGet};
+use vkteams_bot::prelude::{EventKind, EventType, MessagePartsType, ResponseEventsGet};
 
 /// Integration test: deserializes ResponseEventsGet from a real JSON file and checks key fields.
 #[test]
@@ -20,3 +20,79 @@ fn test_response_events_get_from_real_json() {
         _ => panic!("Expected NewMessage event type"),
     }
 }
+
+/// Unknown event and message part types are kept with the raw payload
+#[test]
+fn test_response_events_get_with_unknown_types() {
+    let path = std::path::Path::new("tests/responds/events_get_unknown.json");
+    let data = std::fs::read_to_string(path).expect("Failed to read events_get_unknown.json");
+    let resp: ResponseEventsGet =
+        serde_json::from_str(&data).expect("Failed to deserialize ResponseEventsGet");
+    assert_eq!(resp.events.len(), 3);
+
+    match &resp.events[0].event_type {
+        EventType::Unknown {
+            event_type,
+            payload,
+        } => {
+            assert_eq!(event_type, "changedChatInfo");
+            assert_eq!(payload["chat"]["title"], "Renamed chat");
+        }
+        other => panic!("Expected Unknown event type, got {other:?}"),
+    }
+    assert_eq!(resp.events[0].event_type.kind(), EventKind::Unknown);
+
+    match &resp.events[1].event_type {
+        EventType::NewMessage(payload) => {
+            assert_eq!(payload.parts.len(), 2);
+            match &payload.parts[0].part_type {
+                MessagePartsType::Unknown { part_type, payload } => {
+                    assert_eq!(part_type, "poll");
+                    assert_eq!(payload["pollId"], "p1");
+                }
+                other => panic!("Expected Unknown part type, got {other:?}"),
+            }
+            assert!(matches!(
+                payload.parts[1].part_type,
+                MessagePartsType::Sticker(_)
+            ));
+        }
+        other => panic!("Expected NewMessage event type, got {other:?}"),
+    }
+
+    match &resp.events[2].event_type {
+        EventType::Unknown {
+            event_type,
+            payload,
+        } => {
+            assert_eq!(event_type, "chatReaction");
+            assert!(payload.is_null());
+        }
+        other => panic!("Expected Unknown event type, got {other:?}"),
+    }
+
+    // Unknown types are serialized back in the API format
+    let json = serde_json::to_value(&resp.events[0]).unwrap();
+    assert_eq!(json["eventId"], 10);
+    assert_eq!(json["type"], "changedChatInfo");
+    assert_eq!(json["payload"]["from"]["userId"], "u2");
+}
+
+/// Messages sent to a thread carry a reference to the parent message
+#[test]
+fn test_response_events_get_thread_message() {
+    let path = std::path::Path::new("tests/responds/events_get_thread.json");
+    let data = std::fs::read_to_string(path).expect("Failed to read events_get_thread.json");
+    let resp: ResponseEventsGet =
+        serde_json::from_str(&data).expect("Failed to deserialize ResponseEventsGet");
+    match &resp.events[0].event_type {
+        EventType::NewMessage(payload) => {
+            let parent = payload.parent_topic.as_ref().expect("parentTopic");
+            assert_eq!(parent.chat_id.0, "c1");
+            assert_eq!(parent.msg_id.as_ref().unwrap().0, "m1");
+            assert_eq!(parent.thread_id.as_ref().unwrap().0, "t1");
+            assert_eq!(parent.topic_type.as_deref(), Some("thread"));
+        }
+        other => panic!("Expected NewMessage event type, got {other:?}"),
+    }
+}
@@ -0,0 +1,11 @@
+use vkteams_bot::prelude::ResponseThreadsAdd;
+
+/// Integration test: deserializes ResponseThreadsAdd from a real JSON file and checks key fields.
+#[test]
+fn test_response_threads_add_from_real_json() {
+    let path = std::path::Path::new("tests/responds/threads_add.json");
+    let data = std::fs::read_to_string(path).expect("Failed to read threads_add.json");
+    let resp: ResponseThreadsAdd =
+        serde_json::from_str(&data).expect("Failed to deserialize ResponseThreadsAdd");
+    assert_eq!(resp.thread_id.0, "t1");
+}
@@ -0,0 +1,10 @@
+use vkteams_bot::prelude::ResponseThreadsAutosubscribe;
+
+/// Integration test: deserializes ResponseThreadsAutosubscribe from a real JSON file.
+#[test]
+fn test_response_threads_autosubscribe_from_real_json() {
+    let path = std::path::Path::new("tests/responds/threads_autosubscribe.json");
+    let data = std::fs::read_to_string(path).expect("Failed to read threads_autosubscribe.json");
+    let _: ResponseThreadsAutosubscribe =
+        serde_json::from_str(&data).expect("Failed to deserialize ResponseThreadsAutosubscribe");
+}
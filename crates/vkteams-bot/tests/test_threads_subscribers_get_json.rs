@@ -0,0 +1,14 @@
+use vkteams_bot::prelude::ResponseThreadsSubscribersGet;
+
+/// Integration test: deserializes ResponseThreadsSubscribersGet from a real JSON file and checks key fields.
+#[test]
+fn test_response_threads_subscribers_get_from_real_json() {
+    let path = std::path::Path::new("tests/responds/threads_subscribers_get.json");
+    let data = std::fs::read_to_string(path).expect("Failed to read threads_subscribers_get.json");
+    let resp: ResponseThreadsSubscribersGet =
+        serde_json::from_str(&data).expect("Failed to deserialize ResponseThreadsSubscribersGet");
+    assert_eq!(resp.subscribers.len(), 2);
+    assert_eq!(resp.subscribers[0].user_id.0, "u1");
+    assert_eq!(resp.subscribers[1].user_id.0, "u2");
+    assert_eq!(resp.cursor.as_deref(), Some("c2"));
+}